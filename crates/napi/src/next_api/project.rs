@@ -36,6 +36,7 @@ use turbo_tasks::{
     get_effects,
     message_queue::{CompilationEvent, Severity, TimingEvent},
     trace::TraceRawVcs,
+    update_trace::UpdateTraceReport,
 };
 use turbo_tasks_backend::{BackingStorage, db_invalidation::invalidation_reasons};
 use turbo_tasks_fs::{
@@ -983,11 +984,17 @@ async fn output_assets_operation(
         .try_join()
         .await?;
 
-    let output_assets: FxIndexSet<ResolvedVc<Box<dyn OutputAsset>>> = endpoint_assets
+    let mut output_assets: FxIndexSet<ResolvedVc<Box<dyn OutputAsset>>> = endpoint_assets
         .iter()
         .flat_map(|assets| assets.iter().copied())
         .collect();
 
+    let analyze_report = container
+        .project()
+        .analyze_report(Vc::cell(output_assets.iter().copied().collect()))
+        .await?;
+    output_assets.extend(analyze_report.iter().copied());
+
     Ok(Vc::cell(output_assets.into_iter().collect()))
 }
 
@@ -1269,6 +1276,8 @@ impl From<UpdateMessage> for NapiUpdateMessage {
 struct NapiUpdateInfo {
     pub duration: u32,
     pub tasks: u32,
+    /// How the update propagated. Only available when subscribed with `trace` enabled.
+    pub trace: Option<NapiUpdateTrace>,
 }
 
 impl From<UpdateInfo> for NapiUpdateInfo {
@@ -1276,6 +1285,67 @@ impl From<UpdateInfo> for NapiUpdateInfo {
         Self {
             duration: update_info.duration.as_millis() as u32,
             tasks: update_info.tasks as u32,
+            trace: update_info.trace.map(NapiUpdateTrace::from),
+        }
+    }
+}
+
+#[napi(object)]
+struct NapiUpdateTrace {
+    pub invalidations: Vec<NapiTracedInvalidation>,
+    pub executions: Vec<NapiTracedExecution>,
+    pub changed_cells: Vec<NapiTracedCell>,
+    pub changed_outputs: Vec<String>,
+}
+
+#[napi(object)]
+struct NapiTracedInvalidation {
+    pub cause: String,
+    pub tasks: Vec<String>,
+}
+
+#[napi(object)]
+struct NapiTracedExecution {
+    pub task: String,
+    /// The duration in microseconds.
+    pub duration: u32,
+}
+
+#[napi(object)]
+struct NapiTracedCell {
+    pub task: String,
+    /// The value type and index of the cell, e.g. `u32#0`.
+    pub cell: String,
+}
+
+impl From<UpdateTraceReport> for NapiUpdateTrace {
+    fn from(trace: UpdateTraceReport) -> Self {
+        Self {
+            invalidations: trace
+                .invalidations
+                .into_iter()
+                .map(|invalidation| NapiTracedInvalidation {
+                    cause: invalidation.cause,
+                    tasks: invalidation.tasks,
+                })
+                .collect(),
+            executions: trace
+                .executions
+                .into_iter()
+                .map(|execution| NapiTracedExecution {
+                    task: execution.task,
+                    duration: execution.duration.as_micros() as u32,
+                })
+                .collect(),
+            changed_cells: trace
+                .changed_cells
+                .into_iter()
+                .map(|cell| NapiTracedCell {
+                    task: cell.task,
+                    cell: cell.cell,
+                })
+                .collect(),
+            changed_outputs: trace.changed_outputs,
         }
     }
 }
@@ -1290,18 +1360,30 @@ impl From<UpdateInfo> for NapiUpdateInfo {
 /// (excluding the idle time that was spend waiting for `aggregation_ms`), and
 /// the number of tasks that were executed.
 ///
+/// With `trace` enabled, the [UpdateMessage::End] event also describes how the update propagated:
+/// the file changes and changed tasks that invalidated other tasks, the executed tasks and the
+/// output files that changed. This is also written to the trace output. Tracing is disabled again
+/// when the subscription ends.
+///
 /// The signature of the `func` is `(update_message: UpdateMessage) => void`.
 #[napi]
 pub fn project_update_info_subscribe(
     #[napi(ts_arg_type = "{ __napiType: \"Project\" }")] project: External<ProjectInstance>,
     aggregation_ms: u32,
     func: JsFunction,
+    trace: Option<bool>,
 ) -> napi::Result<()> {
     let func: ThreadsafeFunction<UpdateMessage> = func.create_threadsafe_function(0, |ctx| {
         let message = ctx.value;
         Ok(vec![NapiUpdateMessage::from(message)])
     })?;
+    let trace_guard = trace.unwrap_or_default().then(|| {
+        let tt = project.turbopack_ctx.turbo_tasks().clone();
+        tt.update_trace().enable();
+        DisableUpdateTraceOnDrop(tt)
+    });
     tokio::spawn(async move {
+        let _trace_guard = trace_guard;
         let tt = project.turbopack_ctx.turbo_tasks();
         loop {
             let update_info = tt
@@ -1338,6 +1420,16 @@ pub fn project_update_info_subscribe(
     Ok(())
 }
 
+/// Reverts [`UpdateTraceApi::enable`][turbo_tasks::update_trace::UpdateTraceApi::enable] when an
+/// update info subscription ends.
+struct DisableUpdateTraceOnDrop(NextTurboTasks);
+
+impl Drop for DisableUpdateTraceOnDrop {
+    fn drop(&mut self) {
+        self.0.update_trace().disable();
+    }
+}
+
 /// Subscribes to all compilation events that are not cached like timing and progress information.
 #[napi]
 pub fn project_compilation_events_subscribe(
@@ -182,7 +182,7 @@ impl NapiNextTurbopackCallbacks {
 pub fn create_turbo_tasks(
     output_path: PathBuf,
     persistent_caching: bool,
-    _memory_limit: usize,
+    memory_limit: usize,
     dependency_tracking: bool,
     is_ci: bool,
     is_short_session: bool,
@@ -207,6 +207,7 @@ pub fn create_turbo_tasks(
                     turbo_tasks_backend::StorageMode::ReadWrite
                 }),
                 dependency_tracking,
+                memory_limit: (memory_limit != usize::MAX).then_some(memory_limit),
                 ..Default::default()
             },
             Either::Left(backing_storage),
@@ -0,0 +1,591 @@
+use anyhow::Result;
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::Serialize;
+use tracing::{Level, instrument};
+use turbo_rcstr::RcStr;
+use turbo_tasks::{FxIndexMap, FxIndexSet, ReadRef, ResolvedVc, TryJoinIterExt, ValueToString, Vc};
+use turbopack_browser::ecmascript::EcmascriptBrowserChunk;
+use turbopack_core::{
+    chunk::{Chunk, ChunkItem, ChunkItemExt, ModuleId, chunking::package_name},
+    module::Module,
+    module_graph::{GraphTraversalAction, ModuleGraph},
+    output::OutputAsset,
+};
+use turbopack_css::chunk::{CssChunk, CssChunkItem};
+use turbopack_ecmascript::chunk::EcmascriptChunkItem;
+use turbopack_nodejs::EcmascriptBuildNodeChunk;
+
+/// The maximum number of modules listed in an import chain. Longer chains are truncated at the
+/// entry side.
+const MAX_IMPORT_CHAIN_LENGTH: usize = 32;
+
+/// Generates a bundle analyzer report for the chunks reachable from `entry_assets`.
+///
+/// For each chunk this records the contained modules with their sizes and the import chain that
+/// caused each module to be included, and it lists packages that are bundled from more than one
+/// install location. Import chains are looked up in all of the `module_graphs`.
+///
+/// Browser and Node.js ecmascript chunks and CSS chunks are analyzed. Other output assets (e.g.
+/// static assets, source maps, manifests and wasm) are not part of the report.
+#[instrument(level = Level::INFO, skip_all)]
+pub async fn generate_analyze_report<I>(
+    module_graphs: Vec<Vc<ModuleGraph>>,
+    entry_assets: I,
+) -> Result<AnalyzeReport>
+where
+    I: IntoIterator<Item = ResolvedVc<Box<dyn OutputAsset>>>,
+{
+    let import_parents = module_graphs
+        .into_iter()
+        .map(import_parents)
+        .try_join()
+        .await?;
+
+    let mut chunks = vec![];
+
+    let mut visited = FxHashSet::default();
+    let mut queue = entry_assets.into_iter().collect::<Vec<_>>();
+    while let Some(asset) = queue.pop() {
+        if !visited.insert(asset) {
+            continue;
+        }
+        queue.extend(asset.references().await?.iter().copied());
+
+        let Some(chunk) = output_asset_chunk(asset) else {
+            continue;
+        };
+        let path = asset.path().await?.path.clone();
+        let Some(size) = *asset.size_bytes().await? else {
+            continue;
+        };
+
+        let modules = chunk
+            .chunk_items()
+            .await?
+            .iter()
+            .map(async |&chunk_item| analyze_chunk_item(chunk_item, &import_parents).await)
+            .try_join()
+            .await?;
+
+        chunks.push(AnalyzeChunk::new(path, size, modules));
+    }
+    chunks.sort_by(|a, b| a.path.cmp(&b.path));
+
+    Ok(AnalyzeReport {
+        duplicate_packages: duplicate_packages(&chunks),
+        chunks,
+    })
+}
+
+/// The module that first imported each module in a module graph, with the chunking type of the
+/// reference.
+#[turbo_tasks::value(transparent)]
+struct ImportParents(FxHashMap<ResolvedVc<Box<dyn Module>>, (ResolvedVc<Box<dyn Module>>, RcStr)>);
+
+#[turbo_tasks::function]
+async fn import_parents(module_graph: Vc<ModuleGraph>) -> Result<Vc<ImportParents>> {
+    let module_graph = module_graph.await?;
+    let entries = module_graph
+        .graphs
+        .iter()
+        .try_join()
+        .await?
+        .iter()
+        .flat_map(|graph| graph.entry_modules().collect::<Vec<_>>())
+        .collect::<Vec<_>>();
+
+    // A breadth-first traversal visits every module first from one of its closest parents, so
+    // following the recorded parents yields the shortest import chain to an entry.
+    let mut import_parents = FxHashMap::default();
+    module_graph
+        .traverse_edges_from_entries_bfs(entries, |parent, current| {
+            if let Some((parent_node, r)) = parent
+                && parent_node.module != current.module
+            {
+                import_parents
+                    .entry(current.module)
+                    .or_insert((parent_node.module, r.chunking_type.to_string().into()));
+            }
+            Ok(GraphTraversalAction::Continue)
+        })
+        .await?;
+    Ok(Vc::cell(import_parents))
+}
+
+/// Returns the chunk an output asset was generated from, if it is one of the analyzed chunk types.
+fn output_asset_chunk(asset: ResolvedVc<Box<dyn OutputAsset>>) -> Option<Vc<Box<dyn Chunk>>> {
+    if let Some(chunk) = ResolvedVc::try_downcast_type::<EcmascriptBrowserChunk>(asset) {
+        Some(chunk.chunk())
+    } else if let Some(chunk) = ResolvedVc::try_downcast_type::<EcmascriptBuildNodeChunk>(asset) {
+        Some(chunk.chunk())
+    } else {
+        ResolvedVc::try_downcast_type::<CssChunk>(asset).map(|chunk| Vc::upcast(*chunk))
+    }
+}
+
+/// Lists the packages that are bundled from more than one install location, e.g. two versions of
+/// the same package. A package that is bundled once for the browser and once for the server is
+/// not a duplicate.
+fn duplicate_packages(chunks: &[AnalyzeChunk]) -> Vec<AnalyzeDuplicatePackage> {
+    #[derive(Default)]
+    struct PackageLocations<'a> {
+        paths: FxIndexSet<&'a str>,
+        chunks: FxIndexSet<&'a RcStr>,
+    }
+
+    let mut packages: FxIndexMap<&RcStr, PackageLocations<'_>> = FxIndexMap::default();
+    for chunk in chunks {
+        for module in &chunk.modules {
+            if let Some(path) = package_path(&module.path) {
+                let package = packages.entry(&module.package).or_default();
+                package.paths.insert(path);
+                package.chunks.insert(&chunk.path);
+            }
+        }
+    }
+    packages
+        .into_iter()
+        .filter(|(_, package)| package.paths.len() > 1)
+        .map(|(name, package)| AnalyzeDuplicatePackage {
+            name: name.clone(),
+            paths: package.paths.into_iter().map(RcStr::from).collect(),
+            chunks: package.chunks.into_iter().cloned().collect(),
+        })
+        .collect()
+}
+
+/// Returns the directory of the package a module belongs to, e.g.
+/// `node_modules/a/node_modules/@scope/b` for `node_modules/a/node_modules/@scope/b/index.js`.
+fn package_path(path: &str) -> Option<&str> {
+    let start = path.rfind("node_modules/")? + "node_modules/".len();
+    let rest = &path[start..];
+    let segments = if rest.starts_with('@') { 2 } else { 1 };
+    let len = rest
+        .split('/')
+        .take(segments)
+        .map(|segment| segment.len() + 1)
+        .sum::<usize>()
+        - 1;
+    Some(&path[..start + len])
+}
+
+async fn analyze_chunk_item(
+    chunk_item: ResolvedVc<Box<dyn ChunkItem>>,
+    import_parents: &[ReadRef<ImportParents>],
+) -> Result<AnalyzeModule> {
+    let ident = chunk_item.asset_ident().to_string().owned().await?;
+    let source_size = *chunk_item
+        .content_ident()
+        .path()
+        .await?
+        .read()
+        .len()
+        .await?;
+    let generated_size = if let Some(chunk_item) =
+        ResolvedVc::try_downcast::<Box<dyn EcmascriptChunkItem>>(chunk_item)
+    {
+        chunk_item.content().await?.inner_code.len() as u64
+    } else if let Some(chunk_item) = ResolvedVc::try_downcast::<Box<dyn CssChunkItem>>(chunk_item) {
+        chunk_item.content().await?.inner_code.len() as u64
+    } else {
+        source_size.unwrap_or_default()
+    };
+
+    let mut reasons = vec![];
+    let mut visited = FxHashSet::default();
+    let mut current = chunk_item.module().to_resolved().await?;
+    // The import chain is looked up in the first module graph that contains the module
+    while let Some((parent, ty)) = import_parents
+        .iter()
+        .find_map(|import_parents| import_parents.get(&current))
+    {
+        if !visited.insert(*parent) || reasons.len() >= MAX_IMPORT_CHAIN_LENGTH {
+            break;
+        }
+        reasons.push(AnalyzeImportReason {
+            module: parent.ident().to_string().owned().await?,
+            ty: ty.clone(),
+        });
+        current = *parent;
+    }
+
+    Ok(AnalyzeModule {
+        id: chunk_item.id().owned().await?,
+        package: package_name(&ident).into(),
+        path: chunk_item.asset_ident().path().await?.path.clone(),
+        ident,
+        source_size,
+        generated_size,
+        estimated_minified_size: 0,
+        import_chain: reasons,
+    })
+}
+
+/// Renders the report as a self-contained HTML page with a treemap of the chunks and their
+/// modules.
+pub fn render_analyze_html(report: &AnalyzeReport) -> Result<String> {
+    // Escape `<` so the data can't terminate the surrounding script tag.
+    let data = serde_json::to_string(report)?.replace('<', "\\u003c");
+    Ok(ANALYZE_HTML_TEMPLATE.replace("__ANALYZE_DATA__", &data))
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzeReport {
+    pub chunks: Vec<AnalyzeChunk>,
+    /// Packages that are bundled from more than one install location
+    pub duplicate_packages: Vec<AnalyzeDuplicatePackage>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzeChunk {
+    /// The output path of the chunk
+    pub path: RcStr,
+    /// The size of the emitted (minified) chunk in bytes
+    pub size: u64,
+    /// The sum of the unminified code of all modules in bytes
+    pub generated_size: u64,
+    /// The modules in the chunk, largest first
+    pub modules: Vec<AnalyzeModule>,
+}
+
+impl AnalyzeChunk {
+    fn new(path: RcStr, size: u64, mut modules: Vec<AnalyzeModule>) -> Self {
+        // The chunk is minified as a whole, so the minified size of each module is estimated
+        // from its share of the unminified chunk code.
+        let generated_size = modules.iter().map(|m| m.generated_size).sum::<u64>();
+        if generated_size > 0 {
+            for module in modules.iter_mut() {
+                module.estimated_minified_size = module.generated_size * size / generated_size;
+            }
+        }
+        modules.sort_by(|a, b| b.estimated_minified_size.cmp(&a.estimated_minified_size));
+        AnalyzeChunk {
+            path,
+            size,
+            generated_size,
+            modules,
+        }
+    }
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzeModule {
+    pub id: ModuleId,
+    pub ident: RcStr,
+    /// Path to the actual file
+    pub path: RcStr,
+    /// The npm package the module belongs to, empty for application code
+    pub package: RcStr,
+    /// The size of the original source file in bytes
+    pub source_size: Option<u64>,
+    /// The size of the generated code before minification in bytes
+    pub generated_size: u64,
+    /// The estimated size of the module in the minified chunk in bytes. Chunks are minified as a
+    /// whole, so this is the module's share of the unminified chunk code.
+    pub estimated_minified_size: u64,
+    /// The modules importing this module, starting with the direct importer and ending with an
+    /// entry
+    pub import_chain: Vec<AnalyzeImportReason>,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzeImportReason {
+    /// The ident of the importing module
+    pub module: RcStr,
+    /// The chunking type of the reference
+    #[serde(rename = "type")]
+    pub ty: RcStr,
+}
+
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AnalyzeDuplicatePackage {
+    pub name: RcStr,
+    /// The directories the package is installed in
+    pub paths: Vec<RcStr>,
+    /// The chunks containing modules of any of the copies
+    pub chunks: Vec<RcStr>,
+}
+
+const ANALYZE_HTML_TEMPLATE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Turbopack bundle analysis</title>
+<style>
+  body { margin: 0; font: 12px system-ui, sans-serif; color: #111; }
+  header { padding: 8px 12px; border-bottom: 1px solid #ddd; display: flex; gap: 16px; align-items: baseline; }
+  header h1 { font-size: 14px; margin: 0; }
+  #map { position: absolute; top: 40px; left: 0; right: 320px; bottom: 0; }
+  #side { position: absolute; top: 40px; right: 0; width: 320px; bottom: 0; overflow: auto; border-left: 1px solid #ddd; padding: 8px; box-sizing: border-box; }
+  .node { position: absolute; box-sizing: border-box; border: 1px solid #fff; overflow: hidden; white-space: nowrap; text-overflow: ellipsis; padding: 2px; cursor: default; }
+  .chunk { background: #e8e8e8; font-weight: 600; }
+  .module { font-weight: normal; }
+  .module:hover { outline: 2px solid #111; z-index: 1; }
+  h2 { font-size: 13px; }
+  li { word-break: break-all; }
+</style>
+</head>
+<body>
+<header>
+  <h1>Turbopack bundle analysis</h1>
+  <span id="summary"></span>
+  <label>Size <select id="metric">
+    <option value="estimatedMinifiedSize">minified (estimated)</option>
+    <option value="generatedSize">generated</option>
+    <option value="sourceSize">source</option>
+  </select></label>
+</header>
+<div id="map"></div>
+<div id="side"></div>
+<script id="data" type="application/json">__ANALYZE_DATA__</script>
+<script>
+(function () {
+  var report = JSON.parse(document.getElementById('data').textContent);
+  var map = document.getElementById('map');
+  var side = document.getElementById('side');
+  var metricSelect = document.getElementById('metric');
+
+  function fmt(n) {
+    return n >= 1024 * 1024 ? (n / 1024 / 1024).toFixed(1) + ' MiB'
+      : n >= 1024 ? (n / 1024).toFixed(1) + ' KiB' : n + ' B';
+  }
+  function color(name) {
+    var h = 0;
+    for (var i = 0; i < name.length; i++) h = (h * 31 + name.charCodeAt(i)) | 0;
+    return 'hsl(' + (Math.abs(h) % 360) + ', 55%, 75%)';
+  }
+  function el(tag, text) {
+    var e = document.createElement(tag);
+    if (text !== undefined) e.textContent = text;
+    return e;
+  }
+
+  // Squarified treemap layout.
+  function worst(row, w) {
+    var s = 0, max = 0, min = Infinity;
+    row.forEach(function (r) { s += r.area; max = Math.max(max, r.area); min = Math.min(min, r.area); });
+    return Math.max(w * w * max / (s * s), (s * s) / (w * w * min));
+  }
+  function layout(items, x, y, w, h) {
+    var total = items.reduce(function (s, i) { return s + i.value; }, 0);
+    if (total <= 0) return [];
+    var scale = (w * h) / total;
+    var rest = items.filter(function (i) { return i.value > 0; })
+      .map(function (i) { return { item: i, area: i.value * scale }; });
+    var out = [];
+    while (rest.length) {
+      var side = Math.min(w, h), row = [rest.shift()];
+      while (rest.length && worst(row.concat([rest[0]]), side) <= worst(row, side)) row.push(rest.shift());
+      var area = row.reduce(function (s, r) { return s + r.area; }, 0);
+      var thick = area / side, off = 0;
+      row.forEach(function (r) {
+        var len = r.area / thick;
+        out.push(w >= h
+          ? { item: r.item, x: x, y: y + off, w: thick, h: len }
+          : { item: r.item, x: x + off, y: y, w: len, h: thick });
+        off += len;
+      });
+      if (w >= h) { x += thick; w -= thick; } else { y += thick; h -= thick; }
+    }
+    return out;
+  }
+
+  function showModule(chunk, module) {
+    side.textContent = '';
+    side.appendChild(el('h2', module.path));
+    var info = el('ul');
+    info.appendChild(el('li', 'chunk: ' + chunk.path));
+    if (module.package) info.appendChild(el('li', 'package: ' + module.package));
+    info.appendChild(el('li', 'source: ' + (module.sourceSize == null ? '-' : fmt(module.sourceSize))));
+    info.appendChild(el('li', 'generated: ' + fmt(module.generatedSize)));
+    info.appendChild(el('li', 'minified (estimated): ' + fmt(module.estimatedMinifiedSize)));
+    side.appendChild(info);
+    side.appendChild(el('h2', 'Import chain'));
+    var chain = el('ol');
+    module.importChain.forEach(function (r) { chain.appendChild(el('li', r.module + ' (' + r.type + ')')); });
+    side.appendChild(chain);
+  }
+
+  function showSummary() {
+    side.textContent = '';
+    side.appendChild(el('h2', 'Duplicate packages'));
+    if (!report.duplicatePackages.length) side.appendChild(el('p', 'None'));
+    report.duplicatePackages.forEach(function (p) {
+      side.appendChild(el('h3', p.name));
+      var list = el('ul');
+      p.paths.forEach(function (c) { list.appendChild(el('li', c)); });
+      side.appendChild(list);
+    });
+  }
+
+  function render() {
+    var metric = metricSelect.value;
+    map.textContent = '';
+    var chunks = report.chunks.map(function (c) {
+      var value = c.modules.reduce(function (s, m) { return s + (m[metric] || 0); }, 0);
+      return { chunk: c, value: value };
+    }).sort(function (a, b) { return b.value - a.value; });
+    var total = chunks.reduce(function (s, c) { return s + c.value; }, 0);
+    document.getElementById('summary').textContent =
+      report.chunks.length + ' chunks, ' + fmt(total);
+    layout(chunks, 0, 0, map.clientWidth, map.clientHeight).forEach(function (c) {
+      var node = el('div', c.item.chunk.path + ' (' + fmt(c.item.value) + ')');
+      node.className = 'node chunk';
+      node.style.cssText = 'left:' + c.x + 'px;top:' + c.y + 'px;width:' + c.w + 'px;height:' + c.h + 'px';
+      map.appendChild(node);
+      var modules = c.item.chunk.modules.map(function (m) { return { module: m, value: m[metric] || 0 }; })
+        .sort(function (a, b) { return b.value - a.value; });
+      layout(modules, c.x + 2, c.y + 16, Math.max(c.w - 4, 0), Math.max(c.h - 18, 0)).forEach(function (m) {
+        var mod = m.item.module;
+        var node = el('div', mod.path);
+        node.className = 'node module';
+        node.title = mod.ident + '\n' + fmt(m.item.value);
+        node.style.cssText = 'left:' + m.x + 'px;top:' + m.y + 'px;width:' + m.w + 'px;height:' + m.h +
+          'px;background:' + color(mod.package || c.item.chunk.path);
+        node.onmouseenter = function () { showModule(c.item.chunk, mod); };
+        map.appendChild(node);
+      });
+    });
+  }
+
+  metricSelect.onchange = render;
+  map.onmouseleave = showSummary;
+  window.onresize = render;
+  showSummary();
+  render();
+})();
+</script>
+</body>
+</html>
+"#;
+
+#[cfg(test)]
+mod tests {
+    use turbo_rcstr::rcstr;
+
+    use super::*;
+
+    fn module(ident: &str, package: &str, generated_size: u64) -> AnalyzeModule {
+        AnalyzeModule {
+            id: ModuleId::String(ident.into()),
+            ident: ident.into(),
+            path: ident.into(),
+            package: package.into(),
+            source_size: None,
+            generated_size,
+            estimated_minified_size: 0,
+            import_chain: vec![],
+        }
+    }
+
+    #[test]
+    fn test_minified_size_estimate() {
+        let chunk = AnalyzeChunk::new(
+            rcstr!("static/chunks/app.js"),
+            100,
+            vec![
+                module("[project]/a.js", "", 50),
+                module("[project]/node_modules/react/index.js", "react", 150),
+            ],
+        );
+        assert_eq!(chunk.generated_size, 200);
+        let sizes = chunk
+            .modules
+            .iter()
+            .map(|m| (m.ident.as_str(), m.estimated_minified_size))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            sizes,
+            [
+                ("[project]/node_modules/react/index.js", 75),
+                ("[project]/a.js", 25)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_package_path() {
+        assert_eq!(package_path("[project]/a.js"), None);
+        assert_eq!(
+            package_path("[project]/node_modules/react/cjs/react.js"),
+            Some("[project]/node_modules/react")
+        );
+        assert_eq!(
+            package_path("[project]/node_modules/a/node_modules/@scope/b/index.js"),
+            Some("[project]/node_modules/a/node_modules/@scope/b")
+        );
+    }
+
+    #[test]
+    fn test_duplicate_packages() {
+        let chunks = [
+            AnalyzeChunk::new(
+                rcstr!("static/chunks/a.js"),
+                10,
+                vec![
+                    module("[project]/node_modules/react/index.js", "react", 10),
+                    module("[project]/node_modules/ui/index.js", "ui", 10),
+                    module("[project]/a.js", "", 10),
+                ],
+            ),
+            AnalyzeChunk::new(
+                rcstr!("static/chunks/b.js"),
+                10,
+                vec![module(
+                    "[project]/node_modules/ui/node_modules/react/index.js",
+                    "react",
+                    10,
+                )],
+            ),
+            // The same copy of a package in client and server chunks is not a duplicate
+            AnalyzeChunk::new(
+                rcstr!("server/chunks/ssr/a.js"),
+                10,
+                vec![
+                    module("[project]/node_modules/react/index.js", "react", 10),
+                    module("[project]/node_modules/ui/index.js", "ui", 10),
+                    module("[project]/a.js", "", 10),
+                ],
+            ),
+        ];
+        let duplicates = duplicate_packages(&chunks);
+        assert_eq!(duplicates.len(), 1);
+        assert_eq!(duplicates[0].name, "react");
+        assert_eq!(
+            duplicates[0].paths,
+            [
+                rcstr!("[project]/node_modules/react"),
+                rcstr!("[project]/node_modules/ui/node_modules/react")
+            ]
+        );
+        assert_eq!(
+            duplicates[0].chunks,
+            [
+                rcstr!("static/chunks/a.js"),
+                rcstr!("static/chunks/b.js"),
+                rcstr!("server/chunks/ssr/a.js")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_escapes_script_end() -> Result<()> {
+        let report = AnalyzeReport {
+            chunks: vec![AnalyzeChunk::new(
+                rcstr!("static/chunks/a.js"),
+                10,
+                vec![module("[project]/</script>.js", "", 10)],
+            )],
+            duplicate_packages: vec![],
+        };
+        let html = render_analyze_html(&report)?;
+        assert!(html.contains(r"[project]/\u003c/script>.js"));
+        assert_eq!(html.matches("</script>").count(), 2);
+        Ok(())
+    }
+}
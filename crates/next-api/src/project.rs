@@ -36,14 +36,22 @@ use turbo_tasks::{
     trace::TraceRawVcs,
 };
 use turbo_tasks_env::{EnvMap, ProcessEnv};
-use turbo_tasks_fs::{DiskFileSystem, FileSystem, FileSystemPath, VirtualFileSystem, invalidation};
+use turbo_tasks_fs::{
+    DiskFileSystem, File, FileSystem, FileSystemPath, VirtualFileSystem, invalidation,
+};
 use turbo_unix_path::{join_path, unix_to_sys};
 use turbopack::{
-    ModuleAssetContext, evaluate_context::node_build_environment,
-    global_module_ids::get_global_module_id_strategy, transition::TransitionOptions,
+    ModuleAssetContext,
+    css::emit_css_module_type_declarations,
+    evaluate_context::node_build_environment,
+    global_module_ids::{
+        get_content_hash_module_id_strategy, get_global_module_id_strategy, write_module_id_records,
+    },
+    transition::TransitionOptions,
 };
 use turbopack_core::{
     PROJECT_FILESYSTEM_NAME,
+    asset::AssetContent,
     changed::content_changed,
     chunk::{
         ChunkingContext, EvaluatableAssets, SourceMapsType,
@@ -72,11 +80,13 @@ use turbopack_core::{
     version::{
         NotFoundVersion, OptionVersionedContent, Update, Version, VersionState, VersionedContent,
     },
+    virtual_output::VirtualOutputAsset,
 };
 use turbopack_node::execution_context::ExecutionContext;
 use turbopack_nodejs::NodeJsChunkingContext;
 
 use crate::{
+    analyze::{generate_analyze_report, render_analyze_html},
     app::{AppProject, OptionAppProject},
     empty::EmptyEndpoint,
     entrypoints::Entrypoints,
@@ -810,6 +820,13 @@ impl Project {
         ))
     }
 
+    #[turbo_tasks::function]
+    async fn should_create_analyze_report(&self) -> Result<Vc<bool>> {
+        Ok(Vc::cell(
+            self.env.read(rcstr!("TURBOPACK_ANALYZE")).await?.is_some(),
+        ))
+    }
+
     #[turbo_tasks::function]
     pub(super) async fn execution_context(self: Vc<Self>) -> Result<Vc<ExecutionContext>> {
         let node_root = self.node_root().owned().await?;
@@ -1040,6 +1057,8 @@ impl Project {
             source_maps: self.next_config().client_source_maps(self.next_mode()),
             no_mangling: self.no_mangling(),
             scope_hoisting: self.next_config().turbo_scope_hoisting(self.next_mode()),
+            chunking_strategy: self.next_config().chunking_strategy(),
+            max_parallel_requests: self.next_config().max_parallel_requests(),
         }))
     }
 
@@ -1636,6 +1655,10 @@ impl Project {
             let client_relative_path = self.client_relative_path().owned().await?;
             let node_root = self.node_root().owned().await?;
 
+            self.emit_css_module_type_declarations()
+                .as_side_effect()
+                .await?;
+
             if let Some(map) = self.await?.versioned_content_map {
                 map.insert_output_assets(
                     all_output_assets,
@@ -1664,6 +1687,57 @@ impl Project {
         .await
     }
 
+    /// Writes the `.d.ts` files of the CSS Modules in the app, if enabled with
+    /// `experimental.turbopackCssModuleTypes`.
+    #[turbo_tasks::function]
+    async fn emit_css_module_type_declarations(self: Vc<Self>) -> Result<()> {
+        if !*self.next_config().turbopack_css_module_types().await? {
+            return Ok(());
+        }
+        let module_graphs = self.whole_app_module_graphs().await?;
+        emit_css_module_type_declarations(*module_graphs.full)
+            .as_side_effect()
+            .await?;
+        Ok(())
+    }
+
+    /// Generates the bundle analyzer report of the whole app from the output assets of all
+    /// endpoints, if enabled with `TURBOPACK_ANALYZE`. The report is written to `.next/analyze/`,
+    /// outside of the server output that is deployed.
+    #[turbo_tasks::function]
+    pub async fn analyze_report(
+        self: Vc<Self>,
+        output_assets: Vc<OutputAssets>,
+    ) -> Result<Vc<OutputAssets>> {
+        if !*self.should_create_analyze_report().await? {
+            return Ok(OutputAssets::empty());
+        }
+        let node_root = self.node_root().owned().await?;
+        let module_graphs = self.whole_app_module_graphs().await?;
+        let report = generate_analyze_report(
+            vec![*module_graphs.full],
+            output_assets.await?.iter().copied(),
+        )
+        .await?;
+
+        let report_json = VirtualOutputAsset::new(
+            node_root.join("analyze/report.json")?,
+            AssetContent::file(File::from(serde_json::to_string_pretty(&report)?).into()),
+        )
+        .to_resolved()
+        .await?;
+        let report_html = VirtualOutputAsset::new(
+            node_root.join("analyze/report.html")?,
+            AssetContent::file(File::from(render_analyze_html(&report)?).into()),
+        )
+        .to_resolved()
+        .await?;
+        Ok(Vc::cell(vec![
+            ResolvedVc::upcast(report_json),
+            ResolvedVc::upcast(report_html),
+        ]))
+    }
+
     #[turbo_tasks::function]
     async fn hmr_content(self: Vc<Self>, identifier: RcStr) -> Result<Vc<OptionVersionedContent>> {
         if let Some(map) = self.await?.versioned_content_map {
@@ -1784,6 +1858,22 @@ impl Project {
                     *module_graphs.full,
                 )))
             }
+            ModuleIdStrategyConfig::Hashed => {
+                let module_graphs = self.whole_app_module_graphs().await?;
+                let records_path = self
+                    .node_root()
+                    .owned()
+                    .await?
+                    .join("cache/turbopack-module-ids.json")?;
+                let strategy = get_content_hash_module_id_strategy(
+                    *module_graphs.full,
+                    Some(records_path.clone()),
+                );
+                write_module_id_records(strategy, records_path)
+                    .as_side_effect()
+                    .await?;
+                Ok(Vc::upcast(strategy))
+            }
         }
     }
 
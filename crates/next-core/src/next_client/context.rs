@@ -6,7 +6,7 @@ use turbo_rcstr::{RcStr, rcstr};
 use turbo_tasks::{ResolvedVc, TaskInput, Vc, trace::TraceRawVcs};
 use turbo_tasks_fs::FileSystemPath;
 use turbopack::{
-    css::chunk::CssChunkType,
+    css::{CssModuleTypeDeclarations, chunk::CssChunkType},
     module_options::{
         CssOptionsContext, EcmascriptOptionsContext, JsxTransformOptions, ModuleRule, TypeofWindow,
         TypescriptTransformOptions, module_options_context::ModuleOptionsContext,
@@ -20,7 +20,7 @@ use turbopack_browser::{
 use turbopack_core::{
     chunk::{
         ChunkingConfig, ChunkingContext, MangleType, MinifyType, SourceMapsType,
-        module_id_strategies::ModuleIdStrategy,
+        chunking_strategies::OptionChunkingStrategy, module_id_strategies::ModuleIdStrategy,
     },
     compile_time_info::{CompileTimeDefines, CompileTimeInfo, FreeVarReference, FreeVarReferences},
     environment::{BrowserEnvironment, Environment, ExecutionEnvironment},
@@ -303,6 +303,17 @@ pub async fn get_client_module_options_context(
     let enable_postcss_transform = Some(postcss_transform_options.resolved_cell());
     let enable_foreign_postcss_transform = Some(postcss_foreign_transform_options.resolved_cell());
 
+    let module_type_declarations = next_config
+        .turbopack_css_module_types()
+        .await?
+        .then_some(CssModuleTypeDeclarations::NextToSource);
+    let report_unused_module_classes = *next_config
+        .turbopack_report_unused_css_module_classes()
+        .await?;
+    let remove_unused_module_classes = *next_config
+        .turbopack_remove_unused_css_module_classes()
+        .await?;
+
     let source_maps = if *next_config.client_source_maps(mode).await? {
         SourceMapsType::Full
     } else {
@@ -316,12 +327,16 @@ pub async fn get_client_module_options_context(
         },
         css: CssOptionsContext {
             source_maps,
+            module_type_declarations,
+            report_unused_module_classes,
+            remove_unused_module_classes,
             ..Default::default()
         },
         environment: Some(env),
         execution_context: Some(execution_context),
         tree_shaking_mode: tree_shaking_mode_for_user_code,
         enable_postcss_transform,
+        enable_import_queries: *next_config.turbopack_import_queries().await?,
         side_effect_free_packages: next_config.optimize_package_imports().owned().await?,
         keep_last_successful_parse: next_mode.is_development(),
         ..Default::default()
@@ -337,6 +352,12 @@ pub async fn get_client_module_options_context(
         },
         enable_webpack_loaders: foreign_enable_webpack_loaders,
         enable_postcss_transform: enable_foreign_postcss_transform,
+        css: CssOptionsContext {
+            module_type_declarations: None,
+            report_unused_module_classes: false,
+            remove_unused_module_classes: false,
+            ..module_options_context.css.clone()
+        },
         module_rules: foreign_next_client_rules,
         tree_shaking_mode: tree_shaking_mode_for_foreign_code,
         // NOTE(WEB-1016) PostCSS transforms should also apply to foreign code.
@@ -400,6 +421,8 @@ pub struct ClientChunkingContextOptions {
     pub source_maps: Vc<bool>,
     pub no_mangling: Vc<bool>,
     pub scope_hoisting: Vc<bool>,
+    pub chunking_strategy: Vc<OptionChunkingStrategy>,
+    pub max_parallel_requests: Vc<usize>,
 }
 
 #[turbo_tasks::function]
@@ -420,6 +443,8 @@ pub async fn get_client_chunking_context(
         source_maps,
         no_mangling,
         scope_hoisting,
+        chunking_strategy,
+        max_parallel_requests,
     } = options;
 
     let next_mode = mode.await?;
@@ -467,6 +492,8 @@ pub async fn get_client_chunking_context(
                     min_chunk_size: 50_000,
                     max_chunk_count_per_group: 40,
                     max_merge_chunk_size: 200_000,
+                    max_parallel_requests: *max_parallel_requests.await?,
+                    strategy: *chunking_strategy.await?,
                     ..Default::default()
                 },
             )
@@ -16,6 +16,9 @@ use turbopack::module_options::{
     module_options_context::{MdxTransformOptions, OptionWebpackConditions},
 };
 use turbopack_core::{
+    chunk::chunking_strategies::{
+        OptionChunkingStrategy, RouteGroupsChunkingStrategy, VendorPerPackageChunkingStrategy,
+    },
     issue::{Issue, IssueExt, IssueStage, OptionStyledString, StyledString},
     resolve::ResolveAliasMap,
 };
@@ -27,7 +30,8 @@ use turbopack_ecmascript_plugins::transform::{
 use turbopack_node::transforms::webpack::{WebpackLoaderItem, WebpackLoaderItems};
 
 use crate::{
-    mode::NextMode, next_import_map::mdx_import_source_file,
+    mode::NextMode, next_font::google::provider::GoogleFontsProvider,
+    next_import_map::mdx_import_source_file,
     next_shared::transforms::ModularizeImportPackageConfig,
 };
 
@@ -558,6 +562,8 @@ pub struct TurbopackConfig {
     pub resolve_alias: Option<FxIndexMap<RcStr, JsonValue>>,
     pub resolve_extensions: Option<Vec<RcStr>>,
     pub module_ids: Option<ModuleIds>,
+    pub chunking_strategy: Option<ChunkingStrategyConfig>,
+    pub max_parallel_requests: Option<usize>,
 }
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, Eq, Debug)]
@@ -645,11 +651,24 @@ pub enum LoaderItem {
 pub enum ModuleIds {
     Named,
     Deterministic,
+    /// Short ids derived from a hash of the module ident, kept stable across builds via a
+    /// records file in the dist directory.
+    Hashed,
 }
 
 #[turbo_tasks::value(transparent)]
 pub struct OptionModuleIds(pub Option<ModuleIds>);
 
+/// How client chunks are split in production, in addition to the chunk groups.
+#[turbo_tasks::value(operation)]
+#[derive(Copy, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum ChunkingStrategyConfig {
+    Default,
+    VendorPerPackage,
+    RouteGroups,
+}
+
 #[derive(
     Clone, Debug, PartialEq, Serialize, Deserialize, TraceRawVcs, NonLocalValue, OperationValue,
 )]
@@ -804,10 +823,25 @@ pub struct ExperimentalConfig {
     turbopack_tree_shaking: Option<bool>,
     turbopack_scope_hoisting: Option<bool>,
     turbopack_use_system_tls_certs: Option<bool>,
+    /// The base URL of the Google Fonts API used by `next/font/google`, e.g. a self-hosted mirror.
+    turbopack_google_fonts_base_url: Option<RcStr>,
+    /// A directory, relative to the project, to keep the files downloaded by `next/font/google`
+    /// in.
+    turbopack_google_fonts_cache_dir: Option<RcStr>,
     // Whether to enable the global-not-found convention
     global_not_found: Option<bool>,
     /// Defaults to false in development mode, true in production mode.
     turbopack_remove_unused_exports: Option<bool>,
+    /// Emit a `.d.ts` file next to every CSS Module, declaring its exported classes.
+    turbopack_css_module_types: Option<bool>,
+    /// Report the classes of CSS Modules that no importing module reads, and optionally remove
+    /// their rules. Requires `turbopack_remove_unused_exports`.
+    turbopack_unused_css_module_classes: Option<UnusedCssModuleClasses>,
+    /// Handle the Vite-style `?raw`, `?url` and `?worker` import queries.
+    turbopack_import_queries: Option<bool>,
+    /// A JSON file, relative to the project, that describes packages whose exports load files at
+    /// runtime, so the server output traces them. See `WellKnownPackages::from_json`.
+    turbopack_well_known_packages: Option<RcStr>,
     /// Devtool option for the segment explorer.
     devtool_segment_explorer: Option<bool>,
 }
@@ -937,6 +971,53 @@ pub enum ServerActionsOrLegacyBool {
     LegacyBool(bool),
 }
 
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Deserialize,
+    Serialize,
+    TraceRawVcs,
+    NonLocalValue,
+    OperationValue,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum UnusedCssModuleClasses {
+    /// Report the unused classes as warnings.
+    Report,
+    /// Report the unused classes and leave out the rules that only match them.
+    Remove,
+}
+
+impl UnusedCssModuleClasses {
+    pub fn remove(self) -> bool {
+        self == UnusedCssModuleClasses::Remove
+    }
+}
+
+#[test]
+fn test_unused_css_module_classes_deserialization() {
+    let json = serde_json::json!({
+        "turbopackUnusedCssModuleClasses": "remove"
+    });
+    let config: ExperimentalConfig = serde_json::from_value(json).unwrap();
+    let unused = config.turbopack_unused_css_module_classes.unwrap();
+    assert!(unused.remove());
+
+    let json = serde_json::json!({
+        "turbopackUnusedCssModuleClasses": "report"
+    });
+    let config: ExperimentalConfig = serde_json::from_value(json).unwrap();
+    let unused = config.turbopack_unused_css_module_classes.unwrap();
+    assert!(!unused.remove());
+
+    let json = serde_json::json!({
+        "turbopackUnusedCssModuleClasses": true
+    });
+    assert!(serde_json::from_value::<ExperimentalConfig>(json).is_err());
+}
+
 #[derive(
     Clone, Debug, PartialEq, Deserialize, Serialize, TraceRawVcs, NonLocalValue, OperationValue,
 )]
@@ -1669,6 +1750,49 @@ impl NextConfig {
         ))
     }
 
+    #[turbo_tasks::function]
+    pub fn turbopack_css_module_types(&self) -> Vc<bool> {
+        Vc::cell(
+            self.experimental
+                .turbopack_css_module_types
+                .unwrap_or_default(),
+        )
+    }
+
+    /// Whether to report the classes of CSS Modules that no importing module reads.
+    #[turbo_tasks::function]
+    pub fn turbopack_report_unused_css_module_classes(&self) -> Vc<bool> {
+        Vc::cell(
+            self.experimental
+                .turbopack_unused_css_module_classes
+                .is_some(),
+        )
+    }
+
+    /// Whether to leave out the rules that only match unused classes of CSS Modules.
+    #[turbo_tasks::function]
+    pub fn turbopack_remove_unused_css_module_classes(&self) -> Vc<bool> {
+        Vc::cell(
+            self.experimental
+                .turbopack_unused_css_module_classes
+                .is_some_and(UnusedCssModuleClasses::remove),
+        )
+    }
+
+    #[turbo_tasks::function]
+    pub fn turbopack_import_queries(&self) -> Vc<bool> {
+        Vc::cell(
+            self.experimental
+                .turbopack_import_queries
+                .unwrap_or_default(),
+        )
+    }
+
+    #[turbo_tasks::function]
+    pub fn turbopack_well_known_packages(&self) -> Vc<Option<RcStr>> {
+        Vc::cell(self.experimental.turbopack_well_known_packages.clone())
+    }
+
     #[turbo_tasks::function]
     pub async fn module_ids(&self, mode: Vc<NextMode>) -> Result<Vc<ModuleIds>> {
         Ok(match *mode.await? {
@@ -1683,6 +1807,30 @@ impl NextConfig {
         })
     }
 
+    #[turbo_tasks::function]
+    pub fn chunking_strategy(&self) -> Vc<OptionChunkingStrategy> {
+        let strategy = self.turbopack.as_ref().and_then(|t| t.chunking_strategy);
+        Vc::cell(match strategy {
+            None | Some(ChunkingStrategyConfig::Default) => None,
+            Some(ChunkingStrategyConfig::VendorPerPackage) => Some(ResolvedVc::upcast(
+                VendorPerPackageChunkingStrategy::new_resolved(),
+            )),
+            Some(ChunkingStrategyConfig::RouteGroups) => Some(ResolvedVc::upcast(
+                RouteGroupsChunkingStrategy::new_resolved(),
+            )),
+        })
+    }
+
+    #[turbo_tasks::function]
+    pub fn max_parallel_requests(&self) -> Vc<usize> {
+        Vc::cell(
+            self.turbopack
+                .as_ref()
+                .and_then(|t| t.max_parallel_requests)
+                .unwrap_or(0),
+        )
+    }
+
     #[turbo_tasks::function]
     pub async fn turbo_minify(&self, mode: Vc<NextMode>) -> Result<Vc<bool>> {
         let minify = self.experimental.turbopack_minify;
@@ -1756,6 +1904,33 @@ impl NextConfig {
         }
         .cell())
     }
+
+    #[turbo_tasks::function]
+    pub async fn google_fonts_provider(
+        &self,
+        env: Vc<Box<dyn ProcessEnv>>,
+    ) -> Result<Vc<GoogleFontsProvider>> {
+        // Like in `fetch_client`, the env vars allow configuring this per-system, e.g. for CI
+        // runners without internet access. Empty values are treated the same as unset values.
+        let base_url = env
+            .read(rcstr!("NEXT_TURBOPACK_EXPERIMENTAL_GOOGLE_FONTS_BASE_URL"))
+            .owned()
+            .await?
+            .filter(|value| !value.is_empty())
+            .or_else(|| self.experimental.turbopack_google_fonts_base_url.clone());
+        let cache_dir = env
+            .read(rcstr!("NEXT_TURBOPACK_EXPERIMENTAL_GOOGLE_FONTS_CACHE_DIR"))
+            .owned()
+            .await?
+            .filter(|value| !value.is_empty())
+            .or_else(|| self.experimental.turbopack_google_fonts_cache_dir.clone());
+        let default = GoogleFontsProvider::default();
+        Ok(GoogleFontsProvider {
+            base_url: base_url.unwrap_or(default.base_url),
+            cache_dir: cache_dir.or(default.cache_dir),
+        }
+        .cell())
+    }
 }
 
 /// A subset of ts/jsconfig that next.js implicitly
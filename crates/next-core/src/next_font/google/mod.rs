@@ -1,6 +1,7 @@
 use std::{path::Path, sync::LazyLock};
 
 use anyhow::{Context, Result, bail};
+use const_format::concatcp;
 use futures::FutureExt;
 use indoc::formatdoc;
 use regex::Regex;
@@ -10,7 +11,7 @@ use turbo_rcstr::{RcStr, rcstr};
 use turbo_tasks::{Completion, FxIndexMap, ResolvedVc, Vc};
 use turbo_tasks_bytes::stream::SingleValue;
 use turbo_tasks_env::{CommandLineProcessEnv, ProcessEnv};
-use turbo_tasks_fetch::{FetchClient, HttpResponseBody};
+use turbo_tasks_fetch::{FetchClient, FetchErrorKind, HttpResponseBody};
 use turbo_tasks_fs::{
     DiskFileSystem, File, FileContent, FileSystem, FileSystemPath,
     json::parse_json_with_source_context,
@@ -38,6 +39,7 @@ use turbopack_node::{
 use self::{
     font_fallback::get_font_fallback,
     options::{FontDataEntry, FontWeights, NextFontGoogleOptions, options_from_request},
+    provider::{GOOGLE_FONTS_BASE_URL, GoogleFontsProvider, fetch_with_cache},
     stylesheet::build_stylesheet,
     util::{get_font_axes, get_stylesheet_url},
 };
@@ -55,11 +57,12 @@ use crate::{
 
 pub mod font_fallback;
 pub mod options;
+pub mod provider;
 pub mod request;
 pub mod stylesheet;
 pub mod util;
 
-pub const GOOGLE_FONTS_STYLESHEET_URL: &str = "https://fonts.googleapis.com/css2";
+pub const GOOGLE_FONTS_STYLESHEET_URL: &str = concatcp!(GOOGLE_FONTS_BASE_URL, "/css2");
 // Always sending this user agent ensures consistent results from Google Fonts.
 // Google Fonts will vary responses based on user agent, e.g. only returning
 // references to certain font types for certain browsers.
@@ -184,6 +187,7 @@ pub struct NextFontGoogleCssModuleReplacer {
     execution_context: ResolvedVc<ExecutionContext>,
     next_mode: ResolvedVc<NextMode>,
     fetch_client: ResolvedVc<FetchClient>,
+    provider: ResolvedVc<GoogleFontsProvider>,
 }
 
 #[turbo_tasks::value_impl]
@@ -194,12 +198,14 @@ impl NextFontGoogleCssModuleReplacer {
         execution_context: ResolvedVc<ExecutionContext>,
         next_mode: ResolvedVc<NextMode>,
         fetch_client: ResolvedVc<FetchClient>,
+        provider: ResolvedVc<GoogleFontsProvider>,
     ) -> Vc<Self> {
         Self::cell(NextFontGoogleCssModuleReplacer {
             project_path,
             execution_context,
             next_mode,
             fetch_client,
+            provider,
         })
     }
 
@@ -208,7 +214,7 @@ impl NextFontGoogleCssModuleReplacer {
         let request_hash = get_request_hash(&query);
         let font_data = load_font_data(self.project_path.clone());
         let options = font_options_from_query_map(query, font_data);
-        let stylesheet_url = get_stylesheet_url_from_options(options, font_data)
+        let stylesheet_url = get_stylesheet_url_from_options(options, font_data, *self.provider)
             .owned()
             .await?;
         let font_family = options.font_family().await?;
@@ -218,12 +224,12 @@ impl NextFontGoogleCssModuleReplacer {
             .await?
             .join(&format!(
                 "/{}.module.css",
-                get_request_id(font_family, request_hash)
+                get_request_id(font_family.clone(), request_hash)
             ))?;
 
         // When running Next.js integration tests, use the mock data available in
         // process.env.NEXT_FONT_GOOGLE_MOCKED_RESPONSES instead of making real
-        // requests to Google Fonts.
+        // requests to the font provider.
         let env = Vc::upcast::<Box<dyn ProcessEnv>>(CommandLineProcessEnv::new());
         let mocked_responses_path = &*env
             .read(rcstr!("NEXT_FONT_GOOGLE_MOCKED_RESPONSES"))
@@ -235,8 +241,12 @@ impl NextFontGoogleCssModuleReplacer {
                 || {
                     fetch_real_stylesheet(
                         *self.fetch_client,
+                        *self.provider,
+                        self.project_path.clone(),
                         stylesheet_url.clone(),
                         css_virtual_path.clone(),
+                        &font_family,
+                        *self.next_mode,
                     )
                     .boxed()
                 },
@@ -246,7 +256,7 @@ impl NextFontGoogleCssModuleReplacer {
 
         let font_fallback = get_font_fallback(self.project_path.clone(), options);
         let stylesheet = match stylesheet_str {
-            Some(s) => Some(
+            FetchedStylesheet::Ok(s) => Some(
                 update_google_stylesheet(
                     s,
                     options,
@@ -256,7 +266,8 @@ impl NextFontGoogleCssModuleReplacer {
                 .owned()
                 .await?,
             ),
-            None => {
+            FetchedStylesheet::NotServed => None,
+            FetchedStylesheet::Failed => {
                 match *self.next_mode.await? {
                     // If we're in production mode, we want to fail the build to ensure proper font
                     // rendering.
@@ -377,15 +388,21 @@ struct NextFontGoogleFontFileOptions {
 pub struct NextFontGoogleFontFileReplacer {
     project_path: FileSystemPath,
     fetch_client: ResolvedVc<FetchClient>,
+    provider: ResolvedVc<GoogleFontsProvider>,
 }
 
 #[turbo_tasks::value_impl]
 impl NextFontGoogleFontFileReplacer {
     #[turbo_tasks::function]
-    pub fn new(project_path: FileSystemPath, fetch_client: ResolvedVc<FetchClient>) -> Vc<Self> {
+    pub fn new(
+        project_path: FileSystemPath,
+        fetch_client: ResolvedVc<FetchClient>,
+        provider: ResolvedVc<GoogleFontsProvider>,
+    ) -> Vc<Self> {
         Self::cell(NextFontGoogleFontFileReplacer {
             project_path,
             fetch_client,
+            provider,
         })
     }
 }
@@ -441,9 +458,14 @@ impl ImportMappingReplacement for NextFontGoogleFontFileReplacer {
 
         // doesn't seem ideal to download the font into a string, but probably doesn't
         // really matter either.
-        let Some(font) =
-            fetch_from_google_fonts(*self.fetch_client, url.into(), font_virtual_path.clone())
-                .await?
+        let Some(font) = fetch_from_google_fonts(
+            *self.fetch_client,
+            *self.provider,
+            self.project_path.clone(),
+            url.into(),
+            font_virtual_path.clone(),
+        )
+        .await?
         else {
             return Ok(ImportMapResult::Result(ResolveResult::unresolvable()).cell());
         };
@@ -561,6 +583,7 @@ fn find_font_files_in_css(css: &str, subsets_to_preload: &[RcStr]) -> Vec<FontFi
 async fn get_stylesheet_url_from_options(
     options: Vc<NextFontGoogleOptions>,
     font_data: Vc<FontData>,
+    provider: Vc<GoogleFontsProvider>,
 ) -> Result<Vc<RcStr>> {
     #[allow(unused_mut, unused_assignments)] // This is used in test environments
     let mut css_url: Option<String> = None;
@@ -574,10 +597,14 @@ async fn get_stylesheet_url_from_options(
         }
     }
 
+    let css_url = match css_url {
+        Some(css_url) => css_url,
+        None => provider.await?.stylesheet_url(),
+    };
     let options = options.await?;
     Ok(Vc::cell(
         get_stylesheet_url(
-            css_url.as_deref().unwrap_or(GOOGLE_FONTS_STYLESHEET_URL),
+            &css_url,
             &options.font_family,
             &get_font_axes(
                 &*font_data.await?,
@@ -669,24 +696,95 @@ fn font_file_options_from_query_map(query: &RcStr) -> Result<NextFontGoogleFontF
     parse_json_with_source_context(&json)
 }
 
+enum FetchedStylesheet {
+    Ok(Vc<RcStr>),
+    /// The provider doesn't know the font family, or doesn't have it with the requested axes. An
+    /// issue has already been emitted.
+    NotServed,
+    Failed,
+}
+
+/// Google Fonts responds with 400 for unknown families and axes, a static mirror is more likely to
+/// respond with 404.
+fn is_not_served(kind: &FetchErrorKind) -> bool {
+    matches!(kind, FetchErrorKind::Status(400 | 404))
+}
+
+fn font_not_served_issue(
+    path: FileSystemPath,
+    font_family: &str,
+    stylesheet_url: &str,
+    next_mode: NextMode,
+) -> NextFontIssue {
+    let (severity, label) = match next_mode {
+        // Like a failed fetch, fail the build to ensure proper font rendering.
+        NextMode::Build => (IssueSeverity::Error, rcstr!(" error:")),
+        NextMode::Development => (IssueSeverity::Warning, rcstr!(" warning:")),
+    };
+    NextFontIssue {
+        path,
+        title: StyledString::Line(vec![
+            StyledString::Code(rcstr!("next/font:")),
+            StyledString::Text(label),
+        ])
+        .resolved_cell(),
+        description: StyledString::Text(
+            format!(
+                "The font provider doesn't serve `{font_family}` with the requested weights, \
+                 styles and axes (requested {stylesheet_url}). If a mirror of Google Fonts is \
+                 configured, make sure it includes them."
+            )
+            .into(),
+        )
+        .resolved_cell(),
+        severity,
+    }
+}
+
 async fn fetch_real_stylesheet(
     fetch_client: Vc<FetchClient>,
+    provider: Vc<GoogleFontsProvider>,
+    project_path: FileSystemPath,
     stylesheet_url: RcStr,
     css_virtual_path: FileSystemPath,
-) -> Result<Option<Vc<RcStr>>> {
-    let body = fetch_from_google_fonts(fetch_client, stylesheet_url, css_virtual_path).await?;
+    font_family: &str,
+    next_mode: Vc<NextMode>,
+) -> Result<FetchedStylesheet> {
+    let result =
+        fetch_with_cache(provider, fetch_client, project_path, stylesheet_url.clone()).await?;
 
-    Ok(body.map(|body| body.to_string()))
+    Ok(match *result {
+        Ok(r) => FetchedStylesheet::Ok(r.await?.body.to_string()),
+        Err(err) => {
+            if is_not_served(&*err.await?.kind.await?) {
+                font_not_served_issue(
+                    css_virtual_path,
+                    font_family,
+                    &stylesheet_url,
+                    *next_mode.await?,
+                )
+                .resolved_cell()
+                .emit();
+                return Ok(FetchedStylesheet::NotServed);
+            }
+            err.to_issue(IssueSeverity::Warning, css_virtual_path)
+                .to_resolved()
+                .await?
+                .emit();
+
+            FetchedStylesheet::Failed
+        }
+    })
 }
 
 async fn fetch_from_google_fonts(
     fetch_client: Vc<FetchClient>,
+    provider: Vc<GoogleFontsProvider>,
+    project_path: FileSystemPath,
     url: RcStr,
     virtual_path: FileSystemPath,
 ) -> Result<Option<Vc<HttpResponseBody>>> {
-    let result = fetch_client
-        .fetch(url, Some(rcstr!(USER_AGENT_FOR_GOOGLE_FONTS)))
-        .await?;
+    let result = fetch_with_cache(provider, fetch_client, project_path, url).await?;
 
     Ok(match *result {
         Ok(r) => Some(*r.await?.body),
@@ -705,7 +803,7 @@ async fn get_mock_stylesheet(
     stylesheet_url: RcStr,
     mocked_responses_path: &str,
     execution_context: Vc<ExecutionContext>,
-) -> Result<Option<Vc<RcStr>>> {
+) -> Result<FetchedStylesheet> {
     let response_path = Path::new(&mocked_responses_path);
     let mock_fs = Vc::upcast::<Box<dyn FileSystem>>(DiskFileSystem::new(
         rcstr!("mock"),
@@ -721,7 +819,8 @@ async fn get_mock_stylesheet(
         env,
         project_path: _,
         chunking_context,
-    } = *execution_context.await?;
+        node_pool_options,
+    } = &*execution_context.await?;
     let asset_context = node_evaluate_asset_context(
         execution_context,
         None,
@@ -754,14 +853,15 @@ async fn get_mock_stylesheet(
     let val = evaluate(
         mocked_response_asset,
         root,
-        *env,
+        **env,
         loader_source,
         asset_context,
-        *chunking_context,
+        **chunking_context,
         None,
         vec![],
         Completion::immutable(),
         should_debug("next_font::google"),
+        node_pool_options.clone(),
     )
     .await?;
 
@@ -769,14 +869,87 @@ async fn get_mock_stylesheet(
         SingleValue::Single(val) => {
             let val: FxHashMap<RcStr, Option<RcStr>> =
                 parse_json_with_source_context(val.to_str()?)?;
-            Ok(val
-                .get(&stylesheet_url)
-                .context("url not found")?
-                .clone()
-                .map(Vc::cell))
+            Ok(match val.get(&stylesheet_url).context("url not found")? {
+                Some(stylesheet) => FetchedStylesheet::Ok(Vc::cell(stylesheet.clone())),
+                None => FetchedStylesheet::Failed,
+            })
         }
-        _ => {
-            panic!("Unexpected error evaluating JS")
+        _ => bail!("Unexpected result evaluating the mocked responses"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+    use mockito::Matcher;
+    use turbo_rcstr::RcStr;
+    use turbo_tasks::{CollectiblesSource, TurboTasks, Vc};
+    use turbo_tasks_backend::{BackendOptions, TurboTasksBackend, noop_backing_storage};
+    use turbo_tasks_fetch::FetchClient;
+    use turbo_tasks_fs::{FileSystem, VirtualFileSystem};
+    use turbopack_core::issue::{Issue, IssueSeverity, StyledString};
+
+    use super::{FetchedStylesheet, fetch_real_stylesheet, provider::GoogleFontsProvider};
+    use crate::mode::NextMode;
+
+    #[turbo_tasks::function(operation)]
+    async fn fetch_stylesheet_operation(base_url: RcStr) -> Result<Vc<bool>> {
+        let provider = GoogleFontsProvider {
+            base_url: base_url.clone(),
+            cache_dir: None,
         }
+        .cell();
+        let root = VirtualFileSystem::new().root().owned().await?;
+        let stylesheet = fetch_real_stylesheet(
+            FetchClient::default().cell(),
+            provider,
+            root.clone(),
+            format!("{base_url}/css2?family=Unknown+Font").into(),
+            root.join("font.module.css")?,
+            "Unknown Font",
+            NextMode::Development.cell(),
+        )
+        .await?;
+        Ok(Vc::cell(matches!(stylesheet, FetchedStylesheet::NotServed)))
+    }
+
+    #[tokio::test]
+    async fn test_not_served_issue() {
+        crate::register();
+        let mut server = mockito::Server::new_async().await;
+        let stylesheet_mock = server
+            .mock("GET", "/css2")
+            .match_query(Matcher::Any)
+            .with_status(404)
+            .create_async()
+            .await;
+
+        let tt = TurboTasks::new(TurboTasksBackend::new(
+            BackendOptions::default(),
+            noop_backing_storage(),
+        ));
+        let base_url: RcStr = server.url().into();
+        tt.run_once(async move {
+            let operation = fetch_stylesheet_operation(base_url);
+            assert!(*operation.read_strongly_consistent().await?);
+
+            let issues = operation.peek_collectibles::<Box<dyn Issue>>();
+            assert_eq!(issues.len(), 1);
+            let issue = *issues.iter().next().unwrap();
+            assert_eq!(
+                issue.into_trait_ref().await?.severity(),
+                IssueSeverity::Warning
+            );
+            let description = (*issue.description().await?).unwrap().owned().await?;
+            let StyledString::Text(description) = description else {
+                panic!("expected a text description, got {description:?}");
+            };
+            assert!(description.contains("doesn't serve `Unknown Font`"));
+            anyhow::Ok(())
+        })
+        .await
+        .unwrap();
+
+        stylesheet_mock.assert_async().await;
     }
 }
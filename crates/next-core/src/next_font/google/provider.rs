@@ -0,0 +1,68 @@
+use anyhow::Result;
+use turbo_rcstr::{RcStr, rcstr};
+use turbo_tasks::Vc;
+use turbo_tasks_fetch::{FetchClient, FetchResult, HttpCache};
+use turbo_tasks_fs::{FileSystemPath, to_sys_path};
+
+use super::USER_AGENT_FOR_GOOGLE_FONTS;
+
+pub const GOOGLE_FONTS_BASE_URL: &str = "https://fonts.googleapis.com";
+
+/// Where `next/font/google` downloads stylesheets and font files from.
+///
+/// The base URL can point to a mirror of the Google Fonts API, e.g. a self-hosted one for
+/// air-gapped builds. With a cache directory, downloaded files are kept on disk and reused by
+/// later builds, also when the provider can't be reached.
+#[turbo_tasks::value(shared)]
+#[derive(Clone, Debug)]
+pub struct GoogleFontsProvider {
+    /// The URL that serves the `/css2` endpoint of the Google Fonts API.
+    pub base_url: RcStr,
+    /// The directory to keep downloaded files in, relative to the project directory.
+    pub cache_dir: Option<RcStr>,
+}
+
+impl Default for GoogleFontsProvider {
+    fn default() -> Self {
+        Self {
+            base_url: rcstr!(GOOGLE_FONTS_BASE_URL),
+            cache_dir: None,
+        }
+    }
+}
+
+impl GoogleFontsProvider {
+    pub fn stylesheet_url(&self) -> String {
+        format!("{}/css2", self.base_url.trim_end_matches('/'))
+    }
+}
+
+/// Fetches `url` like [FetchClient::fetch]. When the provider has a cache directory, responses
+/// are kept in an [HttpCache] there, which reuses them according to their caching headers and
+/// while the provider can't be reached.
+#[turbo_tasks::function]
+pub(super) async fn fetch_with_cache(
+    provider: Vc<GoogleFontsProvider>,
+    fetch_client: Vc<FetchClient>,
+    project_path: FileSystemPath,
+    url: RcStr,
+) -> Result<Vc<FetchResult>> {
+    let user_agent = Some(rcstr!(USER_AGENT_FOR_GOOGLE_FONTS));
+    let provider = provider.await?;
+    let Some(cache_dir) = &provider.cache_dir else {
+        return Ok(fetch_client.fetch(url, user_agent));
+    };
+    let Some(dir) = to_sys_path(project_path.join(cache_dir)?).await? else {
+        return Ok(fetch_client.fetch(url, user_agent));
+    };
+
+    Ok(fetch_client.fetch_cached(
+        url,
+        user_agent,
+        HttpCache {
+            dir: dir.to_string_lossy().into(),
+            offline_use_stale: true,
+        }
+        .cell(),
+    ))
+}
@@ -1107,6 +1107,7 @@ async fn insert_next_shared_aliases(
     );
 
     let fetch_client = next_config.fetch_client(execution_context.env());
+    let google_fonts_provider = next_config.google_fonts_provider(execution_context.env());
     import_map.insert_alias(
         AliasPattern::exact(rcstr!(
             "@vercel/turbopack-next/internal/font/google/cssmodule.module.css"
@@ -1117,6 +1118,7 @@ async fn insert_next_shared_aliases(
                 execution_context,
                 next_mode,
                 fetch_client,
+                google_fonts_provider,
             )
             .to_resolved()
             .await?,
@@ -1127,9 +1129,13 @@ async fn insert_next_shared_aliases(
     import_map.insert_alias(
         AliasPattern::exact(rcstr!(GOOGLE_FONTS_INTERNAL_PREFIX)),
         ImportMapping::Dynamic(ResolvedVc::upcast(
-            NextFontGoogleFontFileReplacer::new(project_path.clone(), fetch_client)
-                .to_resolved()
-                .await?,
+            NextFontGoogleFontFileReplacer::new(
+                project_path.clone(),
+                fetch_client,
+                google_fonts_provider,
+            )
+            .to_resolved()
+            .await?,
         ))
         .resolved_cell(),
     );
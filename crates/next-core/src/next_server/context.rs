@@ -6,7 +6,7 @@ use turbo_rcstr::{RcStr, rcstr};
 use turbo_tasks::{ResolvedVc, TaskInput, Vc, trace::TraceRawVcs};
 use turbo_tasks_fs::FileSystemPath;
 use turbopack::{
-    css::chunk::CssChunkType,
+    css::{CssModuleTypeDeclarations, chunk::CssChunkType},
     module_options::{
         CssOptionsContext, EcmascriptOptionsContext, ExternalsTracingOptions, JsxTransformOptions,
         ModuleOptionsContext, ModuleRule, TypeofWindow, TypescriptTransformOptions,
@@ -28,7 +28,10 @@ use turbopack_core::{
     module_graph::export_usage::OptionExportUsageInfo,
     target::CompileTarget,
 };
-use turbopack_ecmascript::{chunk::EcmascriptChunkType, references::esm::UrlRewriteBehavior};
+use turbopack_ecmascript::{
+    chunk::EcmascriptChunkType, references::esm::UrlRewriteBehavior,
+    well_known_packages::WellKnownPackages,
+};
 use turbopack_ecmascript_plugins::transform::directives::{
     client::ClientDirectiveTransformer, client_disallowed::ClientDisallowedDirectiveTransformer,
 };
@@ -571,21 +574,39 @@ pub async fn get_server_module_options_context(
     } else {
         SourceMapsType::None
     };
+    let well_known_packages = match &*next_config.turbopack_well_known_packages().await? {
+        Some(path) => Some(
+            WellKnownPackages::from_json(
+                project_path.join(path)?,
+                rcstr!("experimental.turbopackWellKnownPackages"),
+            )
+            .to_resolved()
+            .await?,
+        ),
+        None => None,
+    };
+    let module_type_declarations = next_config
+        .turbopack_css_module_types()
+        .await?
+        .then_some(CssModuleTypeDeclarations::NextToSource);
     let module_options_context = ModuleOptionsContext {
         ecmascript: EcmascriptOptionsContext {
             enable_typeof_window_inlining: Some(TypeofWindow::Undefined),
             import_externals: *next_config.import_externals().await?,
             ignore_dynamic_requests: true,
             source_maps,
+            well_known_packages,
             ..Default::default()
         },
         execution_context: Some(execution_context),
         environment: Some(environment),
         css: CssOptionsContext {
             source_maps,
+            module_type_declarations,
             ..Default::default()
         },
         tree_shaking_mode: tree_shaking_mode_for_user_code,
+        enable_import_queries: *next_config.turbopack_import_queries().await?,
         side_effect_free_packages: next_config.optimize_package_imports().owned().await?,
         enable_externals_tracing: if next_mode.is_production() {
             Some(
@@ -649,6 +670,10 @@ pub async fn get_server_module_options_context(
                 // NOTE(WEB-1016) PostCSS transforms should also apply to foreign code.
                 enable_postcss_transform: enable_foreign_postcss_transform,
                 tree_shaking_mode: tree_shaking_mode_for_foreign_code,
+                css: CssOptionsContext {
+                    module_type_declarations: None,
+                    ..module_options_context.css.clone()
+                },
                 ..module_options_context.clone()
             };
 
@@ -712,6 +737,10 @@ pub async fn get_server_module_options_context(
                 // NOTE(WEB-1016) PostCSS transforms should also apply to foreign code.
                 enable_postcss_transform: enable_foreign_postcss_transform,
                 tree_shaking_mode: tree_shaking_mode_for_foreign_code,
+                css: CssOptionsContext {
+                    module_type_declarations: None,
+                    ..module_options_context.css.clone()
+                },
                 ..module_options_context.clone()
             };
             let internal_module_options_context = ModuleOptionsContext {
@@ -789,6 +818,10 @@ pub async fn get_server_module_options_context(
                 // NOTE(WEB-1016) PostCSS transforms should also apply to foreign code.
                 enable_postcss_transform: enable_foreign_postcss_transform,
                 tree_shaking_mode: tree_shaking_mode_for_foreign_code,
+                css: CssOptionsContext {
+                    module_type_declarations: None,
+                    ..module_options_context.css.clone()
+                },
                 ..module_options_context.clone()
             };
             let internal_module_options_context = ModuleOptionsContext {
@@ -864,6 +897,10 @@ pub async fn get_server_module_options_context(
                 // NOTE(WEB-1016) PostCSS transforms should also apply to foreign code.
                 enable_postcss_transform: enable_foreign_postcss_transform,
                 tree_shaking_mode: tree_shaking_mode_for_foreign_code,
+                css: CssOptionsContext {
+                    module_type_declarations: None,
+                    ..module_options_context.css.clone()
+                },
                 ..module_options_context.clone()
             };
             let internal_module_options_context = ModuleOptionsContext {
@@ -956,6 +993,10 @@ pub async fn get_server_module_options_context(
                 // NOTE(WEB-1016) PostCSS transforms should also apply to foreign code.
                 enable_postcss_transform: enable_foreign_postcss_transform,
                 tree_shaking_mode: tree_shaking_mode_for_foreign_code,
+                css: CssOptionsContext {
+                    module_type_declarations: None,
+                    ..module_options_context.css.clone()
+                },
                 ..module_options_context.clone()
             };
             let internal_module_options_context = ModuleOptionsContext {
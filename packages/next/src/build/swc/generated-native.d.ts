@@ -311,6 +311,28 @@ export interface NapiUpdateMessage {
 export interface NapiUpdateInfo {
   duration: number
   tasks: number
+  /** How the update propagated. Only available when subscribed with `trace` enabled. */
+  trace?: NapiUpdateTrace
+}
+export interface NapiUpdateTrace {
+  invalidations: Array<NapiTracedInvalidation>
+  executions: Array<NapiTracedExecution>
+  changedCells: Array<NapiTracedCell>
+  changedOutputs: Array<string>
+}
+export interface NapiTracedInvalidation {
+  cause: string
+  tasks: Array<string>
+}
+export interface NapiTracedExecution {
+  task: string
+  /** The duration in microseconds. */
+  duration: number
+}
+export interface NapiTracedCell {
+  task: string
+  /** The value type and index of the cell, e.g. `u32#0`. */
+  cell: string
 }
 /**
  * Subscribes to lifecycle events of the compilation.
@@ -323,12 +345,18 @@ export interface NapiUpdateInfo {
  * (excluding the idle time that was spend waiting for `aggregation_ms`), and
  * the number of tasks that were executed.
  *
+ * With `trace` enabled, the [UpdateMessage::End] event also describes how the update propagated:
+ * the file changes and changed tasks that invalidated other tasks, the executed tasks and the
+ * output files that changed. This is also written to the trace output. Tracing is disabled again
+ * when the subscription ends.
+ *
  * The signature of the `func` is `(update_message: UpdateMessage) => void`.
  */
 export declare function projectUpdateInfoSubscribe(
   project: { __napiType: 'Project' },
   aggregationMs: number,
-  func: (...args: any[]) => any
+  func: (...args: any[]) => any,
+  trace?: boolean | undefined | null
 ): void
 /** Subscribes to all compilation events that are not cached like timing and progress information. */
 export declare function projectCompilationEventsSubscribe(
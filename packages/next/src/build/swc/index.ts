@@ -719,12 +719,13 @@ function bindingToApi(
       return binding.projectGetSourceMapSync(this._nativeProject, filePath)
     }
 
-    updateInfoSubscribe(aggregationMs: number) {
+    updateInfoSubscribe(aggregationMs: number, trace?: boolean) {
       return subscribe<TurbopackResult<UpdateMessage>>(true, async (callback) =>
         binding.projectUpdateInfoSubscribe(
           this._nativeProject,
           aggregationMs,
-          callback
+          callback,
+          trace
         )
       )
     }
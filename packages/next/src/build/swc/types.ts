@@ -215,6 +215,18 @@ export type CompilationEvent = {
 export interface UpdateInfo {
   duration: number
   tasks: number
+  trace?: UpdateTrace
+}
+
+export interface UpdateTrace {
+  /** The changes that made tasks dirty, in the order they happened. */
+  invalidations: { cause: string; tasks: string[] }[]
+  /** The executed tasks, with their duration in microseconds. */
+  executions: { task: string; duration: number }[]
+  /** The cells that were updated with a different value, e.g. `u32#0`. */
+  changedCells: { task: string; cell: string }[]
+  /** The output files that changed. */
+  changedOutputs: string[]
 }
 
 export interface Project {
@@ -243,7 +255,8 @@ export interface Project {
   ): Promise<TurbopackStackFrame | null>
 
   updateInfoSubscribe(
-    aggregationMs: number
+    aggregationMs: number,
+    trace?: boolean
   ): AsyncIterableIterator<TurbopackResult<UpdateMessage>>
 
   compilationEventsSubscribe(
@@ -146,7 +146,11 @@ const zTurbopackConfig: zod.ZodType<TurbopackOptions> = z.strictObject({
     )
     .optional(),
   resolveExtensions: z.array(z.string()).optional(),
-  moduleIds: z.enum(['named', 'deterministic']).optional(),
+  moduleIds: z.enum(['named', 'deterministic', 'hashed']).optional(),
+  chunkingStrategy: z
+    .enum(['default', 'vendorPerPackage', 'routeGroups'])
+    .optional(),
+  maxParallelRequests: z.number().int().nonnegative().optional(),
   root: z.string().optional(),
 })
 
@@ -170,7 +174,7 @@ const zDeprecatedExperimentalTurboConfig: zod.ZodType<DeprecatedExperimentalTurb
     treeShaking: z.boolean().optional(),
     persistentCaching: z.union([z.number(), z.literal(false)]).optional(),
     memoryLimit: z.number().optional(),
-    moduleIds: z.enum(['named', 'deterministic']).optional(),
+    moduleIds: z.enum(['named', 'deterministic', 'hashed']).optional(),
     minify: z.boolean().optional(),
     sourceMaps: z.boolean().optional(),
     root: z.string().optional(),
@@ -463,6 +467,10 @@ export const configSchema: zod.ZodType<NextConfig> = z.lazy(() =>
         turbopackSourceMaps: z.boolean().optional(),
         turbopackTreeShaking: z.boolean().optional(),
         turbopackRemoveUnusedExports: z.boolean().optional(),
+        turbopackCssModuleTypes: z.boolean().optional(),
+        turbopackUnusedCssModuleClasses: z.enum(['report', 'remove']).optional(),
+        turbopackImportQueries: z.boolean().optional(),
+        turbopackWellKnownPackages: z.string().optional(),
         turbopackScopeHoisting: z.boolean().optional(),
         /**
          * Use the system-provided CA roots instead of bundled CA roots for external HTTPS requests
@@ -488,6 +496,18 @@ export const configSchema: zod.ZodType<NextConfig> = z.lazy(() =>
          * proxies are supported, SOCKS proxies are not currently supported.
          */
         turbopackUseSystemTlsCerts: z.boolean().optional(),
+        /**
+         * The base URL of the Google Fonts API used by `next/font/google`. Can be overridden
+         * system-wide using the `NEXT_TURBOPACK_EXPERIMENTAL_GOOGLE_FONTS_BASE_URL` environment
+         * variable.
+         */
+        turbopackGoogleFontsBaseUrl: z.string().optional(),
+        /**
+         * A directory to keep the files downloaded by `next/font/google` in. Can be overridden
+         * system-wide using the `NEXT_TURBOPACK_EXPERIMENTAL_GOOGLE_FONTS_CACHE_DIR` environment
+         * variable.
+         */
+        turbopackGoogleFontsCacheDir: z.string().optional(),
         optimizePackageImports: z.array(z.string()).optional(),
         optimizeServerReact: z.boolean().optional(),
         clientTraceMetadata: z.array(z.string()).optional(),
@@ -303,9 +303,25 @@ export interface TurbopackOptions {
   /**
    * The module ID strategy to use for Turbopack.
    * If not set, the default is `'named'` for development and `'deterministic'`
-   * for production.
+   * for production. `'hashed'` derives short ids from a hash of the module
+   * path and keeps them stable across builds.
    */
-  moduleIds?: 'named' | 'deterministic'
+  moduleIds?: 'named' | 'deterministic' | 'hashed'
+
+  /**
+   * How client chunks are split in production, in addition to the pages and
+   * layouts that need them. `'vendorPerPackage'` puts every npm package into
+   * separate chunks, `'routeGroups'` puts the code of every route group into
+   * separate chunks. Defaults to `'default'`.
+   */
+  chunkingStrategy?: 'default' | 'vendorPerPackage' | 'routeGroups'
+
+  /**
+   * Limits the number of client chunks that a page requests in parallel in
+   * production. Chunks needed by the same pages are merged until the limit is
+   * met. Defaults to no limit.
+   */
+  maxParallelRequests?: number
 
   /**
    * This is the repo root usually and only files above this
@@ -578,6 +594,45 @@ export interface ExperimentalConfig {
    */
   turbopackRemoveUnusedExports?: boolean
 
+  /**
+   * Emit a `.d.ts` file next to every CSS Module, declaring its exported classes, so TypeScript
+   * can check their usage. Only supported by Turbopack.
+   */
+  turbopackCssModuleTypes?: boolean
+
+  /**
+   * Report the classes of CSS Modules that no importing module reads as warnings. With `'remove'`,
+   * the rules that only match those classes are also left out of the CSS. Only supported by
+   * Turbopack, and requires `turbopackRemoveUnusedExports`, which is enabled by default for
+   * production builds.
+   */
+  turbopackUnusedCssModuleClasses?: 'report' | 'remove'
+
+  /**
+   * Handle the Vite-style `?raw`, `?url` and `?worker` import queries, which import the content
+   * of a file as a string, its URL, or a worker that runs it. Only supported by Turbopack.
+   */
+  turbopackImportQueries?: boolean
+
+  /**
+   * A JSON file, relative to the project directory, that describes packages whose exports load
+   * files at runtime (like `bindings`), so Turbopack includes the loaded files in the server output.
+   */
+  turbopackWellKnownPackages?: string
+
+  /**
+   * The base URL of the Google Fonts API used by `next/font/google` with Turbopack, e.g. a
+   * self-hosted mirror for builds without internet access. Defaults to
+   * `https://fonts.googleapis.com`.
+   */
+  turbopackGoogleFontsBaseUrl?: string
+
+  /**
+   * A directory, relative to the project directory, in which Turbopack keeps the stylesheets and
+   * font files downloaded by `next/font/google`, so later builds can reuse them.
+   */
+  turbopackGoogleFontsCacheDir?: string
+
   /**
    * For use with `@next/mdx`. Compile MDX files using the new Rust compiler.
    * @see https://nextjs.org/docs/app/api-reference/next-config-js/mdxRs
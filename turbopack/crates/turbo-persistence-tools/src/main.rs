@@ -1,22 +1,205 @@
 #![feature(iter_intersperse)]
 
-use std::path::PathBuf;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fs::read_dir,
+    path::{Path, PathBuf},
+};
 
 use anyhow::{Context, Result, bail};
-use turbo_persistence::{MetaFileEntryInfo, TurboPersistence};
+use clap::{Parser, ValueEnum};
+use turbo_persistence::{EntryValueInfo, MetaFileEntryInfo, TurboPersistence, VerifyReport};
+use turbo_tasks_backend::{
+    COMPACT_CONFIG, GitVersionInfo, SnapshotManifest, export_snapshot, import_snapshot,
+};
+
+#[derive(Debug, Parser)]
+#[clap(author, version, about, long_about = None)]
+enum Command {
+    /// Prints the meta and SST file layout of a database.
+    Info {
+        /// The TurboPersistence directory.
+        path: PathBuf,
+    },
+    /// Looks up a key in a family and prints its value.
+    Lookup {
+        /// The TurboPersistence directory.
+        path: PathBuf,
+        family: u32,
+        key: String,
+        /// How the key is given on the command line.
+        #[clap(long, value_enum, default_value_t = KeyFormat::Hex)]
+        key_format: KeyFormat,
+    },
+    /// Iterates all entries of a family and prints statistics about keys and values.
+    Dump {
+        /// The TurboPersistence directory.
+        path: PathBuf,
+        family: u32,
+        /// Print every entry, up to this many.
+        #[clap(long, default_value_t = 0)]
+        limit: usize,
+    },
+    /// Decodes every block of every SST file and checks keys, hashes and AMQF filters.
+    Verify {
+        /// The TurboPersistence directory.
+        path: PathBuf,
+    },
+    /// Checks meta files against their SST files and rolls back broken or incomplete commits.
+    Repair {
+        /// The TurboPersistence directory.
+        path: PathBuf,
+    },
+    /// Reports how fragmented each family is and which merge jobs a compaction would run.
+    Compaction {
+        /// The TurboPersistence directory.
+        path: PathBuf,
+    },
+    /// Compacts a database and writes it into a single verified archive.
+    Export {
+        /// The archive to write.
+        output: PathBuf,
+        /// The persistent cache directory containing the version directories.
+        #[clap(long, default_value = ".next/cache/turbopack")]
+        cache_dir: PathBuf,
+        /// The database version to export. Defaults to the only version in the cache directory.
+        #[clap(long)]
+        db_version: Option<String>,
+    },
+    /// Verifies an archive and unpacks it into the persistent cache directory.
+    Import {
+        /// The archive to read.
+        archive: PathBuf,
+        /// The persistent cache directory containing the version directories.
+        #[clap(long, default_value = ".next/cache/turbopack")]
+        cache_dir: PathBuf,
+        /// The expected database version. Defaults to the version of the archive.
+        #[clap(long)]
+        db_version: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy, Debug, ValueEnum)]
+enum KeyFormat {
+    /// Hex encoded bytes, e.g. `0000002a`.
+    Hex,
+    /// UTF-8 bytes of the argument.
+    Utf8,
+    /// A big endian u32, e.g. a task id.
+    U32,
+}
 
 fn main() -> Result<()> {
-    // Get CLI argument
-    let path = PathBuf::from(
-        std::env::args()
-            .nth(1)
-            .context("Please provide a path to the TurboPersistence directory")?,
+    match Command::parse() {
+        Command::Info { path } => info(path),
+        Command::Lookup {
+            path,
+            family,
+            key,
+            key_format,
+        } => lookup(path, family, &key, key_format),
+        Command::Dump {
+            path,
+            family,
+            limit,
+        } => dump(path, family, limit),
+        Command::Verify { path } => verify(path),
+        Command::Repair { path } => repair(path),
+        Command::Compaction { path } => compaction(path),
+        Command::Export {
+            output,
+            cache_dir,
+            db_version,
+        } => {
+            let db_version = match db_version {
+                Some(db_version) => db_version,
+                None => single_db_version(&cache_dir)?,
+            };
+            let manifest = export_snapshot(
+                &cache_dir,
+                &GitVersionInfo {
+                    describe: &db_version,
+                    dirty: false,
+                },
+                &output,
+            )?;
+            print_manifest(&manifest);
+            Ok(())
+        }
+        Command::Import {
+            archive,
+            cache_dir,
+            db_version,
+        } => {
+            let db_version = match db_version {
+                Some(db_version) => db_version,
+                None => turbo_tasks_backend::read_snapshot_manifest(&archive)?.version,
+            };
+            let manifest = import_snapshot(
+                &archive,
+                &cache_dir,
+                &GitVersionInfo {
+                    describe: &db_version,
+                    dirty: false,
+                },
+            )?;
+            print_manifest(&manifest);
+            Ok(())
+        }
+    }
+}
+
+/// Returns the name of the only database version directory in `cache_dir`.
+fn single_db_version(cache_dir: &Path) -> Result<String> {
+    let mut versions = Vec::new();
+    for entry in
+        read_dir(cache_dir).with_context(|| format!("Failed to read {}", cache_dir.display()))?
+    {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if entry.file_type()?.is_dir() && !name.starts_with("__stale_") && name != "temp" {
+            versions.push(name);
+        }
+    }
+    match <[String; 1]>::try_from(versions) {
+        Ok([version]) => Ok(version),
+        Err(versions) if versions.is_empty() => {
+            bail!("There is no database in {}", cache_dir.display())
+        }
+        Err(versions) => bail!(
+            "There are multiple databases in {}, use --db-version to select one of: {}",
+            cache_dir.display(),
+            versions.join(", ")
+        ),
+    }
+}
+
+fn print_manifest(manifest: &SnapshotManifest) {
+    println!(
+        "version = {}, git = {}, files = {}",
+        manifest.version,
+        manifest.git_describe,
+        manifest.files.len()
     );
+    for (family, info) in &manifest.families {
+        println!(
+            "  family {family}: {} entries in {} SSTs = {} KiB",
+            info.entries,
+            info.sst_files,
+            info.sst_size / 1024
+        );
+    }
+}
+
+fn open_read_only(path: PathBuf) -> Result<TurboPersistence> {
     if !path.exists() {
         bail!("The provided path does not exist: {}", path.display());
     }
+    TurboPersistence::open_read_only(path)
+}
 
-    let db = TurboPersistence::open_read_only(path)?;
+fn info(path: PathBuf) -> Result<()> {
+    let db = open_read_only(path)?;
     let meta_info = db
         .meta_info()
         .context("Failed to retrieve meta information")?;
@@ -70,3 +253,197 @@ fn main() -> Result<()> {
     }
     Ok(())
 }
+
+fn parse_key(key: &str, format: KeyFormat) -> Result<Vec<u8>> {
+    Ok(match format {
+        KeyFormat::Hex => {
+            if key.len() % 2 != 0 {
+                bail!("A hex key must have an even number of digits");
+            }
+            (0..key.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&key[i..i + 2], 16))
+                .collect::<Result<_, _>>()
+                .with_context(|| format!("Invalid hex key {key}"))?
+        }
+        KeyFormat::Utf8 => key.as_bytes().to_vec(),
+        KeyFormat::U32 => key
+            .parse::<u32>()
+            .with_context(|| format!("Invalid u32 key {key}"))?
+            .to_be_bytes()
+            .to_vec(),
+    })
+}
+
+fn format_bytes(bytes: &[u8]) -> String {
+    const MAX_PRINTED_BYTES: usize = 64;
+    let mut s = bytes
+        .iter()
+        .take(MAX_PRINTED_BYTES)
+        .map(|b| format!("{b:02x}"))
+        .collect::<String>();
+    if bytes.len() > MAX_PRINTED_BYTES {
+        s.push_str(&format!("... ({} bytes)", bytes.len()));
+    }
+    s
+}
+
+fn lookup(path: PathBuf, family: u32, key: &str, key_format: KeyFormat) -> Result<()> {
+    let db = open_read_only(path)?;
+    let key = parse_key(key, key_format)?;
+    match db.get(family as usize, &key)? {
+        Some(value) => println!("{} bytes: {}", value.len(), format_bytes(&value)),
+        None => println!("Not found"),
+    }
+    Ok(())
+}
+
+/// A histogram with power of two buckets.
+#[derive(Default)]
+struct Histogram {
+    buckets: BTreeMap<u32, u64>,
+    total: u64,
+}
+
+impl Histogram {
+    fn add(&mut self, size: u64) {
+        *self
+            .buckets
+            .entry(u64::BITS - size.leading_zeros())
+            .or_default() += 1;
+        self.total += size;
+    }
+
+    fn print(&self, name: &str) {
+        let count = self.buckets.values().sum::<u64>();
+        if count == 0 {
+            return;
+        }
+        println!(
+            "{name}: {count} entries, {} KiB total, avg {} bytes",
+            self.total / 1024,
+            self.total / count
+        );
+        let max = self.buckets.values().copied().max().unwrap_or(1);
+        for (&bits, &bucket_count) in &self.buckets {
+            let (from, to) = match bits {
+                0 => (0, 0),
+                bits => (1u64 << (bits - 1), (1u64 << (bits - 1)) * 2 - 1),
+            };
+            println!(
+                "  {from:>10} - {to:>10}: {bucket_count:>10} {}",
+                "#".repeat((bucket_count * 40).div_ceil(max) as usize)
+            );
+        }
+    }
+}
+
+fn dump(path: PathBuf, family: u32, limit: usize) -> Result<()> {
+    let db = open_read_only(path)?;
+    let mut seen_keys = HashSet::new();
+    let mut printed = 0;
+    let mut shadowed = 0u64;
+    let mut deleted = 0u64;
+    let mut keys = Histogram::default();
+    let mut inline_values = Histogram::default();
+    let mut blob_values = Histogram::default();
+    db.for_each_entry(family, |entry| {
+        if printed < limit {
+            printed += 1;
+            let value = match &entry.value {
+                EntryValueInfo::Deleted => "deleted".to_string(),
+                EntryValueInfo::Inline { value } => format_bytes(value),
+                EntryValueInfo::Blob { sequence_number } => format!("{sequence_number:08}.blob"),
+            };
+            println!(
+                "{:08}.sst {:016x} {} = {value}",
+                entry.sst_sequence_number,
+                entry.hash,
+                format_bytes(&entry.key)
+            );
+        }
+        // Entries are reported newest first, so later occurrences of a key are outdated.
+        if !seen_keys.insert(entry.key.to_vec()) {
+            shadowed += 1;
+            return Ok(());
+        }
+        keys.add(entry.key.len() as u64);
+        match entry.value {
+            EntryValueInfo::Deleted => deleted += 1,
+            EntryValueInfo::Inline { value } => inline_values.add(value.len() as u64),
+            EntryValueInfo::Blob { sequence_number } => {
+                blob_values.add(db.blob_size(sequence_number)?)
+            }
+        }
+        Ok(())
+    })?;
+    println!(
+        "family {family}: {} keys, {deleted} deleted, {shadowed} outdated entries",
+        seen_keys.len()
+    );
+    keys.print("Keys");
+    inline_values.print("Inline values");
+    blob_values.print("Blob values");
+    Ok(())
+}
+
+fn verify(path: PathBuf) -> Result<()> {
+    let db = open_read_only(path)?;
+    let VerifyReport {
+        sst_files,
+        blob_files,
+        entries,
+        errors,
+    } = db.verify()?;
+    println!("Checked {sst_files} SST files with {entries} entries and {blob_files} blob files");
+    for error in &errors {
+        println!("  ERROR {error}");
+    }
+    if !errors.is_empty() {
+        bail!("{} SST files are corrupted", errors.len());
+    }
+    Ok(())
+}
+
+fn repair(path: PathBuf) -> Result<()> {
+    if !path.exists() {
+        bail!("The provided path does not exist: {}", path.display());
+    }
+    let (db, report) = TurboPersistence::open_with_repair(path)?;
+    if report.is_empty() {
+        println!("The database is consistent");
+    } else {
+        print!("{report}");
+    }
+    db.shutdown()
+}
+
+fn compaction(path: PathBuf) -> Result<()> {
+    let db = open_read_only(path)?;
+    // The same settings as the compaction of the backend, to show what it would do.
+    for family in db.compaction_report(&COMPACT_CONFIG) {
+        println!(
+            "family {}: {} SSTs = {} MiB, coverage = {:.2}, overlap = {:.2}, duplication = {:.2} \
+             ({} MiB)",
+            family.family,
+            family.sst_files,
+            family.size / 1024 / 1024,
+            family.coverage,
+            family.overlap,
+            family.duplication,
+            family.duplicated_size / 1024 / 1024
+        );
+        for job in family.merge_jobs {
+            println!(
+                "  merge {} MiB: {}",
+                job.size / 1024 / 1024,
+                job.sst_files
+                    .iter()
+                    .map(|seq| format!("{seq:08}.sst"))
+                    .intersperse(", ".to_string())
+                    .collect::<String>()
+            );
+        }
+    }
+    Ok(())
+}
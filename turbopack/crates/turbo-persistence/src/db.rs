@@ -5,6 +5,7 @@ use std::{
     io::{BufWriter, Write},
     mem::{MaybeUninit, swap, transmute},
     ops::RangeInclusive,
+    panic::{AssertUnwindSafe, catch_unwind},
     path::{Path, PathBuf},
     sync::{
         Arc,
@@ -34,8 +35,9 @@ use crate::{
     key::{StoreKey, hash_key},
     lookup_entry::{LookupEntry, LookupValue},
     merge_iter::MergeIter,
-    meta_file::{AmqfCache, MetaFile, MetaLookupResult, StaticSortedFileRange},
+    meta_file::{AmqfCache, MetaEntry, MetaFile, MetaLookupResult, StaticSortedFileRange},
     meta_file_builder::MetaFileBuilder,
+    repair::{RepairReport, repair_directory},
     sst_filter::SstFilter,
     static_sorted_file::{BlockCache, SstLookupResult},
     static_sorted_file_builder::{StaticSortedFileBuilderMeta, write_static_stored_file},
@@ -194,6 +196,20 @@ impl TurboPersistence {
         Ok(db)
     }
 
+    /// Open a TurboPersistence database at the given path and repair it if necessary, e.g. when the
+    /// process was killed during a commit. This checks all meta files against their SST files and
+    /// rolls back broken commits, so it's slower than [`TurboPersistence::open`]. The returned
+    /// report lists the discarded files.
+    pub fn open_with_repair(path: PathBuf) -> Result<(Self, RepairReport)> {
+        let report = if fs::exists(&path)? {
+            repair_directory(&path).context("Repairing persistence directory failed")?
+        } else {
+            RepairReport::default()
+        };
+        let db = Self::open(path)?;
+        Ok((db, report))
+    }
+
     /// Open a TurboPersistence database at the given path in read only mode.
     /// This will read the directory. No Cleanup is performed.
     pub fn open_read_only(path: PathBuf) -> Result<Self> {
@@ -1216,8 +1232,7 @@ impl TurboPersistence {
     }
 
     pub fn meta_info(&self) -> Result<Vec<MetaFileInfo>> {
-        Ok(self
-            .inner
+        self.inner
             .read()
             .meta_files
             .iter()
@@ -1227,30 +1242,234 @@ impl TurboPersistence {
                     .entries()
                     .iter()
                     .map(|entry| {
-                        let amqf = entry.raw_amqf(meta_file.amqf_data());
-                        MetaFileEntryInfo {
+                        let amqf = entry.deserialize_amqf(meta_file)?;
+                        Ok(MetaFileEntryInfo {
                             sequence_number: entry.sequence_number(),
                             min_hash: entry.min_hash(),
                             max_hash: entry.max_hash(),
                             sst_size: entry.size(),
                             amqf_size: entry.amqf_size(),
-                            amqf_entries: amqf.len(),
+                            amqf_entries: amqf.len() as usize,
                             key_compression_dictionary_size: entry
                                 .key_compression_dictionary_length(),
                             value_compression_dictionary_size: entry
                                 .value_compression_dictionary_length(),
                             block_count: entry.block_count(),
-                        }
+                        })
                     })
-                    .collect();
-                MetaFileInfo {
+                    .collect::<Result<_>>()?;
+                Ok(MetaFileInfo {
                     sequence_number: meta_file.sequence_number(),
                     family: meta_file.family(),
                     obsolete_sst_files: meta_file.obsolete_sst_files().to_vec(),
                     entries,
+                })
+            })
+            .collect()
+    }
+
+    /// Calls `f` for every entry stored in the SST files of `family`, newest SST file first.
+    ///
+    /// This reports the raw SST contents, so keys that were overwritten or deleted in a newer SST
+    /// file are reported multiple times. Values stored in blob files are not read, use
+    /// [`TurboPersistence::blob_size`] or [`TurboPersistence::read_blob_value`] for that.
+    pub fn for_each_entry(
+        &self,
+        family: u32,
+        mut f: impl FnMut(EntryInfo) -> Result<()>,
+    ) -> Result<()> {
+        let inner = self.inner.read();
+        for meta in inner.meta_files.iter().rev() {
+            if meta.family() != family {
+                continue;
+            }
+            for entry in meta.entries().iter().rev() {
+                let sst_sequence_number = entry.sequence_number();
+                let iter = entry
+                    .sst(meta)?
+                    .iter(&self.key_block_cache, &self.value_block_cache)?;
+                for lookup_entry in iter {
+                    let LookupEntry { hash, key, value } = lookup_entry
+                        .with_context(|| format!("Failed to read {sst_sequence_number:08}.sst"))?;
+                    f(EntryInfo {
+                        sst_sequence_number,
+                        hash,
+                        key,
+                        value: match value {
+                            LookupValue::Deleted => EntryValueInfo::Deleted,
+                            LookupValue::Slice { value } => EntryValueInfo::Inline { value },
+                            LookupValue::Blob { sequence_number } => {
+                                EntryValueInfo::Blob { sequence_number }
+                            }
+                        },
+                    })?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the uncompressed size of a blob file without decompressing it.
+    pub fn blob_size(&self, sequence_number: u32) -> Result<u64> {
+        let path = self.path.join(format!("{sequence_number:08}.blob"));
+        let mut file = File::open(&path).with_context(|| format!("Failed to open {path:?}"))?;
+        Ok(file.read_u32::<BE>()? as u64)
+    }
+
+    /// Reads and decompresses the value stored in a blob file.
+    pub fn read_blob_value(&self, sequence_number: u32) -> Result<ArcSlice<u8>> {
+        self.read_blob(sequence_number)
+    }
+
+    /// Reads every block of every SST file and checks the file structure: keys must be sorted,
+    /// within the hash range of the meta file, match their hash and be contained in the AMQF.
+    /// Referenced blob files must exist and decompress.
+    ///
+    /// The file format has no checksums, so this detects corruption that breaks decompression or
+    /// the structure of the data, but not every flipped bit in a value.
+    pub fn verify(&self) -> Result<VerifyReport> {
+        let inner = self.inner.read();
+        let mut report = VerifyReport::default();
+        let mut blob_files = HashSet::new();
+        for meta in inner.meta_files.iter() {
+            for entry in meta.entries() {
+                report.sst_files += 1;
+                let sst_name = format!("{:08}.sst", entry.sequence_number());
+                // A corrupted file might trip index bounds checks while decoding.
+                let result = catch_unwind(AssertUnwindSafe(|| {
+                    self.verify_sst(meta, entry, &mut report, &mut blob_files)
+                }));
+                match result {
+                    Ok(Ok(())) => {}
+                    Ok(Err(err)) => report.errors.push(format!("{sst_name}: {err:#}")),
+                    Err(_) => report
+                        .errors
+                        .push(format!("{sst_name}: panicked while decoding")),
+                }
+            }
+        }
+        report.blob_files = blob_files.len();
+        Ok(report)
+    }
+
+    fn verify_sst(
+        &self,
+        meta: &MetaFile,
+        entry: &MetaEntry,
+        report: &mut VerifyReport,
+        blob_files: &mut HashSet<u32>,
+    ) -> Result<()> {
+        let sst_path = self
+            .path
+            .join(format!("{:08}.sst", entry.sequence_number()));
+        let file_size = fs::metadata(&sst_path)?.len();
+        if file_size != entry.size() {
+            bail!(
+                "File size is {file_size} bytes, but the meta file lists {} bytes",
+                entry.size()
+            );
+        }
+        let amqf = entry.deserialize_amqf(meta)?;
+        let mut previous_hash = None;
+        for lookup_entry in entry
+            .sst(meta)?
+            .iter(&self.key_block_cache, &self.value_block_cache)?
+        {
+            let LookupEntry { hash, key, value } = lookup_entry?;
+            report.entries += 1;
+            if hash < entry.min_hash() || hash > entry.max_hash() {
+                bail!(
+                    "Key hash {hash:016x} is outside of the range {:016x} - {:016x}",
+                    entry.min_hash(),
+                    entry.max_hash()
+                );
+            }
+            if previous_hash.is_some_and(|previous| previous > hash) {
+                bail!("Keys are not sorted by hash at {hash:016x}");
+            }
+            previous_hash = Some(hash);
+            if hash_key(&&*key) != hash {
+                bail!("Key {:02x?} doesn't match its hash {hash:016x}", &*key);
+            }
+            if !amqf.contains_fingerprint(hash) {
+                bail!("Key hash {hash:016x} is missing in the AMQF");
+            }
+            // Count and read every blob file once, even when multiple entries refer to it.
+            if let LookupValue::Blob { sequence_number } = value
+                && blob_files.insert(sequence_number)
+            {
+                self.read_blob(sequence_number)
+                    .with_context(|| format!("Failed to read {sequence_number:08}.blob"))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Computes the compaction metrics of every family and the merge jobs that a compaction with
+    /// the given `compact_config` would run. This doesn't modify the database.
+    pub fn compaction_report(&self, compact_config: &CompactConfig) -> Vec<FamilyCompactionReport> {
+        struct SstWithRange {
+            seq: u32,
+            range: RangeInclusive<u64>,
+            size: u64,
+        }
+
+        impl Compactable for SstWithRange {
+            fn range(&self) -> RangeInclusive<u64> {
+                self.range.clone()
+            }
+
+            fn size(&self) -> u64 {
+                self.size
+            }
+        }
+
+        let inner = self.inner.read();
+        let mut sst_by_family = Vec::<Vec<SstWithRange>>::new();
+        for meta in inner.meta_files.iter() {
+            let family = meta.family() as usize;
+            if sst_by_family.len() <= family {
+                sst_by_family.resize_with(family + 1, Vec::new);
+            }
+            sst_by_family[family].extend(meta.entries().iter().map(|entry| SstWithRange {
+                seq: entry.sequence_number(),
+                range: entry.min_hash()..=entry.max_hash(),
+                size: entry.size(),
+            }));
+        }
+
+        // Same as in `compact_internal`, the merge segment budget is shared by all families
+        let mut compact_config = compact_config.clone();
+        sst_by_family
+            .into_iter()
+            .enumerate()
+            .filter(|(_, ssts)| !ssts.is_empty())
+            .map(|(family, ssts)| {
+                let metrics = compute_metrics(&ssts, 0..=u64::MAX);
+                let merge_jobs = if compact_config.max_merge_segment_count == 0 {
+                    Vec::new()
+                } else {
+                    get_merge_segments(&ssts, &compact_config)
+                };
+                compact_config.max_merge_segment_count -= merge_jobs.len();
+                FamilyCompactionReport {
+                    family: family as u32,
+                    sst_files: ssts.len(),
+                    size: ssts.iter().map(|s| s.size).sum(),
+                    coverage: metrics.coverage,
+                    overlap: metrics.overlap,
+                    duplicated_size: metrics.duplicated_size,
+                    duplication: metrics.duplication,
+                    merge_jobs: merge_jobs
+                        .into_iter()
+                        .map(|job| MergeJobInfo {
+                            sst_files: job.iter().map(|&i| ssts[i].seq).collect(),
+                            size: job.iter().map(|&i| ssts[i].size).sum(),
+                        })
+                        .collect(),
                 }
             })
-            .collect())
+            .collect()
     }
 
     /// Shuts down the database. This will print statistics if the `print_stats` feature is enabled.
@@ -1279,3 +1498,53 @@ pub struct MetaFileEntryInfo {
     pub value_compression_dictionary_size: u16,
     pub block_count: u16,
 }
+
+/// A raw entry of an SST file, see [`TurboPersistence::for_each_entry`].
+pub struct EntryInfo {
+    /// The SST file containing the entry.
+    pub sst_sequence_number: u32,
+    pub hash: u64,
+    pub key: ArcSlice<u8>,
+    pub value: EntryValueInfo,
+}
+
+pub enum EntryValueInfo {
+    /// The key was deleted.
+    Deleted,
+    /// The value is stored in the SST file.
+    Inline { value: ArcSlice<u8> },
+    /// The value is stored in a blob file.
+    Blob { sequence_number: u32 },
+}
+
+/// The result of [`TurboPersistence::verify`].
+#[derive(Default)]
+pub struct VerifyReport {
+    pub sst_files: usize,
+    /// The number of distinct blob files referenced by the SST files.
+    pub blob_files: usize,
+    pub entries: u64,
+    /// The first problem found in each broken SST file.
+    pub errors: Vec<String>,
+}
+
+/// The result of [`TurboPersistence::compaction_report`] for a single family.
+pub struct FamilyCompactionReport {
+    pub family: u32,
+    pub sst_files: usize,
+    pub size: u64,
+    /// The total coverage of the SST files, i.e. the average number of SST files that need to be
+    /// read to find a key.
+    pub coverage: f32,
+    pub overlap: f32,
+    pub duplicated_size: u64,
+    pub duplication: f32,
+    /// The merge jobs that a compaction would run.
+    pub merge_jobs: Vec<MergeJobInfo>,
+}
+
+pub struct MergeJobInfo {
+    /// The SST files that would be merged.
+    pub sst_files: Vec<u32>,
+    pub size: u64,
+}
@@ -19,13 +19,18 @@ mod write_batch;
 
 mod meta_file;
 mod meta_file_builder;
+mod repair;
 mod sst_filter;
 #[cfg(test)]
 mod tests;
 mod value_buf;
 
 pub use arc_slice::ArcSlice;
-pub use db::{CompactConfig, MetaFileEntryInfo, MetaFileInfo, TurboPersistence};
+pub use db::{
+    CompactConfig, EntryInfo, EntryValueInfo, FamilyCompactionReport, MergeJobInfo,
+    MetaFileEntryInfo, MetaFileInfo, TurboPersistence, VerifyReport,
+};
 pub use key::{KeyBase, QueryKey, StoreKey};
+pub use repair::RepairReport;
 pub use value_buf::ValueBuffer;
 pub use write_batch::WriteBatch;
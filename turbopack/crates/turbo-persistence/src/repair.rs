@@ -0,0 +1,334 @@
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Write},
+    panic::{AssertUnwindSafe, catch_unwind},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use byteorder::{BE, ReadBytesExt, WriteBytesExt};
+use jiff::Timestamp;
+use rustc_hash::FxHashMap;
+
+use crate::meta_file::MetaFile;
+
+/// What [`TurboPersistence::open_with_repair`][crate::TurboPersistence::open_with_repair] removed
+/// to bring the database back into a consistent state.
+#[derive(Debug, Default)]
+pub struct RepairReport {
+    /// The CURRENT file was missing or truncated and has been recreated.
+    pub recreated_current_file: bool,
+    /// Files of a commit that didn't finish, i.e. with a sequence number newer than CURRENT.
+    pub incomplete_files: Vec<String>,
+    /// Meta files that are unreadable or reference missing or truncated SST files, with the
+    /// reason.
+    pub broken_meta_files: Vec<(String, String)>,
+    /// Files of valid commits that were rolled back together with a broken commit.
+    pub rolled_back_files: Vec<String>,
+    /// SST files that are not used by any meta file anymore.
+    pub obsolete_sst_files: Vec<String>,
+    /// Files that don't belong to the database. These are left untouched.
+    pub unknown_files: Vec<String>,
+}
+
+impl RepairReport {
+    /// Returns `true` if the database was consistent and nothing has been changed.
+    pub fn is_empty(&self) -> bool {
+        let Self {
+            recreated_current_file,
+            incomplete_files,
+            broken_meta_files,
+            rolled_back_files,
+            obsolete_sst_files,
+            unknown_files: _,
+        } = self;
+        !recreated_current_file
+            && incomplete_files.is_empty()
+            && broken_meta_files.is_empty()
+            && rolled_back_files.is_empty()
+            && obsolete_sst_files.is_empty()
+    }
+}
+
+impl fmt::Display for RepairReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.recreated_current_file {
+            writeln!(f, "recreated the CURRENT file")?;
+        }
+        for (name, reason) in &self.broken_meta_files {
+            writeln!(f, "discarded broken {name}: {reason}")?;
+        }
+        for (files, description) in [
+            (&self.incomplete_files, "of incomplete commits"),
+            (&self.rolled_back_files, "of rolled back commits"),
+            (&self.obsolete_sst_files, "of obsolete SST files"),
+            (&self.unknown_files, "unknown files (ignored)"),
+        ] {
+            if !files.is_empty() {
+                writeln!(
+                    f,
+                    "discarded {} {description}: {}",
+                    files.len(),
+                    files.join(", ")
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum FileKind {
+    Meta,
+    Sst,
+    Blob,
+    Del,
+}
+
+impl FileKind {
+    fn from_extension(ext: &str) -> Option<Self> {
+        Some(match ext {
+            "meta" => Self::Meta,
+            "sst" => Self::Sst,
+            "blob" => Self::Blob,
+            "del" => Self::Del,
+            _ => return None,
+        })
+    }
+
+    fn file_name(self, seq: u32) -> String {
+        let ext = match self {
+            Self::Meta => "meta",
+            Self::Sst => "sst",
+            Self::Blob => "blob",
+            Self::Del => "del",
+        };
+        format!("{seq:08}.{ext}")
+    }
+}
+
+/// Brings the files of a database directory into a consistent state that can be opened.
+///
+/// Commits that didn't finish are removed, pending deletions are executed and meta files are
+/// checked against their SST files. When a meta file is broken, its whole commit and all later
+/// commits are rolled back, so the database ends up in the state of an earlier commit instead of
+/// mixing families of different commits. Blob files referenced by the SST files are not checked.
+pub(crate) fn repair_directory(path: &Path) -> Result<RepairReport> {
+    let mut report = RepairReport::default();
+
+    let mut files = BTreeSet::new();
+    for entry in fs::read_dir(path)? {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if name == "CURRENT" || name == "LOG" || name.starts_with('.') {
+            continue;
+        }
+        let file = name.split_once('.').and_then(|(stem, ext)| {
+            Some((stem.parse::<u32>().ok()?, FileKind::from_extension(ext)?))
+        });
+        match file {
+            Some((seq, kind)) if kind.file_name(seq) == name => {
+                files.insert((seq, kind));
+            }
+            _ => report.unknown_files.push(name),
+        }
+    }
+
+    let current = match File::open(path.join("CURRENT")).and_then(|mut f| f.read_u32::<BE>()) {
+        Ok(current) => current,
+        Err(_) => {
+            report.recreated_current_file = true;
+            files.iter().map(|(seq, _)| *seq).max().unwrap_or(0)
+        }
+    };
+
+    let mut files_to_delete = Vec::new();
+    files.retain(|&(seq, kind)| {
+        if seq > current {
+            report.incomplete_files.push(kind.file_name(seq));
+            files_to_delete.push(kind.file_name(seq));
+            return false;
+        }
+        true
+    });
+
+    // Finish the deletions of completed commits
+    let mut deleted = HashSet::new();
+    for &(seq, kind) in files.iter() {
+        if kind == FileKind::Del {
+            let content = fs::read(path.join(kind.file_name(seq)))?;
+            // A truncated file only loses the deletion of its last file, which is handled as
+            // an obsolete file below.
+            for mut chunk in content.chunks_exact(size_of::<u32>()) {
+                deleted.insert(chunk.read_u32::<BE>()?);
+            }
+        }
+    }
+    files.retain(|&(seq, kind)| {
+        if kind == FileKind::Del || deleted.contains(&seq) {
+            files_to_delete.push(kind.file_name(seq));
+            return false;
+        }
+        true
+    });
+
+    let sst_sizes = files
+        .iter()
+        .filter(|(_, kind)| *kind == FileKind::Sst)
+        .map(|&(seq, kind)| {
+            let size = fs::metadata(path.join(kind.file_name(seq)))?.len();
+            Ok((seq, size))
+        })
+        .collect::<Result<FxHashMap<_, _>>>()?;
+
+    let mut meta_files = files
+        .iter()
+        .filter(|(_, kind)| *kind == FileKind::Meta)
+        .map(|&(seq, _)| {
+            let meta = catch_unwind(|| MetaFile::open(path, seq))
+                .unwrap_or_else(|_| Err(anyhow::anyhow!("Panicked while reading")))
+                .map_err(|err| format!("{err:#}"));
+            (seq, meta)
+        })
+        .collect::<Vec<_>>();
+
+    loop {
+        // SST files can be deleted once a later meta file marks them as obsolete
+        let mut obsolete = HashSet::new();
+        let mut problems = FxHashMap::default();
+        for (seq, meta) in meta_files.iter().rev() {
+            let problem = match meta {
+                Ok(meta) => {
+                    let problem = check_meta_file(meta, &sst_sizes, &obsolete);
+                    obsolete.extend(meta.obsolete_sst_files().iter().copied());
+                    problem
+                }
+                Err(err) => Some(err.clone()),
+            };
+            if let Some(problem) = problem {
+                problems.insert(*seq, problem);
+            }
+        }
+        let Some(&first_broken) = problems.keys().min() else {
+            break;
+        };
+
+        // The meta files of a write batch are written last with consecutive sequence numbers, so
+        // this finds the start of the commit.
+        let mut commit_start = first_broken;
+        while commit_start > 0
+            && meta_files
+                .binary_search_by_key(&(commit_start - 1), |(seq, _)| *seq)
+                .is_ok()
+        {
+            commit_start -= 1;
+        }
+        let index = meta_files.partition_point(|(seq, _)| *seq < commit_start);
+        for (seq, _) in meta_files.drain(index..) {
+            let name = FileKind::Meta.file_name(seq);
+            match problems.remove(&seq) {
+                Some(problem) => report.broken_meta_files.push((name, problem)),
+                None => report.rolled_back_files.push(name),
+            }
+        }
+    }
+
+    // Every SST and blob file of a commit has a lower sequence number than its meta files, so all
+    // files newer than the last remaining meta file belong to rolled back commits.
+    let last_meta_seq = meta_files.last().map_or(0, |(seq, _)| *seq);
+    let mut last_reference = FxHashMap::default();
+    let mut obsoleted_by = FxHashMap::default();
+    for (seq, meta) in meta_files.iter() {
+        let Ok(meta) = meta else {
+            unreachable!("broken meta files have been removed")
+        };
+        for entry in meta.entries() {
+            last_reference.insert(entry.sequence_number(), *seq);
+        }
+        for &sst in meta.obsolete_sst_files() {
+            obsoleted_by.insert(sst, *seq);
+        }
+    }
+    drop(meta_files);
+    for &(seq, kind) in files.iter() {
+        match kind {
+            FileKind::Meta => {
+                if seq > last_meta_seq {
+                    files_to_delete.push(kind.file_name(seq));
+                }
+            }
+            FileKind::Sst | FileKind::Blob if seq > last_meta_seq => {
+                report.rolled_back_files.push(kind.file_name(seq));
+                files_to_delete.push(kind.file_name(seq));
+            }
+            FileKind::Sst => {
+                let is_used = last_reference.get(&seq).is_some_and(|&last_reference| {
+                    obsoleted_by
+                        .get(&seq)
+                        .is_none_or(|&obsoleted_by| obsoleted_by < last_reference)
+                });
+                if !is_used {
+                    report.obsolete_sst_files.push(kind.file_name(seq));
+                    files_to_delete.push(kind.file_name(seq));
+                }
+            }
+            FileKind::Blob | FileKind::Del => {}
+        }
+    }
+
+    if report.recreated_current_file {
+        let mut current_file = File::create(path.join("CURRENT"))?;
+        current_file.write_u32::<BE>(current)?;
+        current_file.sync_all()?;
+    }
+    for name in files_to_delete.iter() {
+        fs::remove_file(path.join(name)).with_context(|| format!("Failed to remove {name}"))?;
+    }
+
+    if !report.is_empty() {
+        let mut log = BufWriter::new(
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path.join("LOG"))?,
+        );
+        writeln!(log, "Time {}", Timestamp::now())?;
+        writeln!(log, "Repair {current:08}")?;
+        write!(log, "{report}")?;
+    }
+
+    Ok(report)
+}
+
+/// Checks that the SST files of a meta file exist with the expected size and that the AMQF data
+/// is readable. Returns a description of the first problem.
+fn check_meta_file(
+    meta: &MetaFile,
+    sst_sizes: &FxHashMap<u32, u64>,
+    obsolete: &HashSet<u32>,
+) -> Option<String> {
+    catch_unwind(AssertUnwindSafe(|| {
+        for entry in meta.entries() {
+            let seq = entry.sequence_number();
+            match sst_sizes.get(&seq) {
+                Some(&size) if size == entry.size() => {}
+                Some(&size) => {
+                    return Some(format!(
+                        "{seq:08}.sst has {size} bytes, but {} bytes are expected",
+                        entry.size()
+                    ));
+                }
+                // Already deleted by a later compaction
+                None if obsolete.contains(&seq) => continue,
+                None => return Some(format!("{seq:08}.sst is missing")),
+            }
+            if let Err(err) = entry.deserialize_amqf(meta) {
+                return Some(format!("AMQF of {seq:08}.sst is invalid: {err:#}"));
+            }
+        }
+        None
+    }))
+    .unwrap_or_else(|_| Some("Panicked while reading".to_string()))
+}
@@ -5,7 +5,7 @@ use rayon::iter::{IntoParallelIterator, ParallelIterator};
 
 use crate::{
     constants::MAX_MEDIUM_VALUE_SIZE,
-    db::{CompactConfig, TurboPersistence},
+    db::{CompactConfig, EntryValueInfo, TurboPersistence},
     write_batch::WriteBatch,
 };
 
@@ -672,3 +672,97 @@ fn merge_file_removal() -> Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn inspection() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    let db = TurboPersistence::open(path.to_path_buf())?;
+    for round in 0..3u8 {
+        let b = db.write_batch::<_, 1>()?;
+        for i in 0..100u32 {
+            if round == 2 && i == 0 {
+                b.delete(0, i.to_be_bytes())?;
+            } else {
+                b.put(0, i.to_be_bytes(), vec![round; 10].into())?;
+            }
+        }
+        db.commit_write_batch(b)?;
+    }
+
+    let mut entries = 0;
+    let mut deleted = 0;
+    db.for_each_entry(0, |entry| {
+        entries += 1;
+        match entry.value {
+            EntryValueInfo::Deleted => deleted += 1,
+            EntryValueInfo::Inline { value } => assert_eq!(value.len(), 10),
+            EntryValueInfo::Blob { .. } => panic!("unexpected blob"),
+        }
+        Ok(())
+    })?;
+    // overwritten keys are reported once per SST file
+    assert_eq!(entries, 300);
+    assert_eq!(deleted, 1);
+
+    let report = db.verify()?;
+    assert_eq!(report.sst_files, 3);
+    assert_eq!(report.entries, 300);
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+
+    let compaction = db.compaction_report(&CompactConfig::default());
+    assert_eq!(compaction.len(), 1);
+    assert_eq!(compaction[0].sst_files, 3);
+    assert!(compaction[0].duplicated_size > 0);
+
+    db.shutdown()?;
+    Ok(())
+}
+
+#[test]
+fn repair() -> Result<()> {
+    let tempdir = tempfile::tempdir()?;
+    let path = tempdir.path();
+
+    {
+        let db = TurboPersistence::open(path.to_path_buf())?;
+        for value in [1u8, 2] {
+            let b = db.write_batch::<_, 1>()?;
+            for i in 0..100u32 {
+                b.put(0, i.to_be_bytes(), vec![value].into())?;
+            }
+            db.commit_write_batch(b)?;
+        }
+        db.shutdown()?;
+    }
+
+    // Simulate a meta file that wasn't fully written
+    let newest_meta = fs::read_dir(path)?
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "meta"))
+        .max()
+        .unwrap();
+    fs::write(&newest_meta, &fs::read(&newest_meta)?[..10])?;
+    assert!(TurboPersistence::open(path.to_path_buf()).is_err());
+
+    // and a commit that was interrupted. This is written after the failed open, as opening removes
+    // the files of incomplete commits.
+    let current = u32::from_be_bytes(fs::read(path.join("CURRENT"))?[..4].try_into()?);
+    let incomplete = format!("{:08}.sst", current + 1);
+    fs::write(path.join(&incomplete), "incomplete")?;
+
+    let (db, report) = TurboPersistence::open_with_repair(path.to_path_buf())?;
+    assert_eq!(report.incomplete_files, vec![incomplete]);
+    assert_eq!(report.broken_meta_files.len(), 1);
+    assert_eq!(report.rolled_back_files.len(), 1);
+    for i in 0..100u32 {
+        assert_eq!(db.get(0, &i.to_be_bytes())?.as_deref(), Some(&[1][..]));
+    }
+    db.shutdown()?;
+
+    let (db, report) = TurboPersistence::open_with_repair(path.to_path_buf())?;
+    assert!(report.is_empty());
+    db.shutdown()?;
+    Ok(())
+}
@@ -1,6 +1,7 @@
 mod dynamic_storage;
 mod operation;
 mod storage;
+mod task_graph;
 
 use std::{
     borrow::Cow,
@@ -25,8 +26,8 @@ use smallvec::{SmallVec, smallvec};
 use tokio::time::{Duration, Instant};
 use tracing::field::Empty;
 use turbo_tasks::{
-    CellId, FxDashMap, KeyValuePair, RawVc, ReadCellOptions, ReadConsistency, SessionId,
-    TRANSIENT_TASK_BIT, TaskExecutionReason, TaskId, TraitTypeId, TurboTasksBackendApi,
+    CellId, FxDashMap, InvalidationReason, KeyValuePair, RawVc, ReadCellOptions, ReadConsistency,
+    SessionId, TRANSIENT_TASK_BIT, TaskExecutionReason, TaskId, TraitTypeId, TurboTasksBackendApi,
     ValueTypeId,
     backend::{
         Backend, BackendJobId, CachedTaskType, CellContent, TaskExecutionSpec, TransientTaskRoot,
@@ -38,24 +39,28 @@ use turbo_tasks::{
     task_statistics::TaskStatisticsApi,
     trace::TraceRawVcs,
     turbo_tasks,
-    util::IdFactoryWithReuse,
+    util::{IdFactoryWithReuse, StaticOrArc},
 };
+use turbo_tasks_malloc::TurboMalloc;
 
-pub use self::{operation::AnyOperation, storage::TaskDataCategory};
-#[cfg(feature = "trace_task_dirty")]
-use crate::backend::operation::TaskDirtyCause;
+pub use self::{
+    operation::AnyOperation,
+    storage::TaskDataCategory,
+    task_graph::{TaskGraph, TaskGraphDependency, TaskGraphNode},
+};
 use crate::{
     backend::{
         operation::{
             AggregatedDataUpdate, AggregationUpdateJob, AggregationUpdateQueue,
             CleanupOldEdgesOperation, ConnectChildOperation, ExecuteContext, ExecuteContextImpl,
-            Operation, OutdatedEdge, TaskGuard, connect_children, get_aggregation_number,
-            get_uppers, is_root_node, prepare_new_children,
+            Operation, OutdatedEdge, TaskDirtyCause, TaskGuard, connect_children,
+            get_aggregation_number, get_uppers, is_root_node, prepare_new_children,
         },
         storage::{
             InnerStorageSnapshot, Storage, count, get, get_many, get_mut, get_mut_or_insert_with,
             iter_many, remove,
         },
+        task_graph::TaskExecutionInfo,
     },
     backing_storage::BackingStorage,
     data::{
@@ -142,6 +147,20 @@ pub struct BackendOptions {
 
     /// Avoid big preallocations for faster startup. Should only be used for testing purposes.
     pub small_preallocation: bool,
+
+    /// The memory usage in bytes above which tasks are evicted from memory after a snapshot.
+    ///
+    /// Only tasks that weren't accessed since the previous snapshot and are fully persisted are
+    /// evicted. They are restored from the backing storage when they are accessed again.
+    ///
+    /// Requires `storage_mode` to be [`StorageMode::ReadWrite`] and `TurboMalloc` to be the global
+    /// allocator, as the memory usage is measured by it. Has no effect otherwise.
+    pub memory_limit: Option<usize>,
+
+    /// Records how often, why and how long tasks execute, for [`TurboTasksBackend::task_graph`]
+    /// and [`TurboTasksBackend::explain_execution`], including why tasks were made dirty. Costs
+    /// some memory per executed task, so it should only be enabled for debugging.
+    pub introspection: bool,
 }
 
 impl Default for BackendOptions {
@@ -152,6 +171,8 @@ impl Default for BackendOptions {
             active_tracking: true,
             storage_mode: Some(StorageMode::ReadWrite),
             small_preallocation: false,
+            memory_limit: None,
+            introspection: false,
         }
     }
 }
@@ -192,6 +213,12 @@ struct TurboTasksBackendInner<B: BackingStorage> {
     snapshot_completed: Condvar,
     /// The timestamp of the last started snapshot since [`Self::start_time`].
     last_snapshot: AtomicU64,
+    /// Set when persisting the last snapshot failed. Tasks are not evicted until a snapshot
+    /// succeeds again, as their data might be missing from the backing storage.
+    persisting_failed: AtomicBool,
+    /// Set when the last eviction over the memory limit didn't evict any task. Snapshots are no
+    /// longer taken early because of the memory usage then, until an eviction succeeds again.
+    eviction_stalled: AtomicBool,
 
     stopping: AtomicBool,
     stopping_event: Event,
@@ -201,6 +228,8 @@ struct TurboTasksBackendInner<B: BackingStorage> {
     is_idle: AtomicBool,
 
     task_statistics: TaskStatisticsApi,
+    /// Only available with [`BackendOptions::introspection`].
+    task_execution_info: Option<FxDashMap<TaskId, TaskExecutionInfo>>,
 
     backing_storage: B,
 
@@ -219,6 +248,36 @@ impl<B: BackingStorage> TurboTasksBackend<B> {
     pub fn backing_storage(&self) -> &B {
         &self.0.backing_storage
     }
+
+    /// Creates a snapshot of the tasks in memory, their children and dependencies. Execution
+    /// statistics are only included with [`BackendOptions::introspection`].
+    pub fn task_graph(&self) -> TaskGraph {
+        self.0.task_graph()
+    }
+
+    /// Describes why the task executed the last time, e.g. `invalidated (task foo output
+    /// changed)` or `invalidated (invalidator, /src/index.js changed)`. Requires
+    /// [`BackendOptions::introspection`].
+    pub fn explain_execution(&self, task_id: TaskId) -> Option<String> {
+        self.0.explain_execution(task_id)
+    }
+
+    /// Persists a snapshot and evicts cold tasks from memory, independent of
+    /// [`BackendOptions::memory_limit`]. A task is cold when it wasn't accessed since the previous
+    /// eviction. Returns the number of evicted tasks, or `None` when nothing was persisted.
+    ///
+    /// This blocks until in progress operations are suspended. It needs to be called within a
+    /// [`turbo_tasks_scope`][turbo_tasks::turbo_tasks_scope].
+    pub fn snapshot_and_evict(&self) -> Option<usize> {
+        if !self.0.should_persist() {
+            return None;
+        }
+        self.0.snapshot()?;
+        if self.0.persisting_failed.load(Ordering::Acquire) {
+            return None;
+        }
+        Some(self.0.storage.evict_cold_tasks())
+    }
 }
 
 impl<B: BackingStorage> TurboTasksBackendInner<B> {
@@ -230,6 +289,7 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
             options.active_tracking = false;
         }
         let small_preallocation = options.small_preallocation;
+        let introspection = options.introspection;
         Self {
             options,
             start_time: Instant::now(),
@@ -255,6 +315,8 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
             operations_suspended: Condvar::new(),
             snapshot_completed: Condvar::new(),
             last_snapshot: AtomicU64::new(0),
+            persisting_failed: AtomicBool::new(false),
+            eviction_stalled: AtomicBool::new(false),
             stopping: AtomicBool::new(false),
             stopping_event: Event::new(|| || "TurboTasksBackend::stopping_event".to_string()),
             idle_start_event: Event::new(|| || "TurboTasksBackend::idle_start_event".to_string()),
@@ -262,6 +324,7 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
             #[cfg(feature = "verify_aggregation_graph")]
             is_idle: AtomicBool::new(false),
             task_statistics: TaskStatisticsApi::default(),
+            task_execution_info: introspection.then(FxDashMap::default),
             backing_storage,
             #[cfg(feature = "verify_aggregation_graph")]
             root_tasks: Default::default(),
@@ -932,9 +995,28 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
         }
     }
 
+    fn is_over_memory_limit(&self) -> bool {
+        self.options
+            .memory_limit
+            .is_some_and(|memory_limit| TurboMalloc::memory_usage() > memory_limit)
+    }
+
+    /// Evicts cold tasks from memory when over [`BackendOptions::memory_limit`]. Must be called
+    /// after a successful snapshot.
+    fn evict_if_over_memory_limit(&self) {
+        if self.persisting_failed.load(Ordering::Acquire) || !self.is_over_memory_limit() {
+            return;
+        }
+        let span = tracing::trace_span!("evict cold tasks", evicted = Empty).entered();
+        let evicted = self.storage.evict_cold_tasks();
+        span.record("evicted", evicted);
+        self.eviction_stalled.store(evicted == 0, Ordering::Relaxed);
+    }
+
     fn snapshot(&self) -> Option<(Instant, bool)> {
         let start = Instant::now();
         debug_assert!(self.should_persist());
+        self.persisting_failed.store(false, Ordering::Release);
         let mut snapshot_request = self.snapshot_request.lock();
         snapshot_request.snapshot_requested = true;
         let active_operations = self
@@ -1064,6 +1146,8 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
         #[cfg(feature = "print_cache_item_size")]
         let task_cache_stats: Mutex<FxHashMap<_, TaskCacheStats>> =
             Mutex::new(FxHashMap::default());
+        // Tasks that failed to serialize. They are persisted again by the next snapshot.
+        let failed_tasks = Mutex::new(Vec::new());
 
         let task_snapshots = snapshot
             .into_iter()
@@ -1092,6 +1176,8 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
                                         self.get_task_description(task_id),
                                         err
                                     );
+                                    self.persisting_failed.store(true, Ordering::Release);
+                                    failed_tasks.lock().push(task_id);
                                     None
                                 }
                             };
@@ -1112,6 +1198,8 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
                                         self.get_task_description(task_id),
                                         err
                                     );
+                                    self.persisting_failed.store(true, Ordering::Release);
+                                    failed_tasks.lock().push(task_id);
                                     None
                                 }
                             };
@@ -1136,6 +1224,10 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
                 task_snapshots,
             ) {
                 println!("Persisting failed: {err:?}");
+                self.persisting_failed.store(true, Ordering::Release);
+                // It's unknown which tasks were persisted, so all of them are persisted again by
+                // the next snapshot.
+                self.storage.mark_all_modified();
                 return None;
             }
             #[cfg(feature = "print_cache_item_size")]
@@ -1170,6 +1262,9 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
             }
         }
 
+        // The snapshot mode ended when all task snapshots were consumed
+        self.storage.mark_modified(failed_tasks.into_inner());
+
         if new_items {
             let elapsed = start.elapsed();
             turbo_tasks().send_compilation_event(Arc::new(TimingEvent::new(
@@ -1445,12 +1540,26 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
         }
         operation::InvalidateOperation::run(
             smallvec![task_id],
-            #[cfg(feature = "trace_task_dirty")]
             TaskDirtyCause::Invalidator,
             self.execute_context(turbo_tasks),
         );
     }
 
+    fn invalidate_task_with_reason(
+        &self,
+        task_id: TaskId,
+        reason: &StaticOrArc<dyn InvalidationReason>,
+        turbo_tasks: &dyn TurboTasksBackendApi<TurboTasksBackend<B>>,
+    ) {
+        if let Some(task_execution_info) = &self.task_execution_info {
+            task_execution_info
+                .entry(task_id)
+                .or_default()
+                .invalidation_reason = Some(reason.clone());
+        }
+        self.invalidate_task(task_id, turbo_tasks);
+    }
+
     fn invalidate_tasks(
         &self,
         tasks: &[TaskId],
@@ -1461,7 +1570,6 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
         }
         operation::InvalidateOperation::run(
             tasks.iter().copied().collect(),
-            #[cfg(feature = "trace_task_dirty")]
             TaskDirtyCause::Unknown,
             self.execute_context(turbo_tasks),
         );
@@ -1477,7 +1585,6 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
         }
         operation::InvalidateOperation::run(
             tasks.iter().copied().collect(),
-            #[cfg(feature = "trace_task_dirty")]
             TaskDirtyCause::Unknown,
             self.execute_context(turbo_tasks),
         );
@@ -1496,6 +1603,91 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
         task.invalidate_serialization();
     }
 
+    fn record_dirty_cause(&self, task_id: TaskId, cause: impl FnOnce() -> String) {
+        if let Some(task_execution_info) = &self.task_execution_info {
+            task_execution_info.entry(task_id).or_default().dirty_cause = Some(cause());
+        }
+    }
+
+    fn task_graph(&self) -> TaskGraph {
+        let session_id = self.session_id;
+        let mut nodes = Vec::new();
+        self.storage.for_each_task(|task_id, task| {
+            let dirty = get!(task, Dirty).is_some_and(|dirty| dirty.get(session_id));
+            let children = iter_many!(task, Child { task } => task).collect::<Vec<_>>();
+            let dependencies = iter_many!(task, OutputDependency { target } => {
+                TaskGraphDependency::Output { task: target }
+            })
+            .chain(iter_many!(task, CellDependency { target } => {
+                TaskGraphDependency::Cell {
+                    task: target.task,
+                    cell: target.cell.to_string(),
+                }
+            }))
+            .chain(iter_many!(task, CollectiblesDependency { target } => {
+                TaskGraphDependency::Collectibles {
+                    task: target.task,
+                    collectible_type: registry::get_trait(target.collectible_type).name,
+                }
+            }))
+            .collect::<Vec<_>>();
+            nodes.push((task_id, dirty, children, dependencies));
+        });
+
+        // Looking up the task types accesses other data structures, so it happens after all
+        // tasks were visited.
+        let tasks = nodes
+            .into_iter()
+            .map(|(task_id, dirty, children, dependencies)| {
+                let task_type = self.lookup_task_type(task_id);
+                let info = self
+                    .task_execution_info
+                    .as_ref()
+                    .and_then(|task_execution_info| task_execution_info.get(&task_id));
+                let info = info.as_deref();
+                TaskGraphNode {
+                    id: task_id,
+                    name: self.get_task_description(task_id),
+                    arguments: task_type
+                        .map(|task_type| format!("{:?}, {:?}", task_type.this, task_type.arg)),
+                    dirty,
+                    children,
+                    dependencies,
+                    executions: info.map_or(0, |info| info.executions),
+                    last_execution_reason: info.and_then(|info| info.last_reason),
+                    last_execution_duration_us: info
+                        .and_then(|info| info.last_duration)
+                        .map(|duration| duration.as_micros() as u64),
+                    last_dirty_cause: info.and_then(|info| info.last_dirty_cause.clone()),
+                    last_invalidation_reason: info
+                        .and_then(|info| info.last_invalidation_reason.as_ref())
+                        .map(|reason| reason.to_string()),
+                }
+            })
+            .collect();
+        TaskGraph { tasks }
+    }
+
+    fn explain_execution(&self, task_id: TaskId) -> Option<String> {
+        let info = self.task_execution_info.as_ref()?.get(&task_id)?;
+        let reason = info.last_reason?;
+        let details = info
+            .last_dirty_cause
+            .iter()
+            .cloned()
+            .chain(
+                info.last_invalidation_reason
+                    .iter()
+                    .map(|reason| reason.to_string()),
+            )
+            .collect::<Vec<_>>();
+        Some(if details.is_empty() {
+            reason.to_string()
+        } else {
+            format!("{reason} ({})", details.join(", "))
+        })
+    }
+
     fn get_task_description(&self, task_id: TaskId) -> String {
         self.lookup_task_type(task_id).map_or_else(
             || format!("{task_id:?} transient"),
@@ -1642,6 +1834,14 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
             }
         }
 
+        if let Some(task_execution_info) = &self.task_execution_info {
+            let mut info = task_execution_info.entry(task_id).or_default();
+            info.executions += 1;
+            info.last_reason = Some(execution_reason.as_str());
+            info.last_dirty_cause = info.dirty_cause.take();
+            info.last_invalidation_reason = info.invalidation_reason.take();
+        }
+
         let (span, future) = match task_type {
             TaskType::Cached(task_type) => {
                 let CachedTaskType {
@@ -1678,13 +1878,20 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
     fn task_execution_completed(
         &self,
         task_id: TaskId,
-        _duration: Duration,
+        duration: Duration,
         _memory_usage: usize,
         cell_counters: &AutoMap<ValueTypeId, u32, BuildHasherDefault<FxHasher>, 8>,
         stateful: bool,
         has_invalidator: bool,
         turbo_tasks: &dyn TurboTasksBackendApi<TurboTasksBackend<B>>,
     ) -> bool {
+        if let Some(task_execution_info) = &self.task_execution_info {
+            task_execution_info
+                .entry(task_id)
+                .or_default()
+                .last_duration = Some(duration);
+        }
+
         // Task completion is a 4 step process:
         // 1. Remove old edges (dependencies, collectibles, children, cells) and update the
         //    aggregation number of the task and the new children.
@@ -1880,7 +2087,6 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
                         {
                             return Some(OutdatedEdge::RemovedCellDependent {
                                 task_id: task,
-                                #[cfg(feature = "trace_task_dirty")]
                                 value_type_id: cell.type_id,
                             });
                         }
@@ -2127,6 +2333,7 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
                     const FIRST_SNAPSHOT_WAIT: Duration = Duration::from_secs(60);
                     const SNAPSHOT_INTERVAL: Duration = Duration::from_secs(30);
                     const IDLE_TIMEOUT: Duration = Duration::from_secs(2);
+                    const MEMORY_CHECK_INTERVAL: Duration = Duration::from_secs(10);
 
                     let time = if id == BACKEND_JOB_INITIAL_SNAPSHOT {
                         FIRST_SNAPSHOT_WAIT
@@ -2145,6 +2352,17 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
                             } else {
                                 far_future()
                             };
+                            // Tasks can only be evicted after they are persisted, so snapshot
+                            // early when over the memory limit. This is pointless when the last
+                            // eviction didn't find any cold task, so only the regular snapshots
+                            // retry then.
+                            let mut memory_check_time = if self.options.memory_limit.is_some()
+                                && !self.eviction_stalled.load(Ordering::Relaxed)
+                            {
+                                Instant::now() + MEMORY_CHECK_INTERVAL
+                            } else {
+                                far_future()
+                            };
                             loop {
                                 tokio::select! {
                                     _ = &mut stop_listener => {
@@ -2166,13 +2384,26 @@ impl<B: BackingStorage> TurboTasksBackendInner<B> {
                                             break;
                                         }
                                     },
+                                    _ = tokio::time::sleep_until(memory_check_time) => {
+                                        if self.is_over_memory_limit() {
+                                            break;
+                                        }
+                                        memory_check_time = Instant::now() + MEMORY_CHECK_INTERVAL;
+                                    },
                                 }
                             }
                         }
                     }
 
                     let this = self.clone();
-                    let snapshot = turbo_tasks::spawn_blocking(move || this.snapshot()).await;
+                    let snapshot = turbo_tasks::spawn_blocking(move || {
+                        let snapshot = this.snapshot();
+                        if snapshot.is_some() {
+                            this.evict_if_over_memory_limit();
+                        }
+                        snapshot
+                    })
+                    .await;
                     if let Some((snapshot_start, new_data)) = snapshot {
                         last_snapshot = snapshot_start;
                         if new_data {
@@ -2807,6 +3038,16 @@ impl<B: BackingStorage> Backend for TurboTasksBackend<B> {
         self.0.invalidate_task(task_id, turbo_tasks);
     }
 
+    fn invalidate_task_with_reason(
+        &self,
+        task_id: TaskId,
+        reason: &StaticOrArc<dyn InvalidationReason>,
+        turbo_tasks: &dyn TurboTasksBackendApi<Self>,
+    ) {
+        self.0
+            .invalidate_task_with_reason(task_id, reason, turbo_tasks);
+    }
+
     fn invalidate_tasks(&self, tasks: &[TaskId], turbo_tasks: &dyn TurboTasksBackendApi<Self>) {
         self.0.invalidate_tasks(tasks, turbo_tasks);
     }
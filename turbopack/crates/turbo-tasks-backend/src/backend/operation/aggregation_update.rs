@@ -20,14 +20,17 @@ use smallvec::{SmallVec, smallvec};
     feature = "trace_find_and_schedule"
 ))]
 use tracing::{span::Span, trace_span};
-use turbo_tasks::{FxIndexMap, SessionId, TaskExecutionReason, TaskId};
+use turbo_tasks::{
+    FxIndexMap, SessionId, TaskExecutionReason, TaskId, update_trace::InvalidationSource,
+};
 
-#[cfg(feature = "trace_task_dirty")]
-use crate::backend::operation::invalidate::TaskDirtyCause;
 use crate::{
     backend::{
         TaskDataCategory, get_mut, get_mut_or_insert_with,
-        operation::{ExecuteContext, Operation, TaskGuard, invalidate::make_task_dirty},
+        operation::{
+            ExecuteContext, Operation, TaskGuard,
+            invalidate::{TaskDirtyCause, make_task_dirty},
+        },
         storage::{count, get, get_many, iter_many, remove, update, update_count},
     },
     data::{
@@ -172,9 +175,10 @@ pub enum AggregationUpdateJob {
     AggregatedDataUpdate(Box<AggregatedDataUpdateJob>),
     /// Invalidates tasks that are dependent on a collectible type.
     InvalidateDueToCollectiblesChange {
-        task_ids: TaskIdVec,
-        #[cfg(feature = "trace_task_dirty")]
+        /// The task whose (aggregated) collectibles changed.
+        task_id: TaskId,
         collectible_type: turbo_tasks::TraitTypeId,
+        task_ids: TaskIdVec,
     },
     /// Increases the active counter of the task
     #[serde(skip)]
@@ -388,9 +392,9 @@ impl AggregatedDataUpdate {
                 );
                 if !dependent.is_empty() {
                     queue.push(AggregationUpdateJob::InvalidateDueToCollectiblesChange {
-                        task_ids: dependent,
-                        #[cfg(feature = "trace_task_dirty")]
+                        task_id: task.id(),
                         collectible_type: ty,
+                        task_ids: dependent,
                     })
                 }
             }
@@ -867,14 +871,17 @@ impl AggregationUpdateQueue {
                     self.aggregated_data_update(upper_ids, ctx, update);
                 }
                 AggregationUpdateJob::InvalidateDueToCollectiblesChange {
-                    task_ids,
-                    #[cfg(feature = "trace_task_dirty")]
+                    task_id,
                     collectible_type,
+                    task_ids,
                 } => {
+                    ctx.trace_invalidation(
+                        InvalidationSource::Collectibles(task_id, collectible_type),
+                        &task_ids,
+                    );
                     for task_id in task_ids {
                         make_task_dirty(
                             task_id,
-                            #[cfg(feature = "trace_task_dirty")]
                             TaskDirtyCause::CollectiblesChange { collectible_type },
                             self,
                             ctx,
@@ -5,8 +5,6 @@ use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use turbo_tasks::TaskId;
 
-#[cfg(feature = "trace_task_dirty")]
-use crate::backend::operation::invalidate::TaskDirtyCause;
 use crate::{
     backend::{
         TaskDataCategory, get, get_many,
@@ -16,7 +14,7 @@ use crate::{
                 AggregationUpdateJob, AggregationUpdateQueue, InnerOfUppersLostFollowersJob,
                 get_aggregation_number, get_uppers, is_aggregating_node,
             },
-            invalidate::make_task_dirty,
+            invalidate::{TaskDirtyCause, make_task_dirty},
         },
         storage::update_count,
     },
@@ -47,7 +45,6 @@ pub enum OutdatedEdge {
     CollectiblesDependency(CollectiblesRef),
     RemovedCellDependent {
         task_id: TaskId,
-        #[cfg(feature = "trace_task_dirty")]
         value_type_id: turbo_tasks::ValueTypeId,
     },
 }
@@ -149,9 +146,9 @@ impl Operation for CleanupOldEdgesOperation {
                                     let task_ids = get_many!(task, CollectiblesDependent { collectible_type, task } if collectible_type == ty => { task });
                                     queue.push(
                                         AggregationUpdateJob::InvalidateDueToCollectiblesChange {
-                                            task_ids,
-                                            #[cfg(feature = "trace_task_dirty")]
+                                            task_id,
                                             collectible_type: ty,
+                                            task_ids,
                                         },
                                     );
                                 }
@@ -219,12 +216,10 @@ impl Operation for CleanupOldEdgesOperation {
                             }
                             OutdatedEdge::RemovedCellDependent {
                                 task_id,
-                                #[cfg(feature = "trace_task_dirty")]
                                 value_type_id,
                             } => {
                                 make_task_dirty(
                                     task_id,
-                                    #[cfg(feature = "trace_task_dirty")]
                                     TaskDirtyCause::CellRemoved {
                                         value_type: value_type_id,
                                     },
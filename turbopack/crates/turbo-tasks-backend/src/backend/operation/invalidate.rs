@@ -24,7 +24,6 @@ use crate::{
 pub enum InvalidateOperation {
     MakeDirty {
         task_ids: SmallVec<[TaskId; 4]>,
-        #[cfg(feature = "trace_task_dirty")]
         cause: TaskDirtyCause,
     },
     AggregationUpdate {
@@ -37,15 +36,10 @@ pub enum InvalidateOperation {
 impl InvalidateOperation {
     pub fn run(
         task_ids: SmallVec<[TaskId; 4]>,
-        #[cfg(feature = "trace_task_dirty")] cause: TaskDirtyCause,
+        cause: TaskDirtyCause,
         mut ctx: impl ExecuteContext,
     ) {
-        InvalidateOperation::MakeDirty {
-            task_ids,
-            #[cfg(feature = "trace_task_dirty")]
-            cause,
-        }
-        .execute(&mut ctx)
+        InvalidateOperation::MakeDirty { task_ids, cause }.execute(&mut ctx)
     }
 }
 
@@ -54,20 +48,10 @@ impl Operation for InvalidateOperation {
         loop {
             ctx.operation_suspend_point(&self);
             match self {
-                InvalidateOperation::MakeDirty {
-                    task_ids,
-                    #[cfg(feature = "trace_task_dirty")]
-                    cause,
-                } => {
+                InvalidateOperation::MakeDirty { task_ids, cause } => {
                     let mut queue = AggregationUpdateQueue::new();
                     for task_id in task_ids {
-                        make_task_dirty(
-                            task_id,
-                            #[cfg(feature = "trace_task_dirty")]
-                            cause,
-                            &mut queue,
-                            ctx,
-                        );
+                        make_task_dirty(task_id, cause, &mut queue, ctx);
                     }
                     if queue.is_empty() {
                         self = InvalidateOperation::Done
@@ -89,7 +73,6 @@ impl Operation for InvalidateOperation {
     }
 }
 
-#[cfg(feature = "trace_task_dirty")]
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 pub enum TaskDirtyCause {
     InitialDirty,
@@ -109,14 +92,12 @@ pub enum TaskDirtyCause {
     Unknown,
 }
 
-#[cfg(feature = "trace_task_dirty")]
 struct TaskDirtyCauseInContext<'l, 'e, E: ExecuteContext<'e>> {
     cause: &'l TaskDirtyCause,
     ctx: &'l E,
     _phantom: std::marker::PhantomData<&'e ()>,
 }
 
-#[cfg(feature = "trace_task_dirty")]
 impl<'l, 'e, E: ExecuteContext<'e>> TaskDirtyCauseInContext<'l, 'e, E> {
     fn new(cause: &'l TaskDirtyCause, ctx: &'l E) -> Self {
         Self {
@@ -127,7 +108,6 @@ impl<'l, 'e, E: ExecuteContext<'e>> TaskDirtyCauseInContext<'l, 'e, E> {
     }
 }
 
-#[cfg(feature = "trace_task_dirty")]
 impl<'e, E: ExecuteContext<'e>> std::fmt::Display for TaskDirtyCauseInContext<'_, 'e, E> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self.cause {
@@ -168,7 +148,7 @@ impl<'e, E: ExecuteContext<'e>> std::fmt::Display for TaskDirtyCauseInContext<'_
 
 pub fn make_task_dirty(
     task_id: TaskId,
-    #[cfg(feature = "trace_task_dirty")] cause: TaskDirtyCause,
+    cause: TaskDirtyCause,
     queue: &mut AggregationUpdateQueue,
     ctx: &mut impl ExecuteContext,
 ) {
@@ -178,22 +158,14 @@ pub fn make_task_dirty(
 
     let mut task = ctx.task(task_id, TaskDataCategory::Meta);
 
-    make_task_dirty_internal(
-        &mut task,
-        task_id,
-        true,
-        #[cfg(feature = "trace_task_dirty")]
-        cause,
-        queue,
-        ctx,
-    );
+    make_task_dirty_internal(&mut task, task_id, true, cause, queue, ctx);
 }
 
 pub fn make_task_dirty_internal(
     task: &mut impl TaskGuard,
     task_id: TaskId,
     make_stale: bool,
-    #[cfg(feature = "trace_task_dirty")] cause: TaskDirtyCause,
+    cause: TaskDirtyCause,
     queue: &mut AggregationUpdateQueue,
     ctx: &impl ExecuteContext,
 ) {
@@ -201,18 +173,11 @@ pub fn make_task_dirty_internal(
     // immutable.
     #[cfg(any(debug_assertions, feature = "verify_immutable"))]
     if task.is_immutable() {
-        #[cfg(feature = "trace_task_dirty")]
-        let extra_info = format!(
-            " Invalidation cause: {}",
-            TaskDirtyCauseInContext::new(&cause, ctx)
-        );
-        #[cfg(not(feature = "trace_task_dirty"))]
-        let extra_info = "";
-
         panic!(
-            "Task {} is immutable, but was made dirty. This should not happen and is a \
-             bug.{extra_info}",
+            "Task {} is immutable, but was made dirty. This should not happen and is a bug. \
+             Invalidation cause: {}",
             ctx.get_task_description(task_id),
+            TaskDirtyCauseInContext::new(&cause, ctx)
         );
     }
 
@@ -228,6 +193,9 @@ pub fn make_task_dirty_internal(
             cause = %TaskDirtyCauseInContext::new(&cause, ctx)
         )
         .entered();
+        ctx.record_dirty_cause(task_id, || {
+            TaskDirtyCauseInContext::new(&cause, ctx).to_string()
+        });
         *stale = true;
     }
     let old = task.insert(CachedDataItem::Dirty {
@@ -279,6 +247,9 @@ pub fn make_task_dirty_internal(
         cause = %TaskDirtyCauseInContext::new(&cause, ctx)
     )
     .entered();
+    ctx.record_dirty_cause(task_id, || {
+        TaskDirtyCauseInContext::new(&cause, ctx).to_string()
+    });
 
     let should_schedule = if ctx.should_track_children() {
         let aggregated_update = dirty_container.update_with_dirty_state(&DirtyState {
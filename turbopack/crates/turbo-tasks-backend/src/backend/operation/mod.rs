@@ -14,7 +14,9 @@ use std::{
 };
 
 use serde::{Deserialize, Serialize};
-use turbo_tasks::{KeyValuePair, SessionId, TaskId, TurboTasksBackendApi};
+use turbo_tasks::{
+    KeyValuePair, SessionId, TaskId, TurboTasksBackendApi, update_trace::InvalidationSource,
+};
 
 use crate::{
     backend::{
@@ -62,6 +64,11 @@ pub trait ExecuteContext<'e>: Sized {
     fn suspending_requested(&self) -> bool;
     fn get_task_desc_fn(&self, task_id: TaskId) -> impl Fn() -> String + Send + Sync + 'static;
     fn get_task_description(&self, task_id: TaskId) -> String;
+    /// Records why the task was made dirty, when introspection is enabled. `cause` is only called
+    /// in that case.
+    fn record_dirty_cause(&self, task_id: TaskId, cause: impl FnOnce() -> String);
+    /// Records the invalidation in the update trace, when it's enabled.
+    fn trace_invalidation(&self, source: InvalidationSource, tasks: &[TaskId]);
     fn should_track_children(&self) -> bool;
     fn should_track_dependencies(&self) -> bool;
     fn should_track_activeness(&self) -> bool;
@@ -268,6 +275,16 @@ where
         self.backend.get_task_description(task_id)
     }
 
+    fn record_dirty_cause(&self, task_id: TaskId, cause: impl FnOnce() -> String) {
+        self.backend.record_dirty_cause(task_id, cause)
+    }
+
+    fn trace_invalidation(&self, source: InvalidationSource, tasks: &[TaskId]) {
+        if let Some(update_trace) = self.turbo_tasks.update_trace().get() {
+            update_trace.record_invalidation(source, tasks);
+        }
+    }
+
     fn should_track_children(&self) -> bool {
         self.backend.should_track_children()
     }
@@ -565,8 +582,6 @@ impl_operation!(UpdateOutput update_output::UpdateOutputOperation);
 impl_operation!(CleanupOldEdges cleanup_old_edges::CleanupOldEdgesOperation);
 impl_operation!(AggregationUpdate aggregation_update::AggregationUpdateQueue);
 
-#[cfg(feature = "trace_task_dirty")]
-pub use self::invalidate::TaskDirtyCause;
 pub use self::{
     aggregation_update::{
         AggregatedDataUpdate, AggregationUpdateJob, get_aggregation_number, get_uppers,
@@ -574,6 +589,7 @@ pub use self::{
     },
     cleanup_old_edges::OutdatedEdge,
     connect_children::connect_children,
+    invalidate::TaskDirtyCause,
     prepare_new_children::prepare_new_children,
     update_cell::UpdateCellOperation,
     update_collectible::UpdateCollectibleOperation,
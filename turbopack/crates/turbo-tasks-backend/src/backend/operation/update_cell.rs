@@ -1,11 +1,10 @@
-use turbo_tasks::{CellId, TaskId, backend::CellContent};
+use smallvec::SmallVec;
+use turbo_tasks::{CellId, TaskId, backend::CellContent, update_trace::InvalidationSource};
 
-#[cfg(feature = "trace_task_dirty")]
-use crate::backend::operation::invalidate::TaskDirtyCause;
 use crate::{
     backend::{
         TaskDataCategory,
-        operation::{ExecuteContext, InvalidateOperation, TaskGuard},
+        operation::{ExecuteContext, InvalidateOperation, TaskGuard, invalidate::TaskDirtyCause},
         storage::{get_many, remove},
     },
     data::{CachedDataItem, CachedDataItemKey},
@@ -39,7 +38,7 @@ impl UpdateCellOperation {
                 // This is a hack for the streaming hack. Stateful tasks are never recomputed, so this forces invalidation for them in case of this hack.
                 task.has_key(&CachedDataItemKey::Stateful {}))
         {
-            let dependent = get_many!(
+            let dependent: SmallVec<[TaskId; 4]> = get_many!(
                 task,
                 CellDependent { cell: dependent_cell, task }
                 if dependent_cell == cell
@@ -49,9 +48,9 @@ impl UpdateCellOperation {
             drop(task);
             drop(old_content);
 
+            ctx.trace_invalidation(InvalidationSource::Cell(task_id, cell.type_id), &dependent);
             InvalidateOperation::run(
                 dependent,
-                #[cfg(feature = "trace_task_dirty")]
                 TaskDirtyCause::CellChange {
                     value_type: cell.type_id,
                 },
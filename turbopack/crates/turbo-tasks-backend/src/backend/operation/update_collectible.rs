@@ -84,9 +84,9 @@ impl UpdateCollectibleOperation {
                 );
                 if !dependent.is_empty() {
                     queue.push(AggregationUpdateJob::InvalidateDueToCollectiblesChange {
-                        task_ids: dependent,
-                        #[cfg(feature = "trace_task_dirty")]
+                        task_id,
                         collectible_type: ty,
+                        task_ids: dependent,
                     })
                 }
             }
@@ -3,16 +3,16 @@ use std::mem::take;
 use anyhow::Result;
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
-use turbo_tasks::{RawVc, TaskId, backend::TurboTasksExecutionError};
+use turbo_tasks::{
+    RawVc, TaskId, backend::TurboTasksExecutionError, update_trace::InvalidationSource,
+};
 
-#[cfg(feature = "trace_task_dirty")]
-use crate::backend::operation::invalidate::TaskDirtyCause;
 use crate::{
     backend::{
         TaskDataCategory,
         operation::{
             AggregationUpdateQueue, ExecuteContext, Operation, TaskGuard,
-            invalidate::{make_task_dirty, make_task_dirty_internal},
+            invalidate::{TaskDirtyCause, make_task_dirty, make_task_dirty_internal},
         },
         storage::{get, get_many},
     },
@@ -25,7 +25,6 @@ use crate::{
 #[derive(Serialize, Deserialize, Clone, Default)]
 pub enum UpdateOutputOperation {
     MakeDependentTasksDirty {
-        #[cfg(feature = "trace_task_dirty")]
         task_id: TaskId,
         dependent_tasks: SmallVec<[TaskId; 4]>,
         children: SmallVec<[TaskId; 4]>,
@@ -48,7 +47,7 @@ impl UpdateOutputOperation {
         output: Result<RawVc, TurboTasksExecutionError>,
         mut ctx: impl ExecuteContext,
     ) {
-        let mut dependent_tasks = Default::default();
+        let mut dependent_tasks: SmallVec<[TaskId; 4]> = Default::default();
         let mut children = Default::default();
         let mut queue = AggregationUpdateQueue::new();
 
@@ -113,13 +112,13 @@ impl UpdateOutputOperation {
 
             if ctx.should_track_dependencies() {
                 dependent_tasks = get_many!(task, OutputDependent { task } => task);
+                ctx.trace_invalidation(InvalidationSource::Output(task_id), &dependent_tasks);
             }
 
             make_task_dirty_internal(
                 &mut task,
                 task_id,
                 false,
-                #[cfg(feature = "trace_task_dirty")]
                 TaskDirtyCause::InitialDirty,
                 &mut queue,
                 &ctx,
@@ -130,7 +129,6 @@ impl UpdateOutputOperation {
         }
 
         UpdateOutputOperation::MakeDependentTasksDirty {
-            #[cfg(feature = "trace_task_dirty")]
             task_id,
             dependent_tasks,
             children,
@@ -146,7 +144,6 @@ impl Operation for UpdateOutputOperation {
             ctx.operation_suspend_point(&self);
             match self {
                 UpdateOutputOperation::MakeDependentTasksDirty {
-                    #[cfg(feature = "trace_task_dirty")]
                     task_id,
                     ref mut dependent_tasks,
                     ref mut children,
@@ -155,7 +152,6 @@ impl Operation for UpdateOutputOperation {
                     if let Some(dependent_task_id) = dependent_tasks.pop() {
                         make_task_dirty(
                             dependent_task_id,
-                            #[cfg(feature = "trace_task_dirty")]
                             TaskDirtyCause::OutputChange { task_id },
                             queue,
                             ctx,
@@ -179,7 +175,6 @@ impl Operation for UpdateOutputOperation {
                                 &mut child_task,
                                 child_id,
                                 false,
-                                #[cfg(feature = "trace_task_dirty")]
                                 TaskDirtyCause::InitialDirty,
                                 queue,
                                 ctx,
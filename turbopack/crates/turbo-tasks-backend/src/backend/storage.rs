@@ -99,6 +99,8 @@ bitfield! {
     /// Item was modified after snapshot mode was entered. A snapshot was taken.
     pub meta_snapshot, set_meta_snapshot: 4;
     pub data_snapshot, set_data_snapshot: 5;
+    /// Item was accessed since the last eviction pass.
+    pub accessed, set_accessed: 6;
 }
 
 impl InnerStorageState {
@@ -785,10 +787,11 @@ impl Storage {
     }
 
     pub fn access_mut(&self, key: TaskId) -> StorageWriteGuard<'_> {
-        let inner = match self.map.entry(key) {
+        let mut inner = match self.map.entry(key) {
             dashmap::mapref::entry::Entry::Occupied(e) => e.into_ref(),
             dashmap::mapref::entry::Entry::Vacant(e) => e.insert(Box::new(InnerStorage::new())),
         };
+        inner.state_mut().set_accessed(true);
         StorageWriteGuard {
             storage: self,
             inner: inner.into(),
@@ -800,7 +803,10 @@ impl Storage {
         key1: TaskId,
         key2: TaskId,
     ) -> (StorageWriteGuard<'_>, StorageWriteGuard<'_>) {
-        let (a, b) = get_multiple_mut(&self.map, key1, key2, || Box::new(InnerStorage::new()));
+        let (mut a, mut b) =
+            get_multiple_mut(&self.map, key1, key2, || Box::new(InnerStorage::new()));
+        a.state_mut().set_accessed(true);
+        b.state_mut().set_accessed(true);
         (
             StorageWriteGuard {
                 storage: self,
@@ -812,6 +818,80 @@ impl Storage {
             },
         )
     }
+
+    /// Marks the given tasks as modified again, so that they are persisted by the next snapshot.
+    /// Used when persisting them failed, as they are considered as persisted by a snapshot
+    /// otherwise. Must not be called in snapshot mode.
+    pub fn mark_modified(&self, task_ids: impl IntoIterator<Item = TaskId>) {
+        for task_id in task_ids {
+            if let Some(mut inner) = self.map.get_mut(&task_id) {
+                self.mark_inner_modified(task_id, &mut inner);
+            }
+        }
+    }
+
+    /// Like [`Storage::mark_modified`], for all persistent tasks in memory.
+    pub fn mark_all_modified(&self) {
+        for mut entry in self.map.iter_mut() {
+            let task_id = *entry.key();
+            if !task_id.is_transient() {
+                self.mark_inner_modified(task_id, entry.value_mut());
+            }
+        }
+    }
+
+    fn mark_inner_modified(&self, task_id: TaskId, inner: &mut InnerStorage) {
+        debug_assert!(!self.snapshot_mode());
+        let state = inner.state_mut();
+        if !state.any_snapshot() && !state.any_modified() {
+            self.modified.insert(task_id, ModifiedState::Modified);
+        }
+        // Only restored data is persisted, see the `preprocess` step of the snapshot
+        state.set_meta_modified(true);
+        state.set_data_modified(true);
+    }
+
+    /// Calls `f` for every task in memory. `f` must not access the storage, as parts of it are
+    /// locked during the iteration.
+    pub fn for_each_task(&self, mut f: impl FnMut(TaskId, &InnerStorage)) {
+        for entry in self.map.iter() {
+            f(*entry.key(), entry.value());
+        }
+    }
+
+    /// Removes the tasks from memory that weren't accessed since the previous eviction pass and
+    /// can be restored from the backing storage, because all their data is persistent and wasn't
+    /// modified since it was persisted. All other tasks are marked as not accessed, so the next
+    /// pass evicts them unless they are accessed in between.
+    ///
+    /// Must only be called after a snapshot was persisted successfully, since modifications are
+    /// considered as persisted as soon as they are part of a snapshot. Returns the number of
+    /// evicted tasks.
+    pub fn evict_cold_tasks(&self) -> usize {
+        if self.snapshot_mode() {
+            return 0;
+        }
+        let mut evicted = 0;
+        self.map.retain(|task_id, inner| {
+            let state = inner.state_mut();
+            if state.accessed() {
+                state.set_accessed(false);
+                return true;
+            }
+            if task_id.is_transient() || state.any_modified() || state.any_snapshot() {
+                return true;
+            }
+            // Non-persistent items, like in progress executions or activeness, would be lost.
+            let restorable = inner
+                .iter_all()
+                .all(|(key, value)| key.is_persistent() && value.is_persistent());
+            if restorable {
+                evicted += 1;
+            }
+            !restorable
+        });
+        evicted
+    }
 }
 
 pub struct StorageWriteGuard<'a> {
@@ -1145,3 +1225,58 @@ where
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use turbo_tasks::{TRANSIENT_TASK_BIT, TaskId};
+
+    use super::{SpecificTaskDataCategory, Storage};
+    use crate::data::CachedDataItem;
+
+    fn task_id(id: u32) -> TaskId {
+        TaskId::try_from(id).unwrap()
+    }
+
+    fn add_child(storage: &Storage, task: TaskId, child: TaskId) {
+        storage.access_mut(task).add(CachedDataItem::Child {
+            task: child,
+            value: (),
+        });
+    }
+
+    #[test]
+    fn evict_cold_tasks() {
+        let storage = Storage::new(true);
+        let transient = task_id(TRANSIENT_TASK_BIT | 1);
+        // Only persistent data
+        add_child(&storage, task_id(1), task_id(2));
+        // References a transient task, which isn't persisted
+        add_child(&storage, task_id(3), transient);
+        // Transient tasks are never persisted
+        add_child(&storage, transient, task_id(1));
+
+        // All tasks were accessed since the last pass
+        assert_eq!(storage.evict_cold_tasks(), 0);
+
+        add_child(&storage, task_id(4), task_id(1));
+        assert_eq!(storage.evict_cold_tasks(), 1);
+        assert!(!storage.map.contains_key(&task_id(1)));
+        assert!(storage.map.contains_key(&task_id(3)));
+        assert!(storage.map.contains_key(&transient));
+
+        // Modified tasks are kept until they are persisted
+        storage
+            .access_mut(task_id(4))
+            .track_modification(SpecificTaskDataCategory::Data);
+        assert_eq!(storage.evict_cold_tasks(), 0);
+        assert_eq!(storage.evict_cold_tasks(), 0);
+        assert!(storage.map.contains_key(&task_id(4)));
+
+        // Tasks that failed to persist are kept too
+        storage.mark_modified([task_id(3)]);
+        storage.mark_all_modified();
+        assert_eq!(storage.evict_cold_tasks(), 0);
+        assert!(storage.map.contains_key(&task_id(3)));
+        assert!(storage.modified.contains_key(&task_id(3)));
+    }
+}
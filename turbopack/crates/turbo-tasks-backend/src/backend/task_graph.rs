@@ -0,0 +1,190 @@
+use std::{fmt::Write, time::Duration};
+
+use serde::Serialize;
+use turbo_tasks::{InvalidationReason, TaskId, util::StaticOrArc};
+
+/// What is recorded about the executions of a task when [`BackendOptions::introspection`] is
+/// enabled.
+///
+/// [`BackendOptions::introspection`]: super::BackendOptions::introspection
+#[derive(Default)]
+pub(super) struct TaskExecutionInfo {
+    pub executions: u32,
+    pub last_reason: Option<&'static str>,
+    pub last_duration: Option<Duration>,
+    /// Why the task was made dirty since its last execution.
+    pub dirty_cause: Option<String>,
+    /// The reason passed to the invalidator, when the task was invalidated with one since its
+    /// last execution.
+    pub invalidation_reason: Option<StaticOrArc<dyn InvalidationReason>>,
+    /// The `dirty_cause` that led to the last execution.
+    pub last_dirty_cause: Option<String>,
+    /// The `invalidation_reason` that led to the last execution.
+    pub last_invalidation_reason: Option<StaticOrArc<dyn InvalidationReason>>,
+}
+
+/// A snapshot of the tasks in memory and their edges, created by
+/// [`TurboTasksBackend::task_graph`].
+///
+/// Tasks that weren't restored from the persistent cache yet aren't part of the graph, but can
+/// show up as the target of edges.
+///
+/// [`TurboTasksBackend::task_graph`]: super::TurboTasksBackend::task_graph
+#[derive(Debug, Default, Serialize)]
+pub struct TaskGraph {
+    pub tasks: Vec<TaskGraphNode>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TaskGraphNode {
+    pub id: TaskId,
+    /// The name of the task's function.
+    pub name: String,
+    /// The debug representation of the `self` argument and the arguments of the task. `None` for
+    /// transient tasks.
+    pub arguments: Option<String>,
+    pub dirty: bool,
+    pub children: Vec<TaskId>,
+    pub dependencies: Vec<TaskGraphDependency>,
+    /// The number of executions in this session. This and the following fields are only recorded
+    /// with `BackendOptions::introspection` enabled.
+    pub executions: u32,
+    pub last_execution_reason: Option<&'static str>,
+    pub last_execution_duration_us: Option<u64>,
+    /// Why the task was made dirty before its last execution.
+    pub last_dirty_cause: Option<String>,
+    /// The reason passed to the invalidator, when the last execution was caused by an
+    /// invalidation with a reason, e.g. a file change.
+    pub last_invalidation_reason: Option<String>,
+}
+
+/// What a task read from another task during its last execution.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum TaskGraphDependency {
+    Output {
+        task: TaskId,
+    },
+    Cell {
+        task: TaskId,
+        cell: String,
+    },
+    Collectibles {
+        task: TaskId,
+        collectible_type: &'static str,
+    },
+}
+
+impl TaskGraphDependency {
+    pub fn task(&self) -> TaskId {
+        match self {
+            TaskGraphDependency::Output { task }
+            | TaskGraphDependency::Cell { task, .. }
+            | TaskGraphDependency::Collectibles { task, .. } => *task,
+        }
+    }
+}
+
+impl TaskGraph {
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Renders the graph in the Graphviz DOT format. Child edges are solid and point from the
+    /// parent to the child, dependency edges are dashed and point from the reading task to the
+    /// task that was read. Dirty tasks are red. Nodes are identified by the numeric task id.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph tasks {\n");
+        for task in &self.tasks {
+            let mut label = escape_dot(&task.name);
+            if let Some(duration) = task.last_execution_duration_us {
+                write!(label, "\\n{} runs, last {duration}us", task.executions).unwrap();
+            }
+            let color = if task.dirty { ", color=red" } else { "" };
+            writeln!(dot, "  {} [label=\"{label}\"{color}];", *task.id).unwrap();
+        }
+        for task in &self.tasks {
+            for child in &task.children {
+                writeln!(dot, "  {} -> {};", *task.id, **child).unwrap();
+            }
+            for dependency in &task.dependencies {
+                let label = match dependency {
+                    TaskGraphDependency::Output { .. } => "output".to_string(),
+                    TaskGraphDependency::Cell { cell, .. } => escape_dot(cell),
+                    TaskGraphDependency::Collectibles {
+                        collectible_type, ..
+                    } => escape_dot(collectible_type),
+                };
+                writeln!(
+                    dot,
+                    "  {} -> {} [style=dashed, label=\"{label}\"];",
+                    *task.id,
+                    *dependency.task()
+                )
+                .unwrap();
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn node(id: u32, name: &str) -> TaskGraphNode {
+        TaskGraphNode {
+            id: TaskId::try_from(id).unwrap(),
+            name: name.to_string(),
+            arguments: None,
+            dirty: false,
+            children: Vec::new(),
+            dependencies: Vec::new(),
+            executions: 0,
+            last_execution_reason: None,
+            last_execution_duration_us: None,
+            last_dirty_cause: None,
+            last_invalidation_reason: None,
+        }
+    }
+
+    #[test]
+    fn dot() {
+        let mut root = node(1, "root");
+        root.children.push(TaskId::try_from(2).unwrap());
+        let mut child = node(2, "read \"config\"");
+        child.dirty = true;
+        child.executions = 2;
+        child.last_execution_duration_us = Some(15);
+        child.dependencies = vec![
+            TaskGraphDependency::Output {
+                task: TaskId::try_from(3).unwrap(),
+            },
+            TaskGraphDependency::Cell {
+                task: TaskId::try_from(3).unwrap(),
+                cell: "Config#0".to_string(),
+            },
+        ];
+        let graph = TaskGraph {
+            tasks: vec![root, child, node(3, "config")],
+        };
+
+        assert_eq!(
+            graph.to_dot(),
+            r#"digraph tasks {
+  1 [label="root"];
+  2 [label="read \"config\"\n2 runs, last 15us", color=red];
+  3 [label="config"];
+  1 -> 2;
+  2 -> 3 [style=dashed, label="output"];
+  2 -> 3 [style=dashed, label="Config#0"];
+}
+"#
+        );
+    }
+}
@@ -29,6 +29,7 @@ impl<T> ByKeySpace<T> {
         }
     }
 
+    #[cfg(feature = "lmdb")]
     pub fn get_mut(&mut self, key_space: KeySpace) -> &mut T {
         match key_space {
             KeySpace::Infra => &mut self.infra,
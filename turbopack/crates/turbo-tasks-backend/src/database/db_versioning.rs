@@ -1,4 +1,5 @@
 use std::{
+    borrow::Cow,
     env,
     ffi::{OsStr, OsString},
     fs::{DirEntry, read_dir, remove_dir_all, rename},
@@ -28,7 +29,22 @@ const DEFAULT_MAX_OTHER_DB_VERSIONS: usize = 2;
 
 /// Directories are prefixed with this before being deleted, so that if we fail to fully delete the
 /// directory, we can pick up where we left off last time.
-const DELETION_PREFIX: &str = "__stale_";
+pub(crate) const DELETION_PREFIX: &str = "__stale_";
+
+/// Returns the name of the version directory for the given `version_info`, or `None` if persistent
+/// caching is disabled because the git repository is dirty. See [`handle_db_versioning`] for the
+/// environment variables that are respected.
+pub fn db_version<'a>(version_info: &GitVersionInfo<'a>) -> Option<Cow<'a, str>> {
+    if let Ok(version) = env::var("TURBO_ENGINE_VERSION") {
+        Some(Cow::Owned(version))
+    } else if env::var("TURBO_ENGINE_DISABLE_VERSIONING").is_ok() {
+        Some(Cow::Borrowed("unversioned"))
+    } else if !version_info.dirty || env::var("TURBO_ENGINE_IGNORE_DIRTY").is_ok() {
+        Some(Cow::Borrowed(version_info.describe))
+    } else {
+        None
+    }
+}
 
 /// Given a base path, creates a version directory for the given `version_info`. Automatically
 /// cleans up old/stale databases.
@@ -47,32 +63,26 @@ pub fn handle_db_versioning(
     if let Ok(version) = env::var("TURBO_ENGINE_VERSION") {
         return Ok(base_path.join(version));
     }
-    let ignore_dirty = env::var("TURBO_ENGINE_IGNORE_DIRTY").ok().is_some();
-    let disabled_versioning = env::var("TURBO_ENGINE_DISABLE_VERSIONING").ok().is_some();
-    let version = if disabled_versioning {
+    let version = db_version(version_info);
+    if env::var("TURBO_ENGINE_DISABLE_VERSIONING").is_ok() {
         println!(
             "WARNING: Persistent Caching versioning is disabled. Manual removal of the persistent \
              caching database might be required."
         );
-        Some("unversioned")
-    } else if !version_info.dirty {
-        Some(version_info.describe)
-    } else if ignore_dirty {
+    } else if version_info.dirty && version.is_some() {
         println!(
             "WARNING: The git repository is dirty, but Persistent Caching is still enabled. \
              Manual removal of the persistent caching database might be required."
         );
-        Some(version_info.describe)
-    } else {
+    } else if version_info.dirty {
         println!(
             "WARNING: The git repository is dirty: Persistent Caching is disabled. Use \
              TURBO_ENGINE_IGNORE_DIRTY=1 to ignore dirtiness of the repository."
         );
-        None
-    };
+    }
     let path;
     if let Some(version) = version {
-        path = base_path.join(version);
+        path = base_path.join(&*version);
 
         let max_other_db_versions = if is_ci {
             0
@@ -87,7 +97,7 @@ pub fn handle_db_versioning(
 
                 // skip our target version (if it exists)
                 let name = entry.file_name();
-                if name == version {
+                if name == *version {
                     continue;
                 }
 
@@ -1,4 +1,3 @@
-#[cfg(feature = "lmdb")]
 mod by_key_space;
 pub mod db_invalidation;
 pub mod db_versioning;
@@ -10,6 +9,7 @@ pub mod lmdb;
 pub mod noop_kv;
 #[cfg(feature = "lmdb")]
 pub mod read_transaction_cache;
+pub mod shared_cache;
 #[cfg(feature = "lmdb")]
 pub mod startup_cache;
 pub mod turbo;
@@ -0,0 +1,1333 @@
+use std::{
+    borrow::Borrow,
+    collections::BTreeMap,
+    env,
+    fs::{self, File, OpenOptions, create_dir_all, read_dir, remove_dir_all, rename},
+    hash::Hasher,
+    io::{BufReader, ErrorKind, Read, Write},
+    ops::Range,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, LazyLock,
+        atomic::{AtomicBool, Ordering},
+        mpsc::sync_channel,
+    },
+};
+
+use anyhow::{Context, Result, bail};
+use byteorder::{BE, ReadBytesExt, WriteBytesExt};
+use parking_lot::Mutex;
+use reqwest::{Method, StatusCode};
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
+use thread_local::ThreadLocal;
+use tokio::runtime::Runtime;
+use turbo_tasks::FxDashMap;
+use twox_hash::XxHash64;
+
+use crate::database::{
+    by_key_space::ByKeySpace,
+    db_versioning::{GitVersionInfo, db_version},
+    key_value_database::{KeySpace, KeyValueDatabase},
+    write_batch::{
+        BaseWriteBatch, ConcurrentWriteBatch, SerialWriteBatch, WriteBatch, WriteBuffer,
+    },
+};
+
+/// Bump this when the layout of shared cache objects changes.
+const SHARED_CACHE_FORMAT_VERSION: u32 = 2;
+
+/// The number of variants of [`KeySpace`].
+const KEY_SPACE_COUNT: usize = 5;
+
+/// The entries of each key space are spread over this many buckets by the hash of their key. A
+/// bucket is the unit that is read from and written to the shared cache.
+const BUCKET_COUNT: usize = 256;
+
+/// The size of the buckets that are kept in memory after reading them. When the limit is exceeded,
+/// the least recently used buckets are dropped and read again when they are needed.
+const BUCKET_CACHE_SIZE: usize = 512 * 1024 * 1024;
+
+/// Records which shared snapshot a local database is layered on. The database ignores files
+/// starting with a dot.
+const BASE_FILE: &str = ".shared-cache-base";
+
+/// Lists the keys that were written to a layered local database.
+const JOURNAL_FILE: &str = ".shared-cache-journal";
+
+/// A shared cache that stores snapshots of the persistent cache database, so that a build can be
+/// warm-started from a database that another machine produced.
+///
+/// The shared cache is a store of content-addressed objects:
+///
+/// - `objects/<hash>`: buckets of key-value pairs and the manifests listing them. Objects are named
+///   by the hash and size of their content and never modified.
+/// - `refs/<version>`: the name of the latest manifest for a database version.
+///
+/// Objects are never deleted by turbo-tasks. A store that grows too large can be pruned by
+/// removing it, databases layered on a removed snapshot fall back to an empty database.
+pub struct SharedCacheConfig {
+    /// Where the shared cache is stored.
+    pub location: SharedCacheLocation,
+    /// Publish the local database to the shared cache when shutting down.
+    pub publish: bool,
+}
+
+/// The store of a [`SharedCacheConfig`].
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SharedCacheLocation {
+    /// A directory, e.g. a mounted network drive or a directory restored by a CI cache step.
+    Directory(PathBuf),
+    /// An `http://` or `https://` URL of a server that stores the objects and refs below it.
+    /// `GET` and `HEAD` respond with 404 for missing paths, `PUT` stores the request body.
+    Http(String),
+}
+
+impl SharedCacheConfig {
+    /// Reads the shared cache configuration from the environment.
+    ///
+    /// **Environment Variables**
+    /// - `TURBO_ENGINE_SHARED_CACHE_DIR`: Enables the shared cache at the given directory.
+    /// - `TURBO_ENGINE_SHARED_CACHE_URL`: Enables the shared cache at the given HTTP(S) URL.
+    ///   Ignored when `TURBO_ENGINE_SHARED_CACHE_DIR` is set.
+    /// - `TURBO_ENGINE_SHARED_CACHE_PUBLISH`: Publishes the database to the shared cache on
+    ///   shutdown.
+    pub fn from_env() -> Option<Self> {
+        let location = if let Some(path) =
+            env::var_os("TURBO_ENGINE_SHARED_CACHE_DIR").filter(|p| !p.is_empty())
+        {
+            SharedCacheLocation::Directory(PathBuf::from(path))
+        } else {
+            let url = env::var("TURBO_ENGINE_SHARED_CACHE_URL")
+                .ok()
+                .filter(|url| !url.is_empty())?;
+            SharedCacheLocation::Http(url.trim_end_matches('/').to_string())
+        };
+        Some(Self {
+            location,
+            publish: env::var_os("TURBO_ENGINE_SHARED_CACHE_PUBLISH").is_some_and(|value| {
+                !["".as_ref(), "0".as_ref(), "false".as_ref()].contains(&&*value)
+            }),
+        })
+    }
+}
+
+/// The shared cache for a database version.
+pub struct SharedCache {
+    store: SharedStore,
+    publish: bool,
+    /// The version that is stored in (and validated against) the manifest.
+    version: String,
+    /// The name of the ref.
+    key: String,
+}
+
+impl SharedCache {
+    /// Returns the shared cache for the given `version_info`, using the version of the local
+    /// database as returned by [`db_version`].
+    ///
+    /// Returns `None` when persistent caching is disabled for a dirty git repository, or when
+    /// versioning is disabled. An "unversioned" database can't be matched to the build that
+    /// produced it and is never shared.
+    pub fn new(config: SharedCacheConfig, version_info: &GitVersionInfo) -> Option<Self> {
+        if env::var_os("TURBO_ENGINE_VERSION").is_none()
+            && env::var_os("TURBO_ENGINE_DISABLE_VERSIONING").is_some()
+        {
+            return None;
+        }
+        let version = db_version(version_info)?.into_owned();
+        let key = format!(
+            "v{SHARED_CACHE_FORMAT_VERSION}-{}",
+            version
+                .chars()
+                .map(|c| {
+                    if c.is_ascii_alphanumeric() || c == '.' || c == '-' {
+                        c
+                    } else {
+                        '_'
+                    }
+                })
+                .collect::<String>()
+        );
+        Some(Self {
+            store: SharedStore {
+                location: config.location,
+            },
+            publish: config.publish,
+            version,
+            key,
+        })
+    }
+
+    /// Returns the latest snapshot for this version, or `None` on a miss. An incomplete or
+    /// incompatible snapshot is treated as a miss.
+    fn latest_snapshot(&self) -> Result<Option<SharedSnapshot>> {
+        let Some(manifest_name) = self.store.read_ref(&self.key)? else {
+            return Ok(None);
+        };
+        SharedSnapshot::open(self.store.clone(), manifest_name, &self.version)
+    }
+}
+
+/// Runs the requests to [`SharedCacheLocation::Http`] stores. The backend reads from the shared
+/// cache synchronously while restoring tasks, which also happens on tokio worker threads that must
+/// not block on their own runtime.
+static HTTP_RUNTIME: LazyLock<Runtime> = LazyLock::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .worker_threads(1)
+        .thread_name("turbo-tasks-shared-cache")
+        .enable_all()
+        .build()
+        .expect("failed to create the shared cache runtime")
+});
+
+static HTTP_CLIENT: LazyLock<reqwest::Client> = LazyLock::new(reqwest::Client::new);
+
+/// Sends a request on the [`HTTP_RUNTIME`] and waits for the full response.
+fn http_request(
+    method: Method,
+    url: String,
+    body: Option<Vec<u8>>,
+) -> Result<(StatusCode, Vec<u8>)> {
+    let (tx, rx) = sync_channel(1);
+    HTTP_RUNTIME.spawn(async move {
+        let result = async {
+            let mut request = HTTP_CLIENT.request(method, &url);
+            if let Some(body) = body {
+                request = request.body(body);
+            }
+            let response = request.send().await?;
+            let status = response.status();
+            let body = response.bytes().await?;
+            anyhow::Ok((status, body.to_vec()))
+        }
+        .await;
+        // The caller only stops waiting when the runtime shuts down
+        let _ = tx.send(result);
+    });
+    rx.recv().context("the shared cache runtime stopped")?
+}
+
+/// The objects and refs of a [`SharedCacheConfig`].
+#[derive(Clone)]
+struct SharedStore {
+    location: SharedCacheLocation,
+}
+
+impl SharedStore {
+    /// Returns `None` when the file doesn't exist.
+    fn read(&self, dir: &str, name: &str) -> Result<Option<Vec<u8>>> {
+        match &self.location {
+            SharedCacheLocation::Directory(path) => match fs::read(path.join(dir).join(name)) {
+                Ok(content) => Ok(Some(content)),
+                Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+                Err(err) => Err(err.into()),
+            },
+            SharedCacheLocation::Http(url) => {
+                let (status, body) =
+                    http_request(Method::GET, format!("{url}/{dir}/{name}"), None)?;
+                match status {
+                    StatusCode::NOT_FOUND => Ok(None),
+                    _ if status.is_success() => Ok(Some(body)),
+                    status => bail!("GET {url}/{dir}/{name} responded with {status}"),
+                }
+            }
+        }
+    }
+
+    fn exists(&self, dir: &str, name: &str) -> Result<bool> {
+        match &self.location {
+            SharedCacheLocation::Directory(path) => Ok(path.join(dir).join(name).is_file()),
+            SharedCacheLocation::Http(url) => {
+                let (status, _) = http_request(Method::HEAD, format!("{url}/{dir}/{name}"), None)?;
+                match status {
+                    StatusCode::NOT_FOUND => Ok(false),
+                    _ if status.is_success() => Ok(true),
+                    status => bail!("HEAD {url}/{dir}/{name} responded with {status}"),
+                }
+            }
+        }
+    }
+
+    fn write(&self, dir: &str, name: &str, content: &[u8]) -> Result<()> {
+        match &self.location {
+            SharedCacheLocation::Directory(path) => {
+                write_atomically(&path.join(dir).join(name), content)
+            }
+            SharedCacheLocation::Http(url) => {
+                let (status, _) = http_request(
+                    Method::PUT,
+                    format!("{url}/{dir}/{name}"),
+                    Some(content.to_vec()),
+                )?;
+                if !status.is_success() {
+                    bail!("PUT {url}/{dir}/{name} responded with {status}");
+                }
+                Ok(())
+            }
+        }
+    }
+
+    fn has_object(&self, name: &str) -> Result<bool> {
+        self.exists("objects", name)
+    }
+
+    fn read_object(&self, name: &str) -> Result<Vec<u8>> {
+        let content = self
+            .read("objects", name)
+            .and_then(|content| content.context("not found"))
+            .with_context(|| format!("failed to read {name} from shared cache"))?;
+        if object_name(&content) != name {
+            bail!("{name} in shared cache is corrupted");
+        }
+        Ok(content)
+    }
+
+    /// Stores `content` unless an object with the same content exists, and returns its name.
+    fn write_object(&self, content: &[u8]) -> Result<String> {
+        let name = object_name(content);
+        if !self.has_object(&name)? {
+            self.write("objects", &name, content)?;
+        }
+        Ok(name)
+    }
+
+    fn read_ref(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .read("refs", key)?
+            .map(|name| String::from_utf8_lossy(&name).trim().to_string()))
+    }
+
+    fn write_ref(&self, key: &str, name: &str) -> Result<()> {
+        self.write("refs", key, name.as_bytes())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct SharedCacheManifest {
+    format_version: u32,
+    version: String,
+    /// The names of the bucket objects, indexed by [`KeySpace`] and bucket.
+    key_spaces: Vec<Vec<String>>,
+}
+
+/// The content of a bucket object and the ranges of the values in it.
+pub struct Bucket {
+    content: Vec<u8>,
+    values: FxHashMap<Box<[u8]>, Range<usize>>,
+}
+
+impl Bucket {
+    fn get(&self, key: &[u8]) -> Option<Range<usize>> {
+        self.values.get(key).cloned()
+    }
+
+    fn iter(&self) -> impl Iterator<Item = (&[u8], &[u8])> {
+        self.values
+            .iter()
+            .map(|(key, range)| (&**key, &self.content[range.clone()]))
+    }
+}
+
+/// The buckets of a [`SharedSnapshot`] that are held in memory, limited to [`BUCKET_CACHE_SIZE`].
+#[derive(Default)]
+struct BucketCache {
+    buckets: FxHashMap<(u8, usize), (Arc<Bucket>, u64)>,
+    size: usize,
+    /// Incremented on every access, used to find the least recently used bucket.
+    clock: u64,
+}
+
+impl BucketCache {
+    fn get(&mut self, key: (u8, usize)) -> Option<Arc<Bucket>> {
+        self.clock += 1;
+        let (bucket, last_used) = self.buckets.get_mut(&key)?;
+        *last_used = self.clock;
+        Some(bucket.clone())
+    }
+
+    /// Inserts `bucket` and drops the least recently used buckets until the cache fits into
+    /// `limit`. The inserted bucket is kept even if it alone exceeds the limit.
+    fn insert(&mut self, key: (u8, usize), bucket: Arc<Bucket>, limit: usize) {
+        self.clock += 1;
+        self.size += bucket.content.len();
+        if let Some((old, _)) = self.buckets.insert(key, (bucket, self.clock)) {
+            self.size -= old.content.len();
+        }
+        while self.size > limit && self.buckets.len() > 1 {
+            let (&oldest, _) = self
+                .buckets
+                .iter()
+                .filter(|(k, _)| **k != key)
+                .min_by_key(|(_, (_, last_used))| *last_used)
+                .unwrap();
+            let (old, _) = self.buckets.remove(&oldest).unwrap();
+            self.size -= old.content.len();
+        }
+    }
+}
+
+/// A published snapshot. Its buckets are read when a key in them is looked up.
+struct SharedSnapshot {
+    store: SharedStore,
+    manifest_name: String,
+    manifest: SharedCacheManifest,
+    cache: Mutex<BucketCache>,
+    cache_limit: usize,
+}
+
+impl SharedSnapshot {
+    /// Returns `None` when the manifest is missing or incompatible.
+    ///
+    /// A manifest is only written after all of its buckets, so the buckets aren't checked here.
+    /// That would take a request per bucket.
+    fn open(store: SharedStore, manifest_name: String, version: &str) -> Result<Option<Self>> {
+        let Some(content) = store.read("objects", &manifest_name)? else {
+            return Ok(None);
+        };
+        if object_name(&content) != manifest_name {
+            bail!("{manifest_name} in shared cache is corrupted");
+        }
+        let Ok(manifest) = serde_json::from_slice::<SharedCacheManifest>(&content) else {
+            return Ok(None);
+        };
+        if manifest.format_version != SHARED_CACHE_FORMAT_VERSION
+            || manifest.version != version
+            || manifest.key_spaces.len() != KEY_SPACE_COUNT
+            || manifest
+                .key_spaces
+                .iter()
+                .any(|buckets| buckets.len() != BUCKET_COUNT)
+        {
+            return Ok(None);
+        }
+        Ok(Some(Self {
+            store,
+            manifest_name,
+            manifest,
+            cache: Default::default(),
+            cache_limit: BUCKET_CACHE_SIZE,
+        }))
+    }
+
+    fn bucket_name(&self, key_space: KeySpace, index: usize) -> &str {
+        &self.manifest.key_spaces[key_space as usize][index]
+    }
+
+    fn bucket(&self, key_space: KeySpace, index: usize) -> Result<Arc<Bucket>> {
+        let key = (key_space as u8, index);
+        if let Some(bucket) = self.cache.lock().get(key) {
+            return Ok(bucket);
+        }
+        // Another thread might read the bucket concurrently, both are equal.
+        let bucket = Arc::new(read_bucket(
+            self.store.read_object(self.bucket_name(key_space, index))?,
+        )?);
+        self.cache
+            .lock()
+            .insert(key, bucket.clone(), self.cache_limit);
+        Ok(bucket)
+    }
+
+    fn get(&self, key_space: KeySpace, key: &[u8]) -> Result<Option<(Arc<Bucket>, Range<usize>)>> {
+        let bucket = self.bucket(key_space, bucket_index(key))?;
+        Ok(bucket.get(key).map(|range| (bucket, range)))
+    }
+}
+
+/// The contents of [`BASE_FILE`].
+#[derive(Serialize, Deserialize)]
+struct SharedCacheBase {
+    location: SharedCacheLocation,
+    version: String,
+    /// The manifest of the snapshot, or `None` if the database started empty.
+    manifest: Option<String>,
+}
+
+type Changes = ByKeySpace<FxDashMap<Box<[u8]>, bool>>;
+
+struct Layer {
+    versioned_path: PathBuf,
+    base: Option<SharedSnapshot>,
+    /// The keys written to the local database since it was layered, and whether they were
+    /// deleted. Used to shadow deleted keys and to publish the database.
+    changes: Changes,
+    /// The encoded journal records of changes that aren't in the [`JOURNAL_FILE`] yet.
+    pending_journal: ThreadLocal<Mutex<Vec<u8>>>,
+    publish_to: Option<SharedCache>,
+    writes_prevented: AtomicBool,
+}
+
+pub enum ValueBuffer<'l, T: KeyValueDatabase>
+where
+    T: 'l,
+{
+    Database(T::ValueBuffer<'l>),
+    Shared(Arc<Bucket>, Range<usize>),
+}
+
+impl<T: KeyValueDatabase> Borrow<[u8]> for ValueBuffer<'_, T> {
+    fn borrow(&self) -> &[u8] {
+        match self {
+            ValueBuffer::Database(value) => value.borrow(),
+            ValueBuffer::Shared(bucket, range) => &bucket.content[range.clone()],
+        }
+    }
+}
+
+/// Layers a local database on a snapshot in the [`SharedCache`].
+///
+/// Reads fall through to the snapshot when the local database doesn't contain a key, and only
+/// fetch the bucket containing that key. Writes only go to the local database, and the written
+/// keys are recorded in a journal. When publishing, the buckets containing written keys are
+/// merged with the snapshot and uploaded, the other buckets are shared with the previous
+/// snapshot.
+///
+/// Only a database that was empty when the shared cache was enabled is layered, otherwise its
+/// contents aren't known to the journal. If the snapshot of a layered database disappears from the
+/// shared cache, the local database is incomplete and is cleared.
+pub struct SharedCacheLayer<T: KeyValueDatabase> {
+    database: T,
+    layer: Option<Layer>,
+}
+
+impl<T: KeyValueDatabase> SharedCacheLayer<T> {
+    /// Opens the local database in `versioned_path` with `open_database`, layered on the latest
+    /// snapshot in `shared_cache` if the database is empty.
+    ///
+    /// The shared cache is only an optimization, a miss or failure falls back to the local
+    /// database.
+    pub fn open(
+        versioned_path: PathBuf,
+        shared_cache: Option<SharedCache>,
+        open_database: impl FnOnce(PathBuf) -> Result<T>,
+    ) -> Result<Self> {
+        let mut layer = None;
+        if is_layered(&versioned_path) {
+            match restore_layer(&versioned_path) {
+                Ok(Some(restored)) => layer = Some(restored),
+                result => {
+                    if let Err(err) = result {
+                        println!(
+                            "WARNING: Failed to read the shared cache snapshot of the Persistent \
+                             Caching database: {err:?}"
+                        );
+                    }
+                    println!(
+                        "The shared cache snapshot of the Persistent Caching database is no \
+                         longer available. Starting with an empty database."
+                    );
+                    remove_dir_all(&versioned_path)?;
+                    create_dir_all(&versioned_path)?;
+                }
+            }
+        }
+        let is_empty = read_dir(&versioned_path).map_or(true, |mut dir| dir.next().is_none());
+        if layer.is_none()
+            && is_empty
+            && let Some(shared_cache) = &shared_cache
+        {
+            let base = match shared_cache.latest_snapshot() {
+                Ok(base) => base,
+                Err(err) => {
+                    println!(
+                        "WARNING: Failed to read Persistent Caching snapshot from shared cache: \
+                         {err:?}"
+                    );
+                    None
+                }
+            };
+            // Without a snapshot to read from or publishing, there is nothing to layer on
+            if base.is_some() || shared_cache.publish {
+                if base.is_some() {
+                    println!("Using Persistent Caching snapshot from shared cache.");
+                }
+                write_json(
+                    &versioned_path.join(BASE_FILE),
+                    &SharedCacheBase {
+                        location: shared_cache.store.location.clone(),
+                        version: shared_cache.version.clone(),
+                        manifest: base.as_ref().map(|base| base.manifest_name.clone()),
+                    },
+                )?;
+                layer = Some(Layer {
+                    versioned_path: versioned_path.clone(),
+                    base,
+                    changes: ByKeySpace::new(|_| FxDashMap::default()),
+                    pending_journal: ThreadLocal::new(),
+                    publish_to: None,
+                    writes_prevented: AtomicBool::new(false),
+                });
+            }
+        }
+        let publish_to = shared_cache.filter(|shared_cache| shared_cache.publish);
+        if let Some(layer) = &mut layer {
+            layer.publish_to = publish_to;
+        } else if publish_to.is_some() {
+            println!(
+                "WARNING: The Persistent Caching database wasn't created from the shared cache \
+                 and won't be published. Remove it to start publishing."
+            );
+        }
+        Ok(Self {
+            database: open_database(versioned_path)?,
+            layer,
+        })
+    }
+
+    /// Uploads the buckets with changed keys and points the ref to a new manifest.
+    fn publish(&self, layer: &Layer, shared_cache: &SharedCache) -> Result<()> {
+        let tx = self.database.begin_read_transaction()?;
+        let mut key_spaces = Vec::with_capacity(KEY_SPACE_COUNT);
+        for (key_space, changes) in layer.changes.iter() {
+            let mut changed_buckets = vec![Vec::new(); BUCKET_COUNT];
+            for entry in changes.iter() {
+                changed_buckets[bucket_index(entry.key())]
+                    .push((entry.key().clone(), *entry.value()));
+            }
+            let mut buckets = Vec::with_capacity(BUCKET_COUNT);
+            for (index, changed) in changed_buckets.into_iter().enumerate() {
+                if let Some(base) = &layer.base
+                    && changed.is_empty()
+                {
+                    let name = base.bucket_name(key_space, index);
+                    if !shared_cache.store.has_object(name)? {
+                        shared_cache
+                            .store
+                            .write_object(&base.store.read_object(name)?)?;
+                    }
+                    buckets.push(name.to_string());
+                    continue;
+                }
+                let mut entries = BTreeMap::new();
+                if let Some(base) = &layer.base {
+                    for (key, value) in base.bucket(key_space, index)?.iter() {
+                        entries.insert(key.into(), value.into());
+                    }
+                }
+                for (key, deleted) in changed {
+                    if deleted {
+                        entries.remove(&*key);
+                    } else if let Some(value) = self.database.get(&tx, key_space, &key)? {
+                        let value: &[u8] = value.borrow();
+                        entries.insert(key, value.into());
+                    }
+                }
+                buckets.push(shared_cache.store.write_object(&write_bucket(&entries)?)?);
+            }
+            key_spaces.push(buckets);
+        }
+        let manifest = SharedCacheManifest {
+            format_version: SHARED_CACHE_FORMAT_VERSION,
+            version: shared_cache.version.clone(),
+            key_spaces,
+        };
+        let manifest_name = shared_cache
+            .store
+            .write_object(&serde_json::to_vec(&manifest)?)?;
+        shared_cache
+            .store
+            .write_ref(&shared_cache.key, &manifest_name)
+    }
+}
+
+impl<T: KeyValueDatabase> KeyValueDatabase for SharedCacheLayer<T> {
+    type ReadTransaction<'l>
+        = T::ReadTransaction<'l>
+    where
+        Self: 'l;
+
+    fn is_empty(&self) -> bool {
+        self.database.is_empty() && self.layer.as_ref().is_none_or(|layer| layer.base.is_none())
+    }
+
+    fn begin_read_transaction(&self) -> Result<Self::ReadTransaction<'_>> {
+        self.database.begin_read_transaction()
+    }
+
+    type ValueBuffer<'l>
+        = ValueBuffer<'l, T>
+    where
+        Self: 'l;
+
+    fn get<'l, 'db: 'l>(
+        &'l self,
+        transaction: &'l Self::ReadTransaction<'db>,
+        key_space: KeySpace,
+        key: &[u8],
+    ) -> Result<Option<Self::ValueBuffer<'l>>> {
+        if let Some(value) = self.database.get(transaction, key_space, key)? {
+            return Ok(Some(ValueBuffer::Database(value)));
+        }
+        let Some(layer) = &self.layer else {
+            return Ok(None);
+        };
+        let Some(base) = &layer.base else {
+            return Ok(None);
+        };
+        if layer
+            .changes
+            .get(key_space)
+            .get(key)
+            .is_some_and(|deleted| *deleted)
+        {
+            return Ok(None);
+        }
+        Ok(base
+            .get(key_space, key)?
+            .map(|(bucket, range)| ValueBuffer::Shared(bucket, range)))
+    }
+
+    type SerialWriteBatch<'l>
+        = SharedCacheWriteBatch<'l, T::SerialWriteBatch<'l>>
+    where
+        Self: 'l;
+
+    type ConcurrentWriteBatch<'l>
+        = SharedCacheWriteBatch<'l, T::ConcurrentWriteBatch<'l>>
+    where
+        Self: 'l;
+
+    fn write_batch(
+        &self,
+    ) -> Result<WriteBatch<'_, Self::SerialWriteBatch<'_>, Self::ConcurrentWriteBatch<'_>>> {
+        let layer = self.layer.as_ref();
+        Ok(match self.database.write_batch()? {
+            WriteBatch::Serial(batch) => WriteBatch::serial(SharedCacheWriteBatch { batch, layer }),
+            WriteBatch::Concurrent(batch, _) => {
+                WriteBatch::concurrent(SharedCacheWriteBatch { batch, layer })
+            }
+        })
+    }
+
+    fn prevent_writes(&self) {
+        if let Some(layer) = &self.layer {
+            layer.writes_prevented.store(true, Ordering::Release);
+        }
+        self.database.prevent_writes()
+    }
+
+    fn shutdown(&self) -> Result<()> {
+        if let Some(layer) = &self.layer
+            && let Some(shared_cache) = &layer.publish_to
+            && !layer.writes_prevented.load(Ordering::Acquire)
+            && let Err(err) = self.publish(layer, shared_cache)
+        {
+            println!("WARNING: Failed to publish Persistent Caching database: {err:?}");
+        }
+        self.database.shutdown()
+    }
+}
+
+pub struct SharedCacheWriteBatch<'a, B> {
+    batch: B,
+    layer: Option<&'a Layer>,
+}
+
+impl<B> SharedCacheWriteBatch<'_, B> {
+    fn record(&self, key_space: KeySpace, key: &[u8], deleted: bool) {
+        if let Some(layer) = self.layer {
+            let previous = layer
+                .changes
+                .get(key_space)
+                .insert(key.to_vec().into_boxed_slice(), deleted);
+            // Only changed records need to be journaled
+            if previous != Some(deleted) {
+                let mut pending = layer.pending_journal.get_or_default().lock();
+                encode_journal_record(&mut pending, key_space, key, deleted);
+            }
+        }
+    }
+}
+
+impl<'a, B: BaseWriteBatch<'a>> BaseWriteBatch<'a> for SharedCacheWriteBatch<'a, B> {
+    type ValueBuffer<'l>
+        = B::ValueBuffer<'l>
+    where
+        Self: 'l,
+        'a: 'l;
+
+    fn get<'l>(&'l self, key_space: KeySpace, key: &[u8]) -> Result<Option<Self::ValueBuffer<'l>>>
+    where
+        'a: 'l,
+    {
+        self.batch.get(key_space, key)
+    }
+
+    fn commit(self) -> Result<()> {
+        if let Some(layer) = self.layer {
+            // Write the journal first, so that it always covers the committed keys
+            append_journal(
+                &layer.versioned_path.join(JOURNAL_FILE),
+                &layer.pending_journal,
+            )?;
+        }
+        self.batch.commit()
+    }
+}
+
+impl<'a, B: SerialWriteBatch<'a>> SerialWriteBatch<'a> for SharedCacheWriteBatch<'a, B> {
+    fn put(
+        &mut self,
+        key_space: KeySpace,
+        key: WriteBuffer<'_>,
+        value: WriteBuffer<'_>,
+    ) -> Result<()> {
+        self.record(key_space, &key, false);
+        self.batch.put(key_space, key, value)
+    }
+
+    fn delete(&mut self, key_space: KeySpace, key: WriteBuffer<'_>) -> Result<()> {
+        self.record(key_space, &key, true);
+        self.batch.delete(key_space, key)
+    }
+
+    fn flush(&mut self, key_space: KeySpace) -> Result<()> {
+        self.batch.flush(key_space)
+    }
+}
+
+impl<'a, B: ConcurrentWriteBatch<'a>> ConcurrentWriteBatch<'a> for SharedCacheWriteBatch<'a, B> {
+    fn put(&self, key_space: KeySpace, key: WriteBuffer<'_>, value: WriteBuffer<'_>) -> Result<()> {
+        self.record(key_space, &key, false);
+        self.batch.put(key_space, key, value)
+    }
+
+    fn delete(&self, key_space: KeySpace, key: WriteBuffer<'_>) -> Result<()> {
+        self.record(key_space, &key, true);
+        self.batch.delete(key_space, key)
+    }
+
+    unsafe fn flush(&self, key_space: KeySpace) -> Result<()> {
+        unsafe { self.batch.flush(key_space) }
+    }
+}
+
+/// Returns true when the database in `versioned_path` is layered on a shared cache snapshot, i.e.
+/// when it is incomplete without the shared cache.
+pub(crate) fn is_layered(versioned_path: &Path) -> bool {
+    versioned_path.join(BASE_FILE).exists()
+}
+
+/// Reads the [`BASE_FILE`] and [`JOURNAL_FILE`] of a layered database. Returns `None` when its
+/// snapshot is no longer available.
+fn restore_layer(versioned_path: &Path) -> Result<Option<Layer>> {
+    let base_file: SharedCacheBase = read_json(&versioned_path.join(BASE_FILE))?;
+    let base = match base_file.manifest {
+        Some(manifest_name) => {
+            let store = SharedStore {
+                location: base_file.location,
+            };
+            let Some(base) = SharedSnapshot::open(store, manifest_name, &base_file.version)? else {
+                return Ok(None);
+            };
+            Some(base)
+        }
+        None => None,
+    };
+    Ok(Some(Layer {
+        versioned_path: versioned_path.to_owned(),
+        base,
+        changes: read_journal(&versioned_path.join(JOURNAL_FILE))?,
+        pending_journal: ThreadLocal::new(),
+        publish_to: None,
+        writes_prevented: AtomicBool::new(false),
+    }))
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = XxHash64::with_seed(0);
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+fn object_name(content: &[u8]) -> String {
+    format!("{:016x}-{:x}", hash_bytes(content), content.len())
+}
+
+fn bucket_index(key: &[u8]) -> usize {
+    (hash_bytes(key) % BUCKET_COUNT as u64) as usize
+}
+
+fn key_space_from_u8(value: u8) -> Result<KeySpace> {
+    Ok(match value {
+        0 => KeySpace::Infra,
+        1 => KeySpace::TaskMeta,
+        2 => KeySpace::TaskData,
+        3 => KeySpace::ForwardTaskCache,
+        4 => KeySpace::ReverseTaskCache,
+        _ => bail!("Invalid key space {value}"),
+    })
+}
+
+/// Writes `content` to a temporary file first, so that readers never see a partial file.
+fn write_atomically(path: &Path, content: &[u8]) -> Result<()> {
+    let dir = path.parent().context("path has no parent")?;
+    create_dir_all(dir)?;
+    // Temporary files start with a dot, so that a database directory ignores them
+    let tmp_path = dir.join(format!(".tmp-{:016x}", rand::random::<u64>()));
+    let result = fs::write(&tmp_path, content).and_then(|_| rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    Ok(result?)
+}
+
+fn read_json<V: DeserializeOwned>(path: &Path) -> Result<V> {
+    Ok(serde_json::from_reader(BufReader::new(File::open(path)?))?)
+}
+
+fn write_json(path: &Path, value: &impl Serialize) -> Result<()> {
+    write_atomically(path, &serde_json::to_vec(value)?)
+}
+
+/// A bucket is a sequence of key-value pairs, sorted by key. Each pair is encoded as the length of
+/// the key (u32 BE), the length of the value (u32 BE), the key and the value.
+fn write_bucket(entries: &BTreeMap<Box<[u8]>, Box<[u8]>>) -> Result<Vec<u8>> {
+    let mut content = Vec::new();
+    for (key, value) in entries {
+        content.write_u32::<BE>(key.len().try_into()?)?;
+        content.write_u32::<BE>(value.len().try_into()?)?;
+        content.extend_from_slice(key);
+        content.extend_from_slice(value);
+    }
+    Ok(content)
+}
+
+fn read_bucket(content: Vec<u8>) -> Result<Bucket> {
+    let mut values = FxHashMap::default();
+    let mut offset = 0;
+    while offset < content.len() {
+        let mut header = &content[offset..];
+        let key_len = header.read_u32::<BE>()? as usize;
+        let value_len = header.read_u32::<BE>()? as usize;
+        let key_start = offset + 8;
+        let value_start = key_start + key_len;
+        offset = value_start + value_len;
+        if content.len() < offset {
+            bail!("bucket in shared cache is truncated");
+        }
+        values.insert(content[key_start..value_start].into(), value_start..offset);
+    }
+    Ok(Bucket { content, values })
+}
+
+/// The journal is a sequence of records, each encoded as the key space (u8), whether the key was
+/// deleted (u8), the length of the key (u32 BE) and the key. Later records replace earlier ones
+/// for the same key.
+fn encode_journal_record(buffer: &mut Vec<u8>, key_space: KeySpace, key: &[u8], deleted: bool) {
+    buffer.push(key_space as u8);
+    buffer.push(deleted as u8);
+    buffer.extend_from_slice(&(key.len() as u32).to_be_bytes());
+    buffer.extend_from_slice(key);
+}
+
+/// Appends the pending records to the journal.
+fn append_journal(path: &Path, pending: &ThreadLocal<Mutex<Vec<u8>>>) -> Result<()> {
+    let mut file = None;
+    for pending in pending.iter() {
+        let mut pending = pending.lock();
+        if pending.is_empty() {
+            continue;
+        }
+        let file = match &mut file {
+            Some(file) => file,
+            None => file.insert(OpenOptions::new().create(true).append(true).open(path)?),
+        };
+        file.write_all(&pending)?;
+        pending.clear();
+    }
+    if let Some(file) = file {
+        file.sync_data()?;
+    }
+    Ok(())
+}
+
+fn read_journal(path: &Path) -> Result<Changes> {
+    let changes: Changes = ByKeySpace::new(|_| FxDashMap::default());
+    let mut reader = match File::open(path) {
+        Ok(file) => BufReader::new(file),
+        Err(err) if err.kind() == ErrorKind::NotFound => return Ok(changes),
+        Err(err) => return Err(err.into()),
+    };
+    let mut key_space = [0u8; 1];
+    while reader.read(&mut key_space)? != 0 {
+        let key_space = key_space_from_u8(key_space[0])?;
+        let mut record = || -> std::io::Result<(bool, Vec<u8>)> {
+            let deleted = reader.read_u8()? != 0;
+            let mut key = vec![0; reader.read_u32::<BE>()? as usize];
+            reader.read_exact(&mut key)?;
+            Ok((deleted, key))
+        };
+        let (deleted, key) = match record() {
+            Ok(record) => record,
+            // A record that was cut off by a crash belongs to a batch that wasn't committed
+            Err(err) if err.kind() == ErrorKind::UnexpectedEof => break,
+            Err(err) => return Err(err.into()),
+        };
+        changes
+            .get(key_space)
+            .insert(key.into_boxed_slice(), deleted);
+    }
+    Ok(changes)
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::*;
+    use crate::database::turbo::TurboKeyValueDatabase;
+
+    fn shared_cache(path: &Path, describe: &str, publish: bool) -> SharedCache {
+        SharedCache::new(
+            SharedCacheConfig {
+                location: SharedCacheLocation::Directory(path.to_owned()),
+                publish,
+            },
+            &GitVersionInfo {
+                describe,
+                dirty: false,
+            },
+        )
+        .unwrap()
+    }
+
+    fn open(
+        versioned_path: &Path,
+        shared_cache: Option<SharedCache>,
+    ) -> SharedCacheLayer<TurboKeyValueDatabase> {
+        fs::create_dir_all(versioned_path).unwrap();
+        SharedCacheLayer::open(versioned_path.to_owned(), shared_cache, |path| {
+            TurboKeyValueDatabase::new(path, false, true)
+        })
+        .unwrap()
+    }
+
+    fn put(database: &SharedCacheLayer<TurboKeyValueDatabase>, entries: &[(&str, &str)]) {
+        let WriteBatch::Concurrent(batch, _) = database.write_batch().unwrap() else {
+            panic!("expected a concurrent write batch");
+        };
+        for (key, value) in entries {
+            batch
+                .put(
+                    KeySpace::TaskData,
+                    WriteBuffer::Borrowed(key.as_bytes()),
+                    WriteBuffer::Borrowed(value.as_bytes()),
+                )
+                .unwrap();
+        }
+        batch.commit().unwrap();
+    }
+
+    fn get(database: &SharedCacheLayer<TurboKeyValueDatabase>, key: &str) -> Option<String> {
+        let value = database
+            .get(&(), KeySpace::TaskData, key.as_bytes())
+            .unwrap()?;
+        let value: &[u8] = value.borrow();
+        Some(String::from_utf8(value.to_vec()).unwrap())
+    }
+
+    fn objects(shared_path: &Path) -> usize {
+        read_dir(shared_path.join("objects")).unwrap().count()
+    }
+
+    #[test]
+    fn test_read_through() {
+        let tmp_dir = TempDir::new().unwrap();
+        let shared_path = tmp_dir.path().join("shared");
+
+        let producer = open(
+            &tmp_dir.path().join("producer/mock-version"),
+            Some(shared_cache(&shared_path, "mock-version", true)),
+        );
+        put(&producer, &[("a", "1"), ("b", "2")]);
+        producer.shutdown().unwrap();
+
+        let consumer_path = tmp_dir.path().join("consumer/mock-version");
+        let consumer = open(
+            &consumer_path,
+            Some(shared_cache(&shared_path, "mock-version", false)),
+        );
+        assert!(!consumer.is_empty());
+        assert_eq!(get(&consumer, "a").as_deref(), Some("1"));
+        assert_eq!(get(&consumer, "c"), None);
+
+        // local writes take precedence
+        put(&consumer, &[("a", "3")]);
+        assert_eq!(get(&consumer, "a").as_deref(), Some("3"));
+        consumer.shutdown().unwrap();
+        drop(consumer);
+
+        // the layer survives a restart, without the shared cache being configured
+        let consumer = open(&consumer_path, None);
+        assert_eq!(get(&consumer, "a").as_deref(), Some("3"));
+        assert_eq!(get(&consumer, "b").as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_publish_only_changed_buckets() {
+        let tmp_dir = TempDir::new().unwrap();
+        let shared_path = tmp_dir.path().join("shared");
+
+        let first = open(
+            &tmp_dir.path().join("first/mock-version"),
+            Some(shared_cache(&shared_path, "mock-version", true)),
+        );
+        put(&first, &[("a", "1"), ("b", "2")]);
+        first.shutdown().unwrap();
+        // the buckets containing keys, the empty bucket and the manifest
+        let key_buckets = if bucket_index(b"a") == bucket_index(b"b") {
+            1
+        } else {
+            2
+        };
+        assert_eq!(objects(&shared_path), key_buckets + 2);
+
+        let second = open(
+            &tmp_dir.path().join("second/mock-version"),
+            Some(shared_cache(&shared_path, "mock-version", true)),
+        );
+        put(&second, &[("a", "3")]);
+        second.shutdown().unwrap();
+        // only the changed bucket and the manifest are added
+        assert_eq!(objects(&shared_path), key_buckets + 4);
+
+        let third = open(
+            &tmp_dir.path().join("third/mock-version"),
+            Some(shared_cache(&shared_path, "mock-version", false)),
+        );
+        assert_eq!(get(&third, "a").as_deref(), Some("3"));
+        assert_eq!(get(&third, "b").as_deref(), Some("2"));
+    }
+
+    #[test]
+    fn test_miss() {
+        let tmp_dir = TempDir::new().unwrap();
+        let shared_path = tmp_dir.path().join("shared");
+
+        let producer = open(
+            &tmp_dir.path().join("producer/old-version"),
+            Some(shared_cache(&shared_path, "old-version", true)),
+        );
+        put(&producer, &[("a", "1")]);
+        producer.shutdown().unwrap();
+
+        let consumer = open(
+            &tmp_dir.path().join("consumer/new-version"),
+            Some(shared_cache(&shared_path, "new-version", false)),
+        );
+        assert!(consumer.is_empty());
+        assert_eq!(get(&consumer, "a"), None);
+    }
+
+    #[test]
+    fn test_removed_snapshot_clears_database() {
+        let tmp_dir = TempDir::new().unwrap();
+        let shared_path = tmp_dir.path().join("shared");
+
+        let producer = open(
+            &tmp_dir.path().join("producer/mock-version"),
+            Some(shared_cache(&shared_path, "mock-version", true)),
+        );
+        put(&producer, &[("a", "1")]);
+        producer.shutdown().unwrap();
+
+        let consumer_path = tmp_dir.path().join("consumer/mock-version");
+        let consumer = open(
+            &consumer_path,
+            Some(shared_cache(&shared_path, "mock-version", false)),
+        );
+        put(&consumer, &[("b", "2")]);
+        consumer.shutdown().unwrap();
+        drop(consumer);
+
+        fs::remove_dir_all(&shared_path).unwrap();
+        let consumer = open(&consumer_path, None);
+        assert!(consumer.is_empty());
+        assert_eq!(get(&consumer, "a"), None);
+        assert_eq!(get(&consumer, "b"), None);
+    }
+
+    #[test]
+    fn test_journal_only_appends_changes() {
+        let tmp_dir = TempDir::new().unwrap();
+        let shared_path = tmp_dir.path().join("shared");
+        let path = tmp_dir.path().join("db/mock-version");
+        let journal_len = || fs::metadata(path.join(JOURNAL_FILE)).unwrap().len();
+
+        let database = open(
+            &path,
+            Some(shared_cache(&shared_path, "mock-version", true)),
+        );
+        put(&database, &[("a", "1"), ("b", "2")]);
+        let len = journal_len();
+        // rewriting known keys doesn't touch the journal
+        put(&database, &[("a", "3"), ("b", "4")]);
+        assert_eq!(journal_len(), len);
+        put(&database, &[("c", "5")]);
+        assert_eq!(journal_len(), len + len / 2);
+        database.shutdown().unwrap();
+        drop(database);
+
+        let changes = read_journal(&path.join(JOURNAL_FILE)).unwrap();
+        assert_eq!(changes.get(KeySpace::TaskData).len(), 3);
+        // a record cut off by a crash is ignored
+        let file = OpenOptions::new()
+            .write(true)
+            .open(path.join(JOURNAL_FILE))
+            .unwrap();
+        file.set_len(journal_len() - 1).unwrap();
+        let changes = read_journal(&path.join(JOURNAL_FILE)).unwrap();
+        assert_eq!(changes.get(KeySpace::TaskData).len(), 2);
+    }
+
+    #[test]
+    fn test_bucket_cache_limit() {
+        let tmp_dir = TempDir::new().unwrap();
+        let shared_path = tmp_dir.path().join("shared");
+        let keys = ["a", "b", "c", "d", "e", "f", "g", "h"];
+        let entries = keys.map(|key| (key, key));
+
+        let producer = open(
+            &tmp_dir.path().join("producer/mock-version"),
+            Some(shared_cache(&shared_path, "mock-version", true)),
+        );
+        put(&producer, &entries);
+        producer.shutdown().unwrap();
+
+        let mut consumer = open(
+            &tmp_dir.path().join("consumer/mock-version"),
+            Some(shared_cache(&shared_path, "mock-version", false)),
+        );
+        consumer
+            .layer
+            .as_mut()
+            .unwrap()
+            .base
+            .as_mut()
+            .unwrap()
+            .cache_limit = 1;
+        for _ in 0..2 {
+            for key in keys {
+                assert_eq!(get(&consumer, key).as_deref(), Some(key));
+            }
+        }
+        let base = consumer.layer.as_ref().unwrap().base.as_ref().unwrap();
+        assert_eq!(base.cache.lock().buckets.len(), 1);
+    }
+
+    /// A stand-in for a remote cache that serves a directory over HTTP.
+    fn serve(dir: PathBuf) -> String {
+        use std::{
+            io::BufRead,
+            net::{TcpListener, TcpStream},
+        };
+
+        fn handle(stream: TcpStream, dir: &Path) -> Result<()> {
+            let mut reader = BufReader::new(stream.try_clone()?);
+            let mut line = String::new();
+            reader.read_line(&mut line)?;
+            let mut parts = line.split_whitespace();
+            let method = parts.next().context("missing method")?.to_string();
+            let path = dir.join(
+                parts
+                    .next()
+                    .context("missing path")?
+                    .trim_start_matches('/'),
+            );
+            let mut content_length = 0;
+            loop {
+                line.clear();
+                reader.read_line(&mut line)?;
+                if line.trim_end().is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':')
+                    && name.eq_ignore_ascii_case("content-length")
+                {
+                    content_length = value.trim().parse()?;
+                }
+            }
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body)?;
+            let (status, response) = match &*method {
+                "PUT" => {
+                    write_atomically(&path, &body)?;
+                    ("201 Created", Vec::new())
+                }
+                _ => match fs::read(&path) {
+                    Ok(content) => ("200 OK", content),
+                    Err(_) => ("404 Not Found", Vec::new()),
+                },
+            };
+            let mut stream = stream;
+            write!(
+                stream,
+                "HTTP/1.1 {status}\r\nConnection: close\r\nContent-Length: {}\r\n\r\n",
+                response.len()
+            )?;
+            if method != "HEAD" {
+                stream.write_all(&response)?;
+            }
+            Ok(())
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                handle(stream.unwrap(), &dir).unwrap();
+            }
+        });
+        url
+    }
+
+    #[test]
+    fn test_http() {
+        let tmp_dir = TempDir::new().unwrap();
+        let url = serve(tmp_dir.path().join("shared"));
+        let shared_cache = |publish| {
+            SharedCache::new(
+                SharedCacheConfig {
+                    location: SharedCacheLocation::Http(url.clone()),
+                    publish,
+                },
+                &GitVersionInfo {
+                    describe: "mock-version",
+                    dirty: false,
+                },
+            )
+        };
+
+        let producer = open(
+            &tmp_dir.path().join("producer/mock-version"),
+            shared_cache(true),
+        );
+        put(&producer, &[("a", "1"), ("b", "2")]);
+        producer.shutdown().unwrap();
+        assert!(objects(&tmp_dir.path().join("shared")) > 0);
+
+        let consumer = open(
+            &tmp_dir.path().join("consumer/mock-version"),
+            shared_cache(false),
+        );
+        assert_eq!(get(&consumer, "a").as_deref(), Some("1"));
+        assert_eq!(get(&consumer, "b").as_deref(), Some("2"));
+        assert_eq!(get(&consumer, "c"), None);
+    }
+
+    #[test]
+    fn test_dirty_is_not_shared() {
+        let config = SharedCacheConfig {
+            location: SharedCacheLocation::Directory(PathBuf::from("shared")),
+            publish: false,
+        };
+        let version_info = GitVersionInfo {
+            describe: "mock-version",
+            dirty: true,
+        };
+        assert!(SharedCache::new(config, &version_info).is_none());
+    }
+}
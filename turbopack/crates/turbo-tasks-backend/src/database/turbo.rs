@@ -5,7 +5,7 @@ use std::{
     thread::{JoinHandle, available_parallelism, spawn},
 };
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use parking_lot::Mutex;
 use turbo_persistence::{
     ArcSlice, CompactConfig, KeyBase, StoreKey, TurboPersistence, ValueBuffer,
@@ -17,7 +17,8 @@ use crate::database::{
 };
 
 const MB: u64 = 1024 * 1024;
-const COMPACT_CONFIG: CompactConfig = CompactConfig {
+/// The compaction settings of the Persistent Caching database.
+pub const COMPACT_CONFIG: CompactConfig = CompactConfig {
     min_merge_count: 3,
     optimal_merge_count: 8,
     max_merge_count: 64,
@@ -36,7 +37,21 @@ pub struct TurboKeyValueDatabase {
 
 impl TurboKeyValueDatabase {
     pub fn new(versioned_path: PathBuf, is_ci: bool, is_short_session: bool) -> Result<Self> {
-        let db = Arc::new(TurboPersistence::open(versioned_path)?);
+        let db = match TurboPersistence::open(versioned_path.clone()) {
+            Ok(db) => db,
+            Err(err) => {
+                // Usually caused by a process that was killed during a commit. Rolling back the
+                // broken commits keeps most of the cache.
+                let (db, report) = TurboPersistence::open_with_repair(versioned_path)
+                    .context("Failed to repair the Persistent Caching database")?;
+                println!(
+                    "WARNING: Repaired the Persistent Caching database after it failed to open \
+                     ({err:#}):\n{report}"
+                );
+                db
+            }
+        };
+        let db = Arc::new(db);
         let mut this = Self {
             db: db.clone(),
             compact_join_handle: Mutex::new(None),
@@ -9,20 +9,34 @@ mod data;
 mod data_storage;
 mod database;
 mod kv_backing_storage;
+mod snapshot;
 mod utils;
 
 use std::path::Path;
 
 use anyhow::Result;
 
-use crate::database::{noop_kv::NoopKvDb, turbo::TurboKeyValueDatabase};
+use crate::database::{
+    noop_kv::NoopKvDb,
+    shared_cache::{SharedCache, SharedCacheLayer},
+    turbo::TurboKeyValueDatabase,
+};
 pub use crate::{
-    backend::{BackendOptions, StorageMode, TurboTasksBackend},
+    backend::{
+        BackendOptions, StorageMode, TaskGraph, TaskGraphDependency, TaskGraphNode,
+        TurboTasksBackend,
+    },
     backing_storage::BackingStorage,
     database::{
         db_invalidation, db_invalidation::StartupCacheState, db_versioning::GitVersionInfo,
+        shared_cache::{SharedCacheConfig, SharedCacheLocation},
+        turbo::COMPACT_CONFIG,
     },
     kv_backing_storage::KeyValueDatabaseBackingStorage,
+    snapshot::{
+        SnapshotFamilyInfo, SnapshotFile, SnapshotManifest, export_snapshot, import_snapshot,
+        read_snapshot_manifest,
+    },
 };
 
 #[cfg(feature = "lmdb")]
@@ -71,14 +85,16 @@ pub fn lmdb_backing_storage(
     )
 }
 
-pub type TurboBackingStorage = KeyValueDatabaseBackingStorage<TurboKeyValueDatabase>;
+pub type TurboBackingStorage =
+    KeyValueDatabaseBackingStorage<SharedCacheLayer<TurboKeyValueDatabase>>;
 
 /// Creates a `BackingStorage` to be passed to [`TurboTasksBackend::new`].
 ///
 /// Information about the state of the on-disk cache is returned using [`StartupCacheState`].
 ///
 /// This is the fastest most-tested implementation of `BackingStorage`, and is normally returned by
-/// [`default_backing_storage`].
+/// [`default_backing_storage`]. When a [`SharedCacheConfig`] is configured in the environment, an
+/// empty database is layered on the latest snapshot in the shared cache.
 pub fn turbo_backing_storage(
     base_path: &Path,
     version_info: &GitVersionInfo,
@@ -89,7 +105,13 @@ pub fn turbo_backing_storage(
         base_path.to_owned(),
         version_info,
         is_ci,
-        |path| TurboKeyValueDatabase::new(path, is_ci, is_short_session),
+        |path| {
+            let shared_cache = SharedCacheConfig::from_env()
+                .and_then(|config| SharedCache::new(config, version_info));
+            SharedCacheLayer::open(path, shared_cache, |path| {
+                TurboKeyValueDatabase::new(path, is_ci, is_short_session)
+            })
+        },
     )
 }
 
@@ -0,0 +1,478 @@
+//! Export and import of the persistent cache database as a single archive file.
+//!
+//! An archive consists of:
+//!
+//! - the magic bytes [`MAGIC`]
+//! - the length of the manifest (u64 BE), the JSON encoded [`SnapshotManifest`] and the xxh64 hash
+//!   of the manifest (u64 BE)
+//! - the contents of all files listed in the manifest, in order
+//!
+//! Every file is verified against the size and hash in the manifest when importing.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File, create_dir_all, read_dir, remove_dir_all, rename},
+    hash::Hasher,
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use anyhow::{Context, Result, bail};
+use byteorder::{BE, ReadBytesExt, WriteBytesExt};
+use serde::{Deserialize, Serialize};
+use turbo_persistence::TurboPersistence;
+use twox_hash::XxHash64;
+
+use crate::database::{
+    db_invalidation::{StartupCacheState, check_db_invalidation_and_cleanup},
+    db_versioning::{DELETION_PREFIX, GitVersionInfo, db_version},
+    shared_cache::is_layered,
+};
+
+const MAGIC: &[u8; 8] = b"TTSNAPSH";
+
+/// Bump this when the archive layout changes.
+const SNAPSHOT_FORMAT_VERSION: u32 = 1;
+
+/// Describes the contents of a snapshot archive.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SnapshotManifest {
+    pub format_version: u32,
+    /// The name of the version directory, see
+    /// [`handle_db_versioning`][crate::database::db_versioning::handle_db_versioning].
+    pub version: String,
+    /// The `git describe` output of the build that produced the database.
+    pub git_describe: String,
+    pub families: BTreeMap<u32, SnapshotFamilyInfo>,
+    pub files: Vec<SnapshotFile>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct SnapshotFamilyInfo {
+    pub sst_files: usize,
+    pub sst_size: u64,
+    pub entries: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SnapshotFile {
+    pub name: String,
+    pub size: u64,
+    pub hash: u64,
+}
+
+/// Compacts the database for `version_info` in `base_path` and writes it as a single archive to
+/// `output`.
+///
+/// The database must not be used by another process while exporting.
+pub fn export_snapshot(
+    base_path: &Path,
+    version_info: &GitVersionInfo,
+    output: &Path,
+) -> Result<SnapshotManifest> {
+    if let StartupCacheState::Invalidated { .. } = check_db_invalidation_and_cleanup(base_path)? {
+        bail!("The database in {base_path:?} has been invalidated");
+    }
+    let version = db_version(version_info)
+        .context("Persistent Caching is disabled for a dirty git repository")?;
+    let versioned_path = base_path.join(&*version);
+    if !versioned_path.exists() {
+        bail!("There is no database at {versioned_path:?}");
+    }
+    if is_layered(&versioned_path) {
+        bail!(
+            "The database at {versioned_path:?} is layered on a shared cache snapshot and is \
+             incomplete without it"
+        );
+    }
+
+    // Compact into as few files as possible. This also cleans up any leftovers of an unclean
+    // shutdown.
+    let db = TurboPersistence::open(versioned_path.clone())?;
+    db.full_compact()
+        .context("Failed to compact the database")?;
+    let families = family_infos(&db)?;
+    db.shutdown()?;
+    drop(db);
+
+    let mut files = Vec::new();
+    let mut paths = Vec::new();
+    for entry in read_dir(&versioned_path)? {
+        let entry = entry?;
+        let name = entry.file_name();
+        // The LOG is write-only and not needed to open the database. Files starting with a dot
+        // (e.g. temporary files) aren't part of the database either.
+        if !entry.file_type()?.is_file()
+            || name == "LOG"
+            || name.as_encoded_bytes().starts_with(b".")
+        {
+            continue;
+        }
+        let name = name
+            .into_string()
+            .map_err(|name| anyhow::anyhow!("Invalid file name {name:?}"))?;
+        let (size, hash) = hash_reader(File::open(entry.path())?, io::sink())?;
+        files.push(SnapshotFile { name, size, hash });
+        paths.push(entry.path());
+    }
+
+    let manifest = SnapshotManifest {
+        format_version: SNAPSHOT_FORMAT_VERSION,
+        version: version.into_owned(),
+        git_describe: version_info.describe.to_string(),
+        families,
+        files,
+    };
+
+    // Write to a temporary file first, so that an interrupted export never leaves a truncated
+    // archive behind.
+    let mut tmp_output = output.as_os_str().to_owned();
+    tmp_output.push(".tmp");
+    let result = (|| -> Result<()> {
+        let mut writer = BufWriter::new(File::create(&tmp_output)?);
+        writer.write_all(MAGIC)?;
+        let manifest_bytes = serde_json::to_vec(&manifest)?;
+        writer.write_u64::<BE>(manifest_bytes.len() as u64)?;
+        writer.write_all(&manifest_bytes)?;
+        writer.write_u64::<BE>(hash_bytes(&manifest_bytes))?;
+        for (file, path) in manifest.files.iter().zip(paths) {
+            let (size, hash) = hash_reader(File::open(&path)?, &mut writer)?;
+            if size != file.size || hash != file.hash {
+                bail!("{} changed while exporting", file.name);
+            }
+        }
+        writer
+            .into_inner()
+            .map_err(|err| err.into_error())?
+            .sync_all()?;
+        rename(&tmp_output, output)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_output);
+    }
+    result?;
+    Ok(manifest)
+}
+
+/// Reads and validates the manifest of a snapshot archive without unpacking it.
+pub fn read_snapshot_manifest(archive: &Path) -> Result<SnapshotManifest> {
+    read_manifest(&mut BufReader::new(File::open(archive)?))
+}
+
+/// Verifies the archive and unpacks it as the database for `version_info` in `base_path`,
+/// replacing any existing database of that version.
+///
+/// Fails without touching the existing database when the archive is corrupted or was produced by
+/// an incompatible version.
+pub fn import_snapshot(
+    archive: &Path,
+    base_path: &Path,
+    version_info: &GitVersionInfo,
+) -> Result<SnapshotManifest> {
+    let mut reader = BufReader::new(File::open(archive)?);
+    let manifest = read_manifest(&mut reader)?;
+    let version = db_version(version_info)
+        .context("Persistent Caching is disabled for a dirty git repository")?;
+    if manifest.version != version {
+        bail!(
+            "The snapshot was created by version {}, but the current version is {version}",
+            manifest.version
+        );
+    }
+
+    // Finish any pending invalidation first, it would delete the imported database otherwise.
+    check_db_invalidation_and_cleanup(base_path)?;
+    create_dir_all(base_path)?;
+    let tmp_path = base_path.join(format!("{DELETION_PREFIX}import-{version}"));
+    let result = (|| -> Result<()> {
+        if tmp_path.exists() {
+            remove_dir_all(&tmp_path)?;
+        }
+        create_dir_all(&tmp_path)?;
+        for file in &manifest.files {
+            if file.name.contains(['/', '\\']) || file.name.starts_with('.') {
+                bail!("Invalid file name {:?} in snapshot", file.name);
+            }
+            let mut writer = BufWriter::new(File::create(tmp_path.join(&file.name))?);
+            let (size, hash) = hash_reader((&mut reader).take(file.size), &mut writer)?;
+            writer.flush()?;
+            if size != file.size || hash != file.hash {
+                bail!("{} in the snapshot is corrupted", file.name);
+            }
+        }
+        if reader.read(&mut [0])? != 0 {
+            bail!("Unexpected trailing data in the snapshot");
+        }
+
+        // Make sure the database can be opened and matches the manifest
+        let db = TurboPersistence::open_read_only(tmp_path.clone())?;
+        let families = family_infos(&db)?;
+        drop(db);
+        for family in manifest.families.keys().chain(families.keys()) {
+            match (manifest.families.get(family), families.get(family)) {
+                (Some(expected), Some(actual)) if expected == actual => {}
+                (Some(expected), actual) => bail!(
+                    "Family {family} doesn't match the manifest, expected {expected:?}, found \
+                     {actual:?}"
+                ),
+                (None, _) => bail!("Family {family} is not listed in the manifest"),
+            }
+        }
+
+        let versioned_path = base_path.join(&*version);
+        if versioned_path.exists() {
+            let stale_path = base_path.join(format!("{DELETION_PREFIX}{version}"));
+            rename(&versioned_path, &stale_path)?;
+            // It's okay if this fails, the versioning logic cleans it up on the next start
+            let _ = remove_dir_all(&stale_path);
+        }
+        rename(&tmp_path, &versioned_path)?;
+        Ok(())
+    })();
+    if result.is_err() {
+        let _ = remove_dir_all(&tmp_path);
+    }
+    result?;
+    Ok(manifest)
+}
+
+fn family_infos(db: &TurboPersistence) -> Result<BTreeMap<u32, SnapshotFamilyInfo>> {
+    let mut families = BTreeMap::<u32, SnapshotFamilyInfo>::new();
+    for meta_file in db.meta_info()? {
+        let family = families.entry(meta_file.family).or_default();
+        for entry in meta_file.entries {
+            family.sst_files += 1;
+            family.sst_size += entry.sst_size;
+            family.entries += entry.amqf_entries;
+        }
+    }
+    Ok(families)
+}
+
+fn read_manifest(reader: &mut impl Read) -> Result<SnapshotManifest> {
+    let mut magic = [0; MAGIC.len()];
+    reader
+        .read_exact(&mut magic)
+        .context("Failed to read snapshot header")?;
+    if &magic != MAGIC {
+        bail!("Not a snapshot archive");
+    }
+    let len = reader.read_u64::<BE>()?;
+    let mut manifest_bytes = Vec::new();
+    reader.take(len).read_to_end(&mut manifest_bytes)?;
+    if manifest_bytes.len() as u64 != len || reader.read_u64::<BE>()? != hash_bytes(&manifest_bytes)
+    {
+        bail!("The snapshot manifest is corrupted");
+    }
+    let manifest: SnapshotManifest = serde_json::from_slice(&manifest_bytes)?;
+    if manifest.format_version != SNAPSHOT_FORMAT_VERSION {
+        bail!(
+            "Unsupported snapshot format version {} (expected {SNAPSHOT_FORMAT_VERSION})",
+            manifest.format_version
+        );
+    }
+    Ok(manifest)
+}
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = XxHash64::with_seed(0);
+    hasher.write(bytes);
+    hasher.finish()
+}
+
+/// Copies `reader` to `writer`, returning the number of bytes and their hash.
+fn hash_reader(mut reader: impl Read, mut writer: impl Write) -> Result<(u64, u64)> {
+    let mut hasher = XxHash64::with_seed(0);
+    let mut buffer = vec![0; 64 * 1024];
+    let mut size = 0;
+    loop {
+        let n = reader.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        hasher.write(&buffer[..n]);
+        writer.write_all(&buffer[..n])?;
+        size += n as u64;
+    }
+    Ok((size, hasher.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+    use turbo_persistence::TurboPersistence;
+
+    use super::*;
+    use crate::database::{
+        key_value_database::KeyValueDatabase,
+        shared_cache::{SharedCache, SharedCacheConfig, SharedCacheLayer, SharedCacheLocation},
+        turbo::TurboKeyValueDatabase,
+    };
+
+    fn version_info(describe: &str) -> GitVersionInfo<'_> {
+        GitVersionInfo {
+            describe,
+            dirty: false,
+        }
+    }
+
+    fn write_db(path: &Path) {
+        let db = TurboPersistence::open(path.to_owned()).unwrap();
+        let batch = db.write_batch::<Vec<u8>, 1>().unwrap();
+        for i in 0..100u32 {
+            batch
+                .put(0, i.to_be_bytes().to_vec(), vec![i as u8; 100].into())
+                .unwrap();
+        }
+        db.commit_write_batch(batch).unwrap();
+        db.shutdown().unwrap();
+    }
+
+    #[test]
+    fn test_export_import() {
+        let tmp_dir = TempDir::new().unwrap();
+        let source = tmp_dir.path().join("source");
+        let target = tmp_dir.path().join("target");
+        let archive = tmp_dir.path().join("snapshot.bin");
+        write_db(&source.join("mock-version"));
+
+        let manifest = export_snapshot(&source, &version_info("mock-version"), &archive).unwrap();
+        assert_eq!(manifest.version, "mock-version");
+        assert_eq!(manifest.families[&0].entries, 100);
+        assert_eq!(
+            read_snapshot_manifest(&archive).unwrap().files.len(),
+            manifest.files.len()
+        );
+
+        import_snapshot(&archive, &target, &version_info("mock-version")).unwrap();
+        let db = TurboPersistence::open_read_only(target.join("mock-version")).unwrap();
+        let value = db.get(0, &7u32.to_be_bytes().to_vec()).unwrap().unwrap();
+        assert_eq!(&*value, &[7u8; 100]);
+    }
+
+    #[test]
+    fn test_export_skips_dotfiles() {
+        let tmp_dir = TempDir::new().unwrap();
+        let source = tmp_dir.path().join("source");
+        let target = tmp_dir.path().join("target");
+        let archive = tmp_dir.path().join("snapshot.bin");
+        write_db(&source.join("mock-version"));
+        fs::write(
+            source.join("mock-version/.tmp-0123456789abcdef"),
+            b"leftover",
+        )
+        .unwrap();
+
+        let manifest = export_snapshot(&source, &version_info("mock-version"), &archive).unwrap();
+        assert!(
+            manifest
+                .files
+                .iter()
+                .all(|file| !file.name.starts_with('.'))
+        );
+
+        import_snapshot(&archive, &target, &version_info("mock-version")).unwrap();
+        let db = TurboPersistence::open_read_only(target.join("mock-version")).unwrap();
+        let value = db.get(0, &42u32.to_be_bytes().to_vec()).unwrap().unwrap();
+        assert_eq!(&*value, &[42u8; 100]);
+    }
+
+    #[test]
+    fn test_export_rejects_layered_database() {
+        let tmp_dir = TempDir::new().unwrap();
+        let source = tmp_dir.path().join("source");
+        let archive = tmp_dir.path().join("snapshot.bin");
+        let versioned_path = source.join("mock-version");
+        fs::create_dir_all(&versioned_path).unwrap();
+        let database = SharedCacheLayer::open(
+            versioned_path.clone(),
+            SharedCache::new(
+                SharedCacheConfig {
+                    location: SharedCacheLocation::Directory(tmp_dir.path().join("shared")),
+                    publish: true,
+                },
+                &version_info("mock-version"),
+            ),
+            |path| TurboKeyValueDatabase::new(path, false, true),
+        )
+        .unwrap();
+        database.shutdown().unwrap();
+        drop(database);
+
+        assert!(export_snapshot(&source, &version_info("mock-version"), &archive).is_err());
+        assert!(!archive.exists());
+    }
+
+    #[test]
+    fn test_import_rejects_other_version() {
+        let tmp_dir = TempDir::new().unwrap();
+        let source = tmp_dir.path().join("source");
+        let archive = tmp_dir.path().join("snapshot.bin");
+        write_db(&source.join("old-version"));
+        export_snapshot(&source, &version_info("old-version"), &archive).unwrap();
+
+        let target = tmp_dir.path().join("target");
+        assert!(import_snapshot(&archive, &target, &version_info("new-version")).is_err());
+        assert!(!target.join("new-version").exists());
+    }
+
+    #[test]
+    fn test_import_rejects_corrupted_archive() {
+        let tmp_dir = TempDir::new().unwrap();
+        let source = tmp_dir.path().join("source");
+        let archive = tmp_dir.path().join("snapshot.bin");
+        write_db(&source.join("mock-version"));
+        export_snapshot(&source, &version_info("mock-version"), &archive).unwrap();
+
+        let mut bytes = fs::read(&archive).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&archive, bytes).unwrap();
+
+        let target = tmp_dir.path().join("target");
+        assert!(import_snapshot(&archive, &target, &version_info("mock-version")).is_err());
+        assert!(!target.join("mock-version").exists());
+    }
+
+    /// Replaces the manifest of `archive` with the result of `update`, keeping it valid.
+    fn rewrite_manifest(archive: &Path, update: impl FnOnce(&mut SnapshotManifest)) {
+        let bytes = fs::read(archive).unwrap();
+        let mut reader = &bytes[..];
+        let mut manifest = read_manifest(&mut reader).unwrap();
+        update(&mut manifest);
+        let manifest_bytes = serde_json::to_vec(&manifest).unwrap();
+        let mut rewritten = MAGIC.to_vec();
+        rewritten
+            .write_u64::<BE>(manifest_bytes.len() as u64)
+            .unwrap();
+        rewritten.extend_from_slice(&manifest_bytes);
+        rewritten
+            .write_u64::<BE>(hash_bytes(&manifest_bytes))
+            .unwrap();
+        rewritten.extend_from_slice(reader);
+        fs::write(archive, rewritten).unwrap();
+    }
+
+    #[test]
+    fn test_import_rejects_mismatching_families() {
+        let tmp_dir = TempDir::new().unwrap();
+        let source = tmp_dir.path().join("source");
+        let archive = tmp_dir.path().join("snapshot.bin");
+        let target = tmp_dir.path().join("target");
+        write_db(&source.join("mock-version"));
+        export_snapshot(&source, &version_info("mock-version"), &archive).unwrap();
+
+        rewrite_manifest(&archive, |manifest| {
+            manifest.families.get_mut(&0).unwrap().entries -= 1;
+        });
+        assert!(import_snapshot(&archive, &target, &version_info("mock-version")).is_err());
+        assert!(!target.join("mock-version").exists());
+
+        rewrite_manifest(&archive, |manifest| {
+            manifest.families.remove(&0);
+        });
+        assert!(import_snapshot(&archive, &target, &version_info("mock-version")).is_err());
+        assert!(!target.join("mock-version").exists());
+    }
+}
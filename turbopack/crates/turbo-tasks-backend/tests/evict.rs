@@ -0,0 +1,70 @@
+#![feature(arbitrary_self_types)]
+#![feature(arbitrary_self_types_pointers)]
+#![allow(clippy::needless_return)] // tokio macro-generated code doesn't respect this
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use anyhow::Result;
+use turbo_tasks::{TurboTasks, Vc, turbo_tasks_scope};
+use turbo_tasks_backend::{
+    BackendOptions, GitVersionInfo, StorageMode, TurboTasksBackend, turbo_backing_storage,
+};
+use turbo_tasks_testing::{Registration, register};
+
+static REGISTRATION: Registration = register!();
+
+static EXECUTIONS: AtomicUsize = AtomicUsize::new(0);
+
+#[tokio::test]
+async fn evicted_tasks_are_restored() -> Result<()> {
+    REGISTRATION.ensure_registered();
+    let cache_dir = tempfile::tempdir()?;
+    let (backing_storage, _) = turbo_backing_storage(
+        cache_dir.path(),
+        &GitVersionInfo {
+            describe: "test-unversioned",
+            dirty: false,
+        },
+        false,
+        true,
+    )?;
+    let tt = TurboTasks::new(TurboTasksBackend::new(
+        BackendOptions {
+            storage_mode: Some(StorageMode::ReadWrite),
+            ..Default::default()
+        },
+        backing_storage,
+    ));
+
+    tt.run_once(async {
+        assert_eq!(*sum(10).await?, 55);
+        Ok(())
+    })
+    .await?;
+    assert_eq!(EXECUTIONS.load(Ordering::SeqCst), 11);
+
+    // The first pass only marks the tasks as cold, the second one evicts them.
+    turbo_tasks_scope(tt.clone(), || {
+        assert_eq!(tt.backend().snapshot_and_evict(), Some(0));
+        assert!(tt.backend().snapshot_and_evict().unwrap() > 0);
+    });
+
+    tt.run_once(async {
+        assert_eq!(*sum(10).await?, 55);
+        assert_eq!(*sum(11).await?, 66);
+        Ok(())
+    })
+    .await?;
+    // Only the new task executes, the evicted ones are read back from the cache.
+    assert_eq!(EXECUTIONS.load(Ordering::SeqCst), 12);
+
+    tt.stop_and_wait().await;
+    Ok(())
+}
+
+#[turbo_tasks::function]
+async fn sum(n: u32) -> Result<Vc<u32>> {
+    EXECUTIONS.fetch_add(1, Ordering::SeqCst);
+    let rest = if n == 0 { 0 } else { *sum(n - 1).await? };
+    Ok(Vc::cell(n + rest))
+}
@@ -0,0 +1,177 @@
+#![feature(arbitrary_self_types)]
+#![feature(arbitrary_self_types_pointers)]
+#![allow(clippy::needless_return)] // tokio macro-generated code doesn't respect this
+
+use anyhow::Result;
+use turbo_rcstr::{RcStr, rcstr};
+use turbo_tasks::{State, Vc, apply_effects};
+use turbo_tasks_fs::{DiskFileSystem, File, FileContent, FileSystem, FileSystemPath};
+use turbo_tasks_testing::{Registration, Replay, register};
+
+static REGISTRATION: Registration = register!(turbo_tasks_fs::register);
+
+#[tokio::test]
+async fn replay() -> Result<()> {
+    REGISTRATION.ensure_registered();
+    let tt = REGISTRATION.create_turbo_tasks("replay", true);
+    let replay = Replay::new(tt.clone());
+
+    let (input, _) = replay
+        .step(async {
+            Ok(ChangingInput {
+                state: State::new(2),
+            }
+            .resolved_cell())
+        })
+        .await?;
+
+    let (_, step) = replay
+        .step(async move {
+            assert_eq!(*as_number(*input).strongly_consistent().await?, 1);
+            Ok(())
+        })
+        .await?;
+    step.assert_executed(&["as_number", "is_even", "read_value"]);
+    step.assert_changed_cells(&["as_number u32#0", "is_even bool#0", "read_value u32#0"]);
+
+    // The parity doesn't change, so `as_number` doesn't need to be executed again.
+    let (_, step) = replay
+        .step(async move {
+            input.await?.state.set(4);
+            assert_eq!(*as_number(*input).strongly_consistent().await?, 1);
+            Ok(())
+        })
+        .await?;
+    step.assert_executed(&["is_even", "read_value"]);
+    step.assert_changed_cells(&["read_value u32#0"]);
+
+    let (_, step) = replay
+        .step(async move {
+            input.await?.state.set(5);
+            assert_eq!(*as_number(*input).strongly_consistent().await?, 0);
+            Ok(())
+        })
+        .await?;
+    step.assert_executed(&["as_number", "is_even", "read_value"]);
+    step.assert_changed_cells(&["as_number u32#0", "is_even bool#0", "read_value u32#0"]);
+
+    tt.stop_and_wait().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn replay_file_write() -> Result<()> {
+    REGISTRATION.ensure_registered();
+    let tt = REGISTRATION.create_turbo_tasks("replay_file_write", true);
+    let replay = Replay::new(tt.clone());
+    let scratch = tempfile::tempdir()?;
+    let root: RcStr = scratch.path().to_str().unwrap().into();
+
+    let (path, _) = replay
+        .step(async move {
+            let fs = DiskFileSystem::new(rcstr!("test"), root);
+            Ok(fs.root().await?.join("file.txt")?)
+        })
+        .await?;
+
+    let (_, step) = replay
+        .step({
+            let path = path.clone();
+            async move {
+                write_and_apply(path.clone(), rcstr!("abc")).await?;
+                assert!(!*is_empty_file(path).strongly_consistent().await?);
+                Ok(())
+            }
+        })
+        .await?;
+    step.assert_executed(&[
+        "<DiskFileSystem as FileSystem>::read",
+        "<DiskFileSystem as FileSystem>::write",
+        "file_len",
+        "is_empty_file",
+        "write_file",
+    ]);
+
+    // The write invalidates the read of the file, but the length doesn't change, so
+    // `is_empty_file` doesn't need to be executed again.
+    let (_, step) = replay
+        .step({
+            let path = path.clone();
+            async move {
+                write_and_apply(path.clone(), rcstr!("xyz")).await?;
+                assert!(!*is_empty_file(path).strongly_consistent().await?);
+                Ok(())
+            }
+        })
+        .await?;
+    step.assert_executed(&[
+        "<DiskFileSystem as FileSystem>::read",
+        "<DiskFileSystem as FileSystem>::write",
+        "file_len",
+        "write_file",
+    ]);
+
+    let (_, step) = replay
+        .step(async move {
+            write_and_apply(path.clone(), rcstr!("")).await?;
+            assert!(*is_empty_file(path).strongly_consistent().await?);
+            Ok(())
+        })
+        .await?;
+    step.assert_executed(&[
+        "<DiskFileSystem as FileSystem>::read",
+        "<DiskFileSystem as FileSystem>::write",
+        "file_len",
+        "is_empty_file",
+        "write_file",
+    ]);
+
+    tt.stop_and_wait().await;
+    Ok(())
+}
+
+async fn write_and_apply(path: FileSystemPath, content: RcStr) -> Result<()> {
+    let write = write_file(path, content);
+    write.read_strongly_consistent().await?;
+    apply_effects(write).await
+}
+
+#[turbo_tasks::value]
+struct ChangingInput {
+    state: State<u32>,
+}
+
+#[turbo_tasks::function]
+async fn read_value(input: Vc<ChangingInput>) -> Result<Vc<u32>> {
+    Ok(Vc::cell(*input.await?.state.get()))
+}
+
+#[turbo_tasks::function]
+async fn is_even(input: Vc<ChangingInput>) -> Result<Vc<bool>> {
+    Ok(Vc::cell(*read_value(input).await? % 2 == 0))
+}
+
+#[turbo_tasks::function]
+async fn as_number(input: Vc<ChangingInput>) -> Result<Vc<u32>> {
+    Ok(Vc::cell(if *is_even(input).await? { 1 } else { 0 }))
+}
+
+#[turbo_tasks::function(operation)]
+async fn write_file(path: FileSystemPath, content: RcStr) -> Result<()> {
+    path.write(FileContent::Content(File::from(content)).cell())
+        .await?;
+    Ok(())
+}
+
+#[turbo_tasks::function]
+async fn file_len(path: FileSystemPath) -> Result<Vc<u32>> {
+    let FileContent::Content(file) = &*path.read().await? else {
+        return Ok(Vc::cell(0));
+    };
+    Ok(Vc::cell(file.content().len() as u32))
+}
+
+#[turbo_tasks::function]
+async fn is_empty_file(path: FileSystemPath) -> Result<Vc<bool>> {
+    Ok(Vc::cell(*file_len(path).await? == 0))
+}
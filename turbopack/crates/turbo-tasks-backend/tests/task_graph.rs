@@ -0,0 +1,162 @@
+#![feature(arbitrary_self_types)]
+#![feature(arbitrary_self_types_pointers)]
+#![allow(clippy::needless_return)] // tokio macro-generated code doesn't respect this
+
+use std::{fmt, sync::Mutex};
+
+use anyhow::Result;
+use turbo_tasks::{InvalidationReason, Invalidator, State, TurboTasks, Vc, get_invalidator};
+use turbo_tasks_backend::{
+    BackendOptions, TaskGraphDependency, TurboTasksBackend, noop_backing_storage,
+};
+use turbo_tasks_testing::{Registration, register};
+
+static REGISTRATION: Registration = register!();
+
+#[tokio::test]
+async fn task_graph() -> Result<()> {
+    REGISTRATION.ensure_registered();
+    let tt = TurboTasks::new(TurboTasksBackend::new(
+        BackendOptions {
+            storage_mode: None,
+            introspection: true,
+            ..Default::default()
+        },
+        noop_backing_storage(),
+    ));
+
+    let input = tt
+        .run_once(async {
+            Ok(ChangingInput {
+                state: State::new(1),
+            }
+            .resolved_cell())
+        })
+        .await?;
+    tt.run_once(async move {
+        assert_eq!(*double(*input).strongly_consistent().await?, 2);
+        input.await?.state.set(2);
+        assert_eq!(*double(*input).strongly_consistent().await?, 4);
+        Ok(())
+    })
+    .await?;
+
+    let graph = tt.backend().task_graph();
+    let find = |name: &str| {
+        graph
+            .tasks
+            .iter()
+            .find(|task| task.name.ends_with(name))
+            .unwrap()
+    };
+    let value = find("read_value");
+    let double = find("double");
+    assert_eq!(value.executions, 2);
+    assert_eq!(double.executions, 2);
+    assert!(!double.dirty);
+    assert!(double.dependencies.iter().any(
+        |dependency| matches!(dependency, TaskGraphDependency::Output { task } if *task == value.id)
+    ));
+    assert!(double.last_execution_duration_us.is_some());
+    assert!(tt.backend().explain_execution(double.id).is_some());
+    let explanation = tt.backend().explain_execution(value.id).unwrap();
+    assert!(explanation.contains("invalidator"), "{explanation}");
+
+    let dot = graph.to_dot();
+    assert!(dot.starts_with("digraph tasks {"));
+    assert!(dot.contains(&format!(
+        "  {} -> {} [style=dashed, label=\"output\"];\n",
+        *double.id, *value.id
+    )));
+    assert!(
+        dot.lines()
+            .filter(|line| line.starts_with("  "))
+            .all(|line| line.trim_start().starts_with(|c: char| c.is_ascii_digit()))
+    );
+    assert!(graph.to_json()?.contains("\"dependencies\""));
+
+    tt.stop_and_wait().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn explain_invalidation_reason() -> Result<()> {
+    REGISTRATION.ensure_registered();
+    let tt = TurboTasks::new(TurboTasksBackend::new(
+        BackendOptions {
+            storage_mode: None,
+            introspection: true,
+            ..Default::default()
+        },
+        noop_backing_storage(),
+    ));
+
+    tt.run_once(async {
+        read_file().strongly_consistent().await?;
+        Ok(())
+    })
+    .await?;
+    INVALIDATOR
+        .lock()
+        .unwrap()
+        .take()
+        .unwrap()
+        .invalidate_with_reason(FileChanged);
+    tt.run_once(async {
+        read_file().strongly_consistent().await?;
+        Ok(())
+    })
+    .await?;
+
+    let graph = tt.backend().task_graph();
+    let node = graph
+        .tasks
+        .iter()
+        .find(|task| task.name.ends_with("read_file"))
+        .unwrap();
+    assert_eq!(
+        tt.backend().explain_execution(node.id).unwrap(),
+        "invalidated (invalidator, file.txt changed)"
+    );
+    assert_eq!(
+        node.last_invalidation_reason.as_deref(),
+        Some("file.txt changed")
+    );
+
+    tt.stop_and_wait().await;
+    Ok(())
+}
+
+static INVALIDATOR: Mutex<Option<Invalidator>> = Mutex::new(None);
+
+#[derive(PartialEq, Eq, Hash)]
+struct FileChanged;
+
+impl fmt::Display for FileChanged {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "file.txt changed")
+    }
+}
+
+impl InvalidationReason for FileChanged {}
+
+#[turbo_tasks::function]
+fn read_file() -> Vc<u32> {
+    *INVALIDATOR.lock().unwrap() = Some(get_invalidator());
+    Vc::cell(1)
+}
+
+#[turbo_tasks::value]
+struct ChangingInput {
+    state: State<u32>,
+}
+
+#[turbo_tasks::function]
+async fn read_value(input: Vc<ChangingInput>) -> Result<Vc<u32>> {
+    Ok(Vc::cell(*input.await?.state.get()))
+}
+
+#[turbo_tasks::function]
+async fn double(input: Vc<ChangingInput>) -> Result<Vc<u32>> {
+    Ok(Vc::cell(*read_value(input).await? * 2))
+}
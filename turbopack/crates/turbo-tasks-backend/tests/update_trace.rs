@@ -0,0 +1,185 @@
+#![feature(arbitrary_self_types)]
+#![feature(arbitrary_self_types_pointers)]
+#![allow(clippy::needless_return)] // tokio macro-generated code doesn't respect this
+
+use anyhow::Result;
+use turbo_rcstr::RcStr;
+use turbo_tasks::{
+    CollectiblesSource, ResolvedVc, State, TurboTasks, TurboTasksApi, ValueToString, Vc,
+    backend::Backend, emit,
+};
+use turbo_tasks_backend::{BackendOptions, TurboTasksBackend, noop_backing_storage};
+use turbo_tasks_testing::{Registration, register};
+
+static REGISTRATION: Registration = register!();
+
+#[tokio::test]
+async fn update_trace() -> Result<()> {
+    REGISTRATION.ensure_registered();
+    let tt = TurboTasks::new(TurboTasksBackend::new(
+        BackendOptions {
+            storage_mode: None,
+            ..Default::default()
+        },
+        noop_backing_storage(),
+    ));
+    let update_trace = tt.update_trace().enable().clone();
+
+    let input = tt
+        .run_once(async {
+            Ok(ChangingInput {
+                state: State::new(1),
+            }
+            .resolved_cell())
+        })
+        .await?;
+    tt.run_once(async move {
+        assert_eq!(*double(*input).strongly_consistent().await?, 2);
+        Ok(())
+    })
+    .await?;
+    update_trace.take(|task| tt.backend().get_task_description(task));
+
+    tt.run_once(async move {
+        input.await?.state.set(2);
+        assert_eq!(*double(*input).strongly_consistent().await?, 4);
+        Ok(())
+    })
+    .await?;
+    let report = update_trace.take(|task| tt.backend().get_task_description(task));
+
+    let first = &report.invalidations[0];
+    assert_eq!(first.cause, "invalidator");
+    assert!(first.tasks[0].contains("read_value"));
+    assert!(report.invalidations.iter().any(|invalidation| {
+        invalidation.cause.contains("read_value")
+            && invalidation
+                .tasks
+                .iter()
+                .any(|task| task.contains("double"))
+    }));
+    let executed = |name: &str| {
+        report
+            .executions
+            .iter()
+            .any(|execution| execution.task.contains(name))
+    };
+    assert!(executed("read_value"));
+    assert!(executed("double"));
+
+    // Nothing is recorded after disabling the trace
+    tt.update_trace().disable();
+    assert!(tt.update_trace().get().is_none());
+    tt.run_once(async move {
+        input.await?.state.set(3);
+        assert_eq!(*double(*input).strongly_consistent().await?, 6);
+        Ok(())
+    })
+    .await?;
+    let report = update_trace.take(|task| tt.backend().get_task_description(task));
+    assert!(report.invalidations.is_empty());
+    assert!(report.executions.is_empty());
+
+    tt.stop_and_wait().await;
+    Ok(())
+}
+
+#[tokio::test]
+async fn update_trace_collectibles() -> Result<()> {
+    REGISTRATION.ensure_registered();
+    let tt = TurboTasks::new(TurboTasksBackend::new(
+        BackendOptions {
+            storage_mode: None,
+            ..Default::default()
+        },
+        noop_backing_storage(),
+    ));
+    let update_trace = tt.update_trace().enable().clone();
+
+    let input = tt
+        .run_once(async {
+            Ok(ChangingInput {
+                state: State::new(1),
+            }
+            .resolved_cell())
+        })
+        .await?;
+    tt.run_once(async move {
+        assert_eq!(*count_collectibles(*input).strongly_consistent().await?, 0);
+        Ok(())
+    })
+    .await?;
+    update_trace.take(|task| tt.backend().get_task_description(task));
+
+    tt.run_once(async move {
+        input.await?.state.set(2);
+        assert_eq!(*count_collectibles(*input).strongly_consistent().await?, 1);
+        Ok(())
+    })
+    .await?;
+    let report = update_trace.take(|task| tt.backend().get_task_description(task));
+
+    assert!(
+        report.invalidations.iter().any(|invalidation| {
+            invalidation.cause.contains("collectibles of")
+                && invalidation
+                    .tasks
+                    .iter()
+                    .any(|task| task.contains("count_collectibles"))
+        }),
+        "{:#?}",
+        report.invalidations
+    );
+
+    tt.stop_and_wait().await;
+    Ok(())
+}
+
+#[turbo_tasks::value]
+struct ChangingInput {
+    state: State<u32>,
+}
+
+#[turbo_tasks::function]
+async fn read_value(input: Vc<ChangingInput>) -> Result<Vc<u32>> {
+    Ok(Vc::cell(*input.await?.state.get()))
+}
+
+#[turbo_tasks::function]
+async fn double(input: Vc<ChangingInput>) -> Result<Vc<u32>> {
+    Ok(Vc::cell(*read_value(input).await? * 2))
+}
+
+#[turbo_tasks::value(shared)]
+struct Thing(u32);
+
+#[turbo_tasks::value_impl]
+impl ValueToString for Thing {
+    #[turbo_tasks::function]
+    fn to_string(&self) -> Vc<RcStr> {
+        Vc::cell(self.0.to_string().into())
+    }
+}
+
+/// Emits a collectible for even values.
+#[turbo_tasks::function(operation)]
+async fn emit_even(input: ResolvedVc<ChangingInput>) -> Result<()> {
+    let value = *read_value(*input).await?;
+    if value % 2 == 0 {
+        emit(ResolvedVc::upcast::<Box<dyn ValueToString>>(
+            Thing(value).resolved_cell(),
+        ));
+    }
+    Ok(())
+}
+
+#[turbo_tasks::function]
+async fn count_collectibles(input: ResolvedVc<ChangingInput>) -> Result<Vc<u32>> {
+    let operation = emit_even(input);
+    operation.connect().await?;
+    Ok(Vc::cell(
+        operation
+            .peek_collectibles::<Box<dyn ValueToString>>()
+            .len() as u32,
+    ))
+}
@@ -0,0 +1,394 @@
+use std::{
+    io::ErrorKind,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result};
+use reqwest::header::{
+    AGE, CACHE_CONTROL, CONTENT_LENGTH, ETAG, HeaderMap, HeaderName, HeaderValue,
+    IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use serde::{Deserialize, Serialize};
+use turbo_rcstr::RcStr;
+use turbo_tasks::Vc;
+use turbo_tasks_hash::hash_xxh3_hash64;
+
+use crate::{FetchResult, HttpResponse, HttpResponseBody};
+
+/// An on-disk cache for the responses of [`FetchClient::fetch_cached`], which survives restarts.
+///
+/// Responses are reused without a request while they are fresh according to their
+/// `Cache-Control` header. Stale responses are revalidated with a conditional request using their
+/// `ETag` and `Last-Modified` headers.
+///
+/// [`FetchClient::fetch_cached`]: crate::FetchClient::fetch_cached
+#[turbo_tasks::value(shared)]
+#[derive(Debug)]
+pub struct HttpCache {
+    /// The directory to store the responses in. It's created if it doesn't exist.
+    pub dir: RcStr,
+    /// Use a stale response when the server can't be reached, instead of failing. This keeps
+    /// working offline, at the cost of possibly outdated responses.
+    pub offline_use_stale: bool,
+}
+
+impl HttpCache {
+    pub(crate) fn entry_path(&self, url: &str, user_agent: Option<&str>) -> PathBuf {
+        let key = format!("{url}\n{}", user_agent.unwrap_or_default());
+        Path::new(&*self.dir).join(format!("{:016x}", hash_xxh3_hash64(key.as_bytes())))
+    }
+}
+
+/// The directives of a `Cache-Control` response header that affect a private cache.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub(crate) struct CacheControl {
+    pub no_store: bool,
+    pub no_cache: bool,
+    pub max_age: Option<u64>,
+}
+
+impl CacheControl {
+    pub fn from_headers(headers: &HeaderMap) -> Self {
+        let mut cache_control = CacheControl::default();
+        for value in headers.get_all(CACHE_CONTROL) {
+            let Ok(value) = value.to_str() else {
+                continue;
+            };
+            for directive in value.split(',') {
+                let (name, argument) = match directive.split_once('=') {
+                    Some((name, argument)) => (name, Some(argument.trim().trim_matches('"'))),
+                    None => (directive, None),
+                };
+                match &*name.trim().to_ascii_lowercase() {
+                    "no-store" => cache_control.no_store = true,
+                    "no-cache" => cache_control.no_cache = true,
+                    "max-age" => cache_control.max_age = argument.and_then(|a| a.parse().ok()),
+                    _ => {}
+                }
+            }
+        }
+        cache_control
+    }
+
+    /// How many seconds a response with these headers is fresh for, or `None` if it has to be
+    /// revalidated before every use.
+    fn freshness_lifetime(&self, headers: &HeaderMap) -> Option<u64> {
+        if self.no_cache {
+            return None;
+        }
+        let age = headers
+            .get(AGE)
+            .and_then(|age| age.to_str().ok()?.trim().parse().ok())
+            .unwrap_or(0);
+        self.max_age.map(|max_age| max_age.saturating_sub(age))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheEntryMetadata {
+    url: String,
+    status: u16,
+    /// The headers of the response, including the ones of later `304 Not Modified` responses.
+    #[serde(default)]
+    headers: Vec<(String, String)>,
+    /// When the response was received or last revalidated, in seconds since the unix epoch.
+    stored_at: u64,
+    /// For how many seconds after `stored_at` the response is fresh. `None` if it has to be
+    /// revalidated before every use.
+    fresh_for: Option<u64>,
+}
+
+/// A cached response. Stored as a line of JSON metadata followed by the body.
+#[derive(Debug)]
+pub(crate) struct CacheEntry {
+    metadata: CacheEntryMetadata,
+    body: Vec<u8>,
+}
+
+impl CacheEntry {
+    pub fn new(
+        url: &str,
+        status: u16,
+        headers: &HeaderMap,
+        body: Vec<u8>,
+        now: SystemTime,
+    ) -> Self {
+        let mut entry = CacheEntry {
+            metadata: CacheEntryMetadata {
+                url: url.to_string(),
+                status,
+                headers: Vec::new(),
+                stored_at: 0,
+                fresh_for: None,
+            },
+            body,
+        };
+        entry.store_headers(headers.clone(), now);
+        entry
+    }
+
+    /// Updates the entry with the headers of a response that confirmed it, i.e. a `304 Not
+    /// Modified` response to a conditional request.
+    ///
+    /// The headers replace the stored headers of the same name, except for `Content-Length`, which
+    /// describes the body of the `304` response (RFC 9111 section 4.3.4). The freshness is
+    /// computed from the merged headers, as a `304` response may omit e.g. `Cache-Control`.
+    pub fn update(&mut self, headers: &HeaderMap, now: SystemTime) {
+        let mut stored_headers = self.headers();
+        for name in headers.keys() {
+            if name == CONTENT_LENGTH {
+                continue;
+            }
+            stored_headers.remove(name);
+            for value in headers.get_all(name) {
+                stored_headers.append(name.clone(), value.clone());
+            }
+        }
+        self.store_headers(stored_headers, now);
+    }
+
+    fn store_headers(&mut self, headers: HeaderMap, now: SystemTime) {
+        self.metadata.headers = headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+            .collect();
+        self.metadata.stored_at = unix_seconds(now);
+        self.metadata.fresh_for = CacheControl::from_headers(&headers).freshness_lifetime(&headers);
+    }
+
+    fn headers(&self) -> HeaderMap {
+        self.metadata
+            .headers
+            .iter()
+            .filter_map(|(name, value)| {
+                Some((
+                    HeaderName::try_from(name).ok()?,
+                    HeaderValue::try_from(value).ok()?,
+                ))
+            })
+            .collect()
+    }
+
+    fn header(&self, name: HeaderName) -> Option<&str> {
+        self.metadata
+            .headers
+            .iter()
+            .find(|(stored_name, _)| *stored_name == name.as_str())
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn is_fresh(&self, now: SystemTime) -> bool {
+        self.metadata.fresh_for.is_some_and(|fresh_for| {
+            unix_seconds(now) < self.metadata.stored_at.saturating_add(fresh_for)
+        })
+    }
+
+    /// Adds the headers that make the request conditional on the entry being outdated.
+    pub fn add_validators(&self, mut builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        if let Some(etag) = self.header(ETAG) {
+            builder = builder.header(IF_NONE_MATCH, etag);
+        }
+        if let Some(last_modified) = self.header(LAST_MODIFIED) {
+            builder = builder.header(IF_MODIFIED_SINCE, last_modified);
+        }
+        builder
+    }
+
+    /// Reads the entry at `path`. Missing and unreadable entries are treated the same, as the
+    /// response can always be fetched again.
+    pub async fn read(path: &Path) -> Option<Self> {
+        let content = tokio::fs::read(path).await.ok()?;
+        let newline = content.iter().position(|byte| *byte == b'\n')?;
+        let metadata = serde_json::from_slice(&content[..newline]).ok()?;
+        Some(CacheEntry {
+            metadata,
+            body: content[newline + 1..].to_vec(),
+        })
+    }
+
+    pub async fn write(&self, path: &Path) -> Result<()> {
+        let mut content = serde_json::to_vec(&self.metadata)?;
+        content.push(b'\n');
+        content.extend_from_slice(&self.body);
+
+        // Write to a temporary file first, so concurrent readers never see a partial entry. The
+        // name is unique, as other tasks or processes might write the same entry concurrently.
+        let dir = path
+            .parent()
+            .context("cache entry must be in a directory")?;
+        tokio::fs::create_dir_all(dir)
+            .await
+            .with_context(|| format!("failed to create HTTP cache directory {}", dir.display()))?;
+        static TEMP_FILE_COUNTER: AtomicU64 = AtomicU64::new(0);
+        let temp_path = path.with_extension(format!(
+            "{}-{}.tmp",
+            std::process::id(),
+            TEMP_FILE_COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        tokio::fs::write(&temp_path, content)
+            .await
+            .with_context(|| format!("failed to write HTTP cache entry {}", path.display()))?;
+        if let Err(err) = tokio::fs::rename(&temp_path, path).await {
+            let _ = tokio::fs::remove_file(&temp_path).await;
+            return Err(err)
+                .with_context(|| format!("failed to write HTTP cache entry {}", path.display()));
+        }
+        Ok(())
+    }
+
+    pub async fn remove(path: &Path) -> Result<()> {
+        match tokio::fs::remove_file(path).await {
+            Err(err) if err.kind() != ErrorKind::NotFound => Err(err)
+                .with_context(|| format!("failed to remove HTTP cache entry {}", path.display())),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn into_fetch_result(self) -> Vc<FetchResult> {
+        Vc::cell(Ok(HttpResponse {
+            status: self.metadata.status,
+            body: HttpResponseBody(self.body).resolved_cell(),
+        }
+        .resolved_cell()))
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn headers(headers: &[(HeaderName, &'static str)]) -> HeaderMap {
+        headers
+            .iter()
+            .map(|(name, value)| (name.clone(), HeaderValue::from_static(value)))
+            .collect()
+    }
+
+    #[test]
+    fn parse_cache_control() {
+        assert_eq!(
+            CacheControl::from_headers(&headers(&[(
+                CACHE_CONTROL,
+                "public, max-age=\"60\", No-Cache"
+            )])),
+            CacheControl {
+                no_store: false,
+                no_cache: true,
+                max_age: Some(60),
+            }
+        );
+        assert_eq!(
+            CacheControl::from_headers(&headers(&[
+                (CACHE_CONTROL, "no-store"),
+                (CACHE_CONTROL, "max-age=10")
+            ])),
+            CacheControl {
+                no_store: true,
+                no_cache: false,
+                max_age: Some(10),
+            }
+        );
+    }
+
+    #[test]
+    fn freshness() {
+        let now = SystemTime::now();
+        let entry = CacheEntry::new(
+            "https://example.com",
+            200,
+            &headers(&[(CACHE_CONTROL, "max-age=60"), (AGE, "20")]),
+            Vec::new(),
+            now,
+        );
+        assert!(entry.is_fresh(now + Duration::from_secs(39)));
+        assert!(!entry.is_fresh(now + Duration::from_secs(40)));
+
+        let entry = CacheEntry::new(
+            "https://example.com",
+            200,
+            &headers(&[(CACHE_CONTROL, "max-age=60, no-cache")]),
+            Vec::new(),
+            now,
+        );
+        assert!(!entry.is_fresh(now));
+    }
+
+    #[test]
+    fn revalidation_merges_headers() {
+        let now = SystemTime::now();
+        let mut entry = CacheEntry::new(
+            "https://example.com",
+            200,
+            &headers(&[
+                (CACHE_CONTROL, "max-age=60"),
+                (ETAG, "\"a\""),
+                (CONTENT_LENGTH, "5"),
+            ]),
+            Vec::new(),
+            now,
+        );
+        let revalidated_at = now + Duration::from_secs(100);
+        entry.update(
+            &headers(&[(ETAG, "\"b\""), (CONTENT_LENGTH, "0")]),
+            revalidated_at,
+        );
+        // The `Cache-Control` header of the stored response still applies
+        assert!(entry.is_fresh(revalidated_at + Duration::from_secs(59)));
+        assert_eq!(entry.header(ETAG), Some("\"b\""));
+        assert_eq!(entry.header(CONTENT_LENGTH), Some("5"));
+
+        entry.update(&headers(&[(CACHE_CONTROL, "no-cache")]), revalidated_at);
+        assert!(!entry.is_fresh(revalidated_at));
+    }
+
+    #[test]
+    fn freshness_doesnt_overflow() {
+        let now = SystemTime::now();
+        let entry = CacheEntry::new(
+            "https://example.com",
+            200,
+            &headers(&[(CACHE_CONTROL, "max-age=18446744073709551615")]),
+            Vec::new(),
+            now,
+        );
+        assert!(entry.is_fresh(now + Duration::from_secs(365 * 24 * 60 * 60)));
+    }
+
+    #[tokio::test]
+    async fn concurrent_writes() -> Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join("entry");
+        let entry = |body: &[u8]| {
+            CacheEntry::new(
+                "https://example.com",
+                200,
+                &HeaderMap::new(),
+                body.to_vec(),
+                SystemTime::now(),
+            )
+        };
+        let (first, second) = (entry(b"first"), entry(b"second"));
+        let (first_result, second_result) = tokio::join!(first.write(&path), second.write(&path));
+        first_result?;
+        second_result?;
+
+        let body = CacheEntry::read(&path)
+            .await
+            .context("entry is readable")?
+            .body;
+        assert!(body == b"first" || body == b"second");
+        // no temporary files are left behind
+        assert_eq!(std::fs::read_dir(dir.path())?.count(), 1);
+        Ok(())
+    }
+}
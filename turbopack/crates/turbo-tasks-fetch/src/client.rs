@@ -1,14 +1,18 @@
-use std::{hash::Hash, sync::LazyLock};
+use std::{hash::Hash, sync::LazyLock, time::SystemTime};
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use quick_cache::sync::Cache;
+use reqwest::{StatusCode, header::HeaderMap};
 use serde::{Deserialize, Serialize};
 use turbo_rcstr::RcStr;
 use turbo_tasks::{
     NonLocalValue, ReadRef, Vc, duration_span, mark_session_dependent, trace::TraceRawVcs,
 };
 
-use crate::{FetchError, FetchResult, HttpResponse, HttpResponseBody};
+use crate::{
+    FetchError, FetchResult, HttpCache, HttpResponse, HttpResponseBody,
+    cache::{CacheControl, CacheEntry},
+};
 
 const MAX_CLIENTS: usize = 16;
 static CLIENT_CACHE: LazyLock<Cache<ReadRef<FetchClient>, reqwest::Client>> =
@@ -104,18 +108,7 @@ impl FetchClient {
         let url_ref = &*url;
         let this = self.await?;
         let response_result: reqwest::Result<HttpResponse> = async move {
-            let reqwest_client = this.try_get_cached_reqwest_client()?;
-
-            let mut builder = reqwest_client.get(url_ref);
-            if let Some(user_agent) = user_agent {
-                builder = builder.header("User-Agent", user_agent.as_str());
-            }
-
-            let response = {
-                let _span = duration_span!("fetch request", url = url_ref);
-                builder.send().await
-            }
-            .and_then(|r| r.error_for_status())?;
+            let response = send_request(this, url_ref, user_agent.as_deref(), None).await?;
 
             let status = response.status().as_u16();
 
@@ -143,6 +136,101 @@ impl FetchClient {
             }
         }
     }
+
+    /// Like [`FetchClient::fetch`], but keeps the response in `cache` and reuses it according to
+    /// its caching headers, also across restarts. See [`HttpCache`].
+    #[turbo_tasks::function(network)]
+    pub async fn fetch_cached(
+        self: Vc<FetchClient>,
+        url: RcStr,
+        user_agent: Option<RcStr>,
+        cache: Vc<HttpCache>,
+    ) -> Result<Vc<FetchResult>> {
+        let url_ref = &*url;
+        let this = self.await?;
+        let cache = cache.await?;
+        let entry_path = cache.entry_path(url_ref, user_agent.as_deref());
+        let now = SystemTime::now();
+        let cached = match CacheEntry::read(&entry_path).await {
+            Some(entry) if entry.is_fresh(now) => return Ok(entry.into_fetch_result()),
+            cached => cached,
+        };
+
+        let response_result: reqwest::Result<(u16, HeaderMap, Option<Vec<u8>>)> = async {
+            let response =
+                send_request(this, url_ref, user_agent.as_deref(), cached.as_ref()).await?;
+
+            let status = response.status();
+            let headers = response.headers().clone();
+            if status == StatusCode::NOT_MODIFIED {
+                return Ok((status.as_u16(), headers, None));
+            }
+
+            let body = {
+                let _span = duration_span!("fetch response", url = url_ref);
+                response.bytes().await?
+            }
+            .to_vec();
+
+            Ok((status.as_u16(), headers, Some(body)))
+        }
+        .await;
+
+        let (status, headers, body) = match response_result {
+            Ok(response) => response,
+            Err(err) => {
+                mark_session_dependent();
+                // Only fall back to the stale response when the server couldn't be reached. An
+                // error status means that the resource is gone or broken.
+                if cache.offline_use_stale
+                    && err.status().is_none()
+                    && let Some(cached) = cached
+                {
+                    return Ok(cached.into_fetch_result());
+                }
+                return Ok(Vc::cell(Err(
+                    FetchError::from_reqwest_error(&err, &url).resolved_cell()
+                )));
+            }
+        };
+
+        let entry = match (body, cached) {
+            (Some(body), _) => CacheEntry::new(url_ref, status, &headers, body, now),
+            (None, Some(mut cached)) => {
+                cached.update(&headers, now);
+                cached
+            }
+            (None, None) => bail!("Received 304 Not Modified for {url} without a cached response"),
+        };
+        if CacheControl::from_headers(&headers).no_store {
+            CacheEntry::remove(&entry_path).await?;
+        } else {
+            entry.write(&entry_path).await?;
+        }
+        Ok(entry.into_fetch_result())
+    }
+}
+
+/// Sends a `GET` request for `url`. Responses with an error status are turned into errors. With a
+/// `cached` response, the request is conditional on the cached response being outdated.
+async fn send_request(
+    client: ReadRef<FetchClient>,
+    url: &str,
+    user_agent: Option<&str>,
+    cached: Option<&CacheEntry>,
+) -> reqwest::Result<reqwest::Response> {
+    let reqwest_client = client.try_get_cached_reqwest_client()?;
+
+    let mut builder = reqwest_client.get(url);
+    if let Some(user_agent) = user_agent {
+        builder = builder.header("User-Agent", user_agent);
+    }
+    if let Some(cached) = cached {
+        builder = cached.add_validators(builder);
+    }
+
+    let _span = duration_span!("fetch request", url = url);
+    builder.send().await?.error_for_status()
 }
 
 #[doc(hidden)]
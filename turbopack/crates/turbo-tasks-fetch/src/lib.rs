@@ -2,11 +2,13 @@
 #![feature(arbitrary_self_types)]
 #![feature(arbitrary_self_types_pointers)]
 
+mod cache;
 mod client;
 mod error;
 mod response;
 
 pub use crate::{
+    cache::HttpCache,
     client::{
         __test_only_reqwest_client_cache_clear, __test_only_reqwest_client_cache_len, FetchClient,
         ProxyConfig,
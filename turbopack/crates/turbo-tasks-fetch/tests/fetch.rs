@@ -1,12 +1,17 @@
 #![allow(clippy::needless_return)] // tokio macro-generated code doesn't respect this
 #![cfg(test)]
 
-use tokio::sync::Mutex as TokioMutex;
+use mockito::Matcher;
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpListener,
+    sync::Mutex as TokioMutex,
+};
 use turbo_rcstr::{RcStr, rcstr};
 use turbo_tasks::Vc;
 use turbo_tasks_fetch::{
     __test_only_reqwest_client_cache_clear, __test_only_reqwest_client_cache_len, FetchClient,
-    FetchErrorKind,
+    FetchErrorKind, HttpCache,
 };
 use turbo_tasks_fs::{DiskFileSystem, FileSystem, FileSystemPath};
 use turbo_tasks_testing::{Registration, register, run};
@@ -271,3 +276,173 @@ async fn client_cache() {
     .await
     .unwrap()
 }
+
+/// Each fetch in the tests below gets its own cache cell, so it's a separate task that has to
+/// read the cache directory, instead of reusing the result of the previous task.
+fn http_cache(dir: &tempfile::TempDir, offline_use_stale: bool) -> Vc<HttpCache> {
+    HttpCache {
+        dir: dir.path().to_str().unwrap().into(),
+        offline_use_stale,
+    }
+    .cell()
+}
+
+#[tokio::test]
+async fn http_cache_reuses_fresh_response() {
+    let _guard = GLOBAL_TEST_LOCK.lock().await;
+    run(&REGISTRATION, || async {
+        let cache_dir = tempfile::tempdir()?;
+        let mut server = mockito::Server::new_async().await;
+        let resource_mock = server
+            .mock("GET", "/foo.woff")
+            .with_header("Cache-Control", "max-age=3600")
+            .with_body("responsebody")
+            .create_async()
+            .await;
+
+        let url = RcStr::from(format!("{}/foo.woff", server.url()));
+        let client_vc = FetchClient::default().cell();
+        for _ in 0..2 {
+            let response = &*client_vc
+                .fetch_cached(url.clone(), None, http_cache(&cache_dir, false))
+                .await?
+                .unwrap()
+                .await?;
+            assert_eq!(response.status, 200);
+            assert_eq!(*response.body.to_string().await?, "responsebody");
+        }
+
+        // the second response is read from the cache directory
+        resource_mock.expect(1).assert_async().await;
+        anyhow::Ok(())
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn http_cache_refetches_stale_response() {
+    let _guard = GLOBAL_TEST_LOCK.lock().await;
+    run(&REGISTRATION, || async {
+        let cache_dir = tempfile::tempdir()?;
+        let mut server = mockito::Server::new_async().await;
+        // without validators, a stale response can't be revalidated and is fetched again
+        let resource_mock = server
+            .mock("GET", "/foo.woff")
+            .match_header("If-None-Match", Matcher::Missing)
+            .match_header("If-Modified-Since", Matcher::Missing)
+            .with_header("Cache-Control", "max-age=0")
+            .with_body("responsebody")
+            .create_async()
+            .await;
+
+        let url = RcStr::from(format!("{}/foo.woff", server.url()));
+        let client_vc = FetchClient::default().cell();
+        for _ in 0..2 {
+            let response = &*client_vc
+                .fetch_cached(url.clone(), None, http_cache(&cache_dir, false))
+                .await?
+                .unwrap()
+                .await?;
+            assert_eq!(response.status, 200);
+            assert_eq!(*response.body.to_string().await?, "responsebody");
+        }
+
+        resource_mock.expect(2).assert_async().await;
+        anyhow::Ok(())
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn http_cache_revalidates_stale_response() {
+    let _guard = GLOBAL_TEST_LOCK.lock().await;
+    run(&REGISTRATION, || async {
+        let cache_dir = tempfile::tempdir()?;
+        let mut server = mockito::Server::new_async().await;
+        let initial_mock = server
+            .mock("GET", "/foo.woff")
+            .match_header("If-None-Match", Matcher::Missing)
+            .with_header("Cache-Control", "no-cache")
+            .with_header("ETag", "\"v1\"")
+            .with_body("responsebody")
+            .create_async()
+            .await;
+        let revalidation_mock = server
+            .mock("GET", "/foo.woff")
+            .match_header("If-None-Match", "\"v1\"")
+            .with_status(304)
+            .create_async()
+            .await;
+
+        let url = RcStr::from(format!("{}/foo.woff", server.url()));
+        let client_vc = FetchClient::default().cell();
+        for _ in 0..2 {
+            let response = &*client_vc
+                .fetch_cached(url.clone(), None, http_cache(&cache_dir, false))
+                .await?
+                .unwrap()
+                .await?;
+            assert_eq!(response.status, 200);
+            assert_eq!(*response.body.to_string().await?, "responsebody");
+        }
+
+        initial_mock.expect(1).assert_async().await;
+        revalidation_mock.expect(1).assert_async().await;
+        anyhow::Ok(())
+    })
+    .await
+    .unwrap()
+}
+
+#[tokio::test]
+async fn http_cache_offline_uses_stale_response() {
+    let _guard = GLOBAL_TEST_LOCK.lock().await;
+    run(&REGISTRATION, || async {
+        let cache_dir = tempfile::tempdir()?;
+
+        // Serve a single response and stop listening, so later requests fail to connect.
+        let listener = TcpListener::bind("127.0.0.1:0").await?;
+        let url = RcStr::from(format!("http://{}/foo.woff", listener.local_addr()?));
+        let server = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await?;
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request).await?;
+            stream
+                .write_all(
+                    b"HTTP/1.1 200 OK\r\nCache-Control: no-cache\r\nContent-Length: \
+                      12\r\nConnection: close\r\n\r\nresponsebody",
+                )
+                .await?;
+            anyhow::Ok(())
+        });
+
+        let client_vc = FetchClient::default().cell();
+        let response = &*client_vc
+            .fetch_cached(url.clone(), None, http_cache(&cache_dir, true))
+            .await?
+            .unwrap()
+            .await?;
+        assert_eq!(*response.body.to_string().await?, "responsebody");
+        server.await??;
+
+        let stale_response = &*client_vc
+            .fetch_cached(url.clone(), None, http_cache(&cache_dir, true))
+            .await?
+            .unwrap()
+            .await?;
+        assert_eq!(stale_response.status, 200);
+        assert_eq!(*stale_response.body.to_string().await?, "responsebody");
+
+        // without opting in, the connection error is returned
+        let err_vc = &*client_vc
+            .fetch_cached(url.clone(), None, http_cache(&cache_dir, false))
+            .await?
+            .unwrap_err();
+        assert_eq!(*err_vc.await?.kind.await?, FetchErrorKind::Connect);
+        anyhow::Ok(())
+    })
+    .await
+    .unwrap()
+}
@@ -844,6 +844,10 @@ impl FileSystem for DiskFileSystem {
 
             inner.invalidate_from_write(&full_path, old_invalidators);
 
+            if let Some(update_trace) = turbo_tasks::turbo_tasks().update_trace().get() {
+                update_trace.record_changed_output(full_path.display());
+            }
+
             Ok(())
         });
 
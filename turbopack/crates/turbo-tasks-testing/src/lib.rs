@@ -1,5 +1,6 @@
 //! Testing utilities and macros for turbo-tasks and applications based on it.
 
+mod replay;
 pub mod retry;
 mod run;
 
@@ -24,7 +25,10 @@ use turbo_tasks::{
     util::{SharedError, StaticOrArc},
 };
 
-pub use crate::run::{Registration, run, run_with_tt, run_without_cache_check};
+pub use crate::{
+    replay::{Replay, ReplayStep},
+    run::{Registration, run, run_with_tt, run_without_cache_check},
+};
 
 enum Task {
     Spawned(Event),
@@ -36,6 +40,8 @@ pub struct VcStorage {
     this: Weak<Self>,
     cells: Mutex<FxHashMap<(TaskId, CellId), CellContent>>,
     tasks: Mutex<Vec<Task>>,
+    /// Never enabled, but returned so that code checking whether tracing is enabled works.
+    update_trace: turbo_tasks::update_trace::UpdateTraceApi,
 }
 
 impl VcStorage {
@@ -306,6 +312,14 @@ impl TurboTasksApi for VcStorage {
         unimplemented!()
     }
 
+    fn update_trace(&self) -> &turbo_tasks::update_trace::UpdateTraceApi {
+        &self.update_trace
+    }
+
+    fn get_task_description(&self, task: TaskId) -> String {
+        format!("{task:?}")
+    }
+
     fn stop_and_wait(&self) -> std::pin::Pin<Box<dyn Future<Output = ()> + Send + 'static>> {
         Box::pin(async {})
     }
@@ -0,0 +1,102 @@
+use std::{future::Future, sync::Arc};
+
+use anyhow::Result;
+use turbo_tasks::{
+    TaskId, TurboTasksApi, run_once, test_helpers::current_task_for_testing,
+    update_trace::UpdateTrace,
+};
+
+/// A harness for asserting on the recomputation behavior of turbo-tasks functions.
+///
+/// Every [`Replay::step`] runs a future, which applies changes (e.g. setting a
+/// [`State`][turbo_tasks::State] or writing a file through a `FileSystemPath`) and reads the
+/// affected outputs strongly consistent. The step returns which functions were executed and which
+/// cells changed while it ran, so a test can fail when a change introduces extra recomputation.
+///
+/// File changes should be made through turbo-tasks (or invalidated explicitly) instead of relying
+/// on a file watcher, as the watcher reports them at a nondeterministic time.
+///
+/// This needs a real [`TurboTasks`][turbo_tasks::TurboTasks] instance, as it's based on the
+/// [`UpdateTrace`], which the [`VcStorage`][crate::VcStorage] backend doesn't support.
+pub struct Replay {
+    tt: Arc<dyn TurboTasksApi>,
+    update_trace: Arc<UpdateTrace>,
+}
+
+impl Replay {
+    /// Enables the [`UpdateTrace`] of `tt`. Nothing else should take the recorded trace (e.g.
+    /// via `aggregated_update_info`) while the harness is used.
+    pub fn new(tt: Arc<dyn TurboTasksApi>) -> Self {
+        let update_trace = tt.update_trace().enable().clone();
+        Replay { tt, update_trace }
+    }
+
+    /// Runs `future` in a new root task and records the functions it caused to execute and the
+    /// cells they changed. The root task itself isn't part of the result.
+    pub async fn step<T: Send + 'static>(
+        &self,
+        future: impl Future<Output = Result<T>> + Send + 'static,
+    ) -> Result<(T, ReplayStep)> {
+        // Drop everything recorded by previous, unrelated work.
+        self.take(None);
+        let (result, root_task) = run_once(self.tt.clone(), async move {
+            let root_task = current_task_for_testing();
+            Ok((future.await?, root_task))
+        })
+        .await?;
+        Ok((result, self.take(Some(root_task))))
+    }
+
+    fn take(&self, root_task: Option<TaskId>) -> ReplayStep {
+        let report = self.update_trace.take_filtered(
+            |task| Some(task) != root_task,
+            |task| self.tt.get_task_description(task),
+        );
+        let mut executed: Vec<String> = report
+            .executions
+            .into_iter()
+            .map(|execution| execution.task)
+            .collect();
+        let mut changed_cells: Vec<String> = report
+            .changed_cells
+            .into_iter()
+            .map(|changed_cell| format!("{} {}", changed_cell.task, changed_cell.cell))
+            .collect();
+        // Tasks run concurrently, so only the sorted lists are deterministic.
+        executed.sort();
+        changed_cells.sort();
+        ReplayStep {
+            executed,
+            changed_cells,
+        }
+    }
+}
+
+/// What happened during a [`Replay::step`].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct ReplayStep {
+    /// The names of the executed functions, sorted. A function that was executed multiple times,
+    /// e.g. for different arguments, is listed multiple times.
+    pub executed: Vec<String>,
+    /// The cells that were updated with a different value, as `<function> <type>#<index>`, sorted.
+    pub changed_cells: Vec<String>,
+}
+
+impl ReplayStep {
+    /// Asserts that exactly the `expected` functions were executed, in any order.
+    #[track_caller]
+    pub fn assert_executed(&self, expected: &[&str]) {
+        let mut expected = expected.to_vec();
+        expected.sort();
+        assert_eq!(self.executed, expected, "unexpected function executions");
+    }
+
+    /// Asserts that exactly the `expected` cells changed, in any order. See
+    /// [`ReplayStep::changed_cells`] for the format.
+    #[track_caller]
+    pub fn assert_changed_cells(&self, expected: &[&str]) {
+        let mut expected = expected.to_vec();
+        expected.sort();
+        assert_eq!(self.changed_cells, expected, "unexpected changed cells");
+    }
+}
@@ -18,8 +18,8 @@ use turbo_rcstr::RcStr;
 
 pub use crate::id::BackendJobId;
 use crate::{
-    RawVc, ReadCellOptions, ReadRef, SharedReference, TaskId, TaskIdSet, TraitRef, TraitTypeId,
-    TurboTasksPanic, ValueTypeId, VcRead, VcValueTrait, VcValueType,
+    InvalidationReason, RawVc, ReadCellOptions, ReadRef, SharedReference, TaskId, TaskIdSet,
+    TraitRef, TraitTypeId, TurboTasksPanic, ValueTypeId, VcRead, VcValueTrait, VcValueType,
     event::EventListener,
     macro_helpers::NativeFunction,
     magic_any::MagicAny,
@@ -29,6 +29,7 @@ use crate::{
     task::shared_reference::TypedSharedReference,
     task_statistics::TaskStatisticsApi,
     triomphe_utils::unchecked_sidecast_triomphe_arc,
+    util::StaticOrArc,
 };
 
 pub type TransientTaskRoot =
@@ -521,6 +522,18 @@ pub trait Backend: Sync + Send {
 
     fn invalidate_task(&self, task: TaskId, turbo_tasks: &dyn TurboTasksBackendApi<Self>);
 
+    /// Like [`Backend::invalidate_task`], but with the reason of the invalidation, which backends
+    /// can record for introspection.
+    #[allow(unused_variables)]
+    fn invalidate_task_with_reason(
+        &self,
+        task: TaskId,
+        reason: &StaticOrArc<dyn InvalidationReason>,
+        turbo_tasks: &dyn TurboTasksBackendApi<Self>,
+    ) {
+        self.invalidate_task(task, turbo_tasks);
+    }
+
     fn invalidate_tasks(&self, tasks: &[TaskId], turbo_tasks: &dyn TurboTasksBackendApi<Self>);
     fn invalidate_tasks_set(&self, tasks: &TaskIdSet, turbo_tasks: &dyn TurboTasksBackendApi<Self>);
 
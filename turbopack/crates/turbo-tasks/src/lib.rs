@@ -80,6 +80,7 @@ pub mod task_statistics;
 pub mod trace;
 mod trait_ref;
 mod triomphe_utils;
+pub mod update_trace;
 pub mod util;
 mod value;
 mod value_type;
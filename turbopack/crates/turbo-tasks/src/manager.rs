@@ -43,6 +43,7 @@ use crate::{
     task::local_task::{LocalTask, LocalTaskSpec, LocalTaskType},
     task_statistics::TaskStatisticsApi,
     trace::TraceRawVcs,
+    update_trace::{UpdateTraceApi, UpdateTraceReport},
     util::{IdFactory, StaticOrArc},
     vc::ReadVcFuture,
 };
@@ -197,6 +198,11 @@ pub trait TurboTasksApi: TurboTasksCallApi + Sync + Send {
 
     fn task_statistics(&self) -> &TaskStatisticsApi;
 
+    fn update_trace(&self) -> &UpdateTraceApi;
+
+    /// Returns a human-readable description of the task, e.g. the name of its function.
+    fn get_task_description(&self, task: TaskId) -> String;
+
     fn stop_and_wait(&self) -> Pin<Box<dyn Future<Output = ()> + Send>>;
 
     fn subscribe_to_compilation_events(
@@ -282,6 +288,9 @@ pub trait TurboTasksBackendApi<B: Backend + 'static>: TurboTasksCallApi + Sync +
     /// Returns true if the system is idle.
     fn is_idle(&self) -> bool;
 
+    /// Returns the [`UpdateTraceApi`], to record how invalidations propagate.
+    fn update_trace(&self) -> &UpdateTraceApi;
+
     /// Returns a reference to the backend.
     fn backend(&self) -> &B;
 }
@@ -324,6 +333,9 @@ pub struct UpdateInfo {
     pub duration: Duration,
     pub tasks: usize,
     pub reasons: InvalidationReasonSet,
+    /// How the update propagated through the task graph. Only recorded when the
+    /// [`UpdateTraceApi`] is enabled.
+    pub trace: Option<UpdateTraceReport>,
     #[allow(dead_code)]
     placeholder_for_future_fields: (),
 }
@@ -377,6 +389,7 @@ pub struct TurboTasks<B: Backend + 'static> {
     scheduled_tasks: AtomicUsize,
     start: Mutex<Option<Instant>>,
     aggregated_update: Mutex<(Option<(Duration, usize)>, InvalidationReasonSet)>,
+    update_trace: UpdateTraceApi,
     event: Event,
     event_start: Event,
     event_foreground: Event,
@@ -507,6 +520,7 @@ impl<B: Backend + 'static> TurboTasks<B> {
             scheduled_tasks: AtomicUsize::new(0),
             start: Default::default(),
             aggregated_update: Default::default(),
+            update_trace: Default::default(),
             event: Event::new(|| || "TurboTasks::event".to_string()),
             event_start: Event::new(|| || "TurboTasks::event_start".to_string()),
             event_foreground: Event::new(|| || "TurboTasks::event_foreground".to_string()),
@@ -739,6 +753,9 @@ impl<B: Backend + 'static> TurboTasks<B> {
                         } = this.finish_current_task_state();
                         let cell_counters = CURRENT_TASK_STATE
                             .with(|ts| ts.write().unwrap().cell_counters.take().unwrap());
+                        if let Some(update_trace) = this.update_trace.get() {
+                            update_trace.record_execution(task_id, duration);
+                        }
                         let schedule_again = this.backend.task_execution_completed(
                             task_id,
                             duration,
@@ -994,6 +1011,7 @@ impl<B: Backend + 'static> TurboTasks<B> {
                         duration,
                         tasks,
                         reasons: take(reason_set),
+                        trace: self.take_update_trace(),
                         placeholder_for_future_fields: (),
                     });
                 } else {
@@ -1041,6 +1059,7 @@ impl<B: Backend + 'static> TurboTasks<B> {
                 duration,
                 tasks,
                 reasons: take(reason_set),
+                trace: self.take_update_trace(),
                 placeholder_for_future_fields: (),
             })
         } else {
@@ -1048,6 +1067,15 @@ impl<B: Backend + 'static> TurboTasks<B> {
         }
     }
 
+    fn take_update_trace(&self) -> Option<UpdateTraceReport> {
+        let report = self
+            .update_trace
+            .get()?
+            .take(|task| self.backend.get_task_description(task));
+        report.emit_tracing_events();
+        Some(report)
+    }
+
     pub async fn wait_background_done(&self) {
         let listener = self.event_background.listen();
         if self
@@ -1250,16 +1278,23 @@ impl<B: Backend + 'static> TurboTasksCallApi for TurboTasks<B> {
 impl<B: Backend + 'static> TurboTasksApi for TurboTasks<B> {
     #[instrument(level = Level::INFO, skip_all, name = "invalidate")]
     fn invalidate(&self, task: TaskId) {
+        if let Some(update_trace) = self.update_trace.get() {
+            update_trace.record_reason("invalidator", &[task]);
+        }
         self.backend.invalidate_task(task, self);
     }
 
     #[instrument(level = Level::INFO, skip_all, name = "invalidate", fields(name = display(&reason)))]
     fn invalidate_with_reason(&self, task: TaskId, reason: StaticOrArc<dyn InvalidationReason>) {
+        if let Some(update_trace) = self.update_trace.get() {
+            update_trace.record_reason(&reason, &[task]);
+        }
+        self.backend
+            .invalidate_task_with_reason(task, &reason, self);
         {
             let (_, reason_set) = &mut *self.aggregated_update.lock().unwrap();
             reason_set.insert(reason);
         }
-        self.backend.invalidate_task(task, self);
     }
 
     fn invalidate_serialization(&self, task: TaskId) {
@@ -1403,6 +1438,9 @@ impl<B: Backend + 'static> TurboTasksApi for TurboTasks<B> {
     }
 
     fn update_own_task_cell(&self, task: TaskId, index: CellId, content: CellContent) {
+        if let Some(update_trace) = self.update_trace.get() {
+            update_trace.record_changed_cell(task, index);
+        }
         self.backend.update_task_cell(task, index, content, self);
     }
 
@@ -1447,6 +1485,14 @@ impl<B: Backend + 'static> TurboTasksApi for TurboTasks<B> {
         self.backend.task_statistics()
     }
 
+    fn update_trace(&self) -> &UpdateTraceApi {
+        &self.update_trace
+    }
+
+    fn get_task_description(&self, task: TaskId) -> String {
+        self.backend.get_task_description(task)
+    }
+
     fn stop_and_wait(&self) -> Pin<Box<dyn Future<Output = ()> + Send + 'static>> {
         let this = self.pin();
         Box::pin(async move {
@@ -1596,6 +1642,10 @@ impl<B: Backend + 'static> TurboTasksBackendApi<B> for TurboTasks<B> {
     fn is_idle(&self) -> bool {
         self.currently_scheduled_tasks.load(Ordering::Acquire) == 0
     }
+
+    fn update_trace(&self) -> &UpdateTraceApi {
+        &self.update_trace
+    }
 }
 
 pub(crate) fn current_task(from: &str) -> TaskId {
@@ -0,0 +1,262 @@
+use std::{
+    fmt::Display,
+    sync::{
+        Arc, Mutex, OnceLock,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::Duration,
+};
+
+use serde::Serialize;
+
+use crate::{CellId, TaskId, TraitTypeId, ValueTypeId, registry};
+
+/// An API for optionally enabling and reading the [`UpdateTrace`]. Like
+/// [`TaskStatisticsApi`][crate::task_statistics::TaskStatisticsApi], it's disabled by default, as
+/// recording costs time and memory for every invalidation and task execution.
+///
+/// Every call to [`UpdateTraceApi::enable`] should be paired with a call to
+/// [`UpdateTraceApi::disable`] once the trace isn't needed anymore. Recording stops when all
+/// callers disabled it.
+#[derive(Default)]
+pub struct UpdateTraceApi {
+    inner: OnceLock<Arc<UpdateTrace>>,
+    enabled: AtomicUsize,
+}
+
+impl UpdateTraceApi {
+    pub fn enable(&self) -> &Arc<UpdateTrace> {
+        self.enabled.fetch_add(1, Ordering::AcqRel);
+        self.inner.get_or_init(Default::default)
+    }
+
+    /// Reverts a call to [`UpdateTraceApi::enable`]. When the trace isn't enabled anymore, the
+    /// recorded data is dropped.
+    pub fn disable(&self) {
+        let previous = self.enabled.fetch_sub(1, Ordering::AcqRel);
+        debug_assert!(previous > 0, "disable called without enable");
+        if previous == 1
+            && let Some(trace) = self.inner.get()
+        {
+            drop(trace.take_data());
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.enabled.load(Ordering::Acquire) > 0
+    }
+
+    // Returns the trace if it is enabled (via [`UpdateTraceApi::enable`]).
+    pub fn get(&self) -> Option<&Arc<UpdateTrace>> {
+        if self.is_enabled() {
+            self.inner.get()
+        } else {
+            None
+        }
+    }
+}
+
+/// A change of a task that makes the tasks depending on it dirty.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum InvalidationSource {
+    /// The output of the task changed.
+    Output(TaskId),
+    /// A cell of the task changed.
+    Cell(TaskId, ValueTypeId),
+    /// The collectibles emitted by the task changed.
+    Collectibles(TaskId, TraitTypeId),
+}
+
+enum InvalidationCause {
+    /// An external change, e.g. a file change reported by a watcher.
+    Reason(String),
+    Source(InvalidationSource),
+}
+
+#[derive(Default)]
+struct UpdateTraceData {
+    invalidations: Vec<(InvalidationCause, Vec<TaskId>)>,
+    executions: Vec<(TaskId, Duration)>,
+    changed_cells: Vec<(TaskId, CellId)>,
+    changed_outputs: Vec<String>,
+}
+
+/// Records how an update propagates through the task graph: the external changes that invalidated
+/// tasks, the changes of tasks that invalidated the tasks depending on them, the executed tasks,
+/// the cells they changed and the output files that changed as a result.
+///
+/// The recorded data is collected into an [`UpdateTraceReport`] for every update, see
+/// [`UpdateInfo::trace`][crate::UpdateInfo::trace].
+#[derive(Default)]
+pub struct UpdateTrace {
+    data: Mutex<UpdateTraceData>,
+}
+
+impl UpdateTrace {
+    pub fn record_reason(&self, reason: impl Display, tasks: &[TaskId]) {
+        self.data.lock().unwrap().invalidations.push((
+            InvalidationCause::Reason(reason.to_string()),
+            tasks.to_vec(),
+        ));
+    }
+
+    pub fn record_invalidation(&self, source: InvalidationSource, tasks: &[TaskId]) {
+        if tasks.is_empty() {
+            return;
+        }
+        self.data
+            .lock()
+            .unwrap()
+            .invalidations
+            .push((InvalidationCause::Source(source), tasks.to_vec()));
+    }
+
+    pub fn record_execution(&self, task: TaskId, duration: Duration) {
+        self.data.lock().unwrap().executions.push((task, duration));
+    }
+
+    /// Records that a task updated a cell with a value that is different from the previous one.
+    pub fn record_changed_cell(&self, task: TaskId, cell: CellId) {
+        self.data.lock().unwrap().changed_cells.push((task, cell));
+    }
+
+    /// Records that an output, e.g. a file written to disk, changed.
+    pub fn record_changed_output(&self, output: impl Display) {
+        self.data
+            .lock()
+            .unwrap()
+            .changed_outputs
+            .push(output.to_string());
+    }
+
+    fn take_data(&self) -> UpdateTraceData {
+        std::mem::take(&mut *self.data.lock().unwrap())
+    }
+
+    /// Takes everything recorded since the last call. Tasks are described with
+    /// `get_task_description`.
+    pub fn take(&self, get_task_description: impl Fn(TaskId) -> String) -> UpdateTraceReport {
+        self.take_filtered(|_| true, get_task_description)
+    }
+
+    /// Like [`UpdateTrace::take`], but leaves out the executions and changed cells of the tasks
+    /// for which `include_task` returns false.
+    pub fn take_filtered(
+        &self,
+        include_task: impl Fn(TaskId) -> bool,
+        get_task_description: impl Fn(TaskId) -> String,
+    ) -> UpdateTraceReport {
+        let UpdateTraceData {
+            invalidations,
+            mut executions,
+            mut changed_cells,
+            changed_outputs,
+        } = self.take_data();
+        executions.retain(|(task, _)| include_task(*task));
+        changed_cells.retain(|(task, _)| include_task(*task));
+        let describe_tasks = |tasks: Vec<TaskId>| -> Vec<String> {
+            tasks.into_iter().map(&get_task_description).collect()
+        };
+        UpdateTraceReport {
+            invalidations: invalidations
+                .into_iter()
+                .map(|(cause, tasks)| TracedInvalidation {
+                    cause: match cause {
+                        InvalidationCause::Reason(reason) => reason,
+                        InvalidationCause::Source(InvalidationSource::Output(task)) => {
+                            format!("output of {} changed", get_task_description(task))
+                        }
+                        InvalidationCause::Source(InvalidationSource::Cell(task, value_type)) => {
+                            format!(
+                                "{} cell of {} changed",
+                                registry::get_value_type(value_type).name,
+                                get_task_description(task)
+                            )
+                        }
+                        InvalidationCause::Source(InvalidationSource::Collectibles(
+                            task,
+                            trait_type,
+                        )) => {
+                            format!(
+                                "{} collectibles of {} changed",
+                                registry::get_trait(trait_type).name,
+                                get_task_description(task)
+                            )
+                        }
+                    },
+                    tasks: describe_tasks(tasks),
+                })
+                .collect(),
+            executions: executions
+                .into_iter()
+                .map(|(task, duration)| TracedExecution {
+                    task: get_task_description(task),
+                    duration,
+                })
+                .collect(),
+            changed_cells: changed_cells
+                .into_iter()
+                .map(|(task, cell)| TracedCell {
+                    task: get_task_description(task),
+                    cell: cell.to_string(),
+                })
+                .collect(),
+            changed_outputs,
+        }
+    }
+}
+
+/// How an update propagated, from the changes that caused it to the outputs that changed.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct UpdateTraceReport {
+    /// Every change that made tasks dirty, in the order they happened.
+    pub invalidations: Vec<TracedInvalidation>,
+    /// The executed tasks, in the order they finished.
+    pub executions: Vec<TracedExecution>,
+    /// The cells that were updated with a different value, in the order they were updated.
+    pub changed_cells: Vec<TracedCell>,
+    pub changed_outputs: Vec<String>,
+}
+
+impl UpdateTraceReport {
+    /// Emits the report as tracing events, so it becomes part of the trace output.
+    pub fn emit_tracing_events(&self) {
+        for TracedInvalidation { cause, tasks } in &self.invalidations {
+            tracing::info!(%cause, tasks = %tasks.join(", "), "invalidation");
+        }
+        for TracedExecution { task, duration } in &self.executions {
+            tracing::info!(
+                %task,
+                duration_us = duration.as_micros() as u64,
+                "task execution"
+            );
+        }
+        for TracedCell { task, cell } in &self.changed_cells {
+            tracing::info!(%task, %cell, "changed cell");
+        }
+        for output in &self.changed_outputs {
+            tracing::info!(%output, "changed output");
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TracedInvalidation {
+    /// What changed, e.g. `file changed: src/index.js` or `output of foo changed`.
+    pub cause: String,
+    /// The tasks that were made dirty by the change.
+    pub tasks: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TracedExecution {
+    pub task: String,
+    pub duration: Duration,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TracedCell {
+    pub task: String,
+    /// The value type and index of the cell, e.g. `u32#0`.
+    pub cell: String,
+}
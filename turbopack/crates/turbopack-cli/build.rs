@@ -1,7 +1,30 @@
+use std::env;
+
 use turbo_tasks_build::generate_register;
 
-fn main() {
+fn main() -> anyhow::Result<()> {
     println!("cargo::rustc-check-cfg=cfg(codspeed)");
+    println!("cargo:rerun-if-env-changed=CI");
+    let is_ci = env::var("CI").is_ok_and(|value| !value.is_empty());
+
+    // The persistent cache is versioned with the git commit, like in `next-swc-napi`. See its
+    // build script for the tradeoffs of tracking the dirty state.
+    //
+    // Unlike there, a failing git command doesn't fail the build, e.g. when building from a source
+    // archive. The persistent cache is unversioned then.
+    let git = vergen_gitcl::GitclBuilder::default()
+        .dirty(/* include_untracked */ true)
+        .describe(
+            /* tags */ true,
+            /* dirty */ !is_ci, // suppress the dirty suffix in CI
+            /* matches */ Some("v[0-9]*"), // find the last version tag
+        )
+        .build()?;
+    vergen_gitcl::Emitter::default()
+        .add_instructions(&git)?
+        .emit()?;
 
     generate_register();
+
+    Ok(())
 }
@@ -44,6 +44,28 @@ pub enum Target {
     Node,
 }
 
+/// What to do with the classes of CSS Modules that no importing module reads.
+#[derive(
+    Copy,
+    Clone,
+    Debug,
+    ValueEnum,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    Hash,
+    TaskInput,
+    NonLocalValue,
+    TraceRawVcs,
+)]
+pub enum UnusedCssModuleClasses {
+    /// Report them as warnings.
+    Report,
+    /// Report them and leave out the rules that only match them.
+    Remove,
+}
+
 #[derive(Debug, Args, Clone)]
 pub struct CommonArguments {
     /// The entrypoints of the project. Resolved relative to the project's
@@ -78,13 +100,26 @@ pub struct CommonArguments {
     #[clap(long)]
     pub full_stats: bool,
 
-    // Enable experimental garbage collection with the provided memory limit in
-    // MB.
-    // #[clap(long)]
-    // pub memory_limit: Option<usize>,
+    /// Enables persistent caching of the task graph in this directory.
+    #[clap(long, value_parser)]
+    pub cache_dir: Option<PathBuf>,
+
+    /// Evicts cold tasks from memory when the memory usage exceeds this limit in MB. Evicted
+    /// tasks are read back from the cache, so this requires `--cache-dir`.
+    #[clap(long, requires = "cache_dir")]
+    pub memory_limit: Option<usize>,
+
     /// Whether to build for the `browser` or `node``
     #[clap(long)]
     pub target: Option<Target>,
+
+    /// Emit a `.d.ts` file next to every CSS Module, declaring its exported classes.
+    #[clap(long)]
+    pub css_module_types: bool,
+
+    /// Handle the Vite-style `?raw`, `?url` and `?worker` import queries.
+    #[clap(long)]
+    pub import_queries: bool,
 }
 
 #[derive(Debug, Args)]
@@ -113,6 +148,17 @@ pub struct DevArguments {
     #[clap(long)]
     pub no_open: bool,
 
+    /// Forwards the requests below a path prefix, including WebSocket upgrades, to another HTTP
+    /// origin, e.g. `--proxy /api/=http://localhost:4000`. Can be passed multiple times.
+    #[clap(long = "proxy", value_name = "PREFIX=ORIGIN", value_parser = parse_prefixed)]
+    pub proxies: Vec<(String, String)>,
+
+    /// Answers the requests below a path prefix with canned JSON fixtures from a directory,
+    /// relative to the project directory, e.g. `--mock /api/=mocks`. Can be passed multiple
+    /// times.
+    #[clap(long = "mock", value_name = "PREFIX=DIR", value_parser = parse_prefixed)]
+    pub mocks: Vec<(String, String)>,
+
     // ==
     // = Inherited options from next-dev, need revisit later.
     // ==
@@ -140,9 +186,34 @@ pub struct BuildArguments {
     #[clap(long)]
     pub no_scope_hoist: bool,
 
+    /// Report the classes of CSS Modules that no importing module reads, or additionally remove
+    /// their rules from the output.
+    #[clap(long, value_enum)]
+    pub unused_css_module_classes: Option<UnusedCssModuleClasses>,
+
+    /// Write the graph of the executed tasks to the given file after the build, for debugging
+    /// recomputation. Written as Graphviz DOT if the file ends in `.dot`, as JSON otherwise.
+    #[clap(long, value_parser)]
+    pub task_graph: Option<PathBuf>,
+
     /// Drop the `TurboTasks` object upon exit. By default we intentionally leak this memory, as
     /// we're about to exit the process anyways, but that can cause issues with valgrind or other
     /// leak detectors.
     #[clap(long, hide = true)]
     pub force_memory_cleanup: bool,
 }
+
+/// Parses a `PREFIX=VALUE` argument. The prefix is normalized to the form of route prefixes,
+/// without a leading and with a trailing slash.
+fn parse_prefixed(arg: &str) -> Result<(String, String), String> {
+    let (prefix, value) = arg
+        .split_once('=')
+        .ok_or_else(|| format!("expected PREFIX=VALUE, got `{arg}`"))?;
+    let prefix = prefix.trim_matches('/');
+    let prefix = if prefix.is_empty() {
+        String::new()
+    } else {
+        format!("{prefix}/")
+    };
+    Ok((prefix, value.to_string()))
+}
@@ -12,12 +12,11 @@ use turbo_rcstr::RcStr;
 use turbo_tasks::{
     ReadConsistency, ResolvedVc, TransientInstance, TryJoinIterExt, TurboTasks, Vc, apply_effects,
 };
-use turbo_tasks_backend::{
-    BackendOptions, NoopBackingStorage, TurboTasksBackend, noop_backing_storage,
-};
+use turbo_tasks_backend::BackendOptions;
 use turbo_tasks_fs::FileSystem;
 use turbopack::{
-    css::chunk::CssChunkType, ecmascript::chunk::EcmascriptChunkType,
+    css::{chunk::CssChunkType, emit_css_module_type_declarations},
+    ecmascript::chunk::EcmascriptChunkType,
     global_module_ids::get_global_module_id_strategy,
 };
 use turbopack_browser::{BrowserChunkingContext, ContentHashing, CurrentChunkMethod};
@@ -51,15 +50,14 @@ use turbopack_node::execution_context::ExecutionContext;
 use turbopack_nodejs::NodeJsChunkingContext;
 
 use crate::{
-    arguments::{BuildArguments, Target},
+    arguments::{BuildArguments, Target, UnusedCssModuleClasses},
     contexts::{NodeEnv, get_client_asset_context, get_client_compile_time_info},
     util::{
-        EntryRequest, NormalizedDirs, normalize_dirs, normalize_entries, output_fs, project_fs,
+        Backend, EntryRequest, NormalizedDirs, create_turbo_tasks, normalize_dirs,
+        normalize_entries, output_fs, project_fs,
     },
 };
 
-type Backend = TurboTasksBackend<NoopBackingStorage>;
-
 pub struct TurbopackBuildBuilder {
     turbo_tasks: Arc<TurboTasks<Backend>>,
     project_dir: RcStr,
@@ -73,6 +71,9 @@ pub struct TurbopackBuildBuilder {
     minify_type: MinifyType,
     target: Target,
     scope_hoist: bool,
+    css_module_types: bool,
+    unused_css_module_classes: Option<UnusedCssModuleClasses>,
+    import_queries: bool,
 }
 
 impl TurbopackBuildBuilder {
@@ -94,6 +95,9 @@ impl TurbopackBuildBuilder {
             },
             target: Target::Node,
             scope_hoist: true,
+            css_module_types: false,
+            unused_css_module_classes: None,
+            import_queries: false,
         }
     }
 
@@ -137,6 +141,24 @@ impl TurbopackBuildBuilder {
         self
     }
 
+    pub fn css_module_types(mut self, css_module_types: bool) -> Self {
+        self.css_module_types = css_module_types;
+        self
+    }
+
+    pub fn unused_css_module_classes(
+        mut self,
+        unused_css_module_classes: Option<UnusedCssModuleClasses>,
+    ) -> Self {
+        self.unused_css_module_classes = unused_css_module_classes;
+        self
+    }
+
+    pub fn import_queries(mut self, import_queries: bool) -> Self {
+        self.import_queries = import_queries;
+        self
+    }
+
     pub fn target(mut self, target: Target) -> Self {
         self.target = target;
         self
@@ -153,6 +175,9 @@ impl TurbopackBuildBuilder {
                 self.minify_type,
                 self.target,
                 self.scope_hoist,
+                self.css_module_types,
+                self.unused_css_module_classes,
+                self.import_queries,
             );
 
             // Await the result to propagate any errors.
@@ -201,6 +226,9 @@ async fn build_internal(
     minify_type: MinifyType,
     target: Target,
     scope_hoist: bool,
+    css_module_types: bool,
+    unused_css_module_classes: Option<UnusedCssModuleClasses>,
+    import_queries: bool,
 ) -> Result<Vc<()>> {
     let output_fs = output_fs(project_dir.clone());
     let project_fs = project_fs(root_dir.clone(), /* watch= */ false);
@@ -255,6 +283,9 @@ async fn build_internal(
         compile_time_info,
         node_env,
         source_maps_type,
+        css_module_types,
+        unused_css_module_classes,
+        import_queries,
     );
 
     let entry_requests = (*entry_requests
@@ -310,6 +341,9 @@ async fn build_internal(
         Vc::cell(vec![ChunkGroupEntry::Entry(entries.clone())]),
         false,
     );
+    emit_css_module_type_declarations(module_graph)
+        .as_side_effect()
+        .await?;
     let module_id_strategy = ResolvedVc::upcast(
         get_global_module_id_strategy(module_graph)
             .to_resolved()
@@ -515,14 +549,16 @@ pub async fn build(args: &BuildArguments) -> Result<()> {
         root_dir,
     } = normalize_dirs(&args.common.dir, &args.common.root)?;
 
-    let tt = TurboTasks::new(TurboTasksBackend::new(
+    let tt = create_turbo_tasks(
+        &args.common,
         BackendOptions {
-            dependency_tracking: false,
-            storage_mode: None,
+            // The task graph is only useful with the dependencies between tasks.
+            dependency_tracking: args.task_graph.is_some() || args.common.cache_dir.is_some(),
+            introspection: args.task_graph.is_some(),
             ..Default::default()
         },
-        noop_backing_storage(),
-    ));
+        true,
+    )?;
 
     let mut builder = TurbopackBuildBuilder::new(tt.clone(), project_dir, root_dir)
         .log_detail(args.common.log_detail)
@@ -545,6 +581,9 @@ pub async fn build(args: &BuildArguments) -> Result<()> {
         })
         .scope_hoist(!args.no_scope_hoist)
         .target(args.common.target.unwrap_or(Target::Node))
+        .css_module_types(args.common.css_module_types)
+        .unused_css_module_classes(args.unused_css_module_classes)
+        .import_queries(args.common.import_queries)
         .show_all(args.common.show_all);
 
     for entry in normalize_entries(&args.common.entries) {
@@ -553,6 +592,22 @@ pub async fn build(args: &BuildArguments) -> Result<()> {
 
     builder.build().await?;
 
+    if let Some(path) = &args.task_graph {
+        let graph = tt.backend().task_graph();
+        let content = if path.extension().is_some_and(|extension| extension == "dot") {
+            graph.to_dot()
+        } else {
+            graph.to_json()?
+        };
+        std::fs::write(path, content)
+            .with_context(|| format!("failed to write the task graph to {}", path.display()))?;
+    }
+
+    // Persist the final snapshot to the cache.
+    if args.common.cache_dir.is_some() {
+        tt.stop_and_wait().await;
+    }
+
     // Intentionally leak this `Arc`. Otherwise we'll waste time during process exit performing a
     // ton of drop calls.
     if !args.force_memory_cleanup {
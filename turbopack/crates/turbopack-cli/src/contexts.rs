@@ -6,9 +6,10 @@ use turbo_tasks::{ResolvedVc, Vc};
 use turbo_tasks_fs::{FileSystem, FileSystemPath};
 use turbopack::{
     ModuleAssetContext,
+    css::CssModuleTypeDeclarations,
     ecmascript::TreeShakingMode,
     module_options::{
-        EcmascriptOptionsContext, JsxTransformOptions, ModuleOptionsContext,
+        CssOptionsContext, EcmascriptOptionsContext, JsxTransformOptions, ModuleOptionsContext,
         TypescriptTransformOptions,
     },
 };
@@ -29,6 +30,8 @@ use turbopack_node::{
 };
 use turbopack_resolve::resolve_options_context::ResolveOptionsContext;
 
+use crate::arguments::UnusedCssModuleClasses;
+
 #[turbo_tasks::value(shared)]
 pub enum NodeEnv {
     Development,
@@ -109,6 +112,9 @@ async fn get_client_module_options_context(
     env: ResolvedVc<Environment>,
     node_env: Vc<NodeEnv>,
     source_maps_type: SourceMapsType,
+    css_module_types: bool,
+    unused_css_module_classes: Option<UnusedCssModuleClasses>,
+    import_queries: bool,
 ) -> Result<Vc<ModuleOptionsContext>> {
     let is_dev = matches!(*node_env.await?, NodeEnv::Development);
     let module_options_context = ModuleOptionsContext {
@@ -144,7 +150,16 @@ async fn get_client_module_options_context(
             source_maps: source_maps_type,
             ..module_options_context.ecmascript.clone()
         },
+        css: CssOptionsContext {
+            module_type_declarations: css_module_types
+                .then_some(CssModuleTypeDeclarations::NextToSource),
+            report_unused_module_classes: unused_css_module_classes.is_some(),
+            remove_unused_module_classes: unused_css_module_classes
+                == Some(UnusedCssModuleClasses::Remove),
+            ..module_options_context.css.clone()
+        },
         enable_postcss_transform: Some(PostCssTransformOptions::default().resolved_cell()),
+        enable_import_queries: import_queries,
         rules: vec![(
             foreign_code_context_condition(),
             module_options_context.clone().resolved_cell(),
@@ -163,6 +178,9 @@ pub fn get_client_asset_context(
     compile_time_info: Vc<CompileTimeInfo>,
     node_env: Vc<NodeEnv>,
     source_maps_type: SourceMapsType,
+    css_module_types: bool,
+    unused_css_module_classes: Option<UnusedCssModuleClasses>,
+    import_queries: bool,
 ) -> Vc<Box<dyn AssetContext>> {
     let resolve_options_context =
         get_client_resolve_options_context(project_path.clone(), node_env);
@@ -172,6 +190,9 @@ pub fn get_client_asset_context(
         compile_time_info.environment(),
         node_env,
         source_maps_type,
+        css_module_types,
+        unused_css_module_classes,
+        import_queries,
     );
 
     let asset_context: Vc<Box<dyn AssetContext>> = Vc::upcast(ModuleAssetContext::new(
@@ -17,9 +17,7 @@ use turbo_tasks::{
     trace::TraceRawVcs,
     util::{FormatBytes, FormatDuration},
 };
-use turbo_tasks_backend::{
-    BackendOptions, NoopBackingStorage, TurboTasksBackend, noop_backing_storage,
-};
+use turbo_tasks_backend::BackendOptions;
 use turbo_tasks_fs::FileSystem;
 use turbo_tasks_malloc::TurboMalloc;
 use turbopack::evaluate_context::node_build_environment;
@@ -33,8 +31,12 @@ use turbopack_dev_server::{
     DevServer, DevServerBuilder, SourceProvider,
     introspect::IntrospectionSource,
     source::{
-        ContentSource, combined::CombinedContentSource, router::PrefixedRouterContentSource,
+        ContentSource,
+        combined::CombinedContentSource,
+        proxy::{HttpProxyContentSource, HttpProxyOptions},
+        router::PrefixedRouterContentSource,
         static_assets::StaticAssetsContentSource,
+        static_mock::StaticMockContentSource,
     },
 };
 use turbopack_ecmascript_runtime::RuntimeType;
@@ -47,14 +49,13 @@ use crate::{
     arguments::DevArguments,
     contexts::NodeEnv,
     util::{
-        EntryRequest, NormalizedDirs, normalize_dirs, normalize_entries, output_fs, project_fs,
+        Backend, EntryRequest, NormalizedDirs, create_turbo_tasks, normalize_dirs,
+        normalize_entries, output_fs, project_fs,
     },
 };
 
 pub(crate) mod web_entry_source;
 
-type Backend = TurboTasksBackend<NoopBackingStorage>;
-
 pub struct TurbopackDevServerBuilder {
     turbo_tasks: Arc<TurboTasks<Backend>>,
     project_dir: RcStr,
@@ -69,6 +70,10 @@ pub struct TurbopackDevServerBuilder {
     show_all: bool,
     log_detail: bool,
     allow_retry: bool,
+    css_module_types: bool,
+    import_queries: bool,
+    proxies: Vec<(RcStr, HttpProxyOptions)>,
+    mocks: Vec<(RcStr, RcStr)>,
 }
 
 impl TurbopackDevServerBuilder {
@@ -93,6 +98,10 @@ impl TurbopackDevServerBuilder {
             show_all: false,
             log_detail: false,
             allow_retry: false,
+            css_module_types: false,
+            import_queries: false,
+            proxies: vec![],
+            mocks: vec![],
         }
     }
 
@@ -141,6 +150,29 @@ impl TurbopackDevServerBuilder {
         self
     }
 
+    pub fn css_module_types(mut self, css_module_types: bool) -> TurbopackDevServerBuilder {
+        self.css_module_types = css_module_types;
+        self
+    }
+
+    pub fn import_queries(mut self, import_queries: bool) -> TurbopackDevServerBuilder {
+        self.import_queries = import_queries;
+        self
+    }
+
+    /// Forwards the requests below `prefix` to another HTTP origin.
+    pub fn proxy(mut self, prefix: RcStr, options: HttpProxyOptions) -> TurbopackDevServerBuilder {
+        self.proxies.push((prefix, options));
+        self
+    }
+
+    /// Answers the requests below `prefix` with the JSON fixtures in `dir`, which is relative to
+    /// the project directory.
+    pub fn mock(mut self, prefix: RcStr, dir: RcStr) -> TurbopackDevServerBuilder {
+        self.mocks.push((prefix, dir));
+        self
+    }
+
     pub fn issue_reporter(
         mut self,
         issue_reporter: Box<dyn IssueReporterProvider>,
@@ -203,6 +235,10 @@ impl TurbopackDevServerBuilder {
         let show_all = self.show_all;
         let log_detail: bool = self.log_detail;
         let browserslist_query: RcStr = self.browserslist_query;
+        let css_module_types = self.css_module_types;
+        let import_queries = self.import_queries;
+        let proxies = self.proxies;
+        let mocks = self.mocks;
         let log_args = TransientInstance::new(LogOptions {
             current_dir: current_dir().unwrap(),
             project_dir: PathBuf::from(project_dir.clone()),
@@ -224,6 +260,10 @@ impl TurbopackDevServerBuilder {
             entry_requests: Arc<Vec<EntryRequest>>,
             eager_compile: bool,
             browserslist_query: RcStr,
+            css_module_types: bool,
+            import_queries: bool,
+            proxies: Vec<(RcStr, HttpProxyOptions)>,
+            mocks: Vec<(RcStr, RcStr)>,
         }
         impl SourceProvider for ServerSourceProvider {
             fn get_source(&self) -> OperationVc<Box<dyn ContentSource>> {
@@ -233,6 +273,10 @@ impl TurbopackDevServerBuilder {
                     self.entry_requests.clone(),
                     self.eager_compile,
                     self.browserslist_query.clone(),
+                    self.css_module_types,
+                    self.import_queries,
+                    self.proxies.clone(),
+                    self.mocks.clone(),
                 )
             }
         }
@@ -242,6 +286,10 @@ impl TurbopackDevServerBuilder {
             entry_requests,
             eager_compile,
             browserslist_query,
+            css_module_types,
+            import_queries,
+            proxies,
+            mocks,
         };
 
         let issue_reporter_arc = Arc::new(move || issue_provider.get_issue_reporter());
@@ -256,6 +304,10 @@ async fn source(
     entry_requests: Arc<Vec<EntryRequest>>,
     eager_compile: bool,
     browserslist_query: RcStr,
+    css_module_types: bool,
+    import_queries: bool,
+    proxies: Vec<(RcStr, HttpProxyOptions)>,
+    mocks: Vec<(RcStr, RcStr)>,
 ) -> Result<Vc<Box<dyn ContentSource>>> {
     let project_relative = project_dir.strip_prefix(&*root_dir).unwrap();
     let project_relative: RcStr = project_relative
@@ -324,6 +376,8 @@ async fn source(
         NodeEnv::Development.cell(),
         Default::default(),
         browserslist_query,
+        css_module_types,
+        import_queries,
     )
     .to_resolved()
     .await?;
@@ -332,9 +386,22 @@ async fn source(
             .to_resolved()
             .await?,
     );
-    let main_source = CombinedContentSource::new(vec![static_source, web_source])
-        .to_resolved()
-        .await?;
+    let mut sources = vec![static_source, web_source];
+    for (prefix, options) in proxies {
+        sources.push(ResolvedVc::upcast(
+            HttpProxyContentSource::new(prefix, options)
+                .to_resolved()
+                .await?,
+        ));
+    }
+    for (prefix, dir) in mocks {
+        sources.push(ResolvedVc::upcast(
+            StaticMockContentSource::new(prefix, project_path.join(&dir)?)
+                .to_resolved()
+                .await?,
+        ));
+    }
+    let main_source = CombinedContentSource::new(sources).to_resolved().await?;
     let introspect = ResolvedVc::upcast(
         IntrospectionSource {
             roots: FxHashSet::from_iter([ResolvedVc::upcast(main_source)]),
@@ -362,13 +429,7 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
         root_dir,
     } = normalize_dirs(&args.common.dir, &args.common.root)?;
 
-    let tt = TurboTasks::new(TurboTasksBackend::new(
-        BackendOptions {
-            storage_mode: None,
-            ..Default::default()
-        },
-        noop_backing_storage(),
-    ));
+    let tt = create_turbo_tasks(&args.common, BackendOptions::default(), false)?;
 
     let tt_clone = tt.clone();
 
@@ -378,6 +439,8 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
         .port(args.port)
         .log_detail(args.common.log_detail)
         .show_all(args.common.show_all)
+        .css_module_types(args.common.css_module_types)
+        .import_queries(args.common.import_queries)
         .log_level(
             args.common
                 .log_level
@@ -388,6 +451,21 @@ pub async fn start_server(args: &DevArguments) -> Result<()> {
         server = server.entry_request(EntryRequest::Relative(entry))
     }
 
+    for (prefix, origin) in &args.proxies {
+        server = server.proxy(
+            prefix.as_str().into(),
+            HttpProxyOptions {
+                origin: origin.as_str().into(),
+                change_origin: true,
+                ..Default::default()
+            },
+        );
+    }
+
+    for (prefix, dir) in &args.mocks {
+        server = server.mock(prefix.as_str().into(), dir.as_str().into());
+    }
+
     #[cfg(feature = "serializable")]
     {
         server = server.allow_retry(args.allow_retry);
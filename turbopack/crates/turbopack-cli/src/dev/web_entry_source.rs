@@ -3,6 +3,7 @@ use turbo_rcstr::{RcStr, rcstr};
 use turbo_tasks::{ResolvedVc, TryFlatJoinIterExt, TryJoinIterExt, Vc};
 use turbo_tasks_env::ProcessEnv;
 use turbo_tasks_fs::FileSystemPath;
+use turbopack::css::emit_css_module_type_declarations;
 use turbopack_browser::{BrowserChunkingContext, react_refresh::assert_can_resolve_react_refresh};
 use turbopack_cli_utils::runtime_entry::{RuntimeEntries, RuntimeEntry};
 use turbopack_core::{
@@ -109,6 +110,8 @@ pub async fn create_web_entry_source(
     node_env: Vc<NodeEnv>,
     source_maps_type: SourceMapsType,
     browserslist_query: RcStr,
+    css_module_types: bool,
+    import_queries: bool,
 ) -> Result<Vc<Box<dyn ContentSource>>> {
     let compile_time_info = get_client_compile_time_info(browserslist_query, node_env);
     let asset_context = get_client_asset_context(
@@ -117,6 +120,10 @@ pub async fn create_web_entry_source(
         compile_time_info,
         node_env,
         source_maps_type,
+        css_module_types,
+        // The dev server doesn't compute the export usage of the module graph
+        None,
+        import_queries,
     );
     let chunking_context = get_client_chunking_context(
         root_path.clone(),
@@ -162,6 +169,9 @@ pub async fn create_web_entry_source(
         ModuleGraph::from_modules(Vc::cell(vec![ChunkGroupEntry::Entry(all_modules)]), false)
             .to_resolved()
             .await?;
+    emit_css_module_type_declarations(*module_graph)
+        .as_side_effect()
+        .await?;
 
     let entries: Vec<_> = entries
         .into_iter()
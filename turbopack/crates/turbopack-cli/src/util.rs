@@ -1,12 +1,61 @@
-use std::{env::current_dir, path::PathBuf};
+use std::{env::current_dir, path::PathBuf, sync::Arc};
 
 use anyhow::{Context, Result};
 use dunce::canonicalize;
+use either::Either;
 use serde::{Deserialize, Serialize};
 use turbo_rcstr::{RcStr, rcstr};
-use turbo_tasks::{NonLocalValue, TaskInput, Vc, trace::TraceRawVcs};
+use turbo_tasks::{NonLocalValue, TaskInput, TurboTasks, Vc, trace::TraceRawVcs};
+use turbo_tasks_backend::{
+    BackendOptions, GitVersionInfo, NoopBackingStorage, StorageMode, TurboBackingStorage,
+    TurboTasksBackend, noop_backing_storage, turbo_backing_storage,
+};
 use turbo_tasks_fs::{DiskFileSystem, FileSystem};
 
+use crate::arguments::CommonArguments;
+
+pub type Backend = TurboTasksBackend<Either<TurboBackingStorage, NoopBackingStorage>>;
+
+/// Creates the turbo-tasks instance, which persists the task graph when `--cache-dir` is passed.
+pub fn create_turbo_tasks(
+    args: &CommonArguments,
+    options: BackendOptions,
+    is_short_session: bool,
+) -> Result<Arc<TurboTasks<Backend>>> {
+    let backend = if let Some(cache_dir) = &args.cache_dir {
+        // Same as in `next-swc-napi`, `TURBO_ENGINE_IGNORE_DIRTY` allows caching in a dirty
+        // repository.
+        let version_info = GitVersionInfo {
+            describe: match env!("VERGEN_GIT_DESCRIBE") {
+                // The placeholder vergen emits when git is not available
+                "VERGEN_IDEMPOTENT_OUTPUT" => "unversioned",
+                describe => describe,
+            },
+            dirty: option_env!("CI").is_none_or(|value| value.is_empty())
+                && env!("VERGEN_GIT_DIRTY") == "true",
+        };
+        let (backing_storage, _) =
+            turbo_backing_storage(cache_dir, &version_info, false, is_short_session)?;
+        TurboTasksBackend::new(
+            BackendOptions {
+                storage_mode: Some(StorageMode::ReadWrite),
+                memory_limit: args.memory_limit.map(|limit| limit * 1024 * 1024),
+                ..options
+            },
+            Either::Left(backing_storage),
+        )
+    } else {
+        TurboTasksBackend::new(
+            BackendOptions {
+                storage_mode: None,
+                ..options
+            },
+            Either::Right(noop_backing_storage()),
+        )
+    };
+    Ok(TurboTasks::new(backend))
+}
+
 #[derive(
     Clone, Debug, TaskInput, Hash, PartialEq, Eq, NonLocalValue, Serialize, Deserialize, TraceRawVcs,
 )]
@@ -259,7 +259,7 @@ fn is_app_code(ident: &str) -> bool {
 }
 
 /// Returns the package name of the given `ident`.
-fn package_name(ident: &str) -> &str {
+pub fn package_name(ident: &str) -> &str {
     static PACKAGE_NAME_REGEX: Lazy<Regex> =
         Lazy::new(|| Regex::new(r"/node_modules/((?:@[^/]+/)?[^/]+)").unwrap());
     if let Some(result) = PACKAGE_NAME_REGEX.find_iter(ident).last() {
@@ -33,6 +33,8 @@ mod dev;
 mod production;
 mod style_production;
 
+pub use dev::package_name;
+
 #[turbo_tasks::value]
 struct ChunkItemsWithInfo {
     #[allow(clippy::type_complexity)]
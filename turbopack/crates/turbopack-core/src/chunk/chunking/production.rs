@@ -1,16 +1,20 @@
-use std::{borrow::Cow, collections::BinaryHeap, hash::BuildHasherDefault, mem::take};
+use std::{
+    borrow::Cow, cmp::Reverse, collections::BinaryHeap, hash::BuildHasherDefault, mem::take, ptr,
+};
 
-use anyhow::{Context, Result};
-use rustc_hash::FxHasher;
+use anyhow::{Context, Result, bail};
+use rustc_hash::{FxHashMap, FxHasher};
 use smallvec::SmallVec;
 use tracing::{Instrument, field::Empty};
 use turbo_prehash::BuildHasherExt;
-use turbo_tasks::{FxIndexMap, FxIndexSet, ResolvedVc, TryJoinIterExt, Vc};
+use turbo_rcstr::RcStr;
+use turbo_tasks::{FxIndexMap, FxIndexSet, IntoTraitRef, ResolvedVc, TryJoinIterExt, Vc};
 
 use crate::{
     chunk::{
         ChunkItemBatchGroup, ChunkItemWithAsyncModuleInfo, ChunkingConfig,
-        chunking::{ChunkItemOrBatchWithInfo, SplitContext, make_chunk},
+        chunking::{ChunkItemOrBatchWithInfo, SplitContext, dev::expand_batches, make_chunk},
+        chunking_strategies::ChunkingStrategy,
     },
     module_graph::{ModuleGraph, chunk_group_info::RoaringBitmapWrapper},
 };
@@ -34,6 +38,21 @@ pub async fn make_production_chunks(
         let chunk_group_info = module_graph.chunk_group_info().await?;
         let merged_modules = module_graph.merged_modules().await?;
 
+        // Chunking strategies need the asset ident of every chunk item, so batches are expanded
+        let expanded_chunk_items;
+        let chunk_items = if chunking_config.strategy.is_some() {
+            expanded_chunk_items = expand_batches(
+                chunk_items,
+                split_context.ty,
+                split_context.chunking_context,
+            )
+            .await?;
+            expanded_chunk_items.iter().collect()
+        } else {
+            chunk_items
+        };
+        let chunk_keys = chunk_keys(&chunk_items, chunking_config.strategy).await?;
+
         #[derive(Default)]
         struct GroupedChunkItems<'l> {
             chunk_items: Vec<&'l ChunkItemOrBatchWithInfo>,
@@ -60,6 +79,10 @@ pub async fn make_production_chunks(
 
         let batch_group_read_refs = batch_groups.iter().try_join().await?;
 
+        // The chunk groups of every chunk item, to find all chunk groups that request a merged
+        // chunk
+        let mut chunk_item_chunk_groups = FxHashMap::default();
+
         // Put chunk items into `grouped_chunk_items` based on their chunk groups
         for (i, chunk_item) in chunk_items.into_iter().enumerate() {
             let chunk_groups = match chunk_item {
@@ -93,7 +116,13 @@ pub async fn make_production_chunks(
                     batch_read_refs[i].as_ref().unwrap().chunk_groups.as_ref()
                 }
             };
-            let key = BuildHasherDefault::<FxHasher>::default().prehash(chunk_groups);
+            if chunking_config.max_parallel_requests != 0 {
+                chunk_item_chunk_groups.insert(ptr::from_ref(chunk_item), chunk_groups);
+            }
+            let chunk_key = chunk_keys
+                .as_ref()
+                .map_or_else(RcStr::default, |chunk_keys| chunk_keys[i].clone());
+            let key = BuildHasherDefault::<FxHasher>::default().prehash((chunk_key, chunk_groups));
             grouped_chunk_items
                 .entry(key)
                 .or_default()
@@ -103,7 +132,8 @@ pub async fn make_production_chunks(
 
         for (i, batch_group) in batch_groups.into_iter().enumerate() {
             let data = &batch_group_read_refs[i].chunk_groups;
-            let key = BuildHasherDefault::<FxHasher>::default().prehash(Some(data));
+            let key =
+                BuildHasherDefault::<FxHasher>::default().prehash((RcStr::default(), Some(data)));
             grouped_chunk_items.entry(key).or_default().batch_group = Some(batch_group);
         }
 
@@ -111,10 +141,11 @@ pub async fn make_production_chunks(
             min_chunk_size,
             max_chunk_count_per_group,
             max_merge_chunk_size,
+            max_parallel_requests,
             ..
         } = chunking_config;
 
-        if min_chunk_size == 0 && max_chunk_count_per_group == 0 {
+        if min_chunk_size == 0 && max_chunk_count_per_group == 0 && max_parallel_requests == 0 {
             span.record("chunks", grouped_chunk_items.len());
             for group in grouped_chunk_items.into_values() {
                 make_chunk(
@@ -140,11 +171,13 @@ pub async fn make_production_chunks(
                             .iter()
                             .map(|chunk_item| chunk_item.size())
                             .sum::<usize>();
+                        let (_, (chunk_key, chunk_groups)) = key.into_parts();
                         ChunkCandidate {
                             size,
                             chunk_items,
                             batch_groups: batch_group.into_iter().collect(),
-                            chunk_groups: key.map(Cow::Borrowed),
+                            chunk_groups: chunk_groups.map(Cow::Borrowed),
+                            chunk_key,
                         }
                     },
                 )
@@ -179,6 +212,7 @@ pub async fn make_production_chunks(
                                 chunk_items,
                                 batch_groups,
                                 chunk_groups,
+                                chunk_key,
                             } = heap.pop().unwrap();
                             chunks_to_merge_size += size;
                             chunks_to_merge.push(MergeCandidate {
@@ -186,6 +220,7 @@ pub async fn make_production_chunks(
                                 chunk_items,
                                 batch_groups,
                                 chunk_groups,
+                                chunk_key,
                             });
                             continue;
                         }
@@ -219,6 +254,10 @@ pub async fn make_production_chunks(
 
                         // Check all combination with the new candidate
                         for (i, other) in selection.iter().enumerate() {
+                            // Chunk items with different chunk keys are never merged
+                            if candidate.chunk_key != other.chunk_key {
+                                continue;
+                            }
                             let overlap = overlap(&candidate.chunk_groups, &other.chunk_groups);
                             // It need to have at least two chunk groups in common
                             if overlap <= 1 {
@@ -444,6 +483,7 @@ pub async fn make_production_chunks(
                             chunk_items,
                             mut batch_groups,
                             chunk_groups,
+                            ..
                         } = other;
                         candidate.size += size;
                         candidate.chunk_items.extend(chunk_items);
@@ -481,6 +521,7 @@ pub async fn make_production_chunks(
                                 chunk_items: unused.chunk_items,
                                 batch_groups: unused.batch_groups,
                                 chunk_groups: unused.chunk_groups,
+                                chunk_key: unused.chunk_key,
                             });
                         } else {
                             chunks_to_merge.push(unused);
@@ -492,14 +533,13 @@ pub async fn make_production_chunks(
                     }
                 }
 
-                let mut remained_size = 0;
-                let mut remained_chunk_items = Vec::new();
-                let mut remained_batch_groups = FxIndexSet::default();
+                let mut remained = FxIndexMap::<_, (usize, Vec<_>, FxIndexSet<_>)>::default();
                 for MergeCandidate {
                     size,
                     chunk_items,
                     batch_groups,
                     chunk_groups,
+                    chunk_key,
                 } in chunks_to_merge.into_iter()
                 {
                     if size > merge_threshold {
@@ -508,27 +548,41 @@ pub async fn make_production_chunks(
                             chunk_items,
                             batch_groups,
                             chunk_groups,
+                            chunk_key,
                         });
                     } else {
-                        remained_size += size;
+                        let (remained_size, remained_chunk_items, remained_batch_groups) =
+                            remained.entry(chunk_key).or_default();
+                        *remained_size += size;
                         remained_chunk_items.extend(chunk_items);
                         remained_batch_groups.extend(batch_groups);
                     }
                 }
 
-                // Left-over chunks are merged together forming the remained chunk, which includes
-                // all modules that are not sharable
-                if !remained_chunk_items.is_empty() {
-                    heap.push(ChunkCandidate {
-                        size: remained_size,
-                        chunk_items: remained_chunk_items,
-                        batch_groups: remained_batch_groups.into_iter().collect(),
-                        chunk_groups: None,
-                    });
+                // Left-over chunks are merged together forming the remained chunk of every chunk
+                // key, which includes all modules that are not sharable
+                for (chunk_key, (remained_size, remained_chunk_items, remained_batch_groups)) in
+                    remained
+                {
+                    if !remained_chunk_items.is_empty() {
+                        heap.push(ChunkCandidate {
+                            size: remained_size,
+                            chunk_items: remained_chunk_items,
+                            batch_groups: remained_batch_groups.into_iter().collect(),
+                            chunk_groups: None,
+                            chunk_key,
+                        });
+                    }
                 }
             }
 
-            span.record("chunks", heap.len());
+            let mut chunks = heap.into_vec();
+            if max_parallel_requests != 0 {
+                set_requesting_chunk_groups(&mut chunks, &chunk_item_chunk_groups);
+                limit_parallel_requests(&mut chunks, max_parallel_requests);
+            }
+
+            span.record("chunks", chunks.len());
 
             let mut total_size = 0;
             for ChunkCandidate {
@@ -536,7 +590,7 @@ pub async fn make_production_chunks(
                 batch_groups,
                 size,
                 ..
-            } in heap.into_iter()
+            } in chunks
             {
                 total_size += size;
                 make_chunk(
@@ -556,11 +610,137 @@ pub async fn make_production_chunks(
     .await
 }
 
+/// Returns the key the `strategy` assigns to every chunk item, see [ChunkingStrategy::chunk_key].
+async fn chunk_keys(
+    chunk_items: &[&ChunkItemOrBatchWithInfo],
+    strategy: Option<ResolvedVc<Box<dyn ChunkingStrategy>>>,
+) -> Result<Option<Vec<RcStr>>> {
+    let Some(strategy) = strategy else {
+        return Ok(None);
+    };
+    let strategy = strategy.into_trait_ref().await?;
+    chunk_items
+        .iter()
+        .map(|chunk_item| {
+            let ChunkItemOrBatchWithInfo::ChunkItem { asset_ident, .. } = chunk_item else {
+                bail!("Batches need to be expanded to compute chunk keys");
+            };
+            Ok(strategy.chunk_key(asset_ident))
+        })
+        .collect::<Result<_>>()
+        .map(Some)
+}
+
+/// Merged chunks only keep the chunk groups that need all of their chunk items, but limiting the
+/// parallel requests needs every chunk group that requests them.
+fn set_requesting_chunk_groups(
+    chunks: &mut [ChunkCandidate<'_>],
+    chunk_item_chunk_groups: &FxHashMap<
+        *const ChunkItemOrBatchWithInfo,
+        Option<&RoaringBitmapWrapper>,
+    >,
+) {
+    for chunk in chunks {
+        let mut requested_by = RoaringBitmapWrapper::default();
+        for chunk_item in &chunk.chunk_items {
+            if let Some(chunk_groups) = chunk_item_chunk_groups
+                .get(&ptr::from_ref(*chunk_item))
+                .copied()
+                .flatten()
+            {
+                *requested_by |= &**chunk_groups;
+            }
+        }
+        chunk.chunk_groups = Some(Cow::Owned(requested_by));
+    }
+}
+
+/// Merges chunks until no chunk group requests more than `max_parallel_requests` chunks. Only two
+/// chunks that are both requested by an over-budget chunk group are merged. Merges that add the
+/// least code to chunk groups that requested only one of the chunks are preferred, then the
+/// smallest chunks.
+///
+/// Chunk groups are only known for chunk items of modules, so chunks without them are not counted.
+fn limit_parallel_requests(chunks: &mut Vec<ChunkCandidate<'_>>, max_parallel_requests: usize) {
+    debug_assert!(max_parallel_requests != 0);
+    loop {
+        let mut requests = FxHashMap::<u32, usize>::default();
+        for chunk in chunks.iter() {
+            if let Some(chunk_groups) = &chunk.chunk_groups {
+                for chunk_group in chunk_groups.iter() {
+                    *requests.entry(chunk_group).or_default() += 1;
+                }
+            }
+        }
+        // The chunk group with the most requests, for a deterministic result
+        let Some((chunk_group, _)) = requests
+            .into_iter()
+            .filter(|&(_, count)| count > max_parallel_requests)
+            .max_by_key(|&(chunk_group, count)| (count, Reverse(chunk_group)))
+        else {
+            break;
+        };
+
+        let requested = chunks
+            .iter()
+            .enumerate()
+            .filter(|(_, chunk)| {
+                chunk
+                    .chunk_groups
+                    .as_ref()
+                    .is_some_and(|chunk_groups| chunk_groups.contains(chunk_group))
+            })
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        let mut best = None;
+        for (n, &i) in requested.iter().enumerate() {
+            for &j in &requested[n + 1..] {
+                let (a, b) = (&chunks[i], &chunks[j]);
+                let overlap = overlap(&a.chunk_groups, &b.chunk_groups);
+                let added_size = a.size as u64 * (b.chunk_groups_len() - overlap)
+                    + b.size as u64 * (a.chunk_groups_len() - overlap);
+                let value = (Reverse(added_size), Reverse(a.size + b.size));
+                if best.is_none_or(|(_, _, best_value)| value > best_value) {
+                    best = Some((i, j, value));
+                }
+            }
+        }
+        // The chunk group requests more than one chunk, so there is a pair
+        let (i, j, _) = best.unwrap();
+
+        // `i < j`, so removing `j` keeps `i` valid
+        let other = chunks.swap_remove(j);
+        let chunk = &mut chunks[i];
+        chunk.size += other.size;
+        chunk.chunk_items.extend(other.chunk_items);
+        for batch_group in other.batch_groups {
+            if !chunk.batch_groups.contains(&batch_group) {
+                chunk.batch_groups.push(batch_group);
+            }
+        }
+        // The merged chunk is requested by every chunk group that requested one of the chunks
+        if let (Some(chunk_groups), Some(other_chunk_groups)) =
+            (&mut chunk.chunk_groups, &other.chunk_groups)
+        {
+            chunk_groups.to_mut().0 |= &other_chunk_groups.0;
+        }
+    }
+}
+
 struct ChunkCandidate<'l> {
     size: usize,
     chunk_items: Vec<&'l ChunkItemOrBatchWithInfo>,
     batch_groups: SmallVec<[ResolvedVc<ChunkItemBatchGroup>; 1]>,
     chunk_groups: Option<Cow<'l, RoaringBitmapWrapper>>,
+    chunk_key: RcStr,
+}
+
+impl ChunkCandidate<'_> {
+    fn chunk_groups_len(&self) -> u64 {
+        self.chunk_groups
+            .as_ref()
+            .map_or(0, |chunk_groups| chunk_groups.len())
+    }
 }
 
 impl Ord for ChunkCandidate<'_> {
@@ -588,6 +768,7 @@ struct MergeCandidate<'l> {
     chunk_items: Vec<&'l ChunkItemOrBatchWithInfo>,
     batch_groups: SmallVec<[ResolvedVc<ChunkItemBatchGroup>; 1]>,
     chunk_groups: Option<Cow<'l, RoaringBitmapWrapper>>,
+    chunk_key: RcStr,
 }
 
 impl MergeCandidate<'_> {
@@ -643,3 +824,84 @@ fn merge_chunk_groups<'l>(
         None
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(size: usize, chunk_groups: &[u32]) -> ChunkCandidate<'static> {
+        ChunkCandidate {
+            size,
+            chunk_items: Vec::new(),
+            batch_groups: SmallVec::new(),
+            chunk_groups: Some(Cow::Owned(RoaringBitmapWrapper(
+                chunk_groups.iter().copied().collect(),
+            ))),
+            chunk_key: RcStr::default(),
+        }
+    }
+
+    fn chunk_groups(chunks: &[ChunkCandidate<'_>]) -> Vec<(usize, Vec<u32>)> {
+        let mut result = chunks
+            .iter()
+            .map(|chunk| {
+                (
+                    chunk.size,
+                    chunk.chunk_groups.as_ref().unwrap().iter().collect(),
+                )
+            })
+            .collect::<Vec<_>>();
+        result.sort();
+        result
+    }
+
+    #[test]
+    fn test_limit_parallel_requests() {
+        // chunk group 0 requests 4 chunks, chunk group 1 requests 2
+        let mut chunks = vec![
+            chunk(100, &[0]),
+            chunk(10, &[0, 1]),
+            chunk(20, &[0, 1]),
+            chunk(5, &[0]),
+        ];
+        limit_parallel_requests(&mut chunks, 2);
+        // The chunks shared with chunk group 1 are merged, even though the chunks only needed by
+        // chunk group 0 are smaller, so chunk group 1 doesn't load code it doesn't need.
+        assert_eq!(
+            chunk_groups(&chunks),
+            vec![(30, vec![0, 1]), (105, vec![0])]
+        );
+    }
+
+    #[test]
+    fn test_limit_parallel_requests_unrelated_chunks() {
+        // Chunks of other chunk groups are never merged, even if they are the smallest ones
+        let mut chunks = vec![
+            chunk(1, &[1]),
+            chunk(2, &[2]),
+            chunk(100, &[0]),
+            chunk(200, &[0]),
+            chunk(300, &[0]),
+        ];
+        limit_parallel_requests(&mut chunks, 2);
+        assert_eq!(
+            chunk_groups(&chunks),
+            vec![(1, vec![1]), (2, vec![2]), (300, vec![0]), (300, vec![0])]
+        );
+    }
+
+    #[test]
+    fn test_limit_parallel_requests_within_budget() {
+        let mut chunks = vec![chunk(1, &[0]), chunk(2, &[0]), chunk(3, &[1])];
+        limit_parallel_requests(&mut chunks, 2);
+        assert_eq!(chunks.len(), 3);
+
+        let unknown = ChunkCandidate {
+            chunk_groups: None,
+            ..chunk(1, &[])
+        };
+        let mut chunks = vec![unknown, chunk(2, &[0]), chunk(3, &[0])];
+        limit_parallel_requests(&mut chunks, 2);
+        assert_eq!(chunks.len(), 3);
+    }
+}
@@ -9,7 +9,7 @@ use turbo_tasks_hash::DeterministicHash;
 use super::{ChunkableModule, EvaluatableAssets, availability_info::AvailabilityInfo};
 use crate::{
     asset::Asset,
-    chunk::{ChunkItem, ChunkType, ModuleId},
+    chunk::{ChunkItem, ChunkType, ModuleId, chunking_strategies::ChunkingStrategy},
     environment::Environment,
     ident::AssetIdent,
     module::Module,
@@ -124,6 +124,15 @@ pub struct ChunkingConfig {
     /// This makes sure that code in big chunks is not duplicated in multiple chunks.
     pub max_merge_chunk_size: usize,
 
+    /// Limits the number of chunks that a chunk group requests in parallel. Chunks needed by the
+    /// same chunk groups are merged until the limit is met. `0` means no limit. Only applies to
+    /// non-style chunk types.
+    pub max_parallel_requests: usize,
+
+    /// Additionally splits chunk items into chunks, e.g. by npm package. Only applies to non-style
+    /// chunk types.
+    pub strategy: Option<ResolvedVc<Box<dyn ChunkingStrategy>>>,
+
     #[allow(dead_code)]
     pub placeholder_for_future_extensions: (),
 }
@@ -0,0 +1,110 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use turbo_rcstr::RcStr;
+use turbo_tasks::{ResolvedVc, Vc};
+
+use crate::chunk::chunking::package_name;
+
+/// Decides which chunk items production chunking may put into the same chunk, in addition to the
+/// chunk groups the chunk items are needed in. Selected per chunk type with
+/// [`ChunkingConfig::strategy`][crate::chunk::ChunkingConfig::strategy].
+#[turbo_tasks::value_trait]
+pub trait ChunkingStrategy {
+    /// Returns the key of the chunk item with the given asset ident. Chunk items with different
+    /// keys are put into different chunks, unless they have to be merged to stay within
+    /// [`ChunkingConfig::max_parallel_requests`][crate::chunk::ChunkingConfig::max_parallel_requests].
+    /// Chunk items with an empty key are chunked like without a strategy.
+    fn chunk_key(&self, asset_ident: &str) -> RcStr;
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionChunkingStrategy(Option<ResolvedVc<Box<dyn ChunkingStrategy>>>);
+
+/// Puts every npm package into separate chunks ("vendor per package"), so an update of one
+/// dependency doesn't invalidate the cached chunks of the other dependencies.
+#[turbo_tasks::value]
+pub struct VendorPerPackageChunkingStrategy;
+
+impl VendorPerPackageChunkingStrategy {
+    pub fn new() -> Vc<Self> {
+        VendorPerPackageChunkingStrategy {}.cell()
+    }
+
+    pub fn new_resolved() -> ResolvedVc<Self> {
+        VendorPerPackageChunkingStrategy {}.resolved_cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkingStrategy for VendorPerPackageChunkingStrategy {
+    fn chunk_key(&self, asset_ident: &str) -> RcStr {
+        package_name(asset_ident).into()
+    }
+}
+
+/// Puts the chunk items of every route group (a `(group)` directory) into separate chunks.
+#[turbo_tasks::value]
+pub struct RouteGroupsChunkingStrategy;
+
+impl RouteGroupsChunkingStrategy {
+    pub fn new() -> Vc<Self> {
+        RouteGroupsChunkingStrategy {}.cell()
+    }
+
+    pub fn new_resolved() -> ResolvedVc<Self> {
+        RouteGroupsChunkingStrategy {}.resolved_cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkingStrategy for RouteGroupsChunkingStrategy {
+    fn chunk_key(&self, asset_ident: &str) -> RcStr {
+        route_group_name(asset_ident).into()
+    }
+}
+
+/// Returns the route group (a `(group)` directory) of the given `ident` or an empty string.
+fn route_group_name(ident: &str) -> &str {
+    static ROUTE_GROUP_REGEX: Lazy<Regex> = Lazy::new(|| Regex::new(r"/\([^/()]+\)/").unwrap());
+    ROUTE_GROUP_REGEX
+        .find(ident)
+        .map_or("", |result| &result.as_str()[1..result.len() - 1])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vendor_per_package_keys() {
+        assert_eq!(package_name("[project]/app/page.tsx"), "");
+        assert_eq!(
+            package_name("[project]/node_modules/react/index.js"),
+            "react"
+        );
+        assert_eq!(
+            package_name("[project]/node_modules/@swc/helpers/esm/_interop.js"),
+            "@swc/helpers"
+        );
+        // nested packages belong to the innermost package
+        assert_eq!(
+            package_name("[project]/node_modules/a/node_modules/b/index.js"),
+            "b"
+        );
+    }
+
+    #[test]
+    fn test_route_groups_keys() {
+        assert_eq!(route_group_name("[project]/app/page.tsx"), "");
+        assert_eq!(
+            route_group_name("[project]/app/(marketing)/about/page.tsx"),
+            "(marketing)"
+        );
+        // the outermost route group is used
+        assert_eq!(
+            route_group_name("[project]/app/(shop)/(checkout)/cart/page.tsx"),
+            "(shop)"
+        );
+        assert_eq!(route_group_name("[project]/app/(notagroup.tsx"), "");
+    }
+}
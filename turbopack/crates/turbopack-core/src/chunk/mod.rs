@@ -4,6 +4,7 @@ pub mod chunk_group;
 pub(crate) mod chunk_item_batch;
 pub mod chunking;
 pub(crate) mod chunking_context;
+pub mod chunking_strategies;
 pub(crate) mod containment_tree;
 pub(crate) mod data;
 pub(crate) mod evaluate;
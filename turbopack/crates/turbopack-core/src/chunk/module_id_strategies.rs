@@ -1,6 +1,11 @@
+use std::collections::BTreeMap;
+
 use anyhow::{Result, bail};
-use rustc_hash::FxHashMap;
-use turbo_tasks::{ResolvedVc, ValueToString, Vc};
+use rustc_hash::{FxHashMap, FxHashSet};
+use serde::{Deserialize, Serialize};
+use turbo_rcstr::RcStr;
+use turbo_tasks::{NonLocalValue, ResolvedVc, ValueToString, Vc, trace::TraceRawVcs};
+use turbo_tasks_fs::{File, FileContent};
 use turbo_tasks_hash::hash_xxh3_hash64;
 
 use super::ModuleId;
@@ -83,3 +88,196 @@ impl ModuleIdStrategy for GlobalModuleIdStrategy {
         .cell())
     }
 }
+
+/// Assigns short module ids derived from a hash of the module ident. Ids of modules from previous
+/// builds are kept (see [ModuleIdRecords]), so adding or removing modules doesn't change the ids
+/// of other modules.
+#[turbo_tasks::value(shared)]
+pub struct ContentHashModuleIdStrategy {
+    pub module_id_map: FxHashMap<ResolvedVc<AssetIdent>, RcStr>,
+    /// The assignment to persist for the next build.
+    pub records: ModuleIdRecords,
+}
+
+#[turbo_tasks::value_impl]
+impl ModuleIdStrategy for ContentHashModuleIdStrategy {
+    #[turbo_tasks::function]
+    async fn get_module_id(&self, ident: ResolvedVc<AssetIdent>) -> Result<Vc<ModuleId>> {
+        if let Some(module_id) = self.module_id_map.get(&ident) {
+            return Ok(ModuleId::String(module_id.clone()).cell());
+        }
+
+        // Modules outside of the module graph get the full hash, which is longer than the short
+        // ids and therefore can't collide with them.
+        Ok(ModuleId::String(
+            encode_module_id_hash(hash_xxh3_hash64(ident.to_string().await?), usize::MAX).into(),
+        )
+        .cell())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentHashModuleIdStrategy {
+    /// The records file content to persist for the next build.
+    #[turbo_tasks::function]
+    pub fn records_file(&self) -> Result<Vc<FileContent>> {
+        Ok(FileContent::Content(File::from(serde_json::to_string_pretty(&self.records)?)).cell())
+    }
+}
+
+/// The persisted module id assignment of a [ContentHashModuleIdStrategy], mapping module idents
+/// to module ids.
+#[derive(
+    Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs, NonLocalValue,
+)]
+pub struct ModuleIdRecords {
+    pub version: u32,
+    pub module_ids: BTreeMap<RcStr, RcStr>,
+}
+
+impl ModuleIdRecords {
+    /// Bump this when the format or the id derivation changes. Records with a different version
+    /// are ignored.
+    pub const VERSION: u32 = 1;
+
+    /// The length of newly assigned module ids. Longer ids are only used to resolve collisions.
+    pub const MIN_ID_LENGTH: usize = 4;
+
+    /// Assigns module ids to the given idents. Ids from `previous` are kept for idents that still
+    /// exist, new idents get an id derived from the hash of the ident, avoiding any id that is
+    /// already in use. Returns the number of hash collisions that had to be resolved.
+    pub fn assign<'a>(
+        previous: &ModuleIdRecords,
+        idents: impl IntoIterator<Item = &'a str>,
+    ) -> (ModuleIdRecords, usize) {
+        let mut idents = idents.into_iter().collect::<Vec<_>>();
+        // Sort to not depend on the order of the module graph
+        idents.sort_unstable();
+        idents.dedup();
+
+        let mut module_ids = BTreeMap::new();
+        let mut used_ids = FxHashSet::default();
+        let mut new_idents = Vec::new();
+        if previous.version == Self::VERSION {
+            for ident in idents {
+                match previous.module_ids.get(ident) {
+                    Some(id) if used_ids.insert(id.clone()) => {
+                        module_ids.insert(RcStr::from(ident), id.clone());
+                    }
+                    _ => new_idents.push(ident),
+                }
+            }
+        } else {
+            new_idents = idents;
+        }
+
+        let mut collisions = 0;
+        for ident in new_idents {
+            let hash = hash_xxh3_hash64(ident);
+            let mut length = Self::MIN_ID_LENGTH;
+            let mut attempt = 0u64;
+            let id = loop {
+                let candidate = if attempt == 0 {
+                    hash
+                } else {
+                    hash_xxh3_hash64((hash, attempt))
+                };
+                let id = RcStr::from(encode_module_id_hash(candidate, length));
+                if used_ids.insert(id.clone()) {
+                    break id;
+                }
+                collisions += 1;
+                attempt += 1;
+                // Grow the id when the current length is crowded
+                if attempt % 8 == 0 {
+                    length += 1;
+                }
+            };
+            module_ids.insert(RcStr::from(ident), id);
+        }
+
+        (
+            ModuleIdRecords {
+                version: Self::VERSION,
+                module_ids,
+            },
+            collisions,
+        )
+    }
+}
+
+/// Encodes a hash as base36 string, truncated to `length` characters.
+fn encode_module_id_hash(mut hash: u64, length: usize) -> String {
+    const ALPHABET: &[u8; 36] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let mut result = String::new();
+    while result.len() < length {
+        result.push(ALPHABET[(hash % 36) as usize] as char);
+        hash /= 36;
+        if hash == 0 {
+            break;
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_module_id_records_are_stable() {
+        let (first, collisions) = ModuleIdRecords::assign(
+            &ModuleIdRecords::default(),
+            ["[project]/a.js", "[project]/b.js", "[project]/c.js"],
+        );
+        assert_eq!(collisions, 0);
+        assert_eq!(first.module_ids.len(), 3);
+        assert!(
+            first
+                .module_ids
+                .values()
+                .all(|id| id.len() == ModuleIdRecords::MIN_ID_LENGTH)
+        );
+
+        // Adding and removing modules keeps the ids of the other modules
+        let (second, _) = ModuleIdRecords::assign(
+            &first,
+            ["[project]/d.js", "[project]/c.js", "[project]/a.js"],
+        );
+        assert_eq!(second.module_ids.len(), 3);
+        assert_eq!(
+            second.module_ids.get("[project]/a.js"),
+            first.module_ids.get("[project]/a.js")
+        );
+        assert_eq!(
+            second.module_ids.get("[project]/c.js"),
+            first.module_ids.get("[project]/c.js")
+        );
+        assert!(!second.module_ids.contains_key("[project]/b.js"));
+    }
+
+    #[test]
+    fn test_module_id_records_resolve_collisions() {
+        let previous = ModuleIdRecords {
+            version: ModuleIdRecords::VERSION,
+            module_ids: BTreeMap::from([(
+                RcStr::from("[project]/old.js"),
+                RcStr::from(encode_module_id_hash(
+                    hash_xxh3_hash64("[project]/new.js"),
+                    ModuleIdRecords::MIN_ID_LENGTH,
+                )),
+            )]),
+        };
+        let (records, collisions) =
+            ModuleIdRecords::assign(&previous, ["[project]/old.js", "[project]/new.js"]);
+        assert_eq!(collisions, 1);
+        assert_ne!(
+            records.module_ids.get("[project]/old.js"),
+            records.module_ids.get("[project]/new.js")
+        );
+        assert_eq!(
+            records.module_ids.get("[project]/old.js"),
+            previous.module_ids.get("[project]/old.js")
+        );
+    }
+}
@@ -13,21 +13,44 @@ pub async fn compute_export_usage_info(
     graph: ResolvedVc<ModuleGraph>,
 ) -> Result<Vc<ExportUsageInfo>> {
     let mut used_exports = FxHashMap::<_, ModuleExportUsageInfo>::default();
+    let mut reexports = Vec::new();
 
     graph
         .await?
-        .traverse_all_edges_unordered(|(_, ref_data), target| {
+        .traverse_all_edges_unordered(|(source, ref_data), target| {
             if let Some(target_module) = ResolvedVc::try_downcast::<Box<dyn Module>>(target.module)
             {
                 let e = used_exports.entry(target_module).or_default();
 
                 e.add(&ref_data.export);
+
+                if matches!(ref_data.export, ExportUsage::Reexport) {
+                    reexports.push((source.module, target_module));
+                }
             }
 
             Ok(())
         })
         .await?;
 
+    // A re-exported module is used as far as the re-exporting module is. Re-exports can be chained,
+    // so this is repeated until nothing changes.
+    let mut changed = !reexports.is_empty();
+    while changed {
+        changed = false;
+        for (source, target) in &reexports {
+            // Modules without incoming edges are entries, see `ExportUsageInfo::used_exports`.
+            let source_usage = used_exports
+                .get(source)
+                .cloned()
+                .unwrap_or(ModuleExportUsageInfo::All);
+            changed |= used_exports
+                .entry(*target)
+                .or_default()
+                .merge(&source_usage);
+        }
+    }
+
     Ok(ExportUsageInfo { used_exports }.cell())
 }
 
@@ -86,8 +109,40 @@ impl ModuleExportUsageInfo {
                 l.insert(r.clone());
             }
 
-            (_, ExportUsage::Evaluation) => {
-                // Ignore evaluation
+            (Self::Evaluation, ExportUsage::Keys(keys)) if !keys.is_empty() => {
+                *self = Self::Exports(keys.iter().cloned().collect());
+            }
+
+            (Self::Exports(l), ExportUsage::Keys(keys)) => {
+                for key in keys {
+                    l.insert(key.clone());
+                }
+            }
+
+            (_, ExportUsage::Evaluation | ExportUsage::Keys(_) | ExportUsage::Reexport) => {
+                // Ignore evaluation. Re-exports are propagated after all edges are known.
+            }
+        }
+    }
+
+    /// Adds the usage of `other`, returning whether this changed.
+    fn merge(&mut self, other: &ModuleExportUsageInfo) -> bool {
+        match (&mut *self, other) {
+            (Self::All, _) | (_, Self::Evaluation) => false,
+            (_, Self::All) => {
+                *self = Self::All;
+                true
+            }
+            (Self::Evaluation, Self::Exports(r)) => {
+                *self = Self::Exports(r.clone());
+                !r.is_empty()
+            }
+            (Self::Exports(l), Self::Exports(r)) => {
+                let mut changed = false;
+                for export in r {
+                    changed |= l.insert(export.clone());
+                }
+                changed
             }
         }
     }
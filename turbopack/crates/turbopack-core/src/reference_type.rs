@@ -60,6 +60,37 @@ pub enum CommonJsReferenceSubType {
 )]
 pub enum ImportWithType {
     Json,
+    /// A CSS module script, which exports a constructable `CSSStyleSheet`.
+    Css,
+    /// The content of the module as string.
+    Text,
+    /// The content of the module as `Uint8Array`.
+    Bytes,
+    /// A `type` import attribute without built-in handling. Module rules can
+    /// match it to select a module type.
+    Other(RcStr),
+}
+
+impl ImportWithType {
+    pub fn new(ty: &str) -> Self {
+        match ty {
+            "json" => ImportWithType::Json,
+            "css" => ImportWithType::Css,
+            "text" => ImportWithType::Text,
+            "bytes" => ImportWithType::Bytes,
+            _ => ImportWithType::Other(ty.into()),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            ImportWithType::Json => "json",
+            ImportWithType::Css => "css",
+            ImportWithType::Text => "text",
+            ImportWithType::Bytes => "bytes",
+            ImportWithType::Other(ty) => ty,
+        }
+    }
 }
 
 #[derive(
@@ -105,6 +105,12 @@ impl ModuleResolveResultItem {
 #[derive(Debug, Clone, Default, Hash)]
 pub enum ExportUsage {
     Named(RcStr),
+    /// Only these keys of the value exported by the module are used, e.g. the classes read from
+    /// the object that a CSS Module exports.
+    Keys(Vec<RcStr>),
+    /// The referencing module exports what the referenced module exports, so the referenced
+    /// module's exports are used as far as the referencing module's exports are.
+    Reexport,
     /// This means the whole content of the module is used.
     #[default]
     All,
@@ -116,6 +122,8 @@ impl Display for ExportUsage {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
             ExportUsage::Named(name) => write!(f, "export {name}"),
+            ExportUsage::Keys(keys) => write!(f, "keys {}", keys.join(", ")),
+            ExportUsage::Reexport => write!(f, "reexport"),
             ExportUsage::All => write!(f, "all"),
             ExportUsage::Evaluation => write!(f, "evaluation"),
         }
@@ -138,6 +146,16 @@ impl ExportUsage {
     pub fn named(name: RcStr) -> Vc<Self> {
         Self::Named(name).cell()
     }
+
+    #[turbo_tasks::function]
+    pub fn keys(keys: Vec<RcStr>) -> Vc<Self> {
+        Self::Keys(keys).cell()
+    }
+
+    #[turbo_tasks::function]
+    pub fn reexport() -> Vc<Self> {
+        Self::Reexport.cell()
+    }
 }
 
 #[turbo_tasks::value(shared)]
@@ -24,7 +24,7 @@ use crate::{
     code_gen::CodeGenerateable,
     process::{
         CssWithPlaceholderResult, FinalCssResult, ParseCss, ParseCssResult, ProcessCss,
-        finalize_css, parse_css, process_css_with_placeholder,
+        finalize_css, parse_css, process_css_with_placeholder, unused_module_classes,
     },
     references::{
         compose::CssModuleComposeReference, import::ImportAssetReference, url::ReferencedAsset,
@@ -40,6 +40,9 @@ pub struct CssModuleAsset {
     import_context: Option<ResolvedVc<ImportContext>>,
     ty: CssModuleAssetType,
     environment: Option<ResolvedVc<Environment>>,
+    /// Leave out the rules that only match classes that no importing module reads. Only applies
+    /// to the stylesheets of CSS Modules.
+    remove_unused_classes: bool,
 }
 
 #[turbo_tasks::value_impl]
@@ -52,6 +55,7 @@ impl CssModuleAsset {
         ty: CssModuleAssetType,
         import_context: Option<ResolvedVc<ImportContext>>,
         environment: Option<ResolvedVc<Environment>>,
+        remove_unused_classes: bool,
     ) -> Vc<Self> {
         Self::cell(CssModuleAsset {
             source,
@@ -59,6 +63,7 @@ impl CssModuleAsset {
             import_context,
             ty,
             environment,
+            remove_unused_classes,
         })
     }
 
@@ -107,6 +112,20 @@ impl ProcessCss for CssModuleAsset {
         let process_result = self.get_css_with_placeholder();
 
         let this = self.await?;
+        let unused_classes = if this.remove_unused_classes
+            && matches!(this.ty, CssModuleAssetType::Module)
+            && let CssWithPlaceholderResult::Ok {
+                exports: Some(exports),
+                ..
+            } = &*process_result.await?
+        {
+            let usage = chunking_context
+                .module_export_usage(Vc::upcast(self))
+                .await?;
+            unused_module_classes(exports, &usage)
+        } else {
+            Vec::new()
+        };
         let origin_source_map =
             match ResolvedVc::try_sidecast::<Box<dyn GenerateSourceMap>>(this.source) {
                 Some(gsm) => gsm.generate_source_map(),
@@ -118,6 +137,7 @@ impl ProcessCss for CssModuleAsset {
             minify_type,
             origin_source_map,
             this.environment.as_deref().copied(),
+            unused_classes,
         ))
     }
 }
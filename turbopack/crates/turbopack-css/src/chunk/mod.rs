@@ -17,7 +17,7 @@ use turbopack_core::{
     asset::{Asset, AssetContent},
     chunk::{
         AsyncModuleInfo, Chunk, ChunkItem, ChunkItemBatchGroup, ChunkItemExt,
-        ChunkItemOrBatchWithAsyncModuleInfo, ChunkItemWithAsyncModuleInfo, ChunkType,
+        ChunkItemOrBatchWithAsyncModuleInfo, ChunkItemWithAsyncModuleInfo, ChunkItems, ChunkType,
         ChunkableModule, ChunkingContext, MinifyType, OutputChunk, OutputChunkRuntimeInfo,
         round_chunk_item_size,
     },
@@ -250,6 +250,18 @@ impl Chunk for CssChunk {
     fn chunking_context(&self) -> Vc<Box<dyn ChunkingContext>> {
         *self.chunking_context
     }
+
+    #[turbo_tasks::function]
+    async fn chunk_items(&self) -> Result<Vc<ChunkItems>> {
+        Ok(Vc::cell(
+            self.content
+                .await?
+                .chunk_items
+                .iter()
+                .map(|&item| ResolvedVc::upcast(item))
+                .collect(),
+        ))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -337,6 +349,11 @@ impl OutputAsset for CssChunk {
         ))
     }
 
+    #[turbo_tasks::function]
+    fn size_bytes(self: Vc<Self>) -> Vc<Option<u64>> {
+        self.content().len()
+    }
+
     #[turbo_tasks::function]
     async fn references(self: Vc<Self>) -> Result<Vc<OutputAssets>> {
         let this = self.await?;
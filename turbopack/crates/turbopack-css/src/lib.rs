@@ -16,9 +16,10 @@ pub(crate) mod references;
 pub(crate) mod util;
 
 pub use asset::CssModuleAsset;
-pub use module_asset::ModuleCssAsset;
+pub use module_asset::{ModuleCssAsset, emit_css_module_type_declarations};
 use serde::{Deserialize, Serialize};
 use turbo_tasks::{NonLocalValue, TaskInput, trace::TraceRawVcs};
+use turbo_tasks_fs::FileSystemPath;
 
 pub use self::process::*;
 use crate::references::import::ImportAssetReference;
@@ -47,6 +48,18 @@ pub enum CssModuleAssetType {
     Module,
 }
 
+/// Where to write the TypeScript declarations listing the class names exported by CSS Modules.
+#[derive(
+    Eq, PartialEq, Hash, Debug, Clone, Serialize, Deserialize, TaskInput, TraceRawVcs, NonLocalValue,
+)]
+pub enum CssModuleTypeDeclarations {
+    /// Next to the source, e.g. `button.module.css.d.ts` for `button.module.css`.
+    NextToSource,
+    /// Into a types directory, mirroring the path of the source relative to the root of its
+    /// filesystem, e.g. `<dir>/src/button.module.css.d.ts`.
+    Directory(FileSystemPath),
+}
+
 pub fn register() {
     turbo_tasks::register();
     turbo_tasks_fs::register();
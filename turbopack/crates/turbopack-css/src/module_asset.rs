@@ -5,8 +5,10 @@ use indoc::formatdoc;
 use lightningcss::css_modules::CssModuleReference;
 use swc_core::common::{BytePos, FileName, LineCol, SourceMap};
 use turbo_rcstr::{RcStr, rcstr};
-use turbo_tasks::{FxIndexMap, IntoTraitRef, ResolvedVc, ValueToString, Vc};
-use turbo_tasks_fs::{FileSystemPath, rope::Rope};
+use turbo_tasks::{
+    FxIndexMap, FxIndexSet, IntoTraitRef, ResolvedVc, TryJoinIterExt, ValueToString, Vc,
+};
+use turbo_tasks_fs::{File, FileContent, FileSystemPath, rope::Rope};
 use turbopack_core::{
     asset::{Asset, AssetContent},
     chunk::{ChunkItem, ChunkType, ChunkableModule, ChunkingContext, ModuleChunkItemIdExt},
@@ -26,7 +28,7 @@ use turbopack_core::{
 use turbopack_ecmascript::{
     chunk::{
         EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkPlaceable,
-        EcmascriptChunkType, EcmascriptExports,
+        EcmascriptChunkType, EcmascriptExports, EcmascriptValueKeys, OptionValueKeys,
     },
     parse::generate_js_source_map,
     runtime_functions::{TURBOPACK_EXPORT_VALUE, TURBOPACK_IMPORT},
@@ -34,7 +36,8 @@ use turbopack_ecmascript::{
 };
 
 use crate::{
-    process::{CssWithPlaceholderResult, ProcessCss},
+    CssModuleTypeDeclarations,
+    process::{CssWithPlaceholderResult, ProcessCss, unused_module_classes},
     references::{compose::CssModuleComposeReference, internal::InternalCssAssetReference},
 };
 
@@ -44,6 +47,10 @@ use crate::{
 pub struct ModuleCssAsset {
     pub source: ResolvedVc<Box<dyn Source>>,
     pub asset_context: ResolvedVc<Box<dyn AssetContext>>,
+    /// Where to write the TypeScript declarations for the exported classes, if at all.
+    pub type_declarations: Option<CssModuleTypeDeclarations>,
+    /// Report the classes that no importing module reads.
+    pub report_unused_classes: bool,
 }
 
 #[turbo_tasks::value_impl]
@@ -52,10 +59,14 @@ impl ModuleCssAsset {
     pub fn new(
         source: ResolvedVc<Box<dyn Source>>,
         asset_context: ResolvedVc<Box<dyn AssetContext>>,
+        type_declarations: Option<CssModuleTypeDeclarations>,
+        report_unused_classes: bool,
     ) -> Vc<Self> {
         Self::cell(ModuleCssAsset {
             source,
             asset_context,
+            type_declarations,
+            report_unused_classes,
         })
     }
 }
@@ -167,7 +178,7 @@ impl ModuleCssAsset {
     }
 
     #[turbo_tasks::function]
-    async fn classes(self: Vc<Self>) -> Result<Vc<ModuleCssClasses>> {
+    async fn inner_css(self: Vc<Self>) -> Result<Vc<CssWithPlaceholderResult>> {
         let inner = self
             .inner(ReferenceType::Css(CssReferenceSubType::Analyze))
             .module();
@@ -176,7 +187,12 @@ impl ModuleCssAsset {
             .await?
             .context("inner asset should be CSS processable")?;
 
-        let result = inner.get_css_with_placeholder().await?;
+        Ok(inner.get_css_with_placeholder())
+    }
+
+    #[turbo_tasks::function]
+    async fn classes(self: Vc<Self>) -> Result<Vc<ModuleCssClasses>> {
+        let result = self.inner_css().await?;
         let mut classes = FxIndexMap::default();
 
         // TODO(alexkirsz) Should we report an error on parse error here?
@@ -238,6 +254,85 @@ impl ModuleCssAsset {
 
         Ok(Vc::cell(references))
     }
+
+    /// Writes a `.d.ts` file that declares the exported classes, so TypeScript can check their
+    /// usage. See [`emit_css_module_type_declarations`].
+    #[turbo_tasks::function]
+    pub async fn emit_type_declarations(self: Vc<Self>) -> Result<()> {
+        let this = self.await?;
+        let Some(type_declarations) = &this.type_declarations else {
+            return Ok(());
+        };
+
+        let source_path = this.source.ident().path().owned().await?;
+        let path = match type_declarations {
+            CssModuleTypeDeclarations::NextToSource => source_path.append(".d.ts")?,
+            CssModuleTypeDeclarations::Directory(dir) => {
+                dir.join(&format!("{}.d.ts", source_path.path))?
+            }
+        };
+
+        let classes = self.classes().await?;
+        let code = type_declarations_code(
+            source_path.file_name(),
+            classes.keys().map(|export_name| export_name.as_str()),
+        );
+
+        path.write(FileContent::Content(File::from(code)).cell())
+            .as_side_effect()
+            .await?;
+
+        Ok(())
+    }
+
+    /// Reports the classes that no importing module reads, according to the export usage of the
+    /// chunking context.
+    #[turbo_tasks::function]
+    async fn report_unused_classes(
+        self: Vc<Self>,
+        chunking_context: Vc<Box<dyn ChunkingContext>>,
+    ) -> Result<Vc<()>> {
+        let this = self.await?;
+        if !this.report_unused_classes {
+            return Ok(Default::default());
+        }
+        let CssWithPlaceholderResult::Ok {
+            exports: Some(exports),
+            ..
+        } = &*self.inner_css().await?
+        else {
+            return Ok(Default::default());
+        };
+
+        let usage = chunking_context
+            .module_export_usage(Vc::upcast(self))
+            .await?;
+        let classes = unused_module_classes(exports, &usage);
+        if !classes.is_empty() {
+            UnusedCssModuleClassesIssue {
+                source: IssueSource::from_source_only(this.source),
+                classes,
+            }
+            .resolved_cell()
+            .emit();
+        }
+
+        Ok(Default::default())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl EcmascriptValueKeys for ModuleCssAsset {
+    #[turbo_tasks::function]
+    async fn value_keys(self: Vc<Self>) -> Result<Vc<OptionValueKeys>> {
+        let classes = self.classes().await?;
+        Ok(Vc::cell(Some(
+            classes
+                .keys()
+                .map(|name| RcStr::from(name.as_str()))
+                .collect(),
+        )))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -280,6 +375,41 @@ impl ResolveOrigin for ModuleCssAsset {
     }
 }
 
+/// Writes the TypeScript declarations of all CSS Modules in the `module_graph` that have
+/// [`ModuleCssAsset::type_declarations`] enabled.
+#[turbo_tasks::function]
+pub async fn emit_css_module_type_declarations(module_graph: Vc<ModuleGraph>) -> Result<()> {
+    let module_graph = module_graph.await?;
+    let graphs = module_graph.graphs.iter().try_join().await?;
+    let modules = graphs
+        .iter()
+        .flat_map(|graph| graph.iter_nodes())
+        .filter_map(|node| ResolvedVc::try_downcast_type::<ModuleCssAsset>(node.module))
+        .collect::<FxIndexSet<_>>();
+
+    modules
+        .into_iter()
+        .map(|module| async move { module.emit_type_declarations().as_side_effect().await })
+        .try_join()
+        .await?;
+
+    Ok(())
+}
+
+fn type_declarations_code<'a>(
+    file_name: &str,
+    export_names: impl IntoIterator<Item = &'a str>,
+) -> String {
+    let mut code = format!(
+        "// This file is generated from {file_name}. Do not edit.\n\ndeclare const styles: {{\n"
+    );
+    for export_name in export_names {
+        writeln!(code, "  readonly {}: string;", StringifyJs(export_name)).unwrap();
+    }
+    code += "};\n\nexport default styles;\n";
+    code
+}
+
 #[turbo_tasks::value]
 struct ModuleChunkItem {
     module: ResolvedVc<ModuleCssAsset>,
@@ -316,6 +446,10 @@ impl ChunkItem for ModuleChunkItem {
 impl EcmascriptChunkItem for ModuleChunkItem {
     #[turbo_tasks::function]
     async fn content(&self) -> Result<Vc<EcmascriptChunkItemContent>> {
+        self.module
+            .report_unused_classes(*self.chunking_context)
+            .await?;
+
         let classes = self.module.classes().await?;
 
         let mut code = format!("{TURBOPACK_EXPORT_VALUE}({{\n");
@@ -476,3 +610,71 @@ impl Issue for CssModuleComposesIssue {
         Vc::cell(Some(self.source))
     }
 }
+
+#[turbo_tasks::value(shared)]
+struct UnusedCssModuleClassesIssue {
+    source: IssueSource,
+    classes: Vec<RcStr>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for UnusedCssModuleClassesIssue {
+    fn severity(&self) -> IssueSeverity {
+        IssueSeverity::Warning
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> Vc<StyledString> {
+        StyledString::Text(rcstr!("Unused CSS Module classes")).cell()
+    }
+
+    #[turbo_tasks::function]
+    fn stage(&self) -> Vc<IssueStage> {
+        IssueStage::CodeGen.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn file_path(&self) -> Vc<FileSystemPath> {
+        self.source.file_path()
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> Vc<OptionStyledString> {
+        let classes: Vec<&str> = self.classes.iter().map(|class| class.as_str()).collect();
+        Vc::cell(Some(
+            StyledString::Text(
+                format!(
+                    "No module that imports this CSS Module reads these classes: {}",
+                    classes.join(", ")
+                )
+                .into(),
+            )
+            .resolved_cell(),
+        ))
+    }
+
+    #[turbo_tasks::function]
+    fn source(&self) -> Vc<OptionIssueSource> {
+        Vc::cell(Some(self.source))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::type_declarations_code;
+
+    #[test]
+    fn test_type_declarations_code() {
+        assert_eq!(
+            type_declarations_code("button.module.css", ["button", "primary-button"]),
+            "// This file is generated from button.module.css. Do not edit.\n\ndeclare const \
+             styles: {\n  readonly \"button\": string;\n  readonly \"primary-button\": \
+             string;\n};\n\nexport default styles;\n"
+        );
+        assert_eq!(
+            type_declarations_code("empty.module.css", []),
+            "// This file is generated from empty.module.css. Do not edit.\n\ndeclare const \
+             styles: {\n};\n\nexport default styles;\n"
+        );
+    }
+}
@@ -1,8 +1,12 @@
-use std::sync::{Arc, RwLock};
+use std::{
+    convert::Infallible,
+    sync::{Arc, RwLock},
+};
 
 use anyhow::{Context, Result, bail};
 use lightningcss::{
-    css_modules::{CssModuleExport, CssModuleExports, Pattern, Segment},
+    css_modules::{CssModuleExport, CssModuleExports, CssModuleReference, Pattern, Segment},
+    rules::CssRule,
     stylesheet::{MinifyOptions, ParserOptions, PrinterOptions, StyleSheet, ToCssResult},
     targets::{BrowserslistConfig, Features, Targets},
     traits::ToCss,
@@ -10,7 +14,7 @@ use lightningcss::{
     visit_types,
     visitor::Visit,
 };
-use rustc_hash::FxHashMap;
+use rustc_hash::{FxHashMap, FxHashSet};
 use smallvec::smallvec;
 use swc_core::base::sourcemap::SourceMapBuilder;
 use tracing::Instrument;
@@ -26,6 +30,7 @@ use turbopack_core::{
         Issue, IssueExt, IssueSource, IssueStage, OptionIssueSource, OptionStyledString,
         StyledString,
     },
+    module_graph::export_usage::ModuleExportUsageInfo,
     reference::ModuleReferences,
     reference_type::ImportContext,
     resolve::origin::ResolveOrigin,
@@ -269,6 +274,7 @@ pub async fn finalize_css(
     minify_type: MinifyType,
     origin_source_map: Vc<OptionStringifiedSourceMap>,
     environment: Option<ResolvedVc<Environment>>,
+    unused_classes: Vec<RcStr>,
 ) -> Result<Vc<FinalCssResult>> {
     let result = result.await?;
     match &*result {
@@ -301,6 +307,13 @@ pub async fn finalize_css(
 
             replace_url_references(&mut stylesheet, &url_map);
 
+            if !unused_classes.is_empty() {
+                let mut remover = UnusedClassesRemover {
+                    unused_classes: unused_classes.iter().map(|class| class.as_str()).collect(),
+                };
+                stylesheet.0.visit(&mut remover).unwrap();
+            }
+
             let code = code.await?;
             let code = match &*code {
                 FileContent::Content(v) => v.content().to_str()?,
@@ -647,6 +660,95 @@ impl lightningcss::visitor::Visitor<'_> for CssValidator {
     }
 }
 
+/// Returns the classes of a CSS Module that no importing module reads, neither directly nor
+/// through a used class that `composes` them, sorted by name.
+pub(crate) fn unused_module_classes(
+    exports: &FxIndexMap<String, CssModuleExport>,
+    usage: &ModuleExportUsageInfo,
+) -> Vec<RcStr> {
+    let mut used: FxHashSet<&str> = match usage {
+        ModuleExportUsageInfo::All => return Vec::new(),
+        ModuleExportUsageInfo::Evaluation => FxHashSet::default(),
+        ModuleExportUsageInfo::Exports(used_exports) => {
+            // The whole value is used, e.g. when it's imported as the `default` export
+            if used_exports.contains(&rcstr!("default")) {
+                return Vec::new();
+            }
+            exports
+                .keys()
+                .filter(|class| used_exports.contains(&RcStr::from(class.as_str())))
+                .map(|class| class.as_str())
+                .collect()
+        }
+    };
+    used.extend(
+        exports
+            .iter()
+            .filter(|(_, export)| export.is_referenced)
+            .map(|(class, _)| class.as_str()),
+    );
+
+    let classes_by_name: FxHashMap<&str, &str> = exports
+        .iter()
+        .map(|(class, export)| (export.name.as_str(), class.as_str()))
+        .collect();
+    let mut queue: Vec<&str> = used.iter().copied().collect();
+    while let Some(class) = queue.pop() {
+        let Some(export) = exports.get(class) else {
+            continue;
+        };
+        for reference in &export.composes {
+            if let CssModuleReference::Local { name } = reference
+                && let Some(&composed) = classes_by_name.get(name.as_str())
+                && used.insert(composed)
+            {
+                queue.push(composed);
+            }
+        }
+    }
+
+    let mut unused: Vec<RcStr> = exports
+        .keys()
+        .filter(|class| !used.contains(class.as_str()))
+        .map(|class| RcStr::from(class.as_str()))
+        .collect();
+    unused.sort();
+    unused
+}
+
+/// Leaves out the selectors that can't match because they require an unused local class, and the
+/// style rules without any remaining selector. Classes in `:global()` are not local.
+struct UnusedClassesRemover<'a> {
+    unused_classes: FxHashSet<&'a str>,
+}
+
+impl lightningcss::visitor::Visitor<'_> for UnusedClassesRemover<'_> {
+    type Error = Infallible;
+
+    fn visit_types(&self) -> lightningcss::visitor::VisitTypes {
+        visit_types!(RULES)
+    }
+
+    fn visit_rule(&mut self, rule: &mut CssRule) -> Result<(), Self::Error> {
+        if let CssRule::Style(style) = &mut *rule {
+            style.selectors.0.retain(|selector| {
+                !selector.iter_raw_match_order().any(|component| {
+                    matches!(
+                        component,
+                        parcel_selectors::parser::Component::Class(name)
+                            if self.unused_classes.contains(&*name.0)
+                    )
+                })
+            });
+            if style.selectors.0.is_empty() {
+                *rule = CssRule::Ignored;
+                return Ok(());
+            }
+        }
+        rule.visit_children(self)
+    }
+}
+
 fn generate_css_source_map(source_map: &parcel_sourcemap::SourceMap) -> Result<Rope> {
     let mut builder = SourceMapBuilder::new(None);
 
@@ -716,28 +818,31 @@ impl Issue for ParsingIssue {
 #[cfg(test)]
 mod tests {
     use lightningcss::{
-        css_modules::Pattern,
-        stylesheet::{ParserOptions, StyleSheet},
+        css_modules::{CssModuleExport, CssModuleReference, Pattern},
+        stylesheet::{ParserOptions, PrinterOptions, StyleSheet},
         visitor::Visit,
     };
+    use turbo_rcstr::rcstr;
+    use turbo_tasks::FxIndexMap;
+    use turbopack_core::module_graph::export_usage::ModuleExportUsageInfo;
 
-    use super::{CssError, CssValidator};
+    use super::{CssError, CssValidator, UnusedClassesRemover, unused_module_classes};
 
-    fn lint_lightningcss(code: &str) -> Vec<CssError> {
-        let mut ss = StyleSheet::parse(
-            code,
-            ParserOptions {
-                css_modules: Some(lightningcss::css_modules::Config {
-                    pattern: Pattern::default(),
-                    dashed_idents: false,
-                    grid: false,
-                    container: false,
-                    ..Default::default()
-                }),
+    fn css_modules_parser_options() -> ParserOptions<'static, 'static> {
+        ParserOptions {
+            css_modules: Some(lightningcss::css_modules::Config {
+                pattern: Pattern::default(),
+                dashed_idents: false,
+                grid: false,
+                container: false,
                 ..Default::default()
-            },
-        )
-        .unwrap();
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn lint_lightningcss(code: &str) -> Vec<CssError> {
+        let mut ss = StyleSheet::parse(code, css_modules_parser_options()).unwrap();
 
         let mut validator = CssValidator { errors: Vec::new() };
         ss.visit(&mut validator).unwrap();
@@ -882,4 +987,77 @@ mod tests {
             }",
         );
     }
+
+    #[test]
+    fn unused_classes() {
+        let export = |name: &str, composes: Vec<CssModuleReference>| CssModuleExport {
+            name: name.to_string(),
+            composes,
+            is_referenced: false,
+        };
+        let exports = FxIndexMap::from_iter([
+            (
+                "a".to_string(),
+                export(
+                    "hash_a",
+                    vec![CssModuleReference::Local {
+                        name: "hash_b".to_string(),
+                    }],
+                ),
+            ),
+            ("b".to_string(), export("hash_b", vec![])),
+            ("c".to_string(), export("hash_c", vec![])),
+        ]);
+
+        assert_eq!(
+            unused_module_classes(
+                &exports,
+                &ModuleExportUsageInfo::Exports([rcstr!("a")].into_iter().collect())
+            ),
+            vec![rcstr!("c")]
+        );
+        assert_eq!(
+            unused_module_classes(
+                &exports,
+                &ModuleExportUsageInfo::Exports([rcstr!("default")].into_iter().collect())
+            ),
+            vec![]
+        );
+        assert_eq!(
+            unused_module_classes(&exports, &ModuleExportUsageInfo::Evaluation),
+            vec![rcstr!("a"), rcstr!("b"), rcstr!("c")]
+        );
+        assert_eq!(
+            unused_module_classes(&exports, &ModuleExportUsageInfo::All),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn remove_unused_classes() {
+        let mut ss = StyleSheet::parse(
+            ".kept { --marker: first }
+            .gone { --marker: second }
+            .kept, .gone .child { --marker: third }
+            @media (min-width: 100px) {
+                .gone:hover { --marker: fourth }
+            }
+            :global(.gone) { --marker: fifth }",
+            css_modules_parser_options(),
+        )
+        .unwrap();
+
+        ss.visit(&mut UnusedClassesRemover {
+            unused_classes: ["gone"].into_iter().collect(),
+        })
+        .unwrap();
+
+        let code = ss.to_css(PrinterOptions::default()).unwrap().code;
+        assert!(code.contains("first"), "{code}");
+        assert!(!code.contains("second"), "{code}");
+        assert!(code.contains("third"), "{code}");
+        assert!(!code.contains("child"), "{code}");
+        assert!(!code.contains("fourth"), "{code}");
+        assert!(code.contains("fifth"), "{code}");
+    }
 }
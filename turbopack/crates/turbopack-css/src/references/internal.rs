@@ -2,11 +2,14 @@ use anyhow::Result;
 use turbo_rcstr::RcStr;
 use turbo_tasks::{ResolvedVc, ValueToString, Vc};
 use turbopack_core::{
-    chunk::ChunkableModuleReference, module::Module, reference::ModuleReference,
-    resolve::ModuleResolveResult,
+    chunk::ChunkableModuleReference,
+    module::Module,
+    reference::ModuleReference,
+    resolve::{ExportUsage, ModuleResolveResult},
 };
 
-/// A reference to an internal CSS asset.
+/// A reference to an internal CSS asset, i.e. the stylesheet of a CSS Module. The classes of the
+/// stylesheet are used as far as the CSS Module's exports are.
 #[turbo_tasks::value]
 #[derive(Hash, Debug)]
 pub struct InternalCssAssetReference {
@@ -41,4 +44,9 @@ impl ValueToString for InternalCssAssetReference {
 }
 
 #[turbo_tasks::value_impl]
-impl ChunkableModuleReference for InternalCssAssetReference {}
+impl ChunkableModuleReference for InternalCssAssetReference {
+    #[turbo_tasks::function]
+    fn export_usage(&self) -> Vc<ExportUsage> {
+        ExportUsage::reexport()
+    }
+}
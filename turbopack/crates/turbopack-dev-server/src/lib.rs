@@ -33,7 +33,7 @@ use socket2::{Domain, Protocol, Socket, Type};
 use tokio::task::JoinHandle;
 use tracing::{Instrument, Level, Span, event, info_span};
 use turbo_tasks::{
-    NonLocalValue, OperationVc, TurboTasksApi, Vc, apply_effects, run_once_with_reason,
+    NonLocalValue, OperationVc, ResolvedVc, TurboTasksApi, Vc, apply_effects, run_once_with_reason,
     trace::TraceRawVcs, util::FormatDuration,
 };
 use turbopack_core::{
@@ -44,7 +44,10 @@ use turbopack_core::{
 use self::{source::ContentSource, update::UpdateServer};
 use crate::{
     invalidation::{ServerRequest, ServerRequestSideEffects},
-    source::ContentSourceSideEffect,
+    source::{
+        ContentSourceSideEffect,
+        proxy::{find_websocket_proxy, proxy_websocket},
+    },
 };
 
 pub trait SourceProvider: Send + Clone + 'static {
@@ -122,11 +125,15 @@ impl DevServerBuilder {
         let ongoing_side_effects = Arc::new(Mutex::new(VecDeque::<
             Arc<tokio::sync::Mutex<Option<JoinHandle<Result<()>>>>>,
         >::with_capacity(16)));
+        // The source resolved by the last HTTP request. WebSocket upgrades are looked up in it
+        // instead of waiting for the source to be resolved again.
+        let resolved_source = Arc::new(Mutex::new(None::<ResolvedVc<Box<dyn ContentSource>>>));
         let make_svc = make_service_fn(move |_| {
             let tt = turbo_tasks.clone();
             let source_provider = source_provider.clone();
             let get_issue_reporter = get_issue_reporter.clone();
             let ongoing_side_effects = ongoing_side_effects.clone();
+            let resolved_source = resolved_source.clone();
             async move {
                 let handler = move |request: Request<hyper::Body>| {
                     let request_span = info_span!(parent: None, "request", name = ?request.uri());
@@ -135,6 +142,7 @@ impl DevServerBuilder {
                     let get_issue_reporter = get_issue_reporter.clone();
                     let ongoing_side_effects = ongoing_side_effects.clone();
                     let source_provider = source_provider.clone();
+                    let resolved_source = resolved_source.clone();
                     let future = async move {
                         event!(parent: Span::current(), Level::DEBUG, "request start");
                         // Wait until all ongoing side effects are completed
@@ -178,8 +186,7 @@ impl DevServerBuilder {
                             let issue_reporter = get_issue_reporter();
 
                             if hyper_tungstenite::is_upgrade_request(&request) {
-                                let uri = request.uri();
-                                let path = uri.path();
+                                let path = request.uri().path().to_string();
 
                                 if path == "/turbopack-hmr" {
                                     let (response, websocket) =
@@ -190,6 +197,22 @@ impl DevServerBuilder {
                                     return Ok(response);
                                 }
 
+                                let last_resolved_source = *resolved_source.lock();
+                                let source = match last_resolved_source {
+                                    Some(source) => source,
+                                    None => {
+                                        let source = source_provider
+                                            .get_source()
+                                            .resolve_strongly_consistent()
+                                            .await?;
+                                        *resolved_source.lock() = Some(source);
+                                        source
+                                    }
+                                };
+                                if let Some(proxy) = find_websocket_proxy(source, &path).await? {
+                                    return proxy_websocket(request, &proxy).await;
+                                }
+
                                 println!("[404] {path} (WebSocket)");
                                 if path == "/_next/webpack-hmr" {
                                     // Special-case requests to webpack-hmr as these are made by
@@ -214,7 +237,8 @@ impl DevServerBuilder {
                             let path = uri.path().to_string();
                             let source_op = source_provider.get_source();
                             // HACK: Resolve `source` now so that we can get any issues on it
-                            let _ = source_op.resolve_strongly_consistent().await?;
+                            *resolved_source.lock() =
+                                Some(source_op.resolve_strongly_consistent().await?);
                             apply_effects(source_op).await?;
                             handle_issues(
                                 source_op,
@@ -4,12 +4,14 @@ pub mod conditional;
 pub mod headers;
 pub mod issue_context;
 pub mod lazy_instantiated;
+pub mod proxy;
 pub mod query;
 pub mod request;
 pub(crate) mod resolve;
 pub mod route_tree;
 pub mod router;
 pub mod static_assets;
+pub mod static_mock;
 pub mod wrapping_source;
 
 use std::collections::BTreeSet;
@@ -0,0 +1,430 @@
+use std::sync::LazyLock;
+
+use anyhow::{Context, Result, anyhow};
+use futures::{StreamExt, TryStreamExt};
+use hyper::{
+    Client, HeaderMap, Request, Response, StatusCode, Uri,
+    client::HttpConnector,
+    header::{HOST, HeaderName, HeaderValue, LOCATION},
+};
+use serde::{Deserialize, Serialize};
+use turbo_rcstr::{RcStr, rcstr};
+use turbo_tasks::{
+    NonLocalValue, ReadRef, ResolvedVc, TaskInput, Vc, trace::TraceRawVcs, util::SharedError,
+};
+use turbo_tasks_bytes::Bytes;
+use turbopack_core::introspect::Introspectable;
+
+use super::{
+    Body, ContentSource, ContentSourceContent, ContentSourceData, ContentSourceDataVary,
+    GetContentSourceContent, ProxyResult,
+    route_tree::{BaseSegment, RouteTree, RouteTrees, RouteType},
+};
+
+/// Headers that only apply to a single connection and must not be forwarded.
+const HOP_BY_HOP_HEADERS: [&str; 8] = [
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+];
+
+/// The client used for all forwarded requests, so connections to the origins are pooled.
+static CLIENT: LazyLock<Client<HttpConnector>> = LazyLock::new(Client::new);
+
+/// How requests are forwarded by a [HttpProxyContentSource].
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    Serialize,
+    Deserialize,
+    TraceRawVcs,
+    NonLocalValue,
+    TaskInput,
+)]
+pub struct HttpProxyOptions {
+    /// The origin requests are forwarded to, e.g. `http://localhost:4000`. Only plain `http`
+    /// origins are supported.
+    pub origin: RcStr,
+    /// Removes the matched prefix from the path of the forwarded request.
+    pub strip_prefix: bool,
+    /// Sets the `Host` header to the host of the origin instead of passing on the host of the dev
+    /// server.
+    pub change_origin: bool,
+    /// Headers that are set on the forwarded request. An empty value removes the header.
+    pub request_headers: Vec<(RcStr, RcStr)>,
+    /// Headers that are set on the response. An empty value removes the header.
+    pub response_headers: Vec<(RcStr, RcStr)>,
+}
+
+/// Forwards all requests below a path prefix to another HTTP origin. WebSocket upgrades are
+/// forwarded by the dev server itself, see [find_websocket_proxy].
+#[turbo_tasks::value(shared)]
+pub struct HttpProxyContentSource {
+    pub prefix: RcStr,
+    pub options: HttpProxyOptions,
+}
+
+#[turbo_tasks::value_impl]
+impl HttpProxyContentSource {
+    #[turbo_tasks::function]
+    pub fn new(prefix: RcStr, options: HttpProxyOptions) -> Vc<Self> {
+        if cfg!(debug_assertions) {
+            debug_assert!(prefix.is_empty() || prefix.ends_with('/'));
+            debug_assert!(!prefix.starts_with('/'));
+        }
+        HttpProxyContentSource { prefix, options }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for HttpProxyContentSource {
+    #[turbo_tasks::function]
+    async fn get_routes(self: Vc<Self>) -> Result<Vc<RouteTree>> {
+        let base = BaseSegment::from_static_pathname(&self.await?.prefix).collect::<Vec<_>>();
+        Ok(Vc::<RouteTrees>::cell(vec![
+            RouteTree::new_route(base.clone(), RouteType::Exact, Vc::upcast(self))
+                .to_resolved()
+                .await?,
+            RouteTree::new_route(base, RouteType::CatchAll, Vc::upcast(self))
+                .to_resolved()
+                .await?,
+        ])
+        .merge())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl GetContentSourceContent for HttpProxyContentSource {
+    #[turbo_tasks::function]
+    fn vary(&self) -> Vc<ContentSourceDataVary> {
+        ContentSourceDataVary {
+            method: true,
+            url: true,
+            raw_headers: true,
+            body: true,
+            // Responses of the origin can't be cached
+            cache_buster: true,
+            ..Default::default()
+        }
+        .cell()
+    }
+
+    #[turbo_tasks::function]
+    fn get(
+        self: ResolvedVc<Self>,
+        _path: RcStr,
+        data: ContentSourceData,
+    ) -> Vc<ContentSourceContent> {
+        ContentSourceContent::HttpProxy(proxy_request_operation(self, data)).cell()
+    }
+}
+
+#[turbo_tasks::function(operation)]
+async fn proxy_request_operation(
+    source: ResolvedVc<HttpProxyContentSource>,
+    data: ContentSourceData,
+) -> Result<Vc<ProxyResult>> {
+    let source = source.await?;
+    let ContentSourceData {
+        method: Some(method),
+        url: Some(url),
+        raw_headers: Some(raw_headers),
+        body: Some(body),
+        ..
+    } = &data
+    else {
+        anyhow::bail!("Missing request data")
+    };
+
+    let uri = target_uri(&source.options, &source.prefix, url)?;
+    let mut headers = HeaderMap::new();
+    for (name, value) in raw_headers {
+        headers.append(
+            HeaderName::try_from(name.as_str())?,
+            HeaderValue::try_from(value.as_str())?,
+        );
+    }
+    rewrite_request_headers(&source.options, &mut headers, &uri, false)?;
+
+    let mut request_body = Vec::new();
+    let mut read = body.await?.read();
+    while let Some(chunk) = read.try_next().await? {
+        request_body.extend_from_slice(&chunk);
+    }
+    let mut request = Request::builder()
+        .method(method.as_str())
+        .uri(uri.clone())
+        .body(hyper::Body::from(request_body))?;
+    *request.headers_mut() = headers;
+
+    let response = match CLIENT.request(request).await {
+        Ok(response) => response,
+        Err(err) => {
+            return Ok(ProxyResult {
+                status: StatusCode::BAD_GATEWAY.as_u16(),
+                headers: vec![(rcstr!("content-type"), rcstr!("text/plain; charset=utf-8"))],
+                body: format!("Failed to proxy the request to {uri}: {err}").into(),
+            }
+            .cell());
+        }
+    };
+    let (mut parts, body) = response.into_parts();
+    rewrite_response_headers(&source.options, &source.prefix, &mut parts.headers, false)?;
+
+    Ok(ProxyResult {
+        status: parts.status.as_u16(),
+        headers: parts
+            .headers
+            .iter()
+            .filter_map(|(name, value)| Some((name.as_str().into(), value.to_str().ok()?.into())))
+            .collect(),
+        body: Body::from_stream(body.map(|chunk| {
+            chunk
+                .map(Bytes::from)
+                .map_err(|err| SharedError::new(anyhow!(err)))
+        })),
+    }
+    .cell())
+}
+
+/// Returns the URI on the origin for a request to `url`, which is the path and query of the
+/// request to the dev server.
+fn target_uri(options: &HttpProxyOptions, prefix: &str, url: &str) -> Result<Uri> {
+    let prefix = prefix.trim_end_matches('/');
+    let path_and_query = if options.strip_prefix && !prefix.is_empty() {
+        match url.strip_prefix('/').unwrap_or(url).strip_prefix(prefix) {
+            Some(rest) if rest.is_empty() || rest.starts_with(['/', '?']) => {
+                format!("/{}", rest.strip_prefix('/').unwrap_or(rest))
+            }
+            _ => url.to_string(),
+        }
+    } else {
+        url.to_string()
+    };
+    format!("{}{path_and_query}", options.origin.trim_end_matches('/'))
+        .parse()
+        .with_context(|| format!("Invalid proxy origin {}", options.origin))
+}
+
+fn apply_header_overwrites(headers: &mut HeaderMap, overwrites: &[(RcStr, RcStr)]) -> Result<()> {
+    for (name, value) in overwrites {
+        let name = HeaderName::try_from(name.as_str())?;
+        if value.is_empty() {
+            headers.remove(name);
+        } else {
+            headers.insert(name, HeaderValue::try_from(value.as_str())?);
+        }
+    }
+    Ok(())
+}
+
+/// Prepares the headers of a request to the dev server for forwarding to `uri`.
+fn rewrite_request_headers(
+    options: &HttpProxyOptions,
+    headers: &mut HeaderMap,
+    uri: &Uri,
+    is_upgrade: bool,
+) -> Result<()> {
+    if !is_upgrade {
+        for name in HOP_BY_HOP_HEADERS {
+            headers.remove(name);
+        }
+    }
+    if let Some(host) = headers.get(HOST).cloned() {
+        headers.entry("x-forwarded-host").or_insert(host);
+    }
+    headers
+        .entry("x-forwarded-proto")
+        .or_insert(HeaderValue::from_static("http"));
+    if options.change_origin
+        && let Some(authority) = uri.authority()
+    {
+        headers.insert(HOST, HeaderValue::try_from(authority.as_str())?);
+    }
+    apply_header_overwrites(headers, &options.request_headers)
+}
+
+/// Prepares the headers of a response of the origin for sending it to the client. Redirects to
+/// the origin are rewritten to point to the dev server.
+fn rewrite_response_headers(
+    options: &HttpProxyOptions,
+    prefix: &str,
+    headers: &mut HeaderMap,
+    is_upgrade: bool,
+) -> Result<()> {
+    if !is_upgrade {
+        for name in HOP_BY_HOP_HEADERS {
+            headers.remove(name);
+        }
+    }
+    if let Some(location) = headers.get(LOCATION).and_then(|l| l.to_str().ok()) {
+        let origin = options.origin.trim_end_matches('/');
+        let location = match location.strip_prefix(origin) {
+            Some(path) if path.is_empty() || path.starts_with(['/', '?']) => path,
+            _ => location,
+        };
+        let location = if options.strip_prefix && location.starts_with('/') {
+            format!("/{}{location}", prefix.trim_end_matches('/'))
+        } else if location.is_empty() {
+            "/".to_string()
+        } else {
+            location.to_string()
+        };
+        headers.insert(LOCATION, HeaderValue::try_from(location)?);
+    }
+    apply_header_overwrites(headers, &options.response_headers)
+}
+
+/// Finds the [HttpProxyContentSource] in `source` or its children that handles `path`, which is
+/// the path of a WebSocket upgrade request to the dev server.
+pub async fn find_websocket_proxy(
+    source: ResolvedVc<Box<dyn ContentSource>>,
+    path: &str,
+) -> Result<Option<ReadRef<HttpProxyContentSource>>> {
+    let path = path.strip_prefix('/').unwrap_or(path);
+    let mut queue = vec![source];
+    while let Some(source) = queue.pop() {
+        if let Some(proxy) = ResolvedVc::try_downcast_type::<HttpProxyContentSource>(source) {
+            let proxy = proxy.await?;
+            if path.starts_with(proxy.prefix.as_str())
+                || proxy.prefix.strip_suffix('/') == Some(path)
+            {
+                return Ok(Some(proxy));
+            }
+        }
+        queue.extend(source.get_children().await?.iter().rev().copied());
+    }
+    Ok(None)
+}
+
+/// Forwards a WebSocket upgrade request to the origin of `proxy` and connects both sides once
+/// the origin accepted the upgrade.
+pub async fn proxy_websocket(
+    mut request: Request<hyper::Body>,
+    proxy: &HttpProxyContentSource,
+) -> Result<Response<hyper::Body>> {
+    let url = request
+        .uri()
+        .path_and_query()
+        .map_or("/", |path_and_query| path_and_query.as_str());
+    let uri = target_uri(&proxy.options, &proxy.prefix, url)?;
+    let mut headers = request.headers().clone();
+    rewrite_request_headers(&proxy.options, &mut headers, &uri, true)?;
+    let mut upstream_request = Request::builder()
+        .method(request.method())
+        .uri(uri)
+        .body(hyper::Body::empty())?;
+    *upstream_request.headers_mut() = headers;
+
+    let mut upstream_response = CLIENT.request(upstream_request).await?;
+    let is_upgrade = upstream_response.status() == StatusCode::SWITCHING_PROTOCOLS;
+    if is_upgrade {
+        let upstream_upgrade = hyper::upgrade::on(&mut upstream_response);
+        let client_upgrade = hyper::upgrade::on(&mut request);
+        tokio::spawn(async move {
+            let (mut upstream, mut client) = futures::try_join!(upstream_upgrade, client_upgrade)?;
+            tokio::io::copy_bidirectional(&mut client, &mut upstream).await?;
+            anyhow::Ok(())
+        });
+    }
+    let (mut parts, body) = upstream_response.into_parts();
+    rewrite_response_headers(
+        &proxy.options,
+        &proxy.prefix,
+        &mut parts.headers,
+        is_upgrade,
+    )?;
+    Ok(Response::from_parts(parts, body))
+}
+
+#[turbo_tasks::value_impl]
+impl Introspectable for HttpProxyContentSource {
+    #[turbo_tasks::function]
+    fn ty(&self) -> Vc<RcStr> {
+        Vc::cell(rcstr!("http proxy content source"))
+    }
+
+    #[turbo_tasks::function]
+    fn details(&self) -> Vc<RcStr> {
+        Vc::cell(format!("/{} -> {}", self.prefix, self.options.origin).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(strip_prefix: bool) -> HttpProxyOptions {
+        HttpProxyOptions {
+            origin: rcstr!("http://localhost:4000/"),
+            strip_prefix,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_target_uri() {
+        let uri = |strip_prefix, url| target_uri(&options(strip_prefix), "api/", url).unwrap();
+        assert_eq!(
+            uri(false, "/api/users?id=1"),
+            "http://localhost:4000/api/users?id=1"
+        );
+        assert_eq!(
+            uri(true, "/api/users?id=1"),
+            "http://localhost:4000/users?id=1"
+        );
+        assert_eq!(uri(true, "/api"), "http://localhost:4000/");
+        assert_eq!(uri(true, "/api?id=1"), "http://localhost:4000/?id=1");
+        assert_eq!(uri(true, "/apis"), "http://localhost:4000/apis");
+    }
+
+    #[test]
+    fn test_rewrite_response_headers() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            LOCATION,
+            HeaderValue::from_static("http://localhost:4000/login"),
+        );
+        headers.insert("connection", HeaderValue::from_static("close"));
+        rewrite_response_headers(&options(true), "api/", &mut headers, false).unwrap();
+        assert_eq!(headers.get(LOCATION).unwrap(), "/api/login");
+        assert!(headers.get("connection").is_none());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(LOCATION, HeaderValue::from_static("https://example.com/"));
+        rewrite_response_headers(&options(true), "api/", &mut headers, false).unwrap();
+        assert_eq!(headers.get(LOCATION).unwrap(), "https://example.com/");
+    }
+
+    #[test]
+    fn test_rewrite_request_headers() {
+        let options = HttpProxyOptions {
+            change_origin: true,
+            request_headers: vec![
+                (rcstr!("x-api-key"), rcstr!("secret")),
+                (rcstr!("cookie"), rcstr!("")),
+            ],
+            ..options(false)
+        };
+        let mut headers = HeaderMap::new();
+        headers.insert(HOST, HeaderValue::from_static("localhost:3000"));
+        headers.insert("cookie", HeaderValue::from_static("a=b"));
+        headers.insert("transfer-encoding", HeaderValue::from_static("chunked"));
+        let uri = target_uri(&options, "", "/").unwrap();
+        rewrite_request_headers(&options, &mut headers, &uri, false).unwrap();
+        assert_eq!(headers.get(HOST).unwrap(), "localhost:4000");
+        assert_eq!(headers.get("x-forwarded-host").unwrap(), "localhost:3000");
+        assert_eq!(headers.get("x-api-key").unwrap(), "secret");
+        assert!(headers.get("cookie").is_none());
+        assert!(headers.get("transfer-encoding").is_none());
+    }
+}
@@ -0,0 +1,187 @@
+use anyhow::Result;
+use turbo_rcstr::{RcStr, rcstr};
+use turbo_tasks::Vc;
+use turbo_tasks_fs::{FileSystemEntryType, FileSystemPath};
+use turbopack_core::{
+    asset::Asset, file_source::FileSource, introspect::Introspectable, version::VersionedContentExt,
+};
+
+use super::{
+    ContentSource, ContentSourceContent, ContentSourceData, ContentSourceDataVary,
+    GetContentSourceContent, HeaderList,
+    route_tree::{BaseSegment, RouteTree, RouteTrees, RouteType},
+};
+
+/// Serves canned JSON responses from a directory of fixtures, to stand in for an API during
+/// development.
+///
+/// A request to `<prefix><path>` is answered with the first existing file of
+/// `<path>.<method>.json`, `<path>.json`, `<path>/index.<method>.json` and `<path>/index.json`
+/// in `dir`, where `<method>` is the lowercase HTTP method. Fixtures are watched like any other
+/// file, so changes are served without a restart.
+#[turbo_tasks::value(shared)]
+pub struct StaticMockContentSource {
+    pub prefix: RcStr,
+    pub dir: FileSystemPath,
+}
+
+#[turbo_tasks::value_impl]
+impl StaticMockContentSource {
+    #[turbo_tasks::function]
+    pub fn new(prefix: RcStr, dir: FileSystemPath) -> Vc<Self> {
+        if cfg!(debug_assertions) {
+            debug_assert!(prefix.is_empty() || prefix.ends_with('/'));
+            debug_assert!(!prefix.starts_with('/'));
+        }
+        StaticMockContentSource { prefix, dir }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ContentSource for StaticMockContentSource {
+    #[turbo_tasks::function]
+    async fn get_routes(self: Vc<Self>) -> Result<Vc<RouteTree>> {
+        let base = BaseSegment::from_static_pathname(&self.await?.prefix).collect::<Vec<_>>();
+        Ok(Vc::<RouteTrees>::cell(vec![
+            RouteTree::new_route(base.clone(), RouteType::Exact, Vc::upcast(self))
+                .to_resolved()
+                .await?,
+            RouteTree::new_route(base, RouteType::CatchAll, Vc::upcast(self))
+                .to_resolved()
+                .await?,
+        ])
+        .merge())
+    }
+}
+
+/// Returns the fixture file names for a request to `path` in the order they are looked up.
+fn fixture_candidates(path: &str, method: &str) -> Vec<String> {
+    let method = method.to_ascii_lowercase();
+    let path = path.trim_end_matches('/');
+    if path.is_empty() {
+        vec![format!("index.{method}.json"), "index.json".to_string()]
+    } else {
+        vec![
+            format!("{path}.{method}.json"),
+            format!("{path}.json"),
+            format!("{path}/index.{method}.json"),
+            format!("{path}/index.json"),
+        ]
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl GetContentSourceContent for StaticMockContentSource {
+    #[turbo_tasks::function]
+    fn vary(&self) -> Vc<ContentSourceDataVary> {
+        ContentSourceDataVary {
+            method: true,
+            ..Default::default()
+        }
+        .cell()
+    }
+
+    #[turbo_tasks::function]
+    async fn get(&self, path: RcStr, data: ContentSourceData) -> Result<Vc<ContentSourceContent>> {
+        let Some(method) = &data.method else {
+            anyhow::bail!("Missing request data")
+        };
+        let prefix = self.prefix.trim_end_matches('/');
+        let Some(path) = path.strip_prefix(prefix) else {
+            return Ok(ContentSourceContent::not_found());
+        };
+        for name in fixture_candidates(path.trim_start_matches('/'), method) {
+            // The path is URL-decoded, so it can contain `..` segments. Only serve files inside the
+            // fixture directory.
+            let Ok(fixture) = self.dir.join(&name) else {
+                continue;
+            };
+            if !fixture.is_inside_ref(&self.dir) {
+                continue;
+            }
+            if *fixture.get_type().await? != FileSystemEntryType::File {
+                continue;
+            }
+            let content = Vc::upcast::<Box<dyn Asset>>(FileSource::new(fixture)).content();
+            return Ok(ContentSourceContent::static_with_headers(
+                content.versioned(),
+                200,
+                HeaderList::new(vec![(
+                    rcstr!("content-type"),
+                    rcstr!("application/json; charset=utf-8"),
+                )]),
+            ));
+        }
+        Ok(ContentSourceContent::not_found())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Introspectable for StaticMockContentSource {
+    #[turbo_tasks::function]
+    fn ty(&self) -> Vc<RcStr> {
+        Vc::cell(rcstr!("static mock content source"))
+    }
+
+    #[turbo_tasks::function]
+    fn details(&self) -> Vc<RcStr> {
+        Vc::cell(format!("/{} -> {}", self.prefix, self.dir).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use turbo_tasks::TurboTasks;
+    use turbo_tasks_backend::{BackendOptions, TurboTasksBackend, noop_backing_storage};
+    use turbo_tasks_fs::{DiskFileSystem, FileSystem};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_path_traversal() {
+        crate::register();
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir(dir.path().join("fixtures")).unwrap();
+        std::fs::write(dir.path().join("fixtures/users.json"), "[]").unwrap();
+        std::fs::write(dir.path().join("secrets.json"), "{}").unwrap();
+        let root: RcStr = std::fs::canonicalize(dir.path())
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .into();
+
+        let tt = TurboTasks::new(TurboTasksBackend::new(
+            BackendOptions::default(),
+            noop_backing_storage(),
+        ));
+        tt.run_once(async move {
+            let fs = DiskFileSystem::new(rcstr!("project"), root);
+            let source =
+                StaticMockContentSource::new(rcstr!("api/"), fs.root().await?.join("fixtures")?);
+            let get = |path: &'static str| {
+                source.get(
+                    path.into(),
+                    ContentSourceData {
+                        method: Some(rcstr!("GET")),
+                        ..Default::default()
+                    },
+                )
+            };
+            assert!(matches!(
+                *get("api/users").await?,
+                ContentSourceContent::Static(_)
+            ));
+            assert!(matches!(
+                *get("api/../secrets").await?,
+                ContentSourceContent::NotFound
+            ));
+            assert!(matches!(
+                *get("api/users/../../secrets").await?,
+                ContentSourceContent::NotFound
+            ));
+            Ok(())
+        })
+        .await
+        .unwrap();
+    }
+}
@@ -39,7 +39,8 @@ static ANNOTATION_TRANSITION: Lazy<Atom> =
 static ANNOTATION_CHUNKING_TYPE: Lazy<Atom> =
     Lazy::new(|| crate::annotations::ANNOTATION_CHUNKING_TYPE.into());
 
-/// Changes the type of the resolved module (only "json" is supported currently)
+/// Changes the type of the resolved module ("json", "css", "text" and "bytes" are handled natively,
+/// other types are left to module rules)
 static ATTRIBUTE_MODULE_TYPE: Lazy<Atom> = Lazy::new(|| atom!("type"));
 
 impl ImportAnnotations {
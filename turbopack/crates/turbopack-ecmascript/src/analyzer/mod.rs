@@ -35,7 +35,7 @@ use self::imports::ImportAnnotations;
 pub(crate) use self::imports::ImportMap;
 use crate::{
     analyzer::graph::EvalContext, references::require_context::RequireContextMap,
-    utils::StringifyJs,
+    utils::StringifyJs, well_known_packages::WellKnownPackage,
 };
 
 pub mod builtin;
@@ -1806,6 +1806,10 @@ impl JsValue {
                         "import.meta",
                         "The import.meta object"
                     ),
+                    WellKnownObjectKind::Package(package) => (
+                        &*package.name,
+                        "A package from the configured well known packages"
+                    ),
                 };
                 if depth > 0 {
                     let i = hints.len();
@@ -1842,6 +1846,7 @@ impl JsValue {
                     WellKnownFunctionKind::RequireContext => ("require.context".to_string(), "The require.context method from webpack"),
                     WellKnownFunctionKind::RequireContextRequire(..) => ("require.context(...)".to_string(), "The require.context(...) method from webpack: https://webpack.js.org/api/module-methods/#requirecontext"),
                     WellKnownFunctionKind::RequireContextRequireKeys(..) => ("require.context(...).keys".to_string(), "The require.context(...).keys method from webpack: https://webpack.js.org/guides/dependency-management/#requirecontext"),
+                    WellKnownFunctionKind::ImportMetaGlob => ("import.meta.glob".to_string(), "The import.meta.glob method from Vite: https://vite.dev/guide/features.html#glob-import"),
                     WellKnownFunctionKind::RequireContextRequireResolve(..) => ("require.context(...).resolve".to_string(), "The require.context(...).resolve method from webpack: https://webpack.js.org/guides/dependency-management/#requirecontext"),
                     WellKnownFunctionKind::Define => ("define".to_string(), "The define method from AMD"),
                     WellKnownFunctionKind::FsReadMethod(name) => (
@@ -1904,14 +1909,14 @@ impl JsValue {
                       "SetRootDir".to_string(),
                       "require('strong-globalize').SetRootDir(__dirname)  https://github.com/strongloop/strong-globalize"
                     ),
-                    WellKnownFunctionKind::NodeResolveFrom => (
-                      "resolveFrom".to_string(),
-                      "require('resolve-from')(__dirname, 'node-gyp/bin/node-gyp')  https://github.com/sindresorhus/resolve-from"
-                    ),
                     WellKnownFunctionKind::NodeProtobufLoad => (
                       "load/loadSync".to_string(),
                       "require('@grpc/proto-loader').load(filepath, { includeDirs: [root] }) https://github.com/grpc/grpc-node"
                     ),
+                    WellKnownFunctionKind::PackageExport(package, export) => (
+                      package.describe_export(export.as_deref()),
+                      "An export of a well known package"
+                    ),
                     WellKnownFunctionKind::WorkerConstructor => (
                       "Worker".to_string(),
                       "The standard Worker constructor: https://developer.mozilla.org/en-US/docs/Web/API/Worker/Worker"
@@ -3730,6 +3735,9 @@ pub enum WellKnownObjectKind {
     NodeBuffer,
     RequireCache,
     ImportMeta,
+    /// A package described by [crate::well_known_packages::WellKnownPackages] which is not
+    /// callable itself.
+    Package(Box<WellKnownPackage>),
 }
 
 impl WellKnownObjectKind {
@@ -3847,6 +3855,7 @@ pub enum WellKnownFunctionKind {
     RequireContextRequire(RequireContextValue),
     RequireContextRequireKeys(RequireContextValue),
     RequireContextRequireResolve(RequireContextValue),
+    ImportMetaGlob,
     Define,
     FsReadMethod(Atom),
     PathToFileUrl,
@@ -3863,8 +3872,10 @@ pub enum WellKnownFunctionKind {
     NodeExpressSet,
     NodeStrongGlobalize,
     NodeStrongGlobalizeSetRootDir,
-    NodeResolveFrom,
     NodeProtobufLoad,
+    /// An export of a package described by [crate::well_known_packages::WellKnownPackages].
+    /// `None` is the package itself.
+    PackageExport(Box<WellKnownPackage>, Option<RcStr>),
     WorkerConstructor,
     URLConstructor,
 }
@@ -3909,6 +3920,7 @@ pub mod test_utils {
             parse_require_context,
         },
         utils::module_value_to_well_known_object,
+        well_known_packages::package_module_value,
     };
 
     pub async fn early_visitor(mut v: JsValue) -> Result<(JsValue, bool)> {
@@ -4027,7 +4039,9 @@ pub mod test_utils {
                 _ => v.into_unknown(true, "unknown global"),
             },
             JsValue::Module(ref mv) => {
-                if let Some(wko) = module_value_to_well_known_object(mv) {
+                if let Some(wko) =
+                    package_module_value(&[], mv).or_else(|| module_value_to_well_known_object(mv))
+                {
                     wko
                 } else {
                     return Ok((v, false));
@@ -4052,7 +4066,7 @@ mod tests {
     use parking_lot::Mutex;
     use rustc_hash::FxHashMap;
     use swc_core::{
-        common::{Mark, comments::SingleThreadedComments},
+        common::{FileName, Mark, comments::SingleThreadedComments},
         ecma::{
             ast::{EsVersion, Id},
             parser::parse_file_as_program,
@@ -4061,7 +4075,7 @@ mod tests {
         },
         testing::{NormalizedOutput, fixture, run_test},
     };
-    use turbo_tasks::{ResolvedVc, util::FormatDuration};
+    use turbo_tasks::{ResolvedVc, Vc, util::FormatDuration};
     use turbopack_core::{
         compile_time_info::CompileTimeInfo,
         environment::{Environment, ExecutionEnvironment, NodeJsEnvironment, NodeJsVersion},
@@ -4069,11 +4083,14 @@ mod tests {
     };
 
     use super::{
-        JsValue,
+        JsValue, WellKnownFunctionKind,
         graph::{ConditionalKind, Effect, EffectArg, EvalContext, VarGraph, create_graph},
         linker::link,
     };
-    use crate::analyzer::imports::ImportAttributes;
+    use crate::{
+        analyzer::imports::ImportAttributes,
+        well_known_packages::{WellKnownPackage, package_module_value},
+    };
 
     #[fixture("tests/analyzer/graph/**/input.js")]
     fn fixture(input: PathBuf) {
@@ -4461,26 +4478,7 @@ mod tests {
         var_cache: &Mutex<FxHashMap<Id, JsValue>>,
     ) -> (JsValue, u32) {
         turbo_tasks_testing::VcStorage::with(async {
-            let compile_time_info = CompileTimeInfo::builder(
-                Environment::new(ExecutionEnvironment::NodeJsLambda(
-                    NodeJsEnvironment {
-                        compile_target: CompileTarget {
-                            arch: Arch::X64,
-                            platform: Platform::Linux,
-                            endianness: Endianness::Little,
-                            libc: Libc::Glibc,
-                        }
-                        .resolved_cell(),
-                        node_version: NodeJsVersion::default().resolved_cell(),
-                        cwd: ResolvedVc::cell(None),
-                    }
-                    .resolved_cell(),
-                ))
-                .to_resolved()
-                .await?,
-            )
-            .cell()
-            .await?;
+            let compile_time_info = test_compile_time_info().await?;
             link(
                 var_graph,
                 val,
@@ -4500,4 +4498,141 @@ mod tests {
         .await
         .unwrap()
     }
+
+    #[test]
+    fn well_known_package_export() {
+        crate::register();
+        let packages: Vec<WellKnownPackage> = serde_json::from_str(
+            r#"[
+                {
+                    "name": "native-loader",
+                    "exports": [
+                        { "behavior": { "type": "loadFile", "argument": 0 } },
+                        {
+                            "export": "from",
+                            "behavior": { "type": "resolveRequest", "request": 1 }
+                        }
+                    ]
+                }
+            ]"#,
+        )
+        .unwrap();
+        let packages = &packages;
+
+        run_test(false, |cm, _handler| {
+            let fm = cm.new_source_file(
+                FileName::Anon.into(),
+                "const loader = require('native-loader');\nconst load = \
+                 require('node:native-loader');\nconst from = loader.from;\nconst unknown = \
+                 loader.unknown;\n",
+            );
+            let mut m = parse_file_as_program(
+                &fm,
+                Default::default(),
+                EsVersion::latest(),
+                None,
+                &mut vec![],
+            )
+            .unwrap();
+            let unresolved_mark = Mark::new();
+            let top_level_mark = Mark::new();
+            m.visit_mut_with(&mut resolver(unresolved_mark, top_level_mark, false));
+            let eval_context = EvalContext::new(
+                Some(&m),
+                unresolved_mark,
+                top_level_mark,
+                Default::default(),
+                None,
+                None,
+            );
+            let var_graph = create_graph(&m, &eval_context);
+
+            let resolve_var = |name: &str| {
+                let id = var_graph
+                    .values
+                    .keys()
+                    .find(|(var, _)| &**var == name)
+                    .unwrap()
+                    .clone();
+                let runtime = tokio::runtime::Builder::new_current_thread()
+                    .build()
+                    .unwrap();
+                runtime
+                    .block_on(turbo_tasks_testing::VcStorage::with(async {
+                        let compile_time_info = test_compile_time_info().await?;
+                        let (value, _) = link(
+                            &var_graph,
+                            JsValue::Variable(id),
+                            &super::test_utils::early_visitor,
+                            &(|val| {
+                                Box::pin(async move {
+                                    // Configured packages take precedence, like in the references
+                                    // analysis
+                                    if let JsValue::Module(module_value) = &val
+                                        && let Some(value) =
+                                            package_module_value(packages, module_value)
+                                    {
+                                        return Ok((value, true));
+                                    }
+                                    super::test_utils::visitor(
+                                        val,
+                                        compile_time_info,
+                                        ImportAttributes::empty_ref(),
+                                    )
+                                    .await
+                                })
+                            }),
+                            &Default::default(),
+                            &Default::default(),
+                        )
+                        .await?;
+                        anyhow::Ok(value)
+                    }))
+                    .unwrap()
+            };
+
+            for name in ["loader", "load"] {
+                assert!(matches!(
+                    resolve_var(name),
+                    JsValue::WellKnownFunction(WellKnownFunctionKind::PackageExport(package, None))
+                        if &*package.name == "native-loader"
+                ));
+            }
+            assert!(matches!(
+                resolve_var("from"),
+                JsValue::WellKnownFunction(WellKnownFunctionKind::PackageExport(_, Some(export)))
+                    if &*export == "from"
+            ));
+            // Exports that aren't described stay unknown to the analyzer
+            assert!(!matches!(
+                resolve_var("unknown"),
+                JsValue::WellKnownFunction(WellKnownFunctionKind::PackageExport(..))
+            ));
+            Ok(())
+        })
+        .unwrap();
+    }
+
+    async fn test_compile_time_info() -> anyhow::Result<Vc<CompileTimeInfo>> {
+        CompileTimeInfo::builder(
+            Environment::new(ExecutionEnvironment::NodeJsLambda(
+                NodeJsEnvironment {
+                    compile_target: CompileTarget {
+                        arch: Arch::X64,
+                        platform: Platform::Linux,
+                        endianness: Endianness::Little,
+                        libc: Libc::Glibc,
+                    }
+                    .resolved_cell(),
+                    node_version: NodeJsVersion::default().resolved_cell(),
+                    cwd: ResolvedVc::cell(None),
+                }
+                .resolved_cell(),
+            ))
+            .to_resolved()
+            .await?,
+        )
+        .cell()
+        .await
+    }
 }
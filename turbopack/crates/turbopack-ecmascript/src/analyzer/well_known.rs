@@ -10,7 +10,7 @@ use super::{
     ConstantValue, JsValue, JsValueUrlKind, ModuleValue, WellKnownFunctionKind,
     WellKnownObjectKind, imports::ImportAnnotations,
 };
-use crate::analyzer::RequireContextValue;
+use crate::{analyzer::RequireContextValue, well_known_packages::WellKnownPackage};
 
 pub async fn replace_well_known(
     value: JsValue,
@@ -105,11 +105,6 @@ pub async fn well_known_function_call(
         WellKnownFunctionKind::NodeExpress => {
             JsValue::WellKnownObject(WellKnownObjectKind::NodeExpressApp)
         }
-        // bypass
-        WellKnownFunctionKind::NodeResolveFrom => {
-            JsValue::WellKnownFunction(WellKnownFunctionKind::NodeResolveFrom)
-        }
-
         _ => JsValue::unknown(
             JsValue::call(Box::new(JsValue::WellKnownFunction(kind)), args),
             true,
@@ -553,12 +548,14 @@ pub fn well_known_function_member(kind: WellKnownFunctionKind, prop: JsValue) ->
         (WellKnownFunctionKind::NodeStrongGlobalize, Some("SetRootDir")) => {
             JsValue::WellKnownFunction(WellKnownFunctionKind::NodeStrongGlobalizeSetRootDir)
         }
-        (WellKnownFunctionKind::NodeResolveFrom, Some("silent")) => {
-            JsValue::WellKnownFunction(WellKnownFunctionKind::NodeResolveFrom)
-        }
         (WellKnownFunctionKind::Import, Some("meta")) => {
             JsValue::WellKnownObject(WellKnownObjectKind::ImportMeta)
         }
+        (WellKnownFunctionKind::PackageExport(package, None), Some(export))
+            if export == "default" || package.export(Some(export)).is_some() =>
+        {
+            package.to_js_value(Some(export))
+        }
         #[allow(unreachable_patterns)]
         (kind, _) => {
             return (
@@ -596,6 +593,10 @@ pub async fn well_known_object_member(
         WellKnownObjectKind::NodePreGyp => node_pre_gyp(prop),
         WellKnownObjectKind::NodeExpressApp => express(prop),
         WellKnownObjectKind::NodeProtobufLoader => protobuf_loader(prop),
+        WellKnownObjectKind::Package(package) => well_known_package(package, prop),
+        WellKnownObjectKind::ImportMeta if prop.as_str() == Some("glob") => {
+            JsValue::WellKnownFunction(WellKnownFunctionKind::ImportMetaGlob)
+        }
         #[allow(unreachable_patterns)]
         _ => {
             return Ok((
@@ -818,3 +819,21 @@ fn protobuf_loader(prop: JsValue) -> JsValue {
         ),
     }
 }
+
+fn well_known_package(package: Box<WellKnownPackage>, prop: JsValue) -> JsValue {
+    match prop.as_str() {
+        Some(export) if export == "default" || package.export(Some(export)).is_some() => {
+            package.to_js_value(Some(export))
+        }
+        _ => JsValue::unknown(
+            JsValue::member(
+                Box::new(JsValue::WellKnownObject(WellKnownObjectKind::Package(
+                    package,
+                ))),
+                Box::new(prop),
+            ),
+            true,
+            "unsupported property on a well known package",
+        ),
+    }
+}
@@ -36,7 +36,9 @@ pub use self::{
         EcmascriptChunkItem, EcmascriptChunkItemContent, EcmascriptChunkItemExt,
         EcmascriptChunkItemOptions, EcmascriptChunkItemWithAsyncInfo,
     },
-    placeable::{EcmascriptChunkPlaceable, EcmascriptExports},
+    placeable::{
+        EcmascriptChunkPlaceable, EcmascriptExports, EcmascriptValueKeys, OptionValueKeys,
+    },
 };
 
 #[turbo_tasks::value]
@@ -1,5 +1,5 @@
 use anyhow::Result;
-use turbo_rcstr::rcstr;
+use turbo_rcstr::{RcStr, rcstr};
 use turbo_tasks::{ResolvedVc, TryFlatJoinIterExt, Vc};
 use turbo_tasks_fs::{FileJsonContent, FileSystemPath, glob::Glob};
 use turbopack_core::{
@@ -40,6 +40,20 @@ pub trait EcmascriptChunkPlaceable: ChunkableModule + Module + Asset {
     }
 }
 
+/// A module whose [EcmascriptExports::Value] is an object with statically known keys, e.g. the
+/// class names of a CSS Module.
+///
+/// TypeScript modules are checked for accesses of keys that don't exist.
+#[turbo_tasks::value_trait]
+pub trait EcmascriptValueKeys {
+    /// The keys of the exported object, or `None` when they can't be determined.
+    #[turbo_tasks::function]
+    fn value_keys(self: Vc<Self>) -> Vc<OptionValueKeys>;
+}
+
+#[turbo_tasks::value(transparent)]
+pub struct OptionValueKeys(Option<Vec<RcStr>>);
+
 #[turbo_tasks::value]
 enum SideEffectsValue {
     None,
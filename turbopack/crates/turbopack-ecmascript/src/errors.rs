@@ -7,12 +7,14 @@ pub mod failed_to_analyse {
         pub const CHILD_PROCESS_SPAWN: &str = "TP1005";
         pub const PATH_METHOD: &str = "TP1006";
         pub const REQUIRE_CONTEXT: &str = "TP1007";
+        pub const IMPORT_META_GLOB: &str = "TP1008";
         pub const NODE_PRE_GYP_FIND: &str = "TP1100";
         pub const NODE_GYP_BUILD: &str = "TP1101";
         pub const NODE_BINDINGS: &str = "TP1102";
         pub const NODE_EXPRESS: &str = "TP1103";
         pub const NODE_RESOLVE_FROM: &str = "TP1104";
         pub const NODE_PROTOBUF_LOADER: &str = "TP1105";
+        pub const WELL_KNOWN_PACKAGE: &str = "TP1106";
         pub const AMD_DEFINE: &str = "TP1200";
         pub const NEW_URL_IMPORT_META: &str = "TP1201";
         pub const FREE_VAR_REFERENCE: &str = "TP1202";
@@ -32,6 +32,7 @@ pub mod tree_shake;
 pub mod typescript;
 pub mod utils;
 pub mod webpack;
+pub mod well_known_packages;
 pub mod worker_chunk;
 
 use std::{
@@ -126,6 +127,7 @@ use crate::{
     side_effect_optimization::reference::EcmascriptModulePartReference,
     swc_comments::{CowComments, ImmutableComments},
     transform::{remove_directives, remove_shebang},
+    well_known_packages::WellKnownPackages,
 };
 
 #[derive(
@@ -200,6 +202,8 @@ pub struct EcmascriptOptions {
     /// parsing fails. This is useful to keep the module graph structure intact when syntax errors
     /// are temporarily introduced.
     pub keep_last_successful_parse: bool,
+    /// Packages whose exports load files, in addition to the built-in ones.
+    pub well_known_packages: Option<ResolvedVc<WellKnownPackages>>,
 }
 
 #[turbo_tasks::value]
@@ -43,7 +43,7 @@ use crate::{
     code_gen::{CodeGeneration, CodeGenerationHoistedStmt},
     magic_identifier,
     references::{
-        esm::EsmExport,
+        esm::{EsmExport, value_keys::imported_value_keys},
         util::{request_to_string, throw_module_not_found_expr},
     },
     runtime_functions::{TURBOPACK_EXTERNAL_IMPORT, TURBOPACK_EXTERNAL_REQUIRE, TURBOPACK_IMPORT},
@@ -384,8 +384,8 @@ impl EsmAssetReference {
 impl ModuleReference for EsmAssetReference {
     #[turbo_tasks::function]
     async fn resolve_reference(&self) -> Result<Vc<ModuleResolveResult>> {
-        let ty = if matches!(self.annotations.module_type(), Some("json")) {
-            EcmaScriptModulesReferenceSubType::ImportWithType(ImportWithType::Json)
+        let ty = if let Some(module_type) = self.annotations.module_type() {
+            EcmaScriptModulesReferenceSubType::ImportWithType(ImportWithType::new(module_type))
         } else if let Some(part) = &self.export_name {
             EcmaScriptModulesReferenceSubType::ImportPart(part.clone())
         } else {
@@ -504,12 +504,23 @@ impl ChunkableModuleReference for EsmAssetReference {
     }
 
     #[turbo_tasks::function]
-    fn export_usage(&self) -> Vc<ExportUsage> {
-        match &self.export_name {
+    async fn export_usage(self: ResolvedVc<Self>) -> Result<Vc<ExportUsage>> {
+        let this = self.await?;
+        // Default imports of values from which only some keys are read, e.g. `styles.button`
+        // for `import styles from './button.module.css'`
+        let is_default_import = match &this.export_name {
+            None => true,
+            Some(ModulePart::Export(name)) => name == "default",
+            _ => false,
+        };
+        if is_default_import && let Some(keys) = &*imported_value_keys(*self).await? {
+            return Ok(ExportUsage::keys(keys.clone()));
+        }
+        Ok(match &this.export_name {
             Some(ModulePart::Export(export_name)) => ExportUsage::named(export_name.clone()),
             Some(ModulePart::Evaluation) => ExportUsage::evaluation(),
             _ => ExportUsage::all(),
-        }
+        })
     }
 }
 
@@ -6,6 +6,7 @@ pub(crate) mod meta;
 pub(crate) mod module_id;
 pub(crate) mod module_item;
 pub(crate) mod url;
+pub(crate) mod value_keys;
 
 pub use self::{
     base::EsmAssetReference,
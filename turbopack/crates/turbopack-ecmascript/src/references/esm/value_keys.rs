@@ -0,0 +1,160 @@
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_rcstr::{RcStr, rcstr};
+use turbo_tasks::{
+    FxIndexMap, NonLocalValue, ResolvedVc, TaskInput, ValueToString, Vc, trace::TraceRawVcs,
+};
+use turbo_tasks_fs::FileSystemPath;
+use turbopack_core::{
+    issue::{
+        Issue, IssueExt, IssueSeverity, IssueSource, IssueStage, OptionIssueSource,
+        OptionStyledString, StyledString,
+    },
+    reference::ModuleReference,
+};
+
+use crate::{
+    EcmascriptAnalyzable,
+    chunk::{EcmascriptChunkPlaceable, EcmascriptExports, EcmascriptValueKeys, OptionValueKeys},
+    references::esm::EsmAssetReference,
+};
+
+/// The keys read from the value of default imports, for the imports whose value isn't used in any
+/// other way.
+#[turbo_tasks::value(transparent)]
+pub struct ImportedValueKeys(FxIndexMap<ResolvedVc<EsmAssetReference>, Vec<RcStr>>);
+
+/// The keys that the importing module reads from the value of the referenced module, when the
+/// referenced module exports a single value (see [EcmascriptExports::Value]) and the importing
+/// module only reads constant keys from it.
+#[turbo_tasks::function]
+pub async fn imported_value_keys(
+    reference: ResolvedVc<EsmAssetReference>,
+) -> Result<Vc<OptionValueKeys>> {
+    let Some(origin) =
+        ResolvedVc::try_sidecast::<Box<dyn EcmascriptAnalyzable>>(reference.await?.origin)
+    else {
+        return Ok(Vc::cell(None));
+    };
+    let Some(keys) = origin
+        .analyze()
+        .await?
+        .imported_value_keys
+        .await?
+        .get(&reference)
+        .cloned()
+    else {
+        return Ok(Vc::cell(None));
+    };
+    let Some(module) = *reference.resolve_reference().first_module().await? else {
+        return Ok(Vc::cell(None));
+    };
+    let Some(module) = ResolvedVc::try_sidecast::<Box<dyn EcmascriptChunkPlaceable>>(module) else {
+        return Ok(Vc::cell(None));
+    };
+    if !matches!(*module.get_exports().await?, EcmascriptExports::Value) {
+        return Ok(Vc::cell(None));
+    }
+    Ok(Vc::cell(Some(keys)))
+}
+
+/// A read of a constant key from an imported value, e.g. `styles.button` for
+/// `import styles from './button.module.css'`.
+#[derive(
+    Clone, Debug, Eq, PartialEq, Hash, Serialize, Deserialize, TraceRawVcs, TaskInput, NonLocalValue,
+)]
+pub struct ImportedValueKeyAccess {
+    pub key: RcStr,
+    pub issue_source: IssueSource,
+}
+
+/// Reports the `accesses` of keys that don't exist in the value exported by the referenced
+/// module, when the module knows its keys (see [EcmascriptValueKeys]).
+#[turbo_tasks::function]
+pub async fn check_imported_value_keys(
+    reference: ResolvedVc<EsmAssetReference>,
+    accesses: Vec<ImportedValueKeyAccess>,
+) -> Result<Vc<()>> {
+    let Some(module) = *reference.resolve_reference().first_module().await? else {
+        return Ok(Default::default());
+    };
+    let Some(module) = ResolvedVc::try_sidecast::<Box<dyn EcmascriptValueKeys>>(module) else {
+        return Ok(Default::default());
+    };
+    let Some(keys) = &*module.value_keys().await? else {
+        return Ok(Default::default());
+    };
+
+    for access in accesses {
+        if !keys.contains(&access.key) {
+            UnknownValueKeyIssue {
+                source: access.issue_source,
+                key: access.key,
+                request: reference.await?.request.to_string().owned().await?,
+                keys: keys.clone(),
+            }
+            .resolved_cell()
+            .emit();
+        }
+    }
+
+    Ok(Default::default())
+}
+
+#[turbo_tasks::value(shared)]
+struct UnknownValueKeyIssue {
+    source: IssueSource,
+    key: RcStr,
+    request: RcStr,
+    keys: Vec<RcStr>,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for UnknownValueKeyIssue {
+    fn severity(&self) -> IssueSeverity {
+        IssueSeverity::Warning
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> Vc<StyledString> {
+        StyledString::Line(vec![
+            StyledString::Code(self.key.clone()),
+            StyledString::Text(rcstr!(" is not exported by ")),
+            StyledString::Code(self.request.clone()),
+        ])
+        .cell()
+    }
+
+    #[turbo_tasks::function]
+    fn stage(&self) -> Vc<IssueStage> {
+        IssueStage::Analysis.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn file_path(&self) -> Vc<FileSystemPath> {
+        self.source.file_path()
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> Vc<OptionStyledString> {
+        let text = if self.keys.is_empty() {
+            format!(
+                "{} has no exports, so the value is always undefined.",
+                self.request
+            )
+        } else {
+            let keys: Vec<&str> = self.keys.iter().map(|key| key.as_str()).collect();
+            format!(
+                "The value is always undefined. {} only exports: {}",
+                self.request,
+                keys.join(", ")
+            )
+        };
+        Vc::cell(Some(StyledString::Text(text.into()).resolved_cell()))
+    }
+
+    #[turbo_tasks::function]
+    fn source(&self) -> Vc<OptionIssueSource> {
+        Vc::cell(Some(self.source))
+    }
+}
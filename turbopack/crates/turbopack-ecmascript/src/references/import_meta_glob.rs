@@ -0,0 +1,587 @@
+use std::borrow::Cow;
+
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use swc_core::{
+    common::DUMMY_SP,
+    ecma::ast::{Expr, Ident, KeyValueProp, Lit, ObjectLit, Prop, PropName, PropOrSpread},
+    quote, quote_expr,
+};
+use turbo_rcstr::RcStr;
+use turbo_tasks::{
+    FxIndexMap, NonLocalValue, ResolvedVc, TryJoinIterExt, ValueToString, Vc,
+    debug::ValueDebugFormat, trace::TraceRawVcs,
+};
+use turbo_tasks_fs::{DirectoryEntry, glob::Glob};
+use turbopack_core::{
+    chunk::{
+        ChunkableModuleReference, ChunkingContext, ChunkingType, ChunkingTypeOption,
+        ModuleChunkItemIdExt,
+    },
+    environment::ChunkLoading,
+    issue::IssueSource,
+    reference::ModuleReference,
+    reference_type::EcmaScriptModulesReferenceSubType,
+    resolve::{ModuleResolveResult, origin::ResolveOrigin, parse::Request},
+};
+use turbopack_resolve::ecmascript::esm_resolve;
+
+use crate::{
+    analyzer::{JsValue, ObjectPart},
+    code_gen::{CodeGen, CodeGeneration, CodeGenerationHoistedStmt, IntoCodeGenReference},
+    create_visitor,
+    references::{
+        AstPath,
+        esm::base::ReferencedAsset,
+        pattern_mapping::{PatternMapping, ResolveType},
+    },
+    runtime_functions::TURBOPACK_IMPORT,
+    utils::module_id_to_lit,
+};
+
+/// The options of an `import.meta.glob(patterns, options)` call.
+///
+/// See https://vite.dev/guide/features.html#glob-import
+#[derive(Debug, Clone)]
+pub struct ImportMetaGlobOptions {
+    /// Globs relative to the calling module (`./`, `../`) or to the root of the filesystem
+    /// (`/`). Patterns starting with `!` exclude matches and may also be relative to the calling
+    /// module without a leading `./`, e.g. `!**/*.test.ts`.
+    pub patterns: Vec<RcStr>,
+    /// Import all matches when the module is evaluated instead of returning loader functions.
+    pub eager: bool,
+    /// Only select this export of the matched modules instead of the module namespace.
+    pub import: Option<RcStr>,
+    /// A query that is appended to all requests, e.g. `?raw`.
+    pub query: Option<RcStr>,
+    /// Also match files in `node_modules`.
+    pub exhaustive: bool,
+}
+
+/// Parse the arguments passed to an `import.meta.glob` invocation, validate them and convert
+/// them to the appropriate rust values.
+pub fn parse_import_meta_glob(args: &[JsValue]) -> Result<ImportMetaGlobOptions> {
+    if !(1..=2).contains(&args.len()) {
+        bail!("import.meta.glob() only supports 1-2 arguments");
+    }
+
+    let patterns: Vec<RcStr> = match &args[0] {
+        JsValue::Array { items, .. } => {
+            let mut patterns = Vec::with_capacity(items.len());
+            for item in items {
+                let Some(pattern) = item.as_str() else {
+                    bail!(
+                        "import.meta.glob(patterns, ...) requires patterns to be constant strings"
+                    );
+                };
+                patterns.push(pattern.into());
+            }
+            patterns
+        }
+        pattern => match pattern.as_str() {
+            Some(pattern) => vec![pattern.into()],
+            None => bail!(
+                "import.meta.glob(patterns, ...) requires patterns to be a constant string or an \
+                 array of constant strings"
+            ),
+        },
+    };
+    for pattern in patterns.iter().filter(|pattern| !pattern.starts_with('!')) {
+        if !(pattern.starts_with("./") || pattern.starts_with("../") || pattern.starts_with('/')) {
+            bail!(
+                "import.meta.glob() patterns must start with './', '../' or '/', got '{pattern}'"
+            );
+        }
+    }
+    if patterns.iter().all(|pattern| pattern.starts_with('!')) {
+        bail!("import.meta.glob() requires at least one pattern that is not negated");
+    }
+
+    let mut options = ImportMetaGlobOptions {
+        patterns,
+        eager: false,
+        import: None,
+        query: None,
+        exhaustive: false,
+    };
+
+    let Some(arg) = args.get(1) else {
+        return Ok(options);
+    };
+    let JsValue::Object { parts, .. } = arg else {
+        bail!("import.meta.glob(..., options) requires options to be an object literal");
+    };
+    for part in parts {
+        let ObjectPart::KeyValue(key, value) = part else {
+            bail!("import.meta.glob(..., options) does not support spread options");
+        };
+        match key.as_str() {
+            Some("eager") => {
+                let Some(eager) = value.as_bool() else {
+                    bail!(
+                        "import.meta.glob(..., {{ eager }}) requires eager to be a constant \
+                         boolean"
+                    );
+                };
+                options.eager = eager;
+            }
+            Some("exhaustive") => {
+                let Some(exhaustive) = value.as_bool() else {
+                    bail!(
+                        "import.meta.glob(..., {{ exhaustive }}) requires exhaustive to be a \
+                         constant boolean"
+                    );
+                };
+                options.exhaustive = exhaustive;
+            }
+            Some("import") => {
+                let Some(import) = value.as_str() else {
+                    bail!(
+                        "import.meta.glob(..., {{ import }}) requires import to be a constant \
+                         string"
+                    );
+                };
+                options.import = Some(import.into());
+            }
+            Some("query") => options.query = Some(parse_query(value)?),
+            Some(key) => bail!("import.meta.glob(..., {{ {key} }}) is not supported"),
+            None => bail!("import.meta.glob(..., options) requires constant option names"),
+        }
+    }
+
+    Ok(options)
+}
+
+/// Converts the `query` option, which is either a string like `?raw` or an object of constant
+/// values like `{ width: 100 }`.
+fn parse_query(value: &JsValue) -> Result<RcStr> {
+    if let Some(query) = value.as_str() {
+        return Ok(if query.is_empty() || query.starts_with('?') {
+            query.into()
+        } else {
+            format!("?{query}").into()
+        });
+    }
+    let JsValue::Object { parts, .. } = value else {
+        bail!(
+            "import.meta.glob(..., {{ query }}) requires query to be a constant string or object"
+        );
+    };
+    let mut query = String::new();
+    for part in parts {
+        if let ObjectPart::KeyValue(key, value) = part
+            && let Some(key) = key.as_str()
+            && let JsValue::Constant(constant) = value
+        {
+            query.push(if query.is_empty() { '?' } else { '&' });
+            query.push_str(key);
+            query.push('=');
+            match value.as_str() {
+                Some(value) => query.push_str(value),
+                None => query.push_str(&constant.to_string()),
+            }
+        } else {
+            bail!("import.meta.glob(..., {{ query }}) requires query values to be constants");
+        }
+    }
+    Ok(query.into())
+}
+
+/// Splits a pattern into the directory without glob characters, which is used as the key prefix,
+/// and the glob that matches files in that directory, e.g. `./dir/**/*.ts` into `./dir` and
+/// `**/*.ts`.
+fn split_pattern(pattern: &str) -> (&str, &str) {
+    let mut base_end = None;
+    for (index, _) in pattern.match_indices('/') {
+        if pattern[..index].contains(['*', '?', '[', '{']) {
+            break;
+        }
+        base_end = Some(index);
+    }
+    match base_end {
+        Some(index) => (&pattern[..index], &pattern[index + 1..]),
+        None => ("", pattern),
+    }
+}
+
+#[turbo_tasks::value]
+#[derive(Debug)]
+pub struct ImportMetaGlobMapEntry {
+    pub request: ResolvedVc<Request>,
+    pub result: ResolvedVc<ModuleResolveResult>,
+}
+
+/// The matched files of an `import.meta.glob(..)` call, keyed like Vite does: relative to the
+/// calling module for relative patterns and relative to the root for absolute patterns.
+#[turbo_tasks::value(transparent)]
+pub struct ImportMetaGlobMap(FxIndexMap<RcStr, ImportMetaGlobMapEntry>);
+
+#[turbo_tasks::value_impl]
+impl ImportMetaGlobMap {
+    /// Reads the matches with [turbo_tasks_fs::read_glob], so the map is invalidated when
+    /// matching files are added or removed.
+    #[turbo_tasks::function]
+    pub(crate) async fn generate(
+        origin: Vc<Box<dyn ResolveOrigin>>,
+        patterns: Vec<RcStr>,
+        query: Option<RcStr>,
+        eager: bool,
+        exhaustive: bool,
+        issue_source: Option<IssueSource>,
+        is_optional: bool,
+    ) -> Result<Vc<Self>> {
+        let origin_path = origin.origin_path().owned().await?;
+        let origin_dir = origin_path.parent();
+        let root = origin_path.root().owned().await?;
+
+        let resolve_dir = |pattern: &str, base: &str| {
+            if let Some(base) = base.strip_prefix('/') {
+                root.join(base)
+            } else if pattern.starts_with('/') {
+                Ok(root.clone())
+            } else {
+                origin_dir.join(base)
+            }
+        };
+
+        // Directory names may contain glob characters, so excluded globs are matched relative to
+        // their directory
+        let mut exclude = Vec::new();
+        for pattern in patterns.iter().filter_map(|p| p.strip_prefix('!')) {
+            let (base, glob) = split_pattern(pattern);
+            exclude.push((resolve_dir(pattern, base)?, Glob::parse(glob)?));
+        }
+
+        let mut matches = Vec::new();
+        for pattern in patterns.iter().filter(|p| !p.starts_with('!')) {
+            let (base, glob) = split_pattern(pattern);
+            let dir = resolve_dir(pattern, base)?;
+            let mut queue = vec![dir.read_glob(Glob::new(glob.into())).await?];
+            while let Some(result) = queue.pop() {
+                for (relative, entry) in &result.results {
+                    if let DirectoryEntry::File(path) = entry {
+                        matches.push((
+                            format!("{base}/{relative}"),
+                            relative.clone(),
+                            path.clone(),
+                        ));
+                    }
+                }
+                for inner in result.inner.values() {
+                    queue.push(inner.await?);
+                }
+            }
+        }
+
+        matches.retain(|(_, relative, path)| {
+            // Like Vite, the calling module never matches itself
+            *path != origin_path
+                && (exhaustive || !relative.split('/').any(|segment| segment == "node_modules"))
+                && !exclude.iter().any(|(dir, glob)| {
+                    dir.get_path_to(path)
+                        .is_some_and(|relative| glob.matches(relative))
+                })
+        });
+        matches.sort_by(|(a, ..), (b, ..)| a.cmp(b));
+        matches.dedup_by(|(a, ..), (b, ..)| a == b);
+
+        let ty = if eager {
+            EcmaScriptModulesReferenceSubType::Import
+        } else {
+            EcmaScriptModulesReferenceSubType::DynamicImport
+        };
+        let query = query.as_deref().unwrap_or_default();
+        let map = matches
+            .into_iter()
+            .map(|(key, _, path)| {
+                let ty = ty.clone();
+                let origin_dir = &origin_dir;
+                async move {
+                    let Some(origin_relative) = origin_dir.get_relative_path_to(&path) else {
+                        bail!("invariant error: matches are on the filesystem of the origin");
+                    };
+                    let request =
+                        Request::parse(RcStr::from(format!("{origin_relative}{query}")).into())
+                            .to_resolved()
+                            .await?;
+                    let result = esm_resolve(origin, *request, ty, is_optional, issue_source)
+                        .await?
+                        .to_resolved()
+                        .await?;
+                    Ok((RcStr::from(key), ImportMetaGlobMapEntry { request, result }))
+                }
+            })
+            .try_join()
+            .await?
+            .into_iter()
+            .collect();
+
+        Ok(Vc::cell(map))
+    }
+}
+
+/// A reference for `import.meta.glob()`, will replace it with an inlined object of the matched
+/// modules, or functions loading them.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct ImportMetaGlobAssetReference {
+    pub origin: ResolvedVc<Box<dyn ResolveOrigin>>,
+    pub map: ResolvedVc<ImportMetaGlobMap>,
+    pub patterns: Vec<RcStr>,
+    pub eager: bool,
+    pub import: Option<RcStr>,
+}
+
+impl ImportMetaGlobAssetReference {
+    pub async fn new(
+        origin: ResolvedVc<Box<dyn ResolveOrigin>>,
+        options: ImportMetaGlobOptions,
+        issue_source: Option<IssueSource>,
+        in_try: bool,
+    ) -> Result<Self> {
+        let map = ImportMetaGlobMap::generate(
+            *origin,
+            options.patterns.clone(),
+            options.query,
+            options.eager,
+            options.exhaustive,
+            issue_source,
+            in_try,
+        )
+        .to_resolved()
+        .await?;
+
+        Ok(ImportMetaGlobAssetReference {
+            origin,
+            map,
+            patterns: options.patterns,
+            eager: options.eager,
+            import: options.import,
+        })
+    }
+}
+
+/// A module matched by an eager `import.meta.glob()`. Like an ESM import, it is placed in the
+/// chunk group of the calling module and awaited by it when the matched module is async.
+#[turbo_tasks::value]
+#[derive(Hash, Debug)]
+pub struct ImportMetaGlobEagerAssetReference {
+    pub request: ResolvedVc<Request>,
+    pub result: ResolvedVc<ModuleResolveResult>,
+}
+
+#[turbo_tasks::value_impl]
+impl ModuleReference for ImportMetaGlobEagerAssetReference {
+    #[turbo_tasks::function]
+    fn resolve_reference(&self) -> Vc<ModuleResolveResult> {
+        *self.result
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for ImportMetaGlobEagerAssetReference {
+    #[turbo_tasks::function]
+    async fn to_string(&self) -> Result<Vc<RcStr>> {
+        Ok(Vc::cell(
+            format!("import.meta.glob eager {}", self.request.to_string().await?).into(),
+        ))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableModuleReference for ImportMetaGlobEagerAssetReference {
+    #[turbo_tasks::function]
+    fn chunking_type(&self) -> Vc<ChunkingTypeOption> {
+        Vc::cell(Some(ChunkingType::Parallel {
+            inherit_async: true,
+            hoisted: false,
+        }))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ModuleReference for ImportMetaGlobAssetReference {
+    #[turbo_tasks::function]
+    async fn resolve_reference(&self) -> Result<Vc<ModuleResolveResult>> {
+        let map = &*self.map.await?;
+        Ok(ModuleResolveResult::alternatives(
+            map.values().map(|entry| *entry.result).collect(),
+        ))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ValueToString for ImportMetaGlobAssetReference {
+    #[turbo_tasks::function]
+    fn to_string(&self) -> Vc<RcStr> {
+        let patterns: Vec<&str> = self.patterns.iter().map(|p| p.as_str()).collect();
+        Vc::cell(format!("import.meta.glob {}", patterns.join(", ")).into())
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl ChunkableModuleReference for ImportMetaGlobAssetReference {
+    #[turbo_tasks::function]
+    fn chunking_type(&self) -> Vc<ChunkingTypeOption> {
+        // The matches of an eager glob are chunked through their
+        // `ImportMetaGlobEagerAssetReference`s instead
+        Vc::cell((!self.eager).then_some(ChunkingType::Async))
+    }
+}
+
+impl IntoCodeGenReference for ImportMetaGlobAssetReference {
+    fn into_code_gen_reference(
+        self,
+        path: AstPath,
+    ) -> (ResolvedVc<Box<dyn ModuleReference>>, CodeGen) {
+        let reference = self.resolved_cell();
+        (
+            ResolvedVc::upcast(reference),
+            CodeGen::ImportMetaGlobAssetReferenceCodeGen(ImportMetaGlobAssetReferenceCodeGen {
+                reference,
+                path,
+            }),
+        )
+    }
+}
+
+#[derive(PartialEq, Eq, Serialize, Deserialize, TraceRawVcs, ValueDebugFormat, NonLocalValue)]
+pub struct ImportMetaGlobAssetReferenceCodeGen {
+    path: AstPath,
+    reference: ResolvedVc<ImportMetaGlobAssetReference>,
+}
+
+impl ImportMetaGlobAssetReferenceCodeGen {
+    pub async fn code_generation(
+        &self,
+        chunking_context: Vc<Box<dyn ChunkingContext>>,
+    ) -> Result<CodeGeneration> {
+        let reference = self.reference.await?;
+        let map = &*reference.map.await?;
+
+        let resolve_type = if matches!(
+            *chunking_context.environment().chunk_loading().await?,
+            ChunkLoading::Edge
+        ) {
+            ResolveType::ChunkItem
+        } else {
+            ResolveType::AsyncChunkLoader
+        };
+
+        let mut object = ObjectLit {
+            span: DUMMY_SP,
+            props: vec![],
+        };
+        let mut hoisted_stmts = vec![];
+
+        for (key, entry) in map {
+            let key_expr = Expr::Lit(Lit::Str(key.as_str().into()));
+            let value = if reference.eager {
+                let namespace = match &*ReferencedAsset::from_resolve_result(*entry.result).await? {
+                    ReferencedAsset::Some(asset) => {
+                        // Declared like the binding of an ESM import, which `AsyncModule` awaits
+                        // when the module is async
+                        let id = asset.chunk_item_id(Vc::upcast(chunking_context)).await?;
+                        let name = Ident::new(
+                            ReferencedAsset::get_ident_from_placeable(asset, chunking_context)
+                                .await?
+                                .into(),
+                            DUMMY_SP,
+                            Default::default(),
+                        );
+                        hoisted_stmts.push(CodeGenerationHoistedStmt::new(
+                            id.to_string().into(),
+                            quote!(
+                                "var $name = $turbopack_import($id);" as Stmt,
+                                name = name.clone(),
+                                turbopack_import: Expr = TURBOPACK_IMPORT.into(),
+                                id: Expr = module_id_to_lit(&id),
+                            ),
+                        ));
+                        Box::new(Expr::Ident(name))
+                    }
+                    _ => {
+                        let pm = PatternMapping::resolve_request(
+                            *entry.request,
+                            *reference.origin,
+                            Vc::upcast(chunking_context),
+                            *entry.result,
+                            ResolveType::ChunkItem,
+                        )
+                        .await?;
+                        let PatternMapping::Single(pm) = &*pm else {
+                            continue;
+                        };
+                        Box::new(pm.create_require(Cow::Borrowed(&key_expr)))
+                    }
+                };
+                select_export(namespace, reference.import.as_deref())
+            } else {
+                let pm = PatternMapping::resolve_request(
+                    *entry.request,
+                    *reference.origin,
+                    Vc::upcast(chunking_context),
+                    *entry.result,
+                    resolve_type,
+                )
+                .await?;
+                let PatternMapping::Single(pm) = &*pm else {
+                    continue;
+                };
+                let import = pm.create_import(Cow::Borrowed(&key_expr), false);
+                let import = match reference.import.as_deref() {
+                    Some(export) => quote_expr!(
+                        "$import.then((m) => $export)",
+                        import: Expr = import,
+                        export: Expr = *select_export(quote_expr!("m"), Some(export)),
+                    ),
+                    None => Box::new(import),
+                };
+                quote_expr!("() => $import", import: Expr = *import)
+            };
+
+            object
+                .props
+                .push(PropOrSpread::Prop(Box::new(Prop::KeyValue(KeyValueProp {
+                    key: PropName::Str(key.as_str().into()),
+                    value,
+                }))));
+        }
+
+        let visitor = create_visitor!(self.path, visit_mut_expr, |expr: &mut Expr| {
+            if let Expr::Call(_) = expr {
+                *expr = Expr::Object(object.clone());
+            }
+        });
+
+        Ok(CodeGeneration::new(vec![visitor], hoisted_stmts, vec![]))
+    }
+}
+
+fn select_export(namespace: Box<Expr>, export: Option<&str>) -> Box<Expr> {
+    match export {
+        Some(export) => quote_expr!(
+            "$namespace[$export]",
+            namespace: Expr = *namespace,
+            export: Expr = Expr::Lit(Lit::Str(export.into()))
+        ),
+        None => namespace,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_patterns() {
+        assert_eq!(split_pattern("./dir/*.ts"), ("./dir", "*.ts"));
+        assert_eq!(split_pattern("./*.ts"), (".", "*.ts"));
+        assert_eq!(split_pattern("../a/**/b/*.ts"), ("../a", "**/b/*.ts"));
+        assert_eq!(split_pattern("./{a,b}/index.ts"), (".", "{a,b}/index.ts"));
+        assert_eq!(split_pattern("/src/*.ts"), ("/src", "*.ts"));
+        assert_eq!(split_pattern("/*.ts"), ("", "*.ts"));
+        assert_eq!(split_pattern("./dir/file.ts"), ("./dir", "file.ts"));
+        assert_eq!(split_pattern("**/*.test.ts"), ("", "**/*.test.ts"));
+    }
+}
@@ -7,6 +7,7 @@ pub mod dynamic_expression;
 pub mod esm;
 pub mod external_module;
 pub mod ident;
+pub mod import_meta_glob;
 pub mod member;
 pub mod node;
 pub mod pattern_mapping;
@@ -20,7 +21,7 @@ pub mod worker;
 
 use std::{
     borrow::Cow,
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     future::Future,
     mem::take,
     ops::Deref,
@@ -41,7 +42,7 @@ use serde::{Deserialize, Serialize};
 use swc_core::{
     atoms::{Atom, atom},
     common::{
-        GLOBALS, Globals, Span, Spanned,
+        BytePos, GLOBALS, Globals, Span, Spanned,
         comments::{CommentKind, Comments},
         errors::{DiagnosticId, HANDLER, Handler},
         pass::AstNodePath,
@@ -100,7 +101,9 @@ use self::{
     cjs::CjsAssetReference,
     esm::{
         EsmAssetReference, EsmAsyncAssetReference, EsmExports, EsmModuleItem, ImportMetaBinding,
-        ImportMetaRef, UrlAssetReference, export::EsmExport,
+        ImportMetaRef, UrlAssetReference,
+        export::EsmExport,
+        value_keys::{ImportedValueKeyAccess, ImportedValueKeys, check_imported_value_keys},
     },
     node::DirAssetReference,
     raw::FileSourceReference,
@@ -150,6 +153,9 @@ use crate::{
             module_id::EsmModuleIdAssetReference,
         },
         ident::IdentReplacement,
+        import_meta_glob::{
+            ImportMetaGlobAssetReference, ImportMetaGlobEagerAssetReference, parse_import_meta_glob,
+        },
         member::MemberReplacement,
         node::PackageJsonReference,
         require_context::{RequireContextAssetReference, RequireContextMap},
@@ -162,6 +168,10 @@ use crate::{
     },
     tree_shake::{find_turbopack_part_id_in_asserts, part_of_module, split},
     utils::{AstPathRange, module_value_to_well_known_object},
+    well_known_packages::{
+        RelativeTo, WellKnownExportBehavior, WellKnownPackage, WellKnownPackages,
+        package_module_value,
+    },
 };
 
 #[turbo_tasks::value(shared)]
@@ -175,6 +185,7 @@ pub struct AnalyzeEcmascriptModuleResult {
     pub code_generation: ResolvedVc<CodeGens>,
     pub exports: ResolvedVc<EcmascriptExports>,
     pub async_module: ResolvedVc<OptionAsyncModule>,
+    pub imported_value_keys: ResolvedVc<ImportedValueKeys>,
     pub has_side_effect_free_directive: bool,
     /// `true` when the analysis was successful.
     pub successful: bool,
@@ -225,6 +236,7 @@ pub struct AnalyzeEcmascriptModuleResultBuilder {
     code_gens: Vec<CodeGen>,
     exports: EcmascriptExports,
     async_module: ResolvedVc<OptionAsyncModule>,
+    imported_value_keys: FxHashMap<usize, Vec<RcStr>>,
     successful: bool,
     source_map: Option<ResolvedVc<Box<dyn GenerateSourceMap>>>,
     has_side_effect_free_directive: bool,
@@ -242,6 +254,7 @@ impl AnalyzeEcmascriptModuleResultBuilder {
             code_gens: Default::default(),
             exports: EcmascriptExports::Unknown,
             async_module: ResolvedVc::cell(None),
+            imported_value_keys: Default::default(),
             successful: false,
             source_map: None,
             has_side_effect_free_directive: false,
@@ -304,6 +317,12 @@ impl AnalyzeEcmascriptModuleResultBuilder {
         self.async_module = ResolvedVc::cell(Some(async_module));
     }
 
+    /// Sets the keys that are read from the value of the default import of the ESM reference,
+    /// when nothing else is done with the value.
+    pub fn set_imported_value_keys(&mut self, idx: usize, keys: Vec<RcStr>) {
+        self.imported_value_keys.insert(idx, keys);
+    }
+
     /// Set whether this module is side-efffect free according to a user-provided directive.
     pub fn set_has_side_effect_free_directive(&mut self, value: bool) {
         self.has_side_effect_free_directive = value;
@@ -393,6 +412,22 @@ impl AnalyzeEcmascriptModuleResultBuilder {
             }
         }
 
+        let mut imported_value_keys = FxIndexMap::default();
+        for (i, keys) in self.imported_value_keys {
+            let Some(&reference) = import_references.get(i) else {
+                continue;
+            };
+            imported_value_keys.insert(reference, keys.clone());
+            // The default import might have been rewritten to a reference of the `default` export
+            if let Some(&reference) = self
+                .esm_references_rewritten
+                .get(&i)
+                .and_then(|m| m.get(&rcstr!("default")))
+            {
+                imported_value_keys.insert(reference, keys);
+            }
+        }
+
         let references: Vec<_> = self.references.into_iter().collect();
 
         self.code_gens.shrink_to_fit();
@@ -407,6 +442,7 @@ impl AnalyzeEcmascriptModuleResultBuilder {
                 code_generation: ResolvedVc::cell(self.code_gens),
                 exports: self.exports.resolved_cell(),
                 async_module: self.async_module,
+                imported_value_keys: ResolvedVc::cell(imported_value_keys),
                 has_side_effect_free_directive: self.has_side_effect_free_directive,
                 successful: self.successful,
                 source_map: self.source_map,
@@ -439,6 +475,7 @@ struct AnalysisState<'a> {
     ignore_dynamic_requests: bool,
     url_rewrite_behavior: Option<UrlRewriteBehavior>,
     free_var_references: ReadRef<FreeVarReferencesIndividual>,
+    well_known_packages: Option<ReadRef<WellKnownPackages>>,
 }
 
 impl AnalysisState<'_> {
@@ -454,6 +491,7 @@ impl AnalysisState<'_> {
                     value,
                     *self.compile_time_info,
                     &self.free_var_references,
+                    self.well_known_packages.as_deref().map(Vec::as_slice),
                     self.var_graph,
                     attributes,
                 )
@@ -998,6 +1036,10 @@ pub(crate) async fn analyse_ecmascript_module_internal(
                 .free_var_references
                 .individual()
                 .await?,
+            well_known_packages: match options.well_known_packages {
+                Some(well_known_packages) => Some(well_known_packages.await?),
+                None => None,
+            },
         };
 
         enum Action {
@@ -1014,6 +1056,16 @@ pub(crate) async fn analyse_ecmascript_module_internal(
             .get_mut()
             .extend(effects.into_iter().map(Action::Effect).rev());
 
+        // Constant keys read from imported values, by index of the import reference. TypeScript
+        // modules are checked for keys that the imported module doesn't export.
+        let mut value_key_accesses: FxIndexMap<usize, Vec<ImportedValueKeyAccess>> =
+            FxIndexMap::default();
+        // Constant keys read from default imports by the start of the member expression, and the
+        // starts of all uses of default imports. When all uses of an import read a constant key,
+        // the usage of the imported value is known.
+        let mut default_import_key_reads: FxHashMap<BytePos, RcStr> = FxHashMap::default();
+        let mut default_import_uses: FxIndexMap<usize, Vec<BytePos>> = FxIndexMap::default();
+
         while let Some(action) = queue_stack.get_mut().pop() {
             let effect = match action {
                 Action::LeaveScope(func_ident) => {
@@ -1322,6 +1374,42 @@ pub(crate) async fn analyse_ecmascript_module_internal(
                     span,
                     in_try: _,
                 } => {
+                    let imported_module = match &*obj {
+                        JsValue::Module(module) => Some(module),
+                        JsValue::Member(_, module, export)
+                            if export.as_str() == Some("default") =>
+                        {
+                            match &**module {
+                                JsValue::Module(module) => Some(module),
+                                _ => None,
+                            }
+                        }
+                        _ => None,
+                    };
+                    if let JsValue::Member(..) = &*obj
+                        && imported_module.is_some()
+                        && let Some(key) = prop.as_str()
+                    {
+                        default_import_key_reads.insert(span.lo, key.into());
+                    }
+                    if matches!(ty, EcmascriptModuleAssetType::Typescript { .. })
+                        && let Some(module) = imported_module
+                        && let Some(key) = prop.as_str()
+                        // The namespace of a module exporting a value also has it as `default`
+                        && !(key == "default" && matches!(&*obj, JsValue::Module(_)))
+                        && let Some(index) = eval_context.imports.references().position(|r| {
+                            r.module_path == module.module && r.annotations == module.annotations
+                        })
+                    {
+                        value_key_accesses
+                            .entry(index)
+                            .or_default()
+                            .push(ImportedValueKeyAccess {
+                                key: key.into(),
+                                issue_source: issue_source(source, span),
+                            });
+                    }
+
                     // Intentionally not awaited because `handle_member` reads this only when needed
                     let obj = analysis_state.link_value(*obj, ImportAttributes::empty_ref());
 
@@ -1336,13 +1424,20 @@ pub(crate) async fn analyse_ecmascript_module_internal(
                     esm_reference_index,
                     export,
                     ast_path,
-                    span: _,
+                    span,
                     in_try: _,
                 } => {
                     let Some(r) = import_references.get(esm_reference_index) else {
                         continue;
                     };
 
+                    if export.as_deref() == Some("default") {
+                        default_import_uses
+                            .entry(esm_reference_index)
+                            .or_default()
+                            .push(span.lo);
+                    }
+
                     if let Some("__turbopack_module_id__") = export.as_deref() {
                         analysis.add_reference_code_gen(
                             EsmModuleIdAssetReference::new(*r),
@@ -1415,6 +1510,23 @@ pub(crate) async fn analyse_ecmascript_module_internal(
                 }
             }
         }
+
+        for (index, uses) in default_import_uses {
+            let keys: Option<BTreeSet<RcStr>> = uses
+                .iter()
+                .map(|pos| default_import_key_reads.get(pos).cloned())
+                .collect();
+            if let Some(keys) = keys {
+                analysis.set_imported_value_keys(index, keys.into_iter().collect());
+            }
+        }
+
+        for (index, accesses) in value_key_accesses {
+            if let Some(&reference) = import_references.get(index) {
+                check_imported_value_keys(*reference, accesses).await?;
+            }
+        }
+
         anyhow::Ok(())
     }
     .instrument(span)
@@ -1901,6 +2013,47 @@ async fn handle_call<G: Fn(Vec<Effect>) + Send + Sync>(
             );
         }
 
+        JsValue::WellKnownFunction(WellKnownFunctionKind::ImportMetaGlob) => {
+            let args = linked_args(args).await?;
+            let options = match parse_import_meta_glob(&args) {
+                Ok(options) => options,
+                Err(err) => {
+                    let (args, hints) = explain_args(&args);
+                    handler.span_err_with_code(
+                        span,
+                        &format!(
+                            "import.meta.glob({args}) is not statically analyze-able: {}{hints}",
+                            PrettyPrintError(&err)
+                        ),
+                        DiagnosticId::Error(
+                            errors::failed_to_analyse::ecmascript::IMPORT_META_GLOB.to_string(),
+                        ),
+                    );
+                    return Ok(());
+                }
+            };
+
+            let reference = ImportMetaGlobAssetReference::new(
+                origin,
+                options,
+                Some(issue_source(source, span)),
+                in_try,
+            )
+            .await?;
+            if reference.eager {
+                for entry in reference.map.await?.values() {
+                    analysis.add_reference(
+                        ImportMetaGlobEagerAssetReference {
+                            request: entry.request,
+                            result: entry.result,
+                        }
+                        .resolved_cell(),
+                    );
+                }
+            }
+            analysis.add_reference_code_gen(reference, ast_path.to_vec().into());
+        }
+
         JsValue::WellKnownFunction(WellKnownFunctionKind::FsReadMethod(name)) => {
             let args = linked_args(args).await?;
             if !args.is_empty() {
@@ -2343,30 +2496,6 @@ async fn handle_call<G: Fn(Vec<Effect>) + Send + Sync>(
                 ),
             )
         }
-        JsValue::WellKnownFunction(WellKnownFunctionKind::NodeResolveFrom) => {
-            let args = linked_args(args).await?;
-            if args.len() == 2 && args.get(1).and_then(|arg| arg.as_str()).is_some() {
-                analysis.add_reference(
-                    CjsAssetReference::new(
-                        *origin,
-                        Request::parse(js_value_to_pattern(&args[1])),
-                        issue_source(source, span),
-                        in_try,
-                    )
-                    .to_resolved()
-                    .await?,
-                );
-                return Ok(());
-            }
-            let (args, hints) = explain_args(&args);
-            handler.span_warn_with_code(
-                span,
-                &format!("require('resolve-from')({args}) is not statically analyse-able{hints}",),
-                DiagnosticId::Error(
-                    errors::failed_to_analyse::ecmascript::NODE_RESOLVE_FROM.to_string(),
-                ),
-            )
-        }
         JsValue::WellKnownFunction(WellKnownFunctionKind::NodeProtobufLoad) => {
             let args = linked_args(args).await?;
             if args.len() == 2
@@ -2409,6 +2538,92 @@ async fn handle_call<G: Fn(Vec<Effect>) + Send + Sync>(
                 ),
             )
         }
+        JsValue::WellKnownFunction(WellKnownFunctionKind::PackageExport(package, export)) => {
+            let args = linked_args(args).await?;
+            let constant_pattern = |index: u32| {
+                args.get(index as usize)
+                    .map(js_value_to_pattern)
+                    .filter(|pat| pat.has_constant_parts())
+            };
+            match package
+                .export(export.as_deref())
+                .map(|export| &export.behavior)
+            {
+                Some(&WellKnownExportBehavior::LoadFile { argument }) => {
+                    if let Some(pat) = constant_pattern(argument) {
+                        analysis.add_reference(
+                            FileSourceReference::new(*source, Pattern::new(pat))
+                                .to_resolved()
+                                .await?,
+                        );
+                        return Ok(());
+                    }
+                }
+                Some(&WellKnownExportBehavior::LoadDirectory { argument }) => {
+                    if let Some(pat) = constant_pattern(argument) {
+                        analysis.add_reference(
+                            DirAssetReference::new(*source, Pattern::new(pat))
+                                .to_resolved()
+                                .await?,
+                        );
+                        return Ok(());
+                    }
+                }
+                Some(&WellKnownExportBehavior::ResolveRequest {
+                    request,
+                    relative_to,
+                }) => {
+                    let request_origin = match relative_to {
+                        RelativeTo::CallingModule => Some(*origin),
+                        RelativeTo::Argument(index) => {
+                            match args.get(index as usize).and_then(|arg| arg.as_str()) {
+                                Some(dir) => {
+                                    let origin_path = origin.origin_path().owned().await?;
+                                    let dir = if let Some(dir) = dir.strip_prefix("/ROOT/") {
+                                        origin_path.root().await?.join(dir)?
+                                    } else {
+                                        origin_path.parent().join(dir)?
+                                    };
+                                    // The origin is a file in the directory
+                                    Some(Vc::upcast(PlainResolveOrigin::new(
+                                        origin.asset_context(),
+                                        dir.join("_")?,
+                                    )))
+                                }
+                                None => None,
+                            }
+                        }
+                    };
+                    if let Some(request_origin) = request_origin
+                        && let Some(pat) = constant_pattern(request)
+                    {
+                        analysis.add_reference(
+                            CjsAssetReference::new(
+                                request_origin,
+                                Request::parse(pat),
+                                issue_source(source, span),
+                                in_try,
+                            )
+                            .to_resolved()
+                            .await?,
+                        );
+                        return Ok(());
+                    }
+                }
+                None => {}
+            }
+            let (args, hints) = explain_args(&args);
+            handler.span_warn_with_code(
+                span,
+                &format!(
+                    "{}({args}) is not statically analyse-able{hints}",
+                    package.describe_export(export.as_deref())
+                ),
+                DiagnosticId::Error(
+                    errors::failed_to_analyse::ecmascript::WELL_KNOWN_PACKAGE.to_string(),
+                ),
+            )
+        }
         _ => {
             for arg in args {
                 if let EffectArg::Closure(_, block) = arg {
@@ -2854,6 +3069,7 @@ async fn value_visitor(
         DefinableNameSegment,
         FxIndexMap<Vec<DefinableNameSegment>, ResolvedVc<FreeVarReference>>,
     >,
+    well_known_packages: Option<&[WellKnownPackage]>,
     var_graph: &VarGraph,
     attributes: &ImportAttributes,
 ) -> Result<(JsValue, bool)> {
@@ -2862,6 +3078,7 @@ async fn value_visitor(
         v,
         compile_time_info,
         free_var_references,
+        well_known_packages,
         var_graph,
         attributes,
     )
@@ -2878,6 +3095,7 @@ async fn value_visitor_inner(
         DefinableNameSegment,
         FxIndexMap<Vec<DefinableNameSegment>, ResolvedVc<FreeVarReference>>,
     >,
+    well_known_packages: Option<&[WellKnownPackage]>,
     var_graph: &VarGraph,
     attributes: &ImportAttributes,
 ) -> Result<(JsValue, bool)> {
@@ -3002,7 +3220,10 @@ async fn value_visitor_inner(
             .node_externals()
             .await?
             // TODO check externals
-            .then(|| module_value_to_well_known_object(mv))
+            .then(|| {
+                package_module_value(well_known_packages.unwrap_or_default(), mv)
+                    .or_else(|| module_value_to_well_known_object(mv))
+            })
             .flatten()
             .unwrap_or_else(|| v.into_unknown(true, "cross module analyzing is not yet supported")),
         JsValue::Argument(..) => {
@@ -188,7 +188,10 @@ impl EcmascriptModulePartReference {
                     chunking_context,
                     match &*export_usage {
                         ExportUsage::Named(export) => Some(export.clone()),
-                        ExportUsage::All | ExportUsage::Evaluation => None,
+                        ExportUsage::Keys(_)
+                        | ExportUsage::Reexport
+                        | ExportUsage::All
+                        | ExportUsage::Evaluation => None,
                     },
                     scope_hoisting_context,
                 )
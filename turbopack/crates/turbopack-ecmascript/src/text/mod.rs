@@ -1,4 +1,6 @@
 use anyhow::Result;
+use data_encoding::BASE64;
+use indoc::formatdoc;
 use turbo_rcstr::{RcStr, rcstr};
 use turbo_tasks::{ResolvedVc, Vc};
 use turbo_tasks_fs::FileContent;
@@ -6,6 +8,7 @@ use turbopack_core::{
     asset::{Asset, AssetContent},
     ident::AssetIdent,
     source::Source,
+    source_transform::SourceTransform,
 };
 
 use crate::utils::StringifyJs;
@@ -50,3 +53,120 @@ impl Asset for TextContentFileSource {
         Ok(AssetContent::file(content))
     }
 }
+
+/// A source asset that exports the binary content of an asset as a `Uint8Array` default export
+/// of a JS module, e.g. for `import data from "./data.bin" with { type: "bytes" }`.
+#[turbo_tasks::value]
+pub struct BytesContentFileSource {
+    pub source: ResolvedVc<Box<dyn Source>>,
+}
+
+#[turbo_tasks::value_impl]
+impl BytesContentFileSource {
+    #[turbo_tasks::function]
+    pub fn new(source: ResolvedVc<Box<dyn Source>>) -> Vc<Self> {
+        BytesContentFileSource { source }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Source for BytesContentFileSource {
+    #[turbo_tasks::function]
+    fn ident(&self) -> Vc<AssetIdent> {
+        self.source
+            .ident()
+            .with_modifier(rcstr!("bytes content"))
+            .rename_as(rcstr!("*.mjs"))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for BytesContentFileSource {
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<Vc<AssetContent>> {
+        let source = self.source.content().file_content();
+        let FileContent::Content(content) = &*source.await? else {
+            return Ok(AssetContent::file(FileContent::NotFound.cell()));
+        };
+        let data = BASE64.encode(&content.content().to_bytes());
+        let code: RcStr = format!(
+            "export default Uint8Array.from(atob({}), (c) => c.charCodeAt(0));",
+            StringifyJs(&data)
+        )
+        .into();
+        let content = FileContent::Content(code.into()).cell();
+        Ok(AssetContent::file(content))
+    }
+}
+
+/// A source asset that exports the content of a CSS file as a constructable `CSSStyleSheet`, i.e. a
+/// CSS module script for `import sheet from "./styles.css" with { type: "css" }`.
+///
+/// Like in browsers, the CSS is not processed and `@import` rules are not supported. The default
+/// export is `null` in environments without `CSSStyleSheet`, e.g. during server rendering.
+#[turbo_tasks::value]
+pub struct StyleSheetContentFileSource {
+    pub source: ResolvedVc<Box<dyn Source>>,
+}
+
+#[turbo_tasks::value_impl]
+impl StyleSheetContentFileSource {
+    #[turbo_tasks::function]
+    pub fn new(source: ResolvedVc<Box<dyn Source>>) -> Vc<Self> {
+        StyleSheetContentFileSource { source }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Source for StyleSheetContentFileSource {
+    #[turbo_tasks::function]
+    fn ident(&self) -> Vc<AssetIdent> {
+        self.source
+            .ident()
+            .with_modifier(rcstr!("style sheet content"))
+            .rename_as(rcstr!("*.mjs"))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for StyleSheetContentFileSource {
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<Vc<AssetContent>> {
+        let source = self.source.content().file_content();
+        let FileContent::Content(content) = &*source.await? else {
+            return Ok(AssetContent::file(FileContent::NotFound.cell()));
+        };
+        let text = content.content().to_str()?;
+        let code: RcStr = formatdoc! {
+            r#"
+                const sheet = typeof CSSStyleSheet === "function" ? new CSSStyleSheet() : null;
+                sheet?.replaceSync({});
+                export default sheet;
+            "#,
+            StringifyJs(&text)
+        }
+        .into();
+        let content = FileContent::Content(code.into()).cell();
+        Ok(AssetContent::file(content))
+    }
+}
+
+/// A source transform that wraps sources into a [TextContentFileSource].
+#[turbo_tasks::value]
+pub struct TextContentSourceTransform;
+
+#[turbo_tasks::value_impl]
+impl TextContentSourceTransform {
+    #[turbo_tasks::function]
+    pub fn new() -> Vc<Self> {
+        TextContentSourceTransform.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl SourceTransform for TextContentSourceTransform {
+    #[turbo_tasks::function]
+    fn transform(&self, source: ResolvedVc<Box<dyn Source>>) -> Vc<Box<dyn Source>> {
+        Vc::upcast(TextContentFileSource::new(*source))
+    }
+}
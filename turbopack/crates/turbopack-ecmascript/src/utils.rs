@@ -13,8 +13,7 @@ use turbo_tasks::{NonLocalValue, TaskInput, trace::TraceRawVcs};
 use turbopack_core::{chunk::ModuleId, resolve::pattern::Pattern};
 
 use crate::analyzer::{
-    ConstantNumber, ConstantValue, JsValue, JsValueUrlKind, ModuleValue, WellKnownFunctionKind,
-    WellKnownObjectKind,
+    ConstantNumber, ConstantValue, JsValue, JsValueUrlKind, ModuleValue, WellKnownObjectKind,
 };
 
 pub fn unparen(expr: &Expr) -> &Expr {
@@ -187,8 +186,9 @@ pub enum AstPathRange {
     StartAfter(#[turbo_tasks(trace_ignore)] Vec<AstParentKind>),
 }
 
-/// Converts a module value (ie an import) to a well known object,
-/// which we specifically handle.
+/// Converts a module value (ie an import) of a Node.js core module to a well known object,
+/// which we specifically handle. Well known npm packages are converted by
+/// [crate::well_known_packages::package_module_value].
 pub fn module_value_to_well_known_object(module_value: &ModuleValue) -> Option<JsValue> {
     Some(match &*module_value.module {
         "node:path" | "path" => JsValue::WellKnownObject(WellKnownObjectKind::PathModule),
@@ -201,19 +201,6 @@ pub fn module_value_to_well_known_object(module_value: &ModuleValue) -> Option<J
         }
         "node:os" | "os" => JsValue::WellKnownObject(WellKnownObjectKind::OsModule),
         "node:process" | "process" => JsValue::WellKnownObject(WellKnownObjectKind::NodeProcess),
-        "node-pre-gyp" | "@mapbox/node-pre-gyp" => {
-            JsValue::WellKnownObject(WellKnownObjectKind::NodePreGyp)
-        }
-        "node-gyp-build" => JsValue::WellKnownFunction(WellKnownFunctionKind::NodeGypBuild),
-        "node:bindings" | "bindings" => {
-            JsValue::WellKnownFunction(WellKnownFunctionKind::NodeBindings)
-        }
-        "express" => JsValue::WellKnownFunction(WellKnownFunctionKind::NodeExpress),
-        "strong-globalize" => {
-            JsValue::WellKnownFunction(WellKnownFunctionKind::NodeStrongGlobalize)
-        }
-        "resolve-from" => JsValue::WellKnownFunction(WellKnownFunctionKind::NodeResolveFrom),
-        "@grpc/proto-loader" => JsValue::WellKnownObject(WellKnownObjectKind::NodeProtobufLoader),
         _ => return None,
     })
 }
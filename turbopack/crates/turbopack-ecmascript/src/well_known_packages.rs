@@ -0,0 +1,350 @@
+use std::sync::LazyLock;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use turbo_rcstr::{RcStr, rcstr};
+use turbo_tasks::{NonLocalValue, Vc, trace::TraceRawVcs};
+use turbo_tasks_fs::{FileJsonContent, FileSystemPath};
+use turbopack_core::issue::{
+    Issue, IssueExt, IssueSeverity, IssueStage, OptionStyledString, StyledString,
+};
+
+use crate::analyzer::{JsValue, ModuleValue, WellKnownFunctionKind, WellKnownObjectKind};
+
+/// Describes npm packages whose exports load files at runtime, in addition to the
+/// [built-in packages](BUILTIN_PACKAGES) like `resolve-from`, `bindings` or `node-gyp-build`.
+///
+/// Calls of the described exports are followed like the built-in ones, so the loaded files are
+/// included in the output.
+#[turbo_tasks::value(transparent)]
+pub struct WellKnownPackages(Vec<WellKnownPackage>);
+
+#[turbo_tasks::value_impl]
+impl WellKnownPackages {
+    #[turbo_tasks::function]
+    pub fn empty() -> Vc<Self> {
+        Vc::cell(Vec::new())
+    }
+
+    /// Reads the packages from a JSON file with an array of [WellKnownPackage]s, e.g.
+    ///
+    /// ```json
+    /// [
+    ///   {
+    ///     "name": "@internal/native-loader",
+    ///     "exports": [
+    ///       { "behavior": { "type": "loadFile", "argument": 0 } },
+    ///       {
+    ///         "export": "resolve",
+    ///         "behavior": {
+    ///           "type": "resolveRequest",
+    ///           "request": 1,
+    ///           "relativeTo": { "argument": 0 }
+    ///         }
+    ///       }
+    ///     ]
+    ///   }
+    /// ]
+    /// ```
+    ///
+    /// A missing or invalid file is reported as an issue that names `config_key`, the option that
+    /// configured the path, and no packages are added.
+    #[turbo_tasks::function]
+    pub async fn from_json(path: FileSystemPath, config_key: RcStr) -> Result<Vc<Self>> {
+        let error = match &*path.read_json().await? {
+            FileJsonContent::Content(value) => match Vec::<WellKnownPackage>::deserialize(value) {
+                Ok(packages) => return Ok(Vc::cell(packages)),
+                Err(err) => err.to_string(),
+            },
+            FileJsonContent::Unparsable(err) => {
+                format!("The file is not valid JSON: {}", err.message)
+            }
+            FileJsonContent::NotFound => "The file does not exist.".to_string(),
+        };
+        WellKnownPackagesIssue {
+            path,
+            config_key,
+            error: error.into(),
+        }
+        .resolved_cell()
+        .emit();
+        Ok(Vc::cell(Vec::new()))
+    }
+}
+
+#[turbo_tasks::value(shared)]
+struct WellKnownPackagesIssue {
+    path: FileSystemPath,
+    config_key: RcStr,
+    error: RcStr,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for WellKnownPackagesIssue {
+    #[turbo_tasks::function]
+    fn stage(&self) -> Vc<IssueStage> {
+        IssueStage::Config.into()
+    }
+
+    fn severity(&self) -> IssueSeverity {
+        IssueSeverity::Error
+    }
+
+    #[turbo_tasks::function]
+    fn file_path(&self) -> Vc<FileSystemPath> {
+        self.path.clone().cell()
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> Vc<StyledString> {
+        StyledString::Line(vec![
+            StyledString::Text(rcstr!("Invalid well known packages configured with ")),
+            StyledString::Code(self.config_key.clone()),
+        ])
+        .cell()
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> Vc<OptionStyledString> {
+        Vc::cell(Some(StyledString::Text(self.error.clone()).resolved_cell()))
+    }
+}
+
+/// Converts an import of a well known package to its value. The configured `packages` take
+/// precedence over the [built-in ones](BUILTIN_PACKAGES).
+pub fn package_module_value(
+    packages: &[WellKnownPackage],
+    module_value: &ModuleValue,
+) -> Option<JsValue> {
+    let module = module_value.module.as_str();
+    let name = module.strip_prefix("node:").unwrap_or(module);
+    match packages
+        .iter()
+        .chain(BUILTIN_PACKAGES.iter())
+        .find(|package| *package.name == *name)
+    {
+        Some(package) => Some(package.to_js_value(None)),
+        None => builtin_package_value(name),
+    }
+}
+
+/// The packages the analyzer knows about without configuration. The packages that a
+/// [WellKnownPackage] can't describe are handled by [builtin_package_value].
+static BUILTIN_PACKAGES: LazyLock<[WellKnownPackage; 1]> = LazyLock::new(|| {
+    // `resolveFrom(fromDirectory, moduleId)`. The directory is usually computed at runtime, so
+    // the request is resolved relative to the calling module.
+    let resolve_from = WellKnownExportBehavior::ResolveRequest {
+        request: 1,
+        relative_to: RelativeTo::CallingModule,
+    };
+    [WellKnownPackage {
+        name: rcstr!("resolve-from"),
+        exports: vec![
+            WellKnownPackageExport {
+                export: None,
+                behavior: resolve_from.clone(),
+            },
+            WellKnownPackageExport {
+                export: Some(rcstr!("silent")),
+                behavior: resolve_from,
+            },
+        ],
+    }]
+});
+
+/// The built-in packages whose exports need handling that a [WellKnownPackage] can't describe,
+/// e.g. the lookup of the native addon by `bindings`.
+fn builtin_package_value(name: &str) -> Option<JsValue> {
+    Some(match name {
+        "node-pre-gyp" | "@mapbox/node-pre-gyp" => {
+            JsValue::WellKnownObject(WellKnownObjectKind::NodePreGyp)
+        }
+        "node-gyp-build" => JsValue::WellKnownFunction(WellKnownFunctionKind::NodeGypBuild),
+        "bindings" => JsValue::WellKnownFunction(WellKnownFunctionKind::NodeBindings),
+        "express" => JsValue::WellKnownFunction(WellKnownFunctionKind::NodeExpress),
+        "strong-globalize" => {
+            JsValue::WellKnownFunction(WellKnownFunctionKind::NodeStrongGlobalize)
+        }
+        "@grpc/proto-loader" => JsValue::WellKnownObject(WellKnownObjectKind::NodeProtobufLoader),
+        _ => return None,
+    })
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs, NonLocalValue)]
+#[serde(rename_all = "camelCase")]
+pub struct WellKnownPackage {
+    /// The request that imports the package, e.g. `native-loader` or `native-loader/sync`.
+    pub name: RcStr,
+    pub exports: Vec<WellKnownPackageExport>,
+}
+
+impl WellKnownPackage {
+    /// Returns the export with the name, where `None` and `default` refer to the value of the
+    /// module itself.
+    pub fn export(&self, name: Option<&str>) -> Option<&WellKnownPackageExport> {
+        let name = name.filter(|name| *name != "default");
+        self.exports
+            .iter()
+            .find(|export| export.export.as_deref() == name)
+    }
+
+    /// The value of the export `export` of the package. The package itself is a function when
+    /// it has an export without name, an object otherwise.
+    pub fn to_js_value(&self, export: Option<&str>) -> JsValue {
+        let export = export.filter(|name| *name != "default");
+        if export.is_none() && self.export(None).is_none() {
+            return JsValue::WellKnownObject(WellKnownObjectKind::Package(Box::new(self.clone())));
+        }
+        JsValue::WellKnownFunction(WellKnownFunctionKind::PackageExport(
+            Box::new(self.clone()),
+            export.map(RcStr::from),
+        ))
+    }
+
+    /// Describes the call of the export for warnings, e.g. `require('loader').load`.
+    pub fn describe_export(&self, export: Option<&str>) -> String {
+        match export {
+            Some(export) => format!("require('{}').{export}", self.name),
+            None => format!("require('{}')", self.name),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs, NonLocalValue)]
+#[serde(rename_all = "camelCase")]
+pub struct WellKnownPackageExport {
+    /// The name of the export. `None` when the module itself is called, e.g.
+    /// `require('bindings')(...)`.
+    #[serde(default)]
+    pub export: Option<RcStr>,
+    pub behavior: WellKnownExportBehavior,
+}
+
+/// What calling an export of a [WellKnownPackage] does. Arguments are 0-based indices.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, Serialize, Deserialize, TraceRawVcs, NonLocalValue)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum WellKnownExportBehavior {
+    /// Loads the file at the path passed as `argument`, e.g.
+    /// `load(path.join(__dirname, 'binding.node'))`. Relative paths are relative to the calling
+    /// module.
+    LoadFile { argument: u32 },
+    /// Reads files from the directory passed as `argument`, e.g. a directory of templates or
+    /// translations. Relative paths are relative to the calling module.
+    LoadDirectory { argument: u32 },
+    /// Resolves the request passed as `request` like `require()` does, but relative to
+    /// `relative_to`, and loads the result.
+    #[serde(rename_all = "camelCase")]
+    ResolveRequest {
+        request: u32,
+        #[serde(default)]
+        relative_to: RelativeTo,
+    },
+}
+
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Default,
+    Hash,
+    PartialEq,
+    Eq,
+    Serialize,
+    Deserialize,
+    TraceRawVcs,
+    NonLocalValue,
+)]
+#[serde(rename_all = "camelCase")]
+pub enum RelativeTo {
+    /// The directory of the calling module.
+    #[default]
+    CallingModule,
+    /// The directory passed as the argument. Relative paths are relative to the calling module.
+    /// Paths that start with `/ROOT/` are relative to the root of the file system: the analyzer
+    /// links `__dirname` and `__filename` to such paths.
+    Argument(u32),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_config() {
+        let packages: Vec<WellKnownPackage> = serde_json::from_str(
+            r#"[
+                {
+                    "name": "native-loader",
+                    "exports": [
+                        { "behavior": { "type": "loadFile", "argument": 0 } },
+                        {
+                            "export": "from",
+                            "behavior": {
+                                "type": "resolveRequest",
+                                "request": 1,
+                                "relativeTo": { "argument": 0 }
+                            }
+                        },
+                        {
+                            "export": "locales",
+                            "behavior": { "type": "loadDirectory", "argument": 0 }
+                        }
+                    ]
+                }
+            ]"#,
+        )
+        .unwrap();
+        let package = &packages[0];
+        assert_eq!(
+            package.export(Some("default")).unwrap().behavior,
+            WellKnownExportBehavior::LoadFile { argument: 0 }
+        );
+        assert_eq!(
+            package.export(Some("from")).unwrap().behavior,
+            WellKnownExportBehavior::ResolveRequest {
+                request: 1,
+                relative_to: RelativeTo::Argument(0)
+            }
+        );
+        assert!(package.export(Some("unknown")).is_none());
+        assert!(matches!(
+            package.to_js_value(None),
+            JsValue::WellKnownFunction(WellKnownFunctionKind::PackageExport(_, None))
+        ));
+    }
+
+    #[test]
+    fn configured_packages_override_builtin_ones() {
+        let module_value = |module: &str| ModuleValue {
+            module: module.into(),
+            annotations: Default::default(),
+        };
+        assert!(matches!(
+            package_module_value(&[], &module_value("node:bindings")),
+            Some(JsValue::WellKnownFunction(
+                WellKnownFunctionKind::NodeBindings
+            ))
+        ));
+        assert!(matches!(
+            package_module_value(&[], &module_value("resolve-from")),
+            Some(JsValue::WellKnownFunction(
+                WellKnownFunctionKind::PackageExport(package, None)
+            )) if package.export(Some("silent")).is_some()
+        ));
+        assert!(package_module_value(&[], &module_value("fs")).is_none());
+
+        let packages = [WellKnownPackage {
+            name: "bindings".into(),
+            exports: vec![WellKnownPackageExport {
+                export: None,
+                behavior: WellKnownExportBehavior::LoadFile { argument: 0 },
+            }],
+        }];
+        assert!(matches!(
+            package_module_value(&packages, &module_value("bindings")),
+            Some(JsValue::WellKnownFunction(
+                WellKnownFunctionKind::PackageExport(_, None)
+            ))
+        ));
+    }
+}
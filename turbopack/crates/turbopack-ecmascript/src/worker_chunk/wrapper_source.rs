@@ -0,0 +1,78 @@
+use anyhow::Result;
+use turbo_rcstr::{RcStr, rcstr};
+use turbo_tasks::{ResolvedVc, Vc};
+use turbo_tasks_fs::{File, FileContent};
+use turbopack_core::{
+    asset::{Asset, AssetContent},
+    ident::AssetIdent,
+    source::Source,
+    source_transform::SourceTransform,
+};
+
+use crate::utils::StringifyJs;
+
+/// A source asset that exports a function constructing a `Worker` for the
+/// wrapped source as the default export of a JS module. The generated
+/// `new Worker(new URL(...))` expression is picked up by the regular worker
+/// analysis.
+#[turbo_tasks::value]
+pub struct WorkerWrapperFileSource {
+    pub source: ResolvedVc<Box<dyn Source>>,
+}
+
+#[turbo_tasks::value_impl]
+impl WorkerWrapperFileSource {
+    #[turbo_tasks::function]
+    pub fn new(source: ResolvedVc<Box<dyn Source>>) -> Vc<Self> {
+        WorkerWrapperFileSource { source }.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Source for WorkerWrapperFileSource {
+    #[turbo_tasks::function]
+    fn ident(&self) -> Vc<AssetIdent> {
+        self.source
+            .ident()
+            .with_modifier(rcstr!("worker wrapper"))
+            .rename_as(rcstr!("*.mjs"))
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl Asset for WorkerWrapperFileSource {
+    #[turbo_tasks::function]
+    async fn content(&self) -> Result<Vc<AssetContent>> {
+        let path = self.source.ident().path().await?;
+        let request = format!("./{}", path.file_name());
+        let code: RcStr = format!(
+            "export default function WorkerWrapper(options) {{\n  return new Worker(new URL({}, \
+             import.meta.url), options);\n}}\n",
+            StringifyJs(&request)
+        )
+        .into();
+        Ok(AssetContent::file(
+            FileContent::Content(File::from(code)).cell(),
+        ))
+    }
+}
+
+/// A source transform that wraps sources into a [WorkerWrapperFileSource].
+#[turbo_tasks::value]
+pub struct WorkerWrapperSourceTransform;
+
+#[turbo_tasks::value_impl]
+impl WorkerWrapperSourceTransform {
+    #[turbo_tasks::function]
+    pub fn new() -> Vc<Self> {
+        WorkerWrapperSourceTransform.cell()
+    }
+}
+
+#[turbo_tasks::value_impl]
+impl SourceTransform for WorkerWrapperSourceTransform {
+    #[turbo_tasks::function]
+    fn transform(&self, source: ResolvedVc<Box<dyn Source>>) -> Vc<Box<dyn Source>> {
+        Vc::upcast(WorkerWrapperFileSource::new(*source))
+    }
+}
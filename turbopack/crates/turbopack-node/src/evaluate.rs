@@ -1,4 +1,4 @@
-use std::{borrow::Cow, iter, ops::ControlFlow, thread::available_parallelism, time::Duration};
+use std::{borrow::Cow, iter, ops::ControlFlow, time::Duration};
 
 use anyhow::{Result, anyhow, bail};
 use async_stream::try_stream as generator;
@@ -43,7 +43,7 @@ use crate::{
     AssetsForSourceMapping,
     embed_js::embed_file_path,
     emit, emit_package_json, internal_assets_for_source_mapping,
-    pool::{FormattingMode, NodeJsOperation, NodeJsPool},
+    pool::{FormattingMode, NodeJsOperation, NodeJsPool, NodeJsPoolOptions},
     source_map::StructuredError,
 };
 
@@ -62,9 +62,19 @@ enum EvalJavaScriptOutgoingMessage<'a> {
 #[derive(Deserialize, Debug)]
 #[serde(tag = "type", rename_all = "camelCase")]
 enum EvalJavaScriptIncomingMessage {
-    Info { data: JsonValue },
-    Request { id: u64, data: JsonValue },
-    End { data: Option<String> },
+    Info {
+        data: JsonValue,
+    },
+    Request {
+        id: u64,
+        data: JsonValue,
+    },
+    #[serde(rename_all = "camelCase")]
+    End {
+        data: Option<String>,
+        /// The resident set size of the process in bytes.
+        memory_usage: Option<u64>,
+    },
     Error(StructuredError),
 }
 
@@ -242,6 +252,7 @@ pub async fn get_evaluate_pool(
     additional_invalidation: ResolvedVc<Completion>,
     debug: bool,
     env_var_tracking: EnvVarTracking,
+    pool_options: NodeJsPoolOptions,
 ) -> Result<Vc<NodeJsPool>> {
     let EmittedEvaluatePoolAssets {
         bootstrap,
@@ -288,7 +299,7 @@ pub async fn get_evaluate_pool(
         assets_for_source_mapping,
         output_root.clone(),
         chunking_context.root_path().owned().await?,
-        available_parallelism().map_or(1, |v| v.get()),
+        pool_options,
         debug,
     );
     additional_invalidation.await?;
@@ -436,6 +447,7 @@ pub async fn evaluate(
     args: Vec<ResolvedVc<JsonValue>>,
     additional_invalidation: ResolvedVc<Completion>,
     debug: bool,
+    pool_options: NodeJsPoolOptions,
 ) -> Result<Vc<JavaScriptEvaluation>> {
     custom_evaluate(BasicEvaluateContext {
         module_asset,
@@ -448,6 +460,7 @@ pub async fn evaluate(
         args,
         additional_invalidation,
         debug,
+        pool_options,
     })
     .await
 }
@@ -560,7 +573,12 @@ async fn pull_operation<T: EvaluateContext>(
                 // Issue emitted, we want to break but don't want to return an error
                 break ControlFlow::Break(Ok(None));
             }
-            EvalJavaScriptIncomingMessage::End { data } => break ControlFlow::Break(Ok(data)),
+            EvalJavaScriptIncomingMessage::End { data, memory_usage } => {
+                if let Some(memory_usage) = memory_usage {
+                    operation.record_memory_usage(memory_usage);
+                }
+                break ControlFlow::Break(Ok(data));
+            }
             EvalJavaScriptIncomingMessage::Info { data } => {
                 evaluate_context
                     .info(state, serde_json::from_value(data)?, pool)
@@ -618,6 +636,7 @@ struct BasicEvaluateContext {
     args: Vec<ResolvedVc<JsonValue>>,
     additional_invalidation: ResolvedVc<Completion>,
     debug: bool,
+    pool_options: NodeJsPoolOptions,
 }
 
 impl EvaluateContext for BasicEvaluateContext {
@@ -641,6 +660,7 @@ impl EvaluateContext for BasicEvaluateContext {
             self.additional_invalidation,
             self.debug,
             EnvVarTracking::WholeEnvTracked,
+            self.pool_options.clone(),
         )
     }
 
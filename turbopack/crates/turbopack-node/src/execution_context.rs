@@ -3,11 +3,16 @@ use turbo_tasks_env::ProcessEnv;
 use turbo_tasks_fs::FileSystemPath;
 use turbopack_core::chunk::ChunkingContext;
 
+use crate::NodeJsPoolOptions;
+
 #[turbo_tasks::value]
 pub struct ExecutionContext {
     pub project_path: FileSystemPath,
     pub chunking_context: ResolvedVc<Box<dyn ChunkingContext>>,
     pub env: ResolvedVc<Box<dyn ProcessEnv>>,
+    /// The limits for the Node.js workers that evaluate code in this context, e.g. webpack loaders
+    /// and PostCSS.
+    pub node_pool_options: NodeJsPoolOptions,
 }
 
 #[turbo_tasks::value_impl]
@@ -22,6 +27,18 @@ impl ExecutionContext {
             project_path,
             chunking_context,
             env,
+            node_pool_options: Default::default(),
+        }
+        .cell()
+    }
+
+    #[turbo_tasks::function]
+    pub fn with_node_pool_options(&self, node_pool_options: NodeJsPoolOptions) -> Vc<Self> {
+        ExecutionContext {
+            project_path: self.project_path.clone(),
+            chunking_context: self.chunking_context,
+            env: self.env,
+            node_pool_options,
         }
         .cell()
     }
@@ -2,7 +2,7 @@
 #![feature(arbitrary_self_types)]
 #![feature(arbitrary_self_types_pointers)]
 
-use std::{iter::once, thread::available_parallelism};
+use std::iter::once;
 
 use anyhow::{Result, bail};
 pub use node_entry::{NodeEntry, NodeRenderingEntries, NodeRenderingEntry};
@@ -25,6 +25,7 @@ use turbopack_core::{
     virtual_output::VirtualOutputAsset,
 };
 
+pub use self::pool::NodeJsPoolOptions;
 use self::{pool::NodeJsPool, source_map::StructuredError};
 
 pub mod debug;
@@ -250,7 +251,7 @@ pub async fn get_renderer_pool_operation(
         assets_for_source_mapping.to_resolved().await?,
         output_root,
         project_dir,
-        available_parallelism().map_or(1, |v| v.get()),
+        NodeJsPoolOptions::default(),
         debug,
     )
     .cell())
@@ -7,17 +7,18 @@ use std::{
     mem::take,
     path::{Path, PathBuf},
     process::{ExitStatus, Stdio},
-    sync::Arc,
+    sync::{Arc, Weak},
+    thread::available_parallelism,
     time::{Duration, Instant},
 };
 
-use anyhow::{Context, Result, bail};
+use anyhow::{Context, Result, anyhow, bail};
 use futures::join;
 use once_cell::sync::Lazy;
 use owo_colors::{OwoColorize, Style};
 use parking_lot::Mutex;
 use rustc_hash::FxHashMap;
-use serde::{Serialize, de::DeserializeOwned};
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use tokio::{
     io::{
         AsyncBufReadExt, AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt, BufReader, Stderr,
@@ -27,10 +28,12 @@ use tokio::{
     process::{Child, ChildStderr, ChildStdout, Command},
     select,
     sync::{OwnedSemaphorePermit, Semaphore},
-    time::{sleep, timeout},
+    time::{sleep, sleep_until, timeout},
 };
 use turbo_rcstr::RcStr;
-use turbo_tasks::{FxIndexSet, ResolvedVc, Vc, duration_span};
+use turbo_tasks::{
+    FxIndexSet, NonLocalValue, ResolvedVc, TaskInput, Vc, duration_span, trace::TraceRawVcs,
+};
 use turbo_tasks_fs::{FileSystemPath, json::parse_json_with_source_context};
 use turbopack_ecmascript::magic_identifier::unmangle_identifiers;
 
@@ -79,6 +82,10 @@ struct NodeJsPoolProcess {
     stderr_handler: OutputStreamHandler<ChildStderr, Stderr>,
     debug: bool,
     cpu_time_invested: Duration,
+    /// The number of operations this process has completed.
+    operations: u32,
+    /// When the process was returned to the pool the last time.
+    idle_since: Instant,
 }
 
 impl Ord for NodeJsPoolProcess {
@@ -463,6 +470,8 @@ impl NodeJsPoolProcess {
             stderr_handler,
             debug,
             cpu_time_invested: Duration::ZERO,
+            operations: 0,
+            idle_since: Instant::now(),
         };
 
         drop(guard);
@@ -706,6 +715,37 @@ enum AcquiredPermits {
     },
 }
 
+/// Limits for the workers of a [`NodeJsPool`]. All limits are disabled by default.
+#[derive(
+    Clone,
+    Debug,
+    Default,
+    PartialEq,
+    Eq,
+    Hash,
+    TaskInput,
+    Serialize,
+    Deserialize,
+    TraceRawVcs,
+    NonLocalValue,
+)]
+pub struct NodeJsPoolOptions {
+    /// The maximum number of concurrently running workers. Defaults to the available parallelism.
+    pub max_workers: Option<usize>,
+    /// Idle workers are stopped when they weren't used for this long.
+    pub idle_timeout: Option<Duration>,
+    /// An operation fails when it takes longer than this, and its worker is stopped. The time is
+    /// measured from acquiring the worker, so it covers all messages of the operation, including
+    /// the time the caller spends between sending and receiving them.
+    pub operation_timeout: Option<Duration>,
+    /// A worker is replaced after an operation when its resident set size exceeds this number of
+    /// bytes. Only applies to evaluation pools, which report the memory usage of the worker at the
+    /// end of each operation.
+    pub max_worker_memory: Option<u64>,
+    /// A worker is replaced after completing this many operations.
+    pub max_operations_per_worker: Option<u32>,
+}
+
 type IdleProcessesList = Arc<Mutex<BinaryHeap<NodeJsPoolProcess>>>;
 
 /// All non-empty `IdleProcessesList`s of the whole application.
@@ -716,8 +756,8 @@ static ACTIVE_POOLS: Lazy<Mutex<Vec<IdleProcessesList>>> = Lazy::new(Default::de
 /// [env].
 ///
 /// The pool will spawn processes when needed and reuses old ones. It will never
-/// spawn more then a certain number of concurrent processes. This is specified
-/// with the `concurrency` argument in the constructor.
+/// spawn more then a certain number of concurrent processes. This and other
+/// limits are specified with [NodeJsPoolOptions] in the constructor.
 ///
 /// The worker will *not* use the env of the parent process by default. All env
 /// vars need to be provided to make the execution as pure as possible.
@@ -746,13 +786,14 @@ pub struct NodeJsPool {
     #[turbo_tasks(trace_ignore, debug_ignore)]
     shared_stderr: SharedOutputSet,
     debug: bool,
+    options: NodeJsPoolOptions,
     #[turbo_tasks(trace_ignore, debug_ignore)]
     stats: Arc<Mutex<NodeJsPoolStats>>,
 }
 
 impl NodeJsPool {
     /// * debug: Whether to automatically enable Node's `--inspect-brk` when spawning it. Note:
-    ///   automatically overrides concurrency to 1.
+    ///   automatically overrides concurrency to 1 and disables the operation timeout.
     pub(super) fn new(
         cwd: PathBuf,
         entrypoint: PathBuf,
@@ -760,9 +801,26 @@ impl NodeJsPool {
         assets_for_source_mapping: ResolvedVc<AssetsForSourceMapping>,
         assets_root: FileSystemPath,
         project_dir: FileSystemPath,
-        concurrency: usize,
+        options: NodeJsPoolOptions,
         debug: bool,
     ) -> Self {
+        let concurrency = options
+            .max_workers
+            .unwrap_or_else(|| available_parallelism().map_or(1, |v| v.get()))
+            .max(1);
+        let processes = Arc::new(Mutex::new(BinaryHeap::new()));
+        let idle_process_semaphore = Arc::new(Semaphore::new(0));
+        let stats: Arc<Mutex<NodeJsPoolStats>> = Default::default();
+        if let Some(idle_timeout) = options.idle_timeout
+            && let Ok(handle) = tokio::runtime::Handle::try_current()
+        {
+            handle.spawn(reap_idle_processes(
+                Arc::downgrade(&processes),
+                idle_process_semaphore.clone(),
+                stats.clone(),
+                idle_timeout,
+            ));
+        }
         Self {
             cwd,
             entrypoint,
@@ -770,14 +828,15 @@ impl NodeJsPool {
             assets_for_source_mapping,
             assets_root,
             project_dir,
-            processes: Arc::new(Mutex::new(BinaryHeap::new())),
+            processes,
             concurrency_semaphore: Arc::new(Semaphore::new(if debug { 1 } else { concurrency })),
             bootup_semaphore: Arc::new(Semaphore::new(1)),
-            idle_process_semaphore: Arc::new(Semaphore::new(0)),
+            idle_process_semaphore,
             shared_stdout: Arc::new(Mutex::new(FxIndexSet::default())),
             shared_stderr: Arc::new(Mutex::new(FxIndexSet::default())),
             debug,
-            stats: Default::default(),
+            options,
+            stats,
         }
     }
 
@@ -861,6 +920,14 @@ impl NodeJsPool {
             start: Instant::now(),
             stats: self.stats.clone(),
             allow_process_reuse: true,
+            deadline: if self.debug {
+                None
+            } else {
+                self.options
+                    .operation_timeout
+                    .and_then(|operation_timeout| Instant::now().checked_add(operation_timeout))
+            },
+            options: self.options.clone(),
         })
     }
 
@@ -885,6 +952,69 @@ impl NodeJsPool {
     }
 }
 
+/// Stops the idle processes of a pool after `idle_timeout`. There is a single reaper per pool, it
+/// ends when the pool is dropped and all its processes are stopped.
+async fn reap_idle_processes(
+    processes: Weak<Mutex<BinaryHeap<NodeJsPoolProcess>>>,
+    idle_process_semaphore: Arc<Semaphore>,
+    stats: Arc<Mutex<NodeJsPoolStats>>,
+    idle_timeout: Duration,
+) {
+    loop {
+        let next_expiry = {
+            let Some(processes) = processes.upgrade() else {
+                return;
+            };
+            stop_idle_processes(&processes, &idle_process_semaphore, &stats, idle_timeout)
+        };
+        let Some(next_check) = next_expiry.or_else(|| Instant::now().checked_add(idle_timeout))
+        else {
+            // The timeout is too large to ever expire
+            return;
+        };
+        sleep_until(next_check.into()).await;
+    }
+}
+
+/// Stops the processes of the pool that have been idle for at least `idle_timeout`. Returns when
+/// the next of the remaining processes reaches the `idle_timeout`.
+fn stop_idle_processes(
+    processes: &IdleProcessesList,
+    idle_process_semaphore: &Semaphore,
+    stats: &Mutex<NodeJsPoolStats>,
+    idle_timeout: Duration,
+) -> Option<Instant> {
+    let mut idle_processes = processes.lock();
+    if idle_processes.is_empty() {
+        return None;
+    }
+    let mut next_expiry = None;
+    idle_processes.retain(|process| {
+        let Some(expiry) = process.idle_since.checked_add(idle_timeout) else {
+            return true;
+        };
+        if Instant::now() < expiry {
+            next_expiry = Some(next_expiry.map_or(expiry, |next: Instant| next.min(expiry)));
+            return true;
+        }
+        // Every idle process has a permit. When none is available, the remaining processes are
+        // about to be taken by operations that already acquired their permit.
+        let Ok(permit) = idle_process_semaphore.try_acquire() else {
+            return true;
+        };
+        permit.forget();
+        stats.lock().remove_worker();
+        false
+    });
+    if idle_processes.is_empty() {
+        let mut pools = ACTIVE_POOLS.lock();
+        if let Some(idx) = pools.iter().position(|p| Arc::ptr_eq(p, processes)) {
+            pools.swap_remove(idx);
+        }
+    }
+    next_expiry
+}
+
 pub struct NodeJsOperation {
     process: Option<NodeJsPoolProcess>,
     // This is used for drop
@@ -895,6 +1025,9 @@ pub struct NodeJsOperation {
     start: Instant,
     stats: Arc<Mutex<NodeJsPoolStats>>,
     allow_process_reuse: bool,
+    /// When the operation times out, see [NodeJsPoolOptions::operation_timeout].
+    deadline: Option<Instant>,
+    options: NodeJsPoolOptions,
 }
 
 impl NodeJsOperation {
@@ -911,7 +1044,18 @@ impl NodeJsOperation {
             bail!("Node.js process is no longer usable");
         }
 
-        let result = f(process).await;
+        let result = if let Some(deadline) = self.deadline {
+            tokio::time::timeout_at(deadline.into(), f(process))
+                .await
+                .unwrap_or_else(|_| {
+                    Err(anyhow!(
+                        "Node.js operation timed out after {:?}",
+                        self.options.operation_timeout.unwrap_or_default()
+                    ))
+                })
+        } else {
+            f(process).await
+        };
         if result.is_err() && self.allow_process_reuse {
             self.stats.lock().remove_worker();
             self.allow_process_reuse = false;
@@ -979,6 +1123,18 @@ impl NodeJsOperation {
         }
     }
 
+    /// Records the memory usage the process reported at the end of an evaluation. The process isn't
+    /// reused when it's above [NodeJsPoolOptions::max_worker_memory].
+    pub fn record_memory_usage(&mut self, bytes: u64) {
+        if self
+            .options
+            .max_worker_memory
+            .is_some_and(|max_worker_memory| bytes > max_worker_memory)
+        {
+            self.disallow_reuse();
+        }
+    }
+
     pub async fn apply_source_mapping<'a>(
         &self,
         text: &'a str,
@@ -1005,6 +1161,17 @@ impl Drop for NodeJsOperation {
             }
             if self.allow_process_reuse {
                 process.cpu_time_invested += elapsed;
+                process.operations += 1;
+                if self
+                    .options
+                    .max_operations_per_worker
+                    .is_some_and(|max_operations| process.operations >= max_operations)
+                {
+                    // The process is killed when dropped, a new one is started when needed
+                    self.stats.lock().remove_worker();
+                    return;
+                }
+                process.idle_since = Instant::now();
                 {
                     let mut processes = self.processes.lock();
                     if processes.is_empty() {
@@ -1017,3 +1184,166 @@ impl Drop for NodeJsOperation {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::Result;
+    use rustc_hash::FxHashMap;
+    use serde_json::json;
+    use tokio::time::sleep;
+    use turbo_tasks::Vc;
+    use turbo_tasks_fs::VirtualFileSystem;
+
+    use super::{NodeJsOperation, NodeJsPool, NodeJsPoolOptions};
+    use crate::AssetsForSourceMapping;
+
+    /// A worker speaking the protocol of `js/src/ipc/index.ts`. It replies to every message with
+    /// its pid, after waiting for `sleep` milliseconds.
+    const WORKER: &str = r#"
+const socket = require("net").createConnection(Number(process.argv[2]), "127.0.0.1");
+function send(message) {
+  const packet = Buffer.from("0000" + message, "utf8");
+  packet.writeUInt32BE(packet.length - 4, 0);
+  socket.write(packet, () => {
+    process.stderr.write("TURBOPACK_OUTPUT_D\n");
+    process.stdout.write("TURBOPACK_OUTPUT_D\n");
+  });
+}
+let buffer = Buffer.alloc(0);
+socket.on("data", (chunk) => {
+  buffer = Buffer.concat([buffer, chunk]);
+  while (buffer.length >= 4 && buffer.length >= 4 + buffer.readUInt32BE(0)) {
+    const length = buffer.readUInt32BE(0);
+    const message = JSON.parse(buffer.subarray(4, 4 + length).toString("utf8"));
+    buffer = buffer.subarray(4 + length);
+    setTimeout(() => send(JSON.stringify(process.pid)), message.sleep);
+  }
+});
+socket.on("close", () => process.exit(0));
+send("");
+"#;
+
+    async fn pool(dir: &tempfile::TempDir, options: NodeJsPoolOptions) -> Result<NodeJsPool> {
+        let entrypoint = dir.path().join("worker.js");
+        std::fs::write(&entrypoint, WORKER)?;
+        let root = VirtualFileSystem::new().root().owned().await?;
+        Ok(NodeJsPool::new(
+            dir.path().to_path_buf(),
+            entrypoint,
+            FxHashMap::default(),
+            Vc::<AssetsForSourceMapping>::cell(FxHashMap::default())
+                .to_resolved()
+                .await?,
+            root.clone(),
+            root,
+            options,
+            false,
+        ))
+    }
+
+    /// Runs an operation that waits `sleep` milliseconds in the worker. Returns the pid of the
+    /// worker, and calls `f` with the operation before it's returned to the pool.
+    async fn run(
+        pool: &NodeJsPool,
+        sleep: u64,
+        f: impl FnOnce(&mut NodeJsOperation),
+    ) -> Result<u32> {
+        let mut operation = pool.operation().await?;
+        operation.send(json!({ "sleep": sleep })).await?;
+        let pid = operation.recv().await?;
+        f(&mut operation);
+        Ok(pid)
+    }
+
+    fn run_test(test: impl AsyncFnOnce(tempfile::TempDir) -> Result<()>) {
+        crate::register();
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime
+            .block_on(turbo_tasks_testing::VcStorage::with(async move {
+                test(tempfile::tempdir()?).await
+            }))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_reuses_workers() {
+        run_test(async |dir| {
+            let pool = pool(&dir, NodeJsPoolOptions::default()).await?;
+            let pid = run(&pool, 0, |_| {}).await?;
+            assert_eq!(run(&pool, 0, |_| {}).await?, pid);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_operation_timeout() {
+        run_test(async |dir| {
+            let options = NodeJsPoolOptions {
+                operation_timeout: Some(Duration::from_millis(200)),
+                ..Default::default()
+            };
+            let pool = pool(&dir, options).await?;
+            let err = run(&pool, 5000, |_| {}).await.unwrap_err();
+            assert!(err.to_string().contains("timed out"), "{err:?}");
+            // The worker that timed out isn't reused
+            assert!(pool.processes.lock().is_empty());
+            run(&pool, 0, |_| {}).await?;
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_max_operations_per_worker() {
+        run_test(async |dir| {
+            let options = NodeJsPoolOptions {
+                max_operations_per_worker: Some(2),
+                ..Default::default()
+            };
+            let pool = pool(&dir, options).await?;
+            let pid = run(&pool, 0, |_| {}).await?;
+            assert_eq!(run(&pool, 0, |_| {}).await?, pid);
+            assert_ne!(run(&pool, 0, |_| {}).await?, pid);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_idle_timeout() {
+        run_test(async |dir| {
+            let options = NodeJsPoolOptions {
+                idle_timeout: Some(Duration::from_millis(100)),
+                ..Default::default()
+            };
+            let pool = pool(&dir, options).await?;
+            let pid = run(&pool, 0, |_| {}).await?;
+            assert_eq!(pool.processes.lock().len(), 1);
+            sleep(Duration::from_millis(500)).await;
+            assert!(pool.processes.lock().is_empty());
+            assert_ne!(run(&pool, 0, |_| {}).await?, pid);
+            Ok(())
+        });
+    }
+
+    #[test]
+    fn test_max_worker_memory() {
+        run_test(async |dir| {
+            let options = NodeJsPoolOptions {
+                max_worker_memory: Some(1024),
+                ..Default::default()
+            };
+            let pool = pool(&dir, options).await?;
+            let pid = run(&pool, 0, |operation| operation.record_memory_usage(1024)).await?;
+            assert_eq!(
+                run(&pool, 0, |operation| operation.record_memory_usage(1025)).await?,
+                pid
+            );
+            assert_ne!(run(&pool, 0, |_| {}).await?, pid);
+            Ok(())
+        });
+    }
+}
@@ -458,6 +458,7 @@ impl PostCssTransformedAsset {
             project_path,
             chunking_context,
             env,
+            node_pool_options,
         } = &*self.execution_context.await?;
 
         // For this postcss transform, there is no guarantee that looking up for the
@@ -533,6 +534,7 @@ impl PostCssTransformedAsset {
                 ResolvedVc::cell(source_map.into()),
             ],
             additional_invalidation: config_changed,
+            pool_options: node_pool_options.clone(),
         })
         .await?;
 
@@ -57,7 +57,7 @@ use crate::{
         JavaScriptStreamSender, compute, custom_evaluate, get_evaluate_pool,
     },
     execution_context::ExecutionContext,
-    pool::{FormattingMode, NodeJsPool},
+    pool::{FormattingMode, NodeJsPool, NodeJsPoolOptions},
     source_map::{StackFrame, StructuredError},
 };
 
@@ -210,6 +210,7 @@ impl WebpackLoadersProcessedAsset {
             project_path,
             chunking_context,
             env,
+            node_pool_options,
         } = &*transform.execution_context.await?;
         let source_content = this.source.content();
         let AssetContent::File(file) = *source_content.await? else {
@@ -269,6 +270,7 @@ impl WebpackLoadersProcessedAsset {
                 ResolvedVc::cell(transform.source_maps.into()),
             ],
             additional_invalidation: Completion::immutable().to_resolved().await?,
+            pool_options: node_pool_options.clone(),
         })
         .await?;
 
@@ -424,6 +426,7 @@ pub struct WebpackLoaderContext {
     pub resolve_options_context: Option<ResolvedVc<ResolveOptionsContext>>,
     pub args: Vec<ResolvedVc<JsonValue>>,
     pub additional_invalidation: ResolvedVc<Completion>,
+    pub pool_options: NodeJsPoolOptions,
 }
 
 impl EvaluateContext for WebpackLoaderContext {
@@ -452,6 +455,7 @@ impl EvaluateContext for WebpackLoaderContext {
             // vars only. So the runtime code tracks which env vars are read and send a dependency
             // message for them.
             EnvVarTracking::Untracked,
+            self.pool_options.clone(),
         )
     }
 
@@ -17,7 +17,7 @@ use crate::NodeJsChunkingContext;
 
 /// Production Ecmascript chunk targeting Node.js.
 #[turbo_tasks::value(shared)]
-pub(crate) struct EcmascriptBuildNodeChunk {
+pub struct EcmascriptBuildNodeChunk {
     chunking_context: ResolvedVc<NodeJsChunkingContext>,
     chunk: ResolvedVc<EcmascriptChunk>,
 }
@@ -72,6 +72,11 @@ impl EcmascriptBuildNodeChunk {
             self.source_map(),
         ))
     }
+
+    #[turbo_tasks::function]
+    pub fn chunk(&self) -> Result<Vc<Box<dyn Chunk>>> {
+        Ok(Vc::upcast(*self.chunk))
+    }
 }
 
 #[turbo_tasks::value_impl]
@@ -85,6 +90,11 @@ impl OutputAsset for EcmascriptBuildNodeChunk {
             .chunk_path(Some(Vc::upcast(self)), ident, None, rcstr!(".js")))
     }
 
+    #[turbo_tasks::function]
+    fn size_bytes(self: Vc<Self>) -> Vc<Option<u64>> {
+        self.own_content().content().len()
+    }
+
     #[turbo_tasks::function]
     async fn references(self: Vc<Self>) -> Result<Vc<OutputAssets>> {
         let this = self.await?;
@@ -6,6 +6,7 @@ pub(crate) mod chunking_context;
 pub(crate) mod ecmascript;
 
 pub use chunking_context::{NodeJsChunkingContext, NodeJsChunkingContextBuilder};
+pub use ecmascript::node::chunk::EcmascriptBuildNodeChunk;
 
 pub fn register() {
     turbo_tasks::register();
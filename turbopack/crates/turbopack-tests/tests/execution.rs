@@ -50,7 +50,7 @@ use turbopack_core::{
     },
 };
 use turbopack_ecmascript_runtime::RuntimeType;
-use turbopack_node::{debug::should_debug, evaluate::evaluate};
+use turbopack_node::{NodeJsPoolOptions, debug::should_debug, evaluate::evaluate};
 use turbopack_nodejs::NodeJsChunkingContext;
 use turbopack_resolve::resolve_options_context::ResolveOptionsContext;
 use turbopack_test_utils::{jest::JestRunResult, snapshot::UPDATE};
@@ -524,6 +524,7 @@ async fn run_test_operation(prepared_test: ResolvedVc<PreparedTest>) -> Result<V
         vec![],
         Completion::immutable(),
         should_debug("execution_test"),
+        NodeJsPoolOptions::default(),
     )
     .await?;
 
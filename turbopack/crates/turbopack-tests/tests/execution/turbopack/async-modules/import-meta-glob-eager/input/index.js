@@ -0,0 +1,12 @@
+const modules = import.meta.glob('./modules/*.js', { eager: true })
+const values = import.meta.glob('./modules/*.js', {
+  eager: true,
+  import: 'value',
+})
+
+it('should await eagerly globbed modules with top level await', () => {
+  expect(Object.keys(modules)).toEqual(['./modules/a.js', './modules/b.js'])
+  expect(modules['./modules/a.js'].value).toBe('a')
+  expect(modules['./modules/b.js'].value).toBe('b')
+  expect(values).toEqual({ './modules/a.js': 'a', './modules/b.js': 'b' })
+})
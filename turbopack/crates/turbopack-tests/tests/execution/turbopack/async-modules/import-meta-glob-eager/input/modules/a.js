@@ -0,0 +1 @@
+export const value = await Promise.resolve('a')
@@ -0,0 +1,18 @@
+import './setup'
+import sheet from './style.css' with { type: 'css' }
+import text from './text.txt' with { type: 'text' }
+import bytes from './bytes.bin' with { type: 'bytes' }
+
+it('should import a CSS file as a style sheet', () => {
+  expect(sheet).toBeInstanceOf(CSSStyleSheet)
+  expect(sheet.cssText).toBe('.a {\n  color: red;\n}\n')
+})
+
+it('should import a file as text', () => {
+  expect(text).toBe('Hello world\n')
+})
+
+it('should import a file as bytes', () => {
+  expect(bytes.constructor.name).toBe('Uint8Array')
+  expect(Array.from(bytes)).toEqual([0, 1, 127, 128, 255])
+})
@@ -0,0 +1,6 @@
+// Node.js doesn't implement constructable style sheets
+globalThis.CSSStyleSheet = class CSSStyleSheet {
+  replaceSync(text) {
+    this.cssText = text
+  }
+}
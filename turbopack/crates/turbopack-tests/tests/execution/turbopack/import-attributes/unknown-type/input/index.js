@@ -0,0 +1,10 @@
+it('should ignore imports with an unknown type attribute', () => {
+  // Either requiring should throw, or return undefined
+  let ns
+  try {
+    ns = require('./unknown')
+  } catch {
+    return
+  }
+  expect(ns.default).toBe(undefined)
+})
@@ -0,0 +1,3 @@
+import data from './data.yaml' with { type: 'yaml' }
+
+export default data
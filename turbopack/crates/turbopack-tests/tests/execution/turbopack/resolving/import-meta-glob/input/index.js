@@ -0,0 +1,42 @@
+it('should import matches lazily', async () => {
+  const modules = import.meta.glob('./modules/*.js')
+  expect(Object.keys(modules)).toEqual(['./modules/a.js', './modules/b.js'])
+  const a = await modules['./modules/a.js']()
+  expect(a.name).toBe('a')
+  expect(a.default).toBe('default a')
+})
+
+it('should import matches eagerly', () => {
+  const modules = import.meta.glob(['./modules/**/*.js', '!**/b.js'], {
+    eager: true,
+  })
+  expect(Object.keys(modules)).toEqual([
+    './modules/a.js',
+    './modules/nested/c.js',
+  ])
+  expect(modules['./modules/nested/c.js'].name).toBe('c')
+})
+
+it('should select the export', async () => {
+  const eager = import.meta.glob('./modules/*.js', {
+    eager: true,
+    import: 'name',
+  })
+  expect(eager).toEqual({ './modules/a.js': 'a', './modules/b.js': 'b' })
+
+  const lazy = import.meta.glob('./modules/*.js', { import: 'default' })
+  expect(await lazy['./modules/b.js']()).toBe('default b')
+})
+
+it('should not match node_modules unless exhaustive', () => {
+  const modules = import.meta.glob('./modules/**/index.js', { eager: true })
+  expect(Object.keys(modules)).toEqual([])
+
+  const exhaustive = import.meta.glob('./modules/**/index.js', {
+    eager: true,
+    exhaustive: true,
+  })
+  expect(Object.keys(exhaustive)).toEqual([
+    './modules/node_modules/pkg/index.js',
+  ])
+})
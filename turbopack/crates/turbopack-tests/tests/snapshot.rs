@@ -101,6 +101,8 @@ struct SnapshotOptions {
     scope_hoisting: bool,
     #[serde(default)]
     production_chunking: bool,
+    #[serde(default)]
+    import_queries: bool,
 }
 
 #[derive(Debug, Deserialize, Default)]
@@ -130,6 +132,7 @@ impl Default for SnapshotOptions {
             remove_unused_exports: false,
             scope_hoisting: false,
             production_chunking: false,
+            import_queries: false,
         }
     }
 }
@@ -376,6 +379,7 @@ async fn run_test_operation(resource: RcStr) -> Result<Vc<FileSystemPath>> {
             )],
             module_rules: vec![module_rules],
             tree_shaking_mode: options.tree_shaking_mode,
+            enable_import_queries: options.import_queries,
             ..Default::default()
         }
         .into(),
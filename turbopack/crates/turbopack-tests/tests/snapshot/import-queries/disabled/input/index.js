@@ -0,0 +1,4 @@
+// Without import queries enabled, the query is ignored and the module is imported as usual
+import value from './value.js?raw'
+
+console.log(value)
@@ -0,0 +1,4 @@
+import text from './data.txt?raw'
+import css from './style.css?raw'
+
+console.log(text, css)
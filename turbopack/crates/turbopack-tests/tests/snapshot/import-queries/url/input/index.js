@@ -0,0 +1,3 @@
+import url from './asset.txt?url'
+
+console.log(url)
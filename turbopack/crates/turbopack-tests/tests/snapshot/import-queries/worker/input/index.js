@@ -0,0 +1,4 @@
+import createWorker from './worker.js?worker'
+
+const worker = createWorker({ type: 'module' })
+worker.postMessage('ping')
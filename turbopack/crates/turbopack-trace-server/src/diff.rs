@@ -0,0 +1,337 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    io::Write,
+    path::PathBuf,
+    sync::Arc,
+    thread,
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    FxIndexMap,
+    reader::TraceReader,
+    report::{FormatBytes, FormatDuration, ReportFormat},
+    span_graph_ref::{SpanGraphEventRef, SpanGraphRef},
+    store::Store,
+    store_container::StoreContainer,
+};
+
+#[derive(Clone, Copy, Debug)]
+pub struct DiffOptions {
+    /// The number of entries to report.
+    pub top: usize,
+    /// Spans nested deeper than this are folded into their ancestor at this depth.
+    pub depth: usize,
+    pub format: ReportFormat,
+}
+
+impl Default for DiffOptions {
+    fn default() -> Self {
+        Self {
+            top: 20,
+            depth: usize::MAX,
+            format: ReportFormat::Text,
+        }
+    }
+}
+
+/// The values of an aggregated span. All durations are in microseconds.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffValues {
+    pub duration: u64,
+    pub self_duration: u64,
+    pub cpu: u64,
+    pub allocations: u64,
+    pub count: u64,
+}
+
+impl DiffValues {
+    fn add(&mut self, other: &DiffValues) {
+        self.duration += other.duration;
+        self.self_duration += other.self_duration;
+        self.cpu += other.cpu;
+        self.allocations += other.allocations;
+        self.count += other.count;
+    }
+}
+
+/// An aggregated span, i.e. all spans with the same name below the same path, in both traces.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffEntry {
+    pub category: String,
+    pub name: String,
+    /// The names of the parent spans, starting at the root.
+    pub path: Vec<String>,
+    pub before: DiffValues,
+    pub after: DiffValues,
+}
+
+impl DiffEntry {
+    pub fn self_duration_delta(&self) -> i64 {
+        self.after.self_duration as i64 - self.before.self_duration as i64
+    }
+}
+
+/// The differences between two traces, with spans aligned by name and path.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceDiff {
+    pub before: DiffValues,
+    pub after: DiffValues,
+    /// The aggregated spans with the largest change in self duration. The self duration is used
+    /// so that a regression is attributed to the span causing it and not to all its parents.
+    pub entries: Vec<DiffEntry>,
+}
+
+/// Reads both trace files and writes the differences to `out`.
+pub fn write_diff(
+    before: PathBuf,
+    after: PathBuf,
+    options: DiffOptions,
+    out: &mut impl Write,
+) -> Result<()> {
+    let before_store = Arc::new(StoreContainer::new());
+    let after_store = Arc::new(StoreContainer::new());
+    thread::scope(|scope| {
+        let read_before = scope.spawn(|| TraceReader::read_to_end(before_store.clone(), before));
+        TraceReader::read_to_end(after_store.clone(), after)?;
+        read_before.join().unwrap()
+    })?;
+    let diff = TraceDiff::new(&before_store.read(), &after_store.read(), options);
+    match options.format {
+        ReportFormat::Text => write!(out, "{diff}")?,
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, &diff)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+type AggregatedSpans = FxIndexMap<Vec<(String, String)>, DiffValues>;
+
+/// Flattens the aggregated span graph of a trace into a map from path to values.
+fn aggregate(store: &Store, depth: usize) -> (DiffValues, AggregatedSpans) {
+    let root = store.root_span();
+    let total = DiffValues {
+        duration: root.corrected_total_time().as_micros(),
+        self_duration: 0,
+        cpu: root.total_time().as_micros(),
+        allocations: root.total_allocations(),
+        count: root.total_span_count() - 1,
+    };
+
+    let mut spans = AggregatedSpans::default();
+    let mut stack = root
+        .graph()
+        .filter_map(|event| match event {
+            SpanGraphEventRef::Child { graph } => Some((Vec::new(), graph)),
+            SpanGraphEventRef::SelfTime { .. } => None,
+        })
+        .collect::<Vec<(Vec<(String, String)>, SpanGraphRef<'_>)>>();
+    while let Some((mut path, graph)) = stack.pop() {
+        let (category, name) = graph.first_span().group_name();
+        path.push((category.to_string(), name.to_string()));
+        let duration = graph.corrected_total_time().as_micros();
+        let folded = path.len() >= depth;
+        let values = DiffValues {
+            duration,
+            self_duration: if folded {
+                duration
+            } else {
+                graph.corrected_self_time().as_micros()
+            },
+            cpu: graph.total_time().as_micros(),
+            allocations: graph.total_allocations(),
+            count: graph.count() as u64,
+        };
+        if !folded {
+            stack.extend(graph.children().map(|child| (path.clone(), child)));
+        }
+        spans.entry(path).or_default().add(&values);
+    }
+    (total, spans)
+}
+
+impl TraceDiff {
+    pub fn new(before: &Store, after: &Store, options: DiffOptions) -> Self {
+        let (before_total, mut before_spans) = aggregate(before, options.depth);
+        let (after_total, after_spans) = aggregate(after, options.depth);
+
+        let mut entries = after_spans
+            .into_iter()
+            .map(|(path, after)| {
+                let before = before_spans.swap_remove(&path).unwrap_or_default();
+                (path, before, after)
+            })
+            .collect::<Vec<_>>();
+        // Spans that only exist in the first trace
+        entries.extend(
+            before_spans
+                .into_iter()
+                .map(|(path, before)| (path, before, DiffValues::default())),
+        );
+        let mut entries = entries
+            .into_iter()
+            .filter(|(_, before, after)| before != after)
+            .map(|(mut path, before, after)| {
+                let (category, name) = path.pop().unwrap();
+                DiffEntry {
+                    category,
+                    name,
+                    path: path.into_iter().map(|(_, name)| name).collect(),
+                    before,
+                    after,
+                }
+            })
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|entry| std::cmp::Reverse(entry.self_duration_delta().unsigned_abs()));
+        entries.truncate(options.top);
+
+        Self {
+            before: before_total,
+            after: after_total,
+            entries,
+        }
+    }
+}
+
+/// Formats the change from `before` to `after` with a sign.
+fn format_delta<T: Display>(before: u64, after: u64, format: impl Fn(u64) -> T) -> String {
+    if after >= before {
+        format!("+{}", format(after - before))
+    } else {
+        format!("-{}", format(before - after))
+    }
+}
+
+fn format_percentage(before: u64, after: u64) -> String {
+    if before == 0 {
+        if after == 0 {
+            "±0%".to_string()
+        } else {
+            "new".to_string()
+        }
+    } else {
+        format!(
+            "{:+.0}%",
+            (after as f64 - before as f64) * 100.0 / before as f64
+        )
+    }
+}
+
+impl Display for TraceDiff {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let (before, after) = (&self.before, &self.after);
+        writeln!(f, "Total")?;
+        writeln!(
+            f,
+            "  duration:    {} -> {} ({}, {})",
+            FormatDuration(before.duration),
+            FormatDuration(after.duration),
+            format_delta(before.duration, after.duration, FormatDuration),
+            format_percentage(before.duration, after.duration)
+        )?;
+        writeln!(
+            f,
+            "  cpu:         {} -> {} ({}, {})",
+            FormatDuration(before.cpu),
+            FormatDuration(after.cpu),
+            format_delta(before.cpu, after.cpu, FormatDuration),
+            format_percentage(before.cpu, after.cpu)
+        )?;
+        writeln!(
+            f,
+            "  allocations: {} -> {} ({}, {})",
+            FormatBytes(before.allocations),
+            FormatBytes(after.allocations),
+            format_delta(before.allocations, after.allocations, FormatBytes),
+            format_percentage(before.allocations, after.allocations)
+        )?;
+        writeln!(
+            f,
+            "  spans:       {} -> {} ({})",
+            before.count,
+            after.count,
+            format_delta(before.count, after.count, |count| count)
+        )?;
+
+        writeln!(f)?;
+        writeln!(f, "Largest changes in self duration")?;
+        for entry in &self.entries {
+            let (before, after) = (&entry.before, &entry.after);
+            writeln!(
+                f,
+                "  {:>10} {:>6}  {}",
+                format_delta(before.self_duration, after.self_duration, FormatDuration),
+                format_percentage(before.self_duration, after.self_duration),
+                entry.name
+            )?;
+            if !entry.path.is_empty() {
+                writeln!(f, "  {:>17}    in {}", "", entry.path.join(" > "))?;
+            }
+            writeln!(
+                f,
+                "  {:>17}    total {}, cpu {}, allocations {}, count {} -> {}",
+                "",
+                format_delta(before.duration, after.duration, FormatDuration),
+                format_delta(before.cpu, after.cpu, FormatDuration),
+                format_delta(before.allocations, after.allocations, FormatBytes),
+                before.count,
+                after.count
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diff() {
+        let mut before = Store::new();
+        let build = before.add_test_span(None, "build", 0, 10);
+        before.add_test_span(Some(build), "parse", 10, 20);
+        before.add_test_span(Some(build), "resolve", 20, 30);
+
+        let mut after = Store::new();
+        let build = after.add_test_span(None, "build", 0, 10);
+        after.add_test_span(Some(build), "parse", 10, 20);
+        after.add_test_span(Some(build), "parse", 20, 50);
+        after.add_test_span(Some(build), "minify", 50, 55);
+
+        let diff = TraceDiff::new(&before, &after, DiffOptions::default());
+        assert_eq!(diff.before.duration, 30);
+        assert_eq!(diff.after.duration, 55);
+
+        let entries = diff
+            .entries
+            .iter()
+            .map(|entry| {
+                (
+                    entry.name.as_str(),
+                    entry.path.join(" > "),
+                    entry.self_duration_delta(),
+                    entry.before.count,
+                    entry.after.count,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            entries,
+            vec![
+                ("parse", "build".to_string(), 30, 1, 2),
+                ("resolve", "build".to_string(), -10, 1, 0),
+                ("minify", "build".to_string(), 5, 0, 1),
+                // build only changed in total duration
+                ("build", "".to_string(), 0, 1, 1),
+            ]
+        );
+    }
+}
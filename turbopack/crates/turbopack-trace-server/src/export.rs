@@ -0,0 +1,36 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Read},
+    path::Path,
+};
+
+use anyhow::{Context, Result};
+use flate2::read::GzDecoder;
+use turbopack_trace_utils::export::{TraceExportFormat, export_raw_trace};
+
+/// Returns the format matching the extension of the output file, if any.
+pub fn export_format_from_path(path: &Path) -> Option<TraceExportFormat> {
+    match path.extension()?.to_str()? {
+        "json" => Some(TraceExportFormat::ChromeJson),
+        "pftrace" | "perfetto-trace" => Some(TraceExportFormat::Perfetto),
+        _ => None,
+    }
+}
+
+/// Converts the raw trace at `input`, which may be compressed like the traces read by the
+/// viewer, to `output`.
+pub fn export_trace(input: &Path, output: &Path, format: TraceExportFormat) -> Result<()> {
+    let file = File::open(input)
+        .with_context(|| format!("Unable to read trace file at {}", input.display()))?;
+    let input_name = input.to_string_lossy();
+    let reader: Box<dyn Read> = if input_name.ends_with(".zst") {
+        Box::new(zstd::Decoder::new(file)?)
+    } else if input_name.ends_with(".gz") {
+        Box::new(GzDecoder::new(BufReader::new(file)))
+    } else {
+        Box::new(BufReader::new(file))
+    };
+    let output =
+        File::create(output).with_context(|| format!("Unable to create {}", output.display()))?;
+    export_raw_trace(reader, BufWriter::new(output), format)
+}
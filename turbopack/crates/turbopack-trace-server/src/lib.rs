@@ -5,10 +5,18 @@ use std::{hash::BuildHasherDefault, path::PathBuf, sync::Arc};
 
 use rustc_hash::FxHasher;
 
+pub use self::{
+    diff::{DiffOptions, write_diff},
+    export::{export_format_from_path, export_trace},
+    report::{ReportFormat, ReportOptions, write_report},
+};
 use self::{reader::TraceReader, server::serve, store_container::StoreContainer};
 
 mod bottom_up;
+mod diff;
+mod export;
 mod reader;
+mod report;
 mod self_time_tree;
 mod server;
 mod span;
@@ -33,7 +41,7 @@ pub fn start_turbopack_trace_server(path: PathBuf) {
     let store = Arc::new(StoreContainer::new());
     let reader = TraceReader::spawn(store.clone(), path);
 
-    serve(store, 5747);
+    serve(store, None, 5747);
 
     reader.join().unwrap();
 }
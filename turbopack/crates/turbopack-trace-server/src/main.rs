@@ -1,15 +1,26 @@
 #![feature(iter_intersperse)]
 #![feature(box_patterns)]
 
-use std::{hash::BuildHasherDefault, sync::Arc};
+use std::{hash::BuildHasherDefault, path::PathBuf, sync::Arc};
 
 use indexmap::{IndexMap, IndexSet};
 use rustc_hash::FxHasher;
+use turbopack_trace_utils::export::TraceExportFormat;
 
-use self::{reader::TraceReader, server::serve, store_container::StoreContainer};
+use self::{
+    diff::{DiffOptions, write_diff},
+    export::{export_format_from_path, export_trace},
+    reader::TraceReader,
+    report::{ReportFormat, ReportOptions, write_report},
+    server::serve,
+    store_container::StoreContainer,
+};
 
 mod bottom_up;
+mod diff;
+mod export;
 mod reader;
+mod report;
 mod self_time_tree;
 mod server;
 mod span;
@@ -30,7 +41,33 @@ type FxIndexMap<K, V> = IndexMap<K, V, BuildHasherDefault<FxHasher>>;
 fn main() {
     let args: FxIndexSet<String> = std::env::args().skip(1).collect();
 
+    match args.first().map(|arg| arg.as_str()) {
+        Some("report") => {
+            report(std::env::args().skip(2));
+            return;
+        }
+        Some("diff") => {
+            diff(std::env::args().skip(2));
+            return;
+        }
+        Some("export") => {
+            export(std::env::args().skip(2));
+            return;
+        }
+        _ => {}
+    }
+
     let mut iter = args.iter();
+    let mut baseline = None;
+    let mut positional = Vec::new();
+    while let Some(arg) = iter.next() {
+        if arg == "--baseline" {
+            baseline = Some(iter.next().expect("--baseline expects a trace file path"));
+        } else {
+            positional.push(arg);
+        }
+    }
+    let mut iter = positional.into_iter();
     let arg = iter
         .next()
         .expect("missing positional argument for the trace file path");
@@ -38,8 +75,110 @@ fn main() {
 
     let store = Arc::new(StoreContainer::new());
     let reader = TraceReader::spawn(store.clone(), arg.into());
+    // A second trace to compare with, see `ClientToServerMessage::QueryDiff`
+    let baseline = baseline.map(|path| {
+        let baseline = Arc::new(StoreContainer::new());
+        TraceReader::spawn(baseline.clone(), path.into());
+        baseline
+    });
 
-    serve(store, port);
+    serve(store, baseline, port);
 
     reader.join().unwrap();
 }
+
+/// `turbo-trace-server report <trace-file> [--top <n>] [--json]`
+///
+/// Reads the whole trace file and prints a report instead of starting the viewer.
+fn report(mut iter: impl Iterator<Item = String>) {
+    let mut path = None;
+    let mut options = ReportOptions::default();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--json" => options.format = ReportFormat::Json,
+            "--top" => {
+                options.top = iter
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .expect("--top expects a number");
+            }
+            _ => path = Some(arg),
+        }
+    }
+    let path = path.expect("missing positional argument for the trace file path");
+
+    if let Err(err) = write_report(path.into(), options, &mut std::io::stdout().lock()) {
+        eprintln!("{err:?}");
+        std::process::exit(1);
+    }
+}
+
+/// `turbo-trace-server diff <before-trace-file> <after-trace-file> [--top <n>] [--depth <n>]
+/// [--json]`
+///
+/// Reads both trace files and prints the spans that changed the most.
+fn diff(mut iter: impl Iterator<Item = String>) {
+    let mut paths = Vec::new();
+    let mut options = DiffOptions::default();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--json" => options.format = ReportFormat::Json,
+            "--top" => {
+                options.top = iter
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .expect("--top expects a number");
+            }
+            "--depth" => {
+                options.depth = iter
+                    .next()
+                    .and_then(|s| s.parse().ok())
+                    .expect("--depth expects a number");
+            }
+            _ => paths.push(arg),
+        }
+    }
+    let [before, after] = <[String; 2]>::try_from(paths)
+        .expect("expected two positional arguments for the trace file paths");
+
+    if let Err(err) = write_diff(
+        before.into(),
+        after.into(),
+        options,
+        &mut std::io::stdout().lock(),
+    ) {
+        eprintln!("{err:?}");
+        std::process::exit(1);
+    }
+}
+
+/// `turbo-trace-server export <trace-file> <output-file> [--format chrome|perfetto]`
+///
+/// Converts a turbopack trace file to the Chrome Trace Event Format or to a Perfetto trace, so it
+/// can be opened in other tools. The format defaults to the extension of the output file.
+fn export(mut iter: impl Iterator<Item = String>) {
+    let mut paths = Vec::new();
+    let mut format = None;
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--format" => {
+                format = Some(match iter.next().as_deref() {
+                    Some("chrome") => TraceExportFormat::ChromeJson,
+                    Some("perfetto") => TraceExportFormat::Perfetto,
+                    _ => panic!("--format expects chrome or perfetto"),
+                });
+            }
+            _ => paths.push(PathBuf::from(arg)),
+        }
+    }
+    let [input, output] = <[PathBuf; 2]>::try_from(paths)
+        .expect("expected two positional arguments for the trace file and the output file");
+    let format = format
+        .or_else(|| export_format_from_path(&output))
+        .unwrap_or(TraceExportFormat::ChromeJson);
+
+    if let Err(err) = export_trace(&input, &output, format) {
+        eprintln!("{err:?}");
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use turbopack_trace_utils::{
+    chrome_trace::{ChromeEventsToTraceRow, ChromeTraceEvent},
+    tracing::TraceRow,
+};
+
+use super::{TraceFormat, turbopack::TurbopackFormat};
+
+enum State {
+    /// Before the array of events
+    Start,
+    Events,
+    /// After the array of events, the remaining data is ignored
+    End,
+}
+
+/// Reads traces in the Chrome Trace Event Format, e.g. written by `--trace-event-categories` of
+/// Node.js or exported from a turbopack trace. Both the JSON object and the JSON array format are
+/// supported.
+pub struct ChromeTraceFormat {
+    inner: TurbopackFormat,
+    converter: ChromeEventsToTraceRow,
+    state: State,
+}
+
+impl ChromeTraceFormat {
+    pub fn new(inner: TurbopackFormat) -> Self {
+        Self {
+            inner,
+            converter: ChromeEventsToTraceRow::new(),
+            state: State::Start,
+        }
+    }
+}
+
+/// Returns the position of the array of events.
+pub(super) fn find_events_array(buffer: &[u8]) -> Option<usize> {
+    let start = buffer.iter().position(|b| !b.is_ascii_whitespace())?;
+    if buffer[start] == b'[' {
+        return Some(start);
+    }
+    const TRACE_EVENTS: &[u8] = b"\"traceEvents\"";
+    let key = buffer
+        .windows(TRACE_EVENTS.len())
+        .position(|window| window == TRACE_EVENTS)?
+        + TRACE_EVENTS.len();
+    Some(key + buffer[key..].iter().position(|b| *b == b'[')?)
+}
+
+impl TraceFormat for ChromeTraceFormat {
+    type Reused = Vec<TraceRow<'static>>;
+
+    fn read(&mut self, buffer: &[u8], rows: &mut Self::Reused) -> Result<usize> {
+        let mut bytes_read = 0;
+        if let State::Start = self.state {
+            let Some(start) = find_events_array(buffer) else {
+                return Ok(0);
+            };
+            bytes_read = start + 1;
+            self.state = State::Events;
+        }
+        while let State::Events = self.state {
+            let rest = &buffer[bytes_read..];
+            let Some(next) = rest
+                .iter()
+                .position(|b| !b.is_ascii_whitespace() && *b != b',')
+            else {
+                bytes_read = buffer.len();
+                break;
+            };
+            bytes_read += next;
+            if rest[next] == b']' {
+                self.converter.finish(rows);
+                self.state = State::End;
+                break;
+            }
+            let mut events =
+                serde_json::Deserializer::from_slice(&rest[next..]).into_iter::<ChromeTraceEvent>();
+            match events.next() {
+                Some(Ok(event)) => {
+                    bytes_read += events.byte_offset();
+                    self.converter.convert(event, rows);
+                }
+                Some(Err(err)) if err.is_eof() => break,
+                Some(Err(err)) => return Err(err.into()),
+                None => break,
+            }
+        }
+        if let State::End = self.state {
+            bytes_read = buffer.len();
+        }
+        if !rows.is_empty() {
+            self.inner.process_rows(rows.drain(..));
+        }
+        Ok(bytes_read)
+    }
+}
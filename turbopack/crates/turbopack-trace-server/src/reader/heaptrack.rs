@@ -406,7 +406,7 @@ impl TraceFormat for HeaptrackFormat {
                 }
                 b'X' => {
                     let line = from_utf8(line)?;
-                    println!("Debuggee: {line}");
+                    eprintln!("Debuggee: {line}");
                 }
                 b'c' => {
                     // timestamp
@@ -464,7 +464,7 @@ impl TraceFormat for HeaptrackFormat {
                 }
                 _ => {
                     let line = from_utf8(line)?;
-                    println!("{} {line}", ty as char)
+                    eprintln!("{} {line}", ty as char)
                 }
             }
         }
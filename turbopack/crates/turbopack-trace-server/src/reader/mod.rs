@@ -1,5 +1,7 @@
+mod chrome;
 mod heaptrack;
 mod nextjs;
+mod perfetto;
 mod turbopack;
 
 use std::{
@@ -13,16 +15,72 @@ use std::{
     time::{Duration, Instant},
 };
 
-use anyhow::Result;
+use anyhow::{Result, bail};
 use flate2::bufread::GzDecoder;
+use serde::{Deserialize, de::IgnoredAny};
 
 use crate::{
-    reader::{heaptrack::HeaptrackFormat, nextjs::NextJsFormat, turbopack::TurbopackFormat},
+    reader::{
+        chrome::{ChromeTraceFormat, find_events_array},
+        heaptrack::HeaptrackFormat,
+        nextjs::NextJsFormat,
+        perfetto::PerfettoFormat,
+        turbopack::TurbopackFormat,
+    },
     store_container::StoreContainer,
 };
 
 const MIN_INITIAL_REPORT_SIZE: u64 = 100 * 1024 * 1024;
 
+/// The first byte of a Perfetto trace, the key of the first `packet` field.
+const PERFETTO_TRACE_PACKET: u8 = 0x0a;
+
+/// How much of a trace is read at most to detect the Chrome Trace Event Format.
+const MAX_CHROME_TRACE_DETECTION_SIZE: usize = 1024 * 1024;
+
+/// The fields of a trace event that detect the Chrome Trace Event Format.
+#[derive(Deserialize)]
+struct ChromeTraceEventDetection {
+    ph: Option<IgnoredAny>,
+    ts: Option<IgnoredAny>,
+}
+
+/// Detects the JSON object and JSON array format of the Chrome Trace Event Format by the `ph` and
+/// `ts` fields of the first event. Next.js traces are JSON arrays of events without them. Returns
+/// `None` when more data is needed, unless `eof` is set because no more data is available.
+fn is_chrome_trace(buffer: &[u8], eof: bool) -> Option<bool> {
+    // The tag byte of a Perfetto trace is a newline, which would be skipped as whitespace below
+    if buffer.first() == Some(&PERFETTO_TRACE_PACKET) {
+        return Some(false);
+    }
+    let incomplete = || (eof || buffer.len() >= MAX_CHROME_TRACE_DETECTION_SIZE).then_some(false);
+    let mut tokens = buffer.iter().filter(|b| !b.is_ascii_whitespace());
+    match (tokens.next(), tokens.next()) {
+        (None, _) | (Some(b'{'), None) => return incomplete(),
+        (Some(b'['), _) | (Some(b'{'), Some(b'"')) => {}
+        _ => return Some(false),
+    }
+    let Some(start) = find_events_array(buffer) else {
+        return incomplete();
+    };
+    let events = &buffer[start + 1..];
+    let Some(next) = events.iter().position(|b| !b.is_ascii_whitespace()) else {
+        return incomplete();
+    };
+    if events[next] == b']' {
+        // No events, there is nothing to read anyway
+        return Some(true);
+    }
+    match serde_json::Deserializer::from_slice(&events[next..])
+        .into_iter::<ChromeTraceEventDetection>()
+        .next()
+    {
+        Some(Ok(event)) => Some(event.ph.is_some() && event.ts.is_some()),
+        Some(Err(err)) if err.is_eof() => incomplete(),
+        _ => Some(false),
+    }
+}
+
 trait TraceFormat {
     type Reused: Default;
     fn read(&mut self, buffer: &[u8], reuse: &mut Self::Reused) -> Result<usize>;
@@ -123,20 +181,39 @@ impl TraceFile {
 pub struct TraceReader {
     store: Arc<StoreContainer>,
     path: PathBuf,
+    /// Keep reading when the file grows or is replaced.
+    follow: bool,
 }
 
 impl TraceReader {
     pub fn spawn(store: Arc<StoreContainer>, path: PathBuf) -> JoinHandle<()> {
-        let mut reader = Self { store, path };
+        let mut reader = Self {
+            store,
+            path,
+            follow: true,
+        };
         std::thread::spawn(move || reader.run())
     }
 
+    /// Reads the trace file once until the end, e.g. to create a report.
+    pub fn read_to_end(store: Arc<StoreContainer>, path: PathBuf) -> Result<()> {
+        let mut reader = Self {
+            store,
+            path,
+            follow: false,
+        };
+        if !reader.try_read() {
+            bail!("Unable to read trace file at {:?}", reader.path);
+        }
+        Ok(())
+    }
+
     pub fn run(&mut self) {
         let mut file_warning_printed = false;
         loop {
             let read_success = self.try_read();
             if !file_warning_printed && !read_success {
-                println!("Unable to read trace file at {:?}, waiting...", self.path);
+                eprintln!("Unable to read trace file at {:?}, waiting...", self.path);
                 file_warning_printed = true;
             }
             thread::sleep(Duration::from_millis(500));
@@ -158,13 +235,13 @@ impl TraceReader {
         let Ok(mut file) = File::open(&self.path) else {
             return false;
         };
-        println!("Trace file opened");
+        eprintln!("Trace file opened");
         let stop_at = env::var("STOP_AT")
             .unwrap_or_default()
             .parse()
             .map_or(u64::MAX, |v: u64| v * 1024 * 1024);
         if stop_at != u64::MAX {
-            println!("Will stop reading file at {} MB", stop_at / 1024 / 1024)
+            eprintln!("Will stop reading file at {} MB", stop_at / 1024 / 1024)
         }
 
         {
@@ -185,7 +262,7 @@ impl TraceReader {
         let mut file = match self.trace_file_from_file(file) {
             Ok(f) => f,
             Err(err) => {
-                println!("Error creating zstd decoder: {err}");
+                eprintln!("Error creating zstd decoder: {err}");
                 return false;
             }
         };
@@ -197,7 +274,9 @@ impl TraceReader {
         loop {
             match file.read(&mut chunk) {
                 Ok(bytes_read) => {
-                    if bytes_read == 0 {
+                    // A trace that is smaller than needed to detect its format is detected once
+                    // all of it has been read
+                    if bytes_read == 0 && (format.is_some() || buffer.is_empty()) {
                         if let Some(value) = self.wait_for_more_data(
                             &mut file,
                             &mut initial_read,
@@ -213,14 +292,25 @@ impl TraceReader {
                             index = 0;
                         }
                         buffer.extend_from_slice(&chunk[..bytes_read]);
-                        if format.is_none() && buffer.len() >= 8 {
+                        if format.is_none()
+                            && (buffer.len() >= 8 || bytes_read == 0)
+                            && let Some(is_chrome_trace) = is_chrome_trace(&buffer, bytes_read == 0)
+                        {
                             let erased_format = if buffer.starts_with(b"TRACEv0") {
                                 index = 7;
                                 ErasedTraceFormat(Box::new(TurbopackFormat::new(
                                     self.store.clone(),
                                 )))
+                            } else if is_chrome_trace {
+                                ErasedTraceFormat(Box::new(ChromeTraceFormat::new(
+                                    TurbopackFormat::new(self.store.clone()),
+                                )))
                             } else if buffer.starts_with(b"[{\"name\"") {
                                 ErasedTraceFormat(Box::new(NextJsFormat::new(self.store.clone())))
+                            } else if buffer[0] == PERFETTO_TRACE_PACKET {
+                                ErasedTraceFormat(Box::new(PerfettoFormat::new(
+                                    TurbopackFormat::new(self.store.clone()),
+                                )))
                             } else if buffer.starts_with(b"v ") {
                                 ErasedTraceFormat(Box::new(HeaptrackFormat::new(
                                     self.store.clone(),
@@ -241,7 +331,7 @@ impl TraceReader {
                                     index += bytes_read;
                                 }
                                 Err(err) => {
-                                    println!("Trace file error: {err}");
+                                    eprintln!("Trace file error: {err}");
                                     return true;
                                 }
                             }
@@ -264,7 +354,7 @@ impl TraceReader {
                                     let uncompressed = current_read / (1024 * 1024);
                                     let total = *total / (1024 * 1024);
                                     let stats = format.stats();
-                                    print!(
+                                    eprint!(
                                         "{}% read ({}/{} MB, {} MB/s)",
                                         percentage,
                                         read,
@@ -272,17 +362,23 @@ impl TraceReader {
                                         read * 1000 / (start.elapsed().as_millis() + 1) as u64
                                     );
                                     if uncompressed != read {
-                                        print!(" ({uncompressed} MB uncompressed)");
+                                        eprint!(" ({uncompressed} MB uncompressed)");
                                     }
                                     if stats.is_empty() {
-                                        println!();
+                                        eprintln!();
                                     } else {
-                                        println!(" - {stats}");
+                                        eprintln!(" - {stats}");
                                     }
                                 }
                             }
                             if current_read >= stop_at {
-                                println!(
+                                if !self.follow {
+                                    eprintln!(
+                                        "Stopped reading file as requested by STOP_AT env var."
+                                    );
+                                    return true;
+                                }
+                                eprintln!(
                                     "Stopped reading file as requested by STOP_AT env var. \
                                      Waiting for new file..."
                                 );
@@ -303,7 +399,7 @@ impl TraceReader {
                         }
                     } else {
                         // Error reading file, maybe it was removed
-                        println!("Error reading trace file: {err:?}");
+                        eprintln!("Error reading trace file: {err:?}");
                         return true;
                     }
                 }
@@ -323,16 +419,19 @@ impl TraceReader {
         if let Some((total, start)) = initial_read.take() {
             if let Some(format) = format {
                 let stats = format.stats();
-                println!("{stats}");
+                eprintln!("{stats}");
             }
             if total > MIN_INITIAL_REPORT_SIZE {
-                println!(
+                eprintln!(
                     "Initial read completed ({} MB, {}s)",
                     total / (1024 * 1024),
                     (start.elapsed().as_millis() / 100) as f32 / 10.0
                 );
             }
         }
+        if !self.follow {
+            return Some(true);
+        }
         loop {
             // No more data to read, sleep for a while to wait for more data
             thread::sleep(Duration::from_millis(100));
@@ -367,3 +466,169 @@ impl TraceReader {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use turbopack_trace_utils::{
+        chrome_trace::{TraceEventWriter, TraceRowToChromeEvents},
+        perfetto::PerfettoWriter,
+        tracing::TraceRow,
+    };
+
+    use super::*;
+    use crate::span_ref::SpanRef;
+
+    /// Reads `data` like the trace reader does, when the data arrives in chunks of `chunk_size`.
+    fn read_in_chunks(format: &mut impl TraceFormat, data: &[u8], chunk_size: usize) -> Result<()> {
+        let mut reuse = Default::default();
+        let mut buffer = Vec::new();
+        for chunk in data.chunks(chunk_size) {
+            buffer.extend_from_slice(chunk);
+            let bytes_read = format.read(&buffer, &mut reuse)?;
+            buffer.drain(..bytes_read);
+        }
+        Ok(())
+    }
+
+    /// Returns the path and the duration of every span in the store.
+    fn spans(store: &StoreContainer) -> Vec<(String, u64)> {
+        fn add(span: SpanRef<'_>, parent: &str, spans: &mut Vec<(String, u64)>) {
+            let path = if parent.is_empty() {
+                span.nice_name().1.to_string()
+            } else {
+                format!("{parent} > {}", span.nice_name().1)
+            };
+            spans.push((path.clone(), (span.end() - span.start()).as_micros()));
+            for child in span.children() {
+                add(child, &path, spans);
+            }
+        }
+        let store = store.read();
+        let mut spans = Vec::new();
+        for span in store.root_spans() {
+            add(span, "", &mut spans);
+        }
+        spans
+    }
+
+    #[test]
+    fn test_is_chrome_trace() {
+        // Events of the JSON array format may start with any field
+        assert_eq!(
+            is_chrome_trace(
+                br#"[{"name":"build","ph":"B","ts":10,"pid":1,"tid":1}]"#,
+                false
+            ),
+            Some(true)
+        );
+        assert_eq!(
+            is_chrome_trace(br#" {"traceEvents":[{"ph":"X","ts":0,"dur":1}]}"#, false),
+            Some(true)
+        );
+        assert_eq!(
+            is_chrome_trace(
+                br#"[{"name":"build","duration":10,"timestamp":1,"id":1,"tags":{},"startTime":1}]"#,
+                false
+            ),
+            Some(false)
+        );
+        assert_eq!(is_chrome_trace(b"TRACEv0", false), Some(false));
+        // A Perfetto trace whose first packet is 123 or 91 bytes long
+        assert_eq!(
+            is_chrome_trace(&[0x0a, b'{', 0x50, 0x01], false),
+            Some(false)
+        );
+        assert_eq!(
+            is_chrome_trace(&[0x0a, b'[', b'{', b'"'], false),
+            Some(false)
+        );
+        assert_eq!(is_chrome_trace(br#"[{"name":"bui"#, false), None);
+        assert_eq!(is_chrome_trace(br#"{"displayTimeUnit":"ns","#, false), None);
+        // A small file that has been read completely
+        assert_eq!(
+            is_chrome_trace(br#"{"displayTimeUnit":"ns"}"#, true),
+            Some(false)
+        );
+        assert_eq!(is_chrome_trace(b"[]", true), Some(true));
+    }
+
+    #[test]
+    fn test_read_chrome_trace() -> Result<()> {
+        let store = Arc::new(StoreContainer::new());
+        let mut format = ChromeTraceFormat::new(TurbopackFormat::new(store.clone()));
+        let trace = br#"[{"name":"build","ph":"B","ts":10,"pid":1,"tid":1},
+            {"name":"parse","ph":"X","ts":12,"dur":8,"pid":1,"tid":1},
+            {"name":"build","ph":"E","ts":30,"pid":1,"tid":1}]"#;
+        read_in_chunks(&mut format, trace, 16)?;
+        assert_eq!(
+            spans(&store),
+            [("build".to_string(), 20), ("build > parse".to_string(), 8)]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_read_perfetto_trace() -> Result<()> {
+        let rows = [
+            TraceRow::Start {
+                ts: 10,
+                id: 1,
+                parent: None,
+                name: "build".into(),
+                target: "turbopack".into(),
+                values: vec![],
+            },
+            TraceRow::Enter {
+                ts: 10,
+                id: 1,
+                thread_id: 1,
+            },
+            TraceRow::Start {
+                ts: 12,
+                id: 2,
+                parent: Some(1),
+                name: "parse".into(),
+                target: "turbopack".into(),
+                values: vec![],
+            },
+            TraceRow::Enter {
+                ts: 12,
+                id: 2,
+                thread_id: 1,
+            },
+            TraceRow::Exit {
+                ts: 20,
+                id: 2,
+                thread_id: 1,
+            },
+            TraceRow::End { ts: 20, id: 2 },
+            TraceRow::Exit {
+                ts: 30,
+                id: 1,
+                thread_id: 1,
+            },
+            TraceRow::End { ts: 30, id: 1 },
+        ];
+        let mut trace = Vec::new();
+        let mut writer = PerfettoWriter::new(&mut trace);
+        let mut converter = TraceRowToChromeEvents::new();
+        let mut events = Vec::new();
+        for row in rows {
+            converter.convert(row, &mut events);
+        }
+        for event in &events {
+            writer.write_event(event)?;
+        }
+        writer.finish()?;
+        assert_eq!(trace[0], PERFETTO_TRACE_PACKET);
+
+        let store = Arc::new(StoreContainer::new());
+        let mut format = PerfettoFormat::new(TurbopackFormat::new(store.clone()));
+        read_in_chunks(&mut format, &trace, 7)?;
+        assert_eq!(
+            spans(&store),
+            [("build".to_string(), 20), ("build > parse".to_string(), 8)]
+        );
+        Ok(())
+    }
+}
@@ -0,0 +1,42 @@
+use anyhow::Result;
+use turbopack_trace_utils::{
+    chrome_trace::{ChromeEventsToTraceRow, ChromeTraceEvent},
+    perfetto::PerfettoDecoder,
+    tracing::TraceRow,
+};
+
+use super::{TraceFormat, turbopack::TurbopackFormat};
+
+/// Reads Perfetto protobuf traces, e.g. exported from a turbopack trace.
+pub struct PerfettoFormat {
+    inner: TurbopackFormat,
+    decoder: PerfettoDecoder,
+    converter: ChromeEventsToTraceRow,
+    events: Vec<ChromeTraceEvent>,
+}
+
+impl PerfettoFormat {
+    pub fn new(inner: TurbopackFormat) -> Self {
+        Self {
+            inner,
+            decoder: PerfettoDecoder::new(),
+            converter: ChromeEventsToTraceRow::new(),
+            events: Vec::new(),
+        }
+    }
+}
+
+impl TraceFormat for PerfettoFormat {
+    type Reused = Vec<TraceRow<'static>>;
+
+    fn read(&mut self, buffer: &[u8], rows: &mut Self::Reused) -> Result<usize> {
+        let bytes_read = self.decoder.decode(buffer, &mut self.events)?;
+        for event in self.events.drain(..) {
+            self.converter.convert(event, rows);
+        }
+        if !rows.is_empty() {
+            self.inner.process_rows(rows.drain(..));
+        }
+        Ok(bytes_read)
+    }
+}
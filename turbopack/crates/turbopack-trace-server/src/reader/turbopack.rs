@@ -152,6 +152,17 @@ impl TurbopackFormat {
         }
     }
 
+    /// Adds the rows to the store. Used by the formats that are converted to [TraceRow]s.
+    pub(super) fn process_rows<'a>(&mut self, rows: impl IntoIterator<Item = TraceRow<'a>>) {
+        let store = self.store.clone();
+        let mut store = store.write();
+        for row in rows {
+            self.process(&mut store, row);
+        }
+        store.invalidate_outdated_spans(&self.outdated_spans);
+        self.outdated_spans.clear();
+    }
+
     fn process(&mut self, store: &mut StoreWriteGuard, row: TraceRow<'_>) {
         match row {
             TraceRow::Start {
@@ -509,16 +520,7 @@ impl TraceFormat for TurbopackFormat {
             }
         }
         if !rows.is_empty() {
-            let store = self.store.clone();
-            let mut iter = rows.drain(..);
-            {
-                let mut store = store.write();
-                for row in iter.by_ref() {
-                    self.process(&mut store, row);
-                }
-                store.invalidate_outdated_spans(&self.outdated_spans);
-                self.outdated_spans.clear();
-            }
+            self.process_rows(rows.drain(..));
         }
         Ok(bytes_read)
     }
@@ -0,0 +1,356 @@
+use std::{
+    fmt::{self, Display, Formatter},
+    io::Write,
+    path::PathBuf,
+    sync::Arc,
+};
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    reader::TraceReader, span_bottom_up_ref::SpanBottomUpRef, span_ref::SpanRef, store::Store,
+    store_container::StoreContainer,
+};
+
+/// How many callers are listed for each bottom-up entry.
+const BOTTOM_UP_CALLERS: usize = 5;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Text,
+    Json,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ReportOptions {
+    /// The number of entries of each list.
+    pub top: usize,
+    pub format: ReportFormat,
+}
+
+impl Default for ReportOptions {
+    fn default() -> Self {
+        Self {
+            top: 20,
+            format: ReportFormat::Text,
+        }
+    }
+}
+
+/// A summary of a trace file. All durations are in microseconds.
+///
+/// `duration` is the wall clock time, which is corrected for spans running in parallel, while
+/// `cpu` is the time spent in the span regardless of concurrency.
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceReport {
+    pub total: ReportTotals,
+    pub top_self_duration: Vec<ReportSpan>,
+    pub top_self_cpu: Vec<ReportSpan>,
+    pub top_self_allocations: Vec<ReportSpan>,
+    /// Spans grouped by name with their self values summed up, ordered by duration.
+    pub bottom_up: Vec<ReportBottomUp>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportTotals {
+    pub duration: u64,
+    pub cpu: u64,
+    pub allocations: u64,
+    pub allocation_count: u64,
+    pub persistent_allocations: u64,
+    pub spans: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportSpan {
+    pub category: String,
+    pub name: String,
+    pub self_duration: u64,
+    pub self_cpu: u64,
+    pub self_allocations: u64,
+    pub self_allocation_count: u64,
+    pub total_duration: u64,
+    /// The names of the parent spans, starting at the root.
+    pub path: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ReportBottomUp {
+    pub category: String,
+    pub name: String,
+    pub count: u64,
+    pub self_duration: u64,
+    pub self_cpu: u64,
+    pub self_allocations: u64,
+    pub self_allocation_count: u64,
+    /// The spans calling this span, ordered by the self duration spent in this span.
+    pub callers: Vec<ReportBottomUp>,
+}
+
+/// Reads the whole trace file at `path` and writes a report to `out`.
+pub fn write_report(path: PathBuf, options: ReportOptions, out: &mut impl Write) -> Result<()> {
+    let store = Arc::new(StoreContainer::new());
+    TraceReader::read_to_end(store.clone(), path)?;
+    let store = store.read();
+    let report = TraceReport::new(&store, options.top);
+    match options.format {
+        ReportFormat::Text => write!(out, "{report}")?,
+        ReportFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, &report)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+impl TraceReport {
+    pub fn new(store: &Store, top: usize) -> Self {
+        let root = store.root_span();
+        let mut spans = Vec::new();
+        let mut stack = root.children().collect::<Vec<_>>();
+        while let Some(span) = stack.pop() {
+            stack.extend(span.children());
+            spans.push(span);
+        }
+
+        let mut top_by = |value: fn(&SpanRef<'_>) -> u64| {
+            spans.sort_by_cached_key(|span| std::cmp::Reverse(value(span)));
+            spans
+                .iter()
+                .take(top)
+                .filter(|span| value(span) > 0)
+                .map(ReportSpan::new)
+                .collect()
+        };
+        let top_self_duration = top_by(|span| *span.corrected_self_time());
+        let top_self_cpu = top_by(|span| *span.self_time());
+        let top_self_allocations = top_by(|span| span.self_allocations());
+
+        let mut bottom_up = root.bottom_up().collect::<Vec<_>>();
+        bottom_up.sort_by_cached_key(|entry| std::cmp::Reverse(entry.corrected_self_time()));
+        let bottom_up = bottom_up
+            .iter()
+            .take(top)
+            .map(|entry| ReportBottomUp::new(entry, BOTTOM_UP_CALLERS))
+            .collect();
+
+        Self {
+            total: ReportTotals {
+                duration: root.corrected_total_time().as_micros(),
+                cpu: root.total_time().as_micros(),
+                allocations: root.total_allocations(),
+                allocation_count: root.total_allocation_count(),
+                persistent_allocations: root.total_persistent_allocations(),
+                // The root span is not a real span
+                spans: root.total_span_count() - 1,
+            },
+            top_self_duration,
+            top_self_cpu,
+            top_self_allocations,
+            bottom_up,
+        }
+    }
+}
+
+impl ReportSpan {
+    fn new(span: &SpanRef<'_>) -> Self {
+        let (category, name) = span.nice_name();
+        let mut path = Vec::new();
+        let mut parent = span.parent();
+        while let Some(span) = parent
+            && !span.is_root()
+        {
+            path.push(span.nice_name().1.to_string());
+            parent = span.parent();
+        }
+        path.reverse();
+        Self {
+            category: category.to_string(),
+            name: name.to_string(),
+            self_duration: span.corrected_self_time().as_micros(),
+            self_cpu: span.self_time().as_micros(),
+            self_allocations: span.self_allocations(),
+            self_allocation_count: span.self_allocation_count(),
+            total_duration: span.corrected_total_time().as_micros(),
+            path,
+        }
+    }
+}
+
+impl ReportBottomUp {
+    fn new(entry: &SpanBottomUpRef<'_>, callers: usize) -> Self {
+        let (category, name) = entry.example_group_name();
+        let mut children = entry.children().collect::<Vec<_>>();
+        children.sort_by_cached_key(|child| std::cmp::Reverse(child.corrected_self_time()));
+        Self {
+            category: category.to_string(),
+            name: name.to_string(),
+            count: entry.self_span_count(),
+            self_duration: entry.corrected_self_time().as_micros(),
+            self_cpu: entry.self_time().as_micros(),
+            self_allocations: entry.self_allocations(),
+            self_allocation_count: entry.self_allocation_count(),
+            // Callers are only listed one level deep to keep the report readable
+            callers: children
+                .iter()
+                .take(callers)
+                .map(|child| ReportBottomUp::new(child, 0))
+                .collect(),
+        }
+    }
+}
+
+/// Formats a duration in microseconds.
+pub(crate) struct FormatDuration(pub u64);
+
+impl Display for FormatDuration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let micros = self.0;
+        if micros >= 10_000_000 {
+            write!(f, "{:.1}s", micros as f64 / 1_000_000.0)
+        } else if micros >= 10_000 {
+            write!(f, "{:.1}ms", micros as f64 / 1_000.0)
+        } else {
+            write!(f, "{micros}µs")
+        }
+    }
+}
+
+/// Formats a number of bytes.
+pub(crate) struct FormatBytes(pub u64);
+
+impl Display for FormatBytes {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let bytes = self.0;
+        if bytes >= 10 * 1024 * 1024 * 1024 {
+            write!(f, "{:.1} GB", bytes as f64 / (1024.0 * 1024.0 * 1024.0))
+        } else if bytes >= 10 * 1024 * 1024 {
+            write!(f, "{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+        } else if bytes >= 10 * 1024 {
+            write!(f, "{:.1} KB", bytes as f64 / 1024.0)
+        } else {
+            write!(f, "{bytes} B")
+        }
+    }
+}
+
+impl Display for TraceReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let ReportTotals {
+            duration,
+            cpu,
+            allocations,
+            allocation_count,
+            persistent_allocations,
+            spans,
+        } = self.total;
+        writeln!(f, "Total")?;
+        writeln!(f, "  duration:               {}", FormatDuration(duration))?;
+        writeln!(f, "  cpu:                    {}", FormatDuration(cpu))?;
+        writeln!(f, "  allocations:            {}", FormatBytes(allocations))?;
+        writeln!(f, "  allocation count:       {allocation_count}")?;
+        writeln!(
+            f,
+            "  persistent allocations: {}",
+            FormatBytes(persistent_allocations)
+        )?;
+        writeln!(f, "  spans:                  {spans}")?;
+
+        for (title, spans, value) in [
+            (
+                "Top spans by self duration",
+                &self.top_self_duration,
+                (|span: &ReportSpan| FormatDuration(span.self_duration).to_string())
+                    as fn(&ReportSpan) -> String,
+            ),
+            ("Top spans by self cpu", &self.top_self_cpu, |span| {
+                FormatDuration(span.self_cpu).to_string()
+            }),
+            (
+                "Top spans by self allocations",
+                &self.top_self_allocations,
+                |span| FormatBytes(span.self_allocations).to_string(),
+            ),
+        ] {
+            writeln!(f)?;
+            writeln!(f, "{title}")?;
+            for span in spans {
+                writeln!(f, "  {:>10}  {}", value(span), span.name)?;
+                if !span.path.is_empty() {
+                    writeln!(f, "  {:>10}    in {}", "", span.path.join(" > "))?;
+                }
+            }
+        }
+
+        writeln!(f)?;
+        writeln!(f, "Bottom-up by self duration")?;
+        for entry in &self.bottom_up {
+            writeln!(
+                f,
+                "  {:>10}  {} ({}x, {} cpu, {})",
+                FormatDuration(entry.self_duration).to_string(),
+                entry.name,
+                entry.count,
+                FormatDuration(entry.self_cpu),
+                FormatBytes(entry.self_allocations)
+            )?;
+            for caller in &entry.callers {
+                writeln!(
+                    f,
+                    "  {:>10}    called by {} ({}x)",
+                    FormatDuration(caller.self_duration).to_string(),
+                    caller.name,
+                    caller.count
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rustc_hash::FxHashSet;
+
+    use super::*;
+
+    #[test]
+    fn report() {
+        let mut store = Store::new();
+        let build = store.add_test_span(None, "build", 0, 10);
+        store.add_test_span(Some(build), "parse", 10, 40);
+        let transform = store.add_test_span(Some(build), "transform", 40, 50);
+        store.add_test_span(Some(transform), "parse", 60, 80);
+        store.add_allocation(transform, 1032, 5, &mut FxHashSet::default());
+
+        let report = TraceReport::new(&store, 2);
+        assert_eq!(report.total.duration, 70);
+        assert_eq!(report.total.spans, 4);
+
+        let top = report
+            .top_self_duration
+            .iter()
+            .map(|span| (span.name.as_str(), span.self_duration))
+            .collect::<Vec<_>>();
+        assert_eq!(top, vec![("parse", 30), ("parse", 20)]);
+        assert_eq!(report.top_self_allocations.len(), 1);
+        assert_eq!(report.top_self_allocations[0].name, "transform");
+        assert_eq!(report.top_self_allocations[0].self_allocations, 1000);
+        assert_eq!(report.top_self_allocations[0].path, vec!["build"]);
+
+        let parse = &report.bottom_up[0];
+        assert_eq!((parse.name.as_str(), parse.count), ("parse", 2));
+        assert_eq!(parse.self_duration, 50);
+        let callers = parse
+            .callers
+            .iter()
+            .map(|caller| (caller.name.as_str(), caller.self_duration))
+            .collect::<Vec<_>>();
+        assert_eq!(callers, vec![("build", 30), ("transform", 20)]);
+    }
+}
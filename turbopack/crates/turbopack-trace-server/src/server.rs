@@ -9,6 +9,7 @@ use serde::{Deserialize, Serialize};
 use tungstenite::{Message, accept};
 
 use crate::{
+    diff::{DiffOptions, TraceDiff},
     store::SpanId,
     store_container::StoreContainer,
     timestamp::Timestamp,
@@ -44,6 +45,10 @@ pub enum ServerToClientMessage {
         args: Vec<(String, String)>,
         path: Vec<String>,
     },
+    /// The differences to the baseline trace. `None` when no baseline trace has been loaded.
+    DiffResult {
+        diff: Option<TraceDiff>,
+    },
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -68,6 +73,10 @@ pub enum ClientToServerMessage {
         #[serde(with = "u64_string")]
         id: SpanId,
     },
+    /// Compares the trace with the baseline trace.
+    QueryDiff {
+        top: usize,
+    },
     Ack,
     CheckForMoreData,
 }
@@ -111,12 +120,13 @@ pub struct ViewRect {
 
 struct ConnectionState {
     store: Arc<StoreContainer>,
+    baseline: Option<Arc<StoreContainer>>,
     viewer: Viewer,
     view_rect: ViewRect,
     last_update_generation: usize,
 }
 
-pub fn serve(store: Arc<StoreContainer>, port: u16) {
+pub fn serve(store: Arc<StoreContainer>, baseline: Option<Arc<StoreContainer>>, port: u16) {
     let server = TcpListener::bind(SocketAddr::V4(SocketAddrV4::new(
         std::net::Ipv4Addr::new(127, 0, 0, 1),
         port,
@@ -124,10 +134,11 @@ pub fn serve(store: Arc<StoreContainer>, port: u16) {
     .unwrap();
     for stream in server.incoming() {
         let store = store.clone();
+        let baseline = baseline.clone();
 
         spawn(move || {
             let websocket = accept(stream.unwrap()).unwrap();
-            if let Err(err) = handle_connection(websocket, store) {
+            if let Err(err) = handle_connection(websocket, store, baseline) {
                 eprintln!("Error: {err:?}");
             }
         });
@@ -137,9 +148,11 @@ pub fn serve(store: Arc<StoreContainer>, port: u16) {
 fn handle_connection(
     mut websocket: tungstenite::WebSocket<TcpStream>,
     store: Arc<StoreContainer>,
+    baseline: Option<Arc<StoreContainer>>,
 ) -> Result<()> {
     let state = Arc::new(Mutex::new(ConnectionState {
         store,
+        baseline,
         viewer: Viewer::new(),
         view_rect: ViewRect {
             x: 0,
@@ -338,6 +351,21 @@ fn handle_connection(
 
                         continue;
                     }
+                    ClientToServerMessage::QueryDiff { top } => {
+                        let diff = state.baseline.as_ref().map(|baseline| {
+                            TraceDiff::new(
+                                &baseline.read(),
+                                &state.store.read(),
+                                DiffOptions {
+                                    top,
+                                    ..Default::default()
+                                },
+                            )
+                        });
+                        let message = ServerToClientMessage::DiffResult { diff };
+                        let message = serde_json::to_string(&message).unwrap();
+                        websocket.send(Message::Text(message))?;
+                    }
                     ClientToServerMessage::Ack => {
                         ready_for_update = true;
                         if update_skipped {
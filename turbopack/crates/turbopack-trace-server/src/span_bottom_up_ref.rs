@@ -58,6 +58,12 @@ impl<'a> SpanBottomUpRef<'a> {
         self.first_span().group_name()
     }
 
+    /// The group name of the span this entry is keyed by. For caller entries this is the calling
+    /// span, while [`Self::group_name`] names the spans that were called.
+    pub fn example_group_name(&self) -> (&'a str, &'a str) {
+        self.example_span().group_name()
+    }
+
     pub fn nice_name(&self) -> (&'a str, &'a str) {
         if self.count() == 1 {
             self.example_span().nice_name()
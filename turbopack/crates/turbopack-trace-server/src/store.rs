@@ -384,3 +384,33 @@ impl Store {
         })
     }
 }
+
+#[cfg(test)]
+impl Store {
+    /// Adds a completed span that spends `start..end` (in microseconds) in itself, for tests.
+    pub(crate) fn add_test_span(
+        &mut self,
+        parent: Option<SpanIndex>,
+        name: &str,
+        start: u64,
+        end: u64,
+    ) -> SpanIndex {
+        let mut outdated_spans = FxHashSet::default();
+        let span = self.add_span(
+            parent,
+            Timestamp::from_micros(start),
+            "".to_string(),
+            name.to_string(),
+            vec![],
+            &mut outdated_spans,
+        );
+        self.add_self_time(
+            span,
+            Timestamp::from_micros(start),
+            Timestamp::from_micros(end),
+            &mut outdated_spans,
+        );
+        self.complete_span(span);
+        span
+    }
+}
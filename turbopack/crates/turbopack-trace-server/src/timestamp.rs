@@ -21,6 +21,10 @@ impl Timestamp {
         Self(micros * DUR_VALUE_MICROSECOND)
     }
 
+    pub fn as_micros(self) -> u64 {
+        self.0 / DUR_VALUE_MICROSECOND
+    }
+
     pub fn is_zero(&self) -> bool {
         self.0 == 0
     }
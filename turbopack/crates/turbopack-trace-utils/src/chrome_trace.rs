@@ -0,0 +1,534 @@
+use std::{borrow::Cow, io::Write, mem::take};
+
+use anyhow::Result;
+use rustc_hash::FxHashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+
+use crate::tracing::{TraceRow, TraceValue};
+
+/// The process id of exported traces.
+pub const TURBOPACK_PID: u64 = 1;
+
+/// The name of the counter events that carry the allocation counters of a thread.
+pub const ALLOCATIONS_COUNTER: &str = "allocations";
+
+const ALLOCATIONS_COUNTER_ARGS: [&str; 4] = [
+    "allocations",
+    "allocation_count",
+    "deallocations",
+    "deallocation_count",
+];
+
+/// An event of the [Chrome Trace Event Format](https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU).
+///
+/// [TraceRow]s are mapped to events like this:
+/// * The lifetime of a span is a nestable async event (`b`/`e`), recorded fields are an async
+///   instant event (`n`).
+/// * The time a thread spends in a span is a duration event (`B`/`E`) on that thread.
+/// * Events are instant events (`i`).
+/// * Allocations are [ALLOCATIONS_COUNTER] counter events (`C`) per thread.
+///
+/// The span id and the parent span id are stored in `id` and `parent`. Other tools ignore
+/// `parent`, so the hierarchy is only preserved when the trace is read back.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ChromeTraceEvent {
+    #[serde(default)]
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub cat: String,
+    pub ph: String,
+    /// Timestamp in microseconds
+    #[serde(default)]
+    pub ts: f64,
+    /// Duration in microseconds, for complete events (`X`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dur: Option<f64>,
+    #[serde(default)]
+    pub pid: u64,
+    #[serde(default)]
+    pub tid: u64,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "chrome_trace_id"
+    )]
+    pub id: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<u64>,
+    /// The scope of an instant event
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub s: Option<String>,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub args: Map<String, Value>,
+}
+
+impl ChromeTraceEvent {
+    /// Creates an event of the turbopack process.
+    pub fn new(ph: &str, ts: u64) -> Self {
+        Self {
+            name: String::new(),
+            cat: String::new(),
+            ph: ph.to_string(),
+            ts: ts as f64,
+            dur: None,
+            pid: TURBOPACK_PID,
+            tid: 0,
+            id: None,
+            parent: None,
+            s: None,
+            args: Map::new(),
+        }
+    }
+}
+
+/// Ids are hex strings in most traces, but numbers are valid too.
+mod chrome_trace_id {
+    use std::hash::{BuildHasher, BuildHasherDefault};
+
+    use rustc_hash::FxHasher;
+    use serde::{Deserialize, Deserializer, Serializer};
+
+    pub fn serialize<S: Serializer>(id: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error> {
+        match id {
+            Some(id) => serializer.serialize_str(&format!("0x{id:x}")),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<u64>, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Id {
+            Number(u64),
+            String(String),
+        }
+        Ok(Option::<Id>::deserialize(deserializer)?.map(|id| match id {
+            Id::Number(id) => id,
+            Id::String(id) => id
+                .strip_prefix("0x")
+                .and_then(|hex| u64::from_str_radix(hex, 16).ok())
+                .or_else(|| id.parse().ok())
+                // Arbitrary strings only need to be unique
+                .unwrap_or_else(|| BuildHasherDefault::<FxHasher>::default().hash_one(&id)),
+        }))
+    }
+}
+
+fn value_to_json(value: TraceValue<'_>) -> Value {
+    match value {
+        TraceValue::String(s) => Value::String(s.into_owned()),
+        TraceValue::Bool(b) => Value::Bool(b),
+        TraceValue::UInt(u) => Value::Number(u.into()),
+        TraceValue::Int(i) => Value::Number(i.into()),
+        TraceValue::Float(f) => Number::from_f64(f).map_or(Value::Null, Value::Number),
+    }
+}
+
+fn json_to_value(value: Value) -> TraceValue<'static> {
+    match value {
+        Value::String(s) => TraceValue::String(s.into()),
+        Value::Bool(b) => TraceValue::Bool(b),
+        Value::Number(n) => {
+            if let Some(u) = n.as_u64() {
+                TraceValue::UInt(u)
+            } else if let Some(i) = n.as_i64() {
+                TraceValue::Int(i)
+            } else {
+                TraceValue::Float(n.as_f64().unwrap_or_default())
+            }
+        }
+        value => TraceValue::String(value.to_string().into()),
+    }
+}
+
+fn values_to_args(values: Vec<(Cow<'_, str>, TraceValue<'_>)>) -> Map<String, Value> {
+    values
+        .into_iter()
+        .map(|(key, value)| (key.into_owned(), value_to_json(value)))
+        .collect()
+}
+
+fn args_to_values(args: Map<String, Value>) -> Vec<(Cow<'static, str>, TraceValue<'static>)> {
+    args.into_iter()
+        .map(|(key, value)| (key.into(), json_to_value(value)))
+        .collect()
+}
+
+#[derive(Default, Clone, Copy)]
+struct AllocationCounters {
+    allocations: u64,
+    allocation_count: u64,
+    deallocations: u64,
+    deallocation_count: u64,
+}
+
+impl AllocationCounters {
+    fn to_event(self, ts: u64, thread_id: u64) -> ChromeTraceEvent {
+        let mut event = ChromeTraceEvent::new("C", ts);
+        event.name = ALLOCATIONS_COUNTER.to_string();
+        event.tid = thread_id;
+        // Separates the counters of different threads in the viewer
+        event.id = Some(thread_id);
+        for (key, value) in ALLOCATIONS_COUNTER_ARGS.into_iter().zip([
+            self.allocations,
+            self.allocation_count,
+            self.deallocations,
+            self.deallocation_count,
+        ]) {
+            event.args.insert(key.to_string(), value.into());
+        }
+        event
+    }
+
+    fn from_args(args: &Map<String, Value>) -> Option<Self> {
+        let [
+            allocations,
+            allocation_count,
+            deallocations,
+            deallocation_count,
+        ] = ALLOCATIONS_COUNTER_ARGS.map(|key| args.get(key).and_then(|value| value.as_u64()));
+        Some(Self {
+            allocations: allocations?,
+            allocation_count: allocation_count?,
+            deallocations: deallocations?,
+            deallocation_count: deallocation_count?,
+        })
+    }
+}
+
+/// Converts [TraceRow]s to [ChromeTraceEvent]s.
+#[derive(Default)]
+pub struct TraceRowToChromeEvents {
+    /// The name and target of all active spans
+    spans: FxHashMap<u64, (String, String)>,
+    /// The sum of the `Allocation` rows per thread
+    allocations: FxHashMap<u64, AllocationCounters>,
+}
+
+impl TraceRowToChromeEvents {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn convert(&mut self, row: TraceRow<'_>, events: &mut Vec<ChromeTraceEvent>) {
+        match row {
+            TraceRow::Start {
+                ts,
+                id,
+                parent,
+                name,
+                target,
+                values,
+            } => {
+                let mut event = ChromeTraceEvent::new("b", ts);
+                event.name = name.into_owned();
+                event.cat = target.into_owned();
+                event.id = Some(id);
+                event.parent = parent;
+                event.args = values_to_args(values);
+                self.spans
+                    .insert(id, (event.name.clone(), event.cat.clone()));
+                events.push(event);
+            }
+            TraceRow::End { ts, id } => {
+                let mut event = ChromeTraceEvent::new("e", ts);
+                if let Some((name, target)) = self.spans.remove(&id) {
+                    event.name = name;
+                    event.cat = target;
+                }
+                event.id = Some(id);
+                events.push(event);
+            }
+            TraceRow::Enter { ts, id, thread_id } => {
+                events.push(self.span_event("B", ts, id, thread_id));
+            }
+            TraceRow::Exit { ts, id, thread_id } => {
+                events.push(self.span_event("E", ts, id, thread_id));
+            }
+            TraceRow::Event { ts, parent, values } => {
+                let mut event = ChromeTraceEvent::new("i", ts);
+                event.parent = parent;
+                event.s = Some("p".to_string());
+                event.args = values_to_args(values);
+                event.name = event
+                    .args
+                    .get("message")
+                    .and_then(|message| message.as_str())
+                    .unwrap_or("event")
+                    .to_string();
+                events.push(event);
+            }
+            TraceRow::Record { id, values } => {
+                // Recorded fields have no timestamp
+                let mut event = ChromeTraceEvent::new("n", 0);
+                if let Some((name, target)) = self.spans.get(&id) {
+                    event.name = name.clone();
+                    event.cat = target.clone();
+                }
+                event.id = Some(id);
+                event.args = values_to_args(values);
+                events.push(event);
+            }
+            TraceRow::Allocation {
+                ts,
+                thread_id,
+                allocations,
+                allocation_count,
+                deallocations,
+                deallocation_count,
+            } => {
+                let counters = self.allocations.entry(thread_id).or_insert_with(|| {
+                    // Readers only count the difference to the first counter event
+                    events.push(AllocationCounters::default().to_event(ts, thread_id));
+                    AllocationCounters::default()
+                });
+                counters.allocations += allocations;
+                counters.allocation_count += allocation_count;
+                counters.deallocations += deallocations;
+                counters.deallocation_count += deallocation_count;
+                events.push(counters.to_event(ts, thread_id));
+            }
+            TraceRow::AllocationCounters {
+                ts,
+                thread_id,
+                allocations,
+                allocation_count,
+                deallocations,
+                deallocation_count,
+            } => {
+                let counters = AllocationCounters {
+                    allocations,
+                    allocation_count,
+                    deallocations,
+                    deallocation_count,
+                };
+                events.push(counters.to_event(ts, thread_id));
+            }
+        }
+    }
+
+    fn span_event(&self, ph: &str, ts: u64, id: u64, thread_id: u64) -> ChromeTraceEvent {
+        let mut event = ChromeTraceEvent::new(ph, ts);
+        if let Some((name, target)) = self.spans.get(&id) {
+            event.name = name.clone();
+            event.cat = target.clone();
+        }
+        event.tid = thread_id;
+        event.id = Some(id);
+        event
+    }
+}
+
+/// A span created from a duration event of another tool, which has no span id.
+struct SyntheticSpan {
+    id: u64,
+    /// The end of complete events (`X`)
+    end: Option<u64>,
+}
+
+/// Converts [ChromeTraceEvent]s back to [TraceRow]s.
+///
+/// Events written by [TraceRowToChromeEvents] are converted back to the original rows. Duration
+/// events of other tools (e.g. Node.js) become spans which are nested by the order of the events
+/// on each thread.
+pub struct ChromeEventsToTraceRow {
+    next_synthetic_id: u64,
+    synthetic_stacks: FxHashMap<u64, Vec<SyntheticSpan>>,
+}
+
+impl Default for ChromeEventsToTraceRow {
+    fn default() -> Self {
+        Self {
+            // Far away from the ids of exported spans
+            next_synthetic_id: 1 << 62,
+            synthetic_stacks: FxHashMap::default(),
+        }
+    }
+}
+
+impl ChromeEventsToTraceRow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Threads of other processes get distinct ids.
+    fn thread_id(event: &ChromeTraceEvent) -> u64 {
+        if event.pid == TURBOPACK_PID {
+            event.tid
+        } else {
+            (event.pid << 32) ^ event.tid
+        }
+    }
+
+    /// Async events of other processes get distinct ids.
+    fn span_id(event: &ChromeTraceEvent) -> Option<u64> {
+        if event.pid == TURBOPACK_PID {
+            event.id
+        } else {
+            event.id.map(|id| id ^ (event.pid << 40))
+        }
+    }
+
+    pub fn convert(&mut self, event: ChromeTraceEvent, rows: &mut Vec<TraceRow<'static>>) {
+        let ts = event.ts.max(0.0) as u64;
+        let thread_id = Self::thread_id(&event);
+        let id = Self::span_id(&event);
+        self.close_complete_events(thread_id, Some(ts), rows);
+        let ChromeTraceEvent {
+            name,
+            cat,
+            ph,
+            dur,
+            parent,
+            args,
+            ..
+        } = event;
+        match (ph.as_str(), id) {
+            ("b", Some(id)) => rows.push(TraceRow::Start {
+                ts,
+                id,
+                parent,
+                name: name.into(),
+                target: cat.into(),
+                values: args_to_values(args),
+            }),
+            ("e", Some(id)) => rows.push(TraceRow::End { ts, id }),
+            ("n", Some(id)) => rows.push(TraceRow::Record {
+                id,
+                values: args_to_values(args),
+            }),
+            ("B", Some(id)) => rows.push(TraceRow::Enter { ts, id, thread_id }),
+            ("E", Some(id)) => rows.push(TraceRow::Exit { ts, id, thread_id }),
+            ("B" | "X", _) => {
+                let id = self.next_synthetic_id;
+                self.next_synthetic_id += 1;
+                let stack = self.synthetic_stacks.entry(thread_id).or_default();
+                rows.push(TraceRow::Start {
+                    ts,
+                    id,
+                    parent: stack.last().map(|span| span.id),
+                    name: name.into(),
+                    target: cat.into(),
+                    values: args_to_values(args),
+                });
+                rows.push(TraceRow::Enter { ts, id, thread_id });
+                stack.push(SyntheticSpan {
+                    id,
+                    end: dur.map(|dur| ts + dur.max(0.0) as u64),
+                });
+            }
+            ("E", None) => {
+                if let Some(stack) = self.synthetic_stacks.get_mut(&thread_id)
+                    && let Some(SyntheticSpan { id, end: None }) = stack.last()
+                {
+                    let id = *id;
+                    stack.pop();
+                    rows.push(TraceRow::Exit { ts, id, thread_id });
+                    rows.push(TraceRow::End { ts, id });
+                }
+            }
+            ("i" | "I", _) => {
+                let mut values = args_to_values(args);
+                if !values.iter().any(|(key, _)| key == "message") {
+                    values.push(("message".into(), TraceValue::String(name.into())));
+                }
+                rows.push(TraceRow::Event {
+                    ts,
+                    parent: parent.or_else(|| {
+                        self.synthetic_stacks
+                            .get(&thread_id)
+                            .and_then(|stack| stack.last())
+                            .map(|span| span.id)
+                    }),
+                    values,
+                })
+            }
+            ("C", _) if name == ALLOCATIONS_COUNTER => {
+                if let Some(counters) = AllocationCounters::from_args(&args) {
+                    rows.push(TraceRow::AllocationCounters {
+                        ts,
+                        thread_id,
+                        allocations: counters.allocations,
+                        allocation_count: counters.allocation_count,
+                        deallocations: counters.deallocations,
+                        deallocation_count: counters.deallocation_count,
+                    });
+                }
+            }
+            // Metadata, flow events, other counters, etc.
+            _ => {}
+        }
+    }
+
+    /// Ends all spans created from complete events (`X`) that are still open.
+    pub fn finish(&mut self, rows: &mut Vec<TraceRow<'static>>) {
+        let threads = self.synthetic_stacks.keys().copied().collect::<Vec<_>>();
+        for thread_id in threads {
+            self.close_complete_events(thread_id, None, rows);
+        }
+    }
+
+    /// Ends the spans created from complete events on the thread that end before `ts`.
+    fn close_complete_events(
+        &mut self,
+        thread_id: u64,
+        ts: Option<u64>,
+        rows: &mut Vec<TraceRow<'static>>,
+    ) {
+        let Some(stack) = self.synthetic_stacks.get_mut(&thread_id) else {
+            return;
+        };
+        while let Some(&SyntheticSpan { id, end: Some(end) }) = stack.last()
+            && ts.is_none_or(|ts| end <= ts)
+        {
+            stack.pop();
+            rows.push(TraceRow::Exit {
+                ts: end,
+                id,
+                thread_id,
+            });
+            rows.push(TraceRow::End { ts: end, id });
+        }
+    }
+}
+
+/// A sink for [ChromeTraceEvent]s, e.g. a file in a specific format.
+pub trait TraceEventWriter {
+    fn write_event(&mut self, event: &ChromeTraceEvent) -> Result<()>;
+
+    /// Writes the end of the trace. No events must be written after this.
+    fn finish(&mut self) -> Result<()>;
+}
+
+/// Writes the JSON object format of the Chrome Trace Event Format.
+pub struct ChromeTraceWriter<W: Write> {
+    out: W,
+    empty: bool,
+}
+
+impl<W: Write> ChromeTraceWriter<W> {
+    pub fn new(mut out: W) -> Result<Self> {
+        out.write_all(b"{\"traceEvents\":[")?;
+        Ok(Self { out, empty: true })
+    }
+}
+
+impl<W: Write> TraceEventWriter for ChromeTraceWriter<W> {
+    fn write_event(&mut self, event: &ChromeTraceEvent) -> Result<()> {
+        if !take(&mut self.empty) {
+            self.out.write_all(b",")?;
+        }
+        self.out.write_all(b"\n")?;
+        serde_json::to_writer(&mut self.out, event)?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.out.write_all(b"\n]}\n")?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
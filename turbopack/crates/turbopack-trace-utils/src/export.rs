@@ -0,0 +1,205 @@
+use std::io::{Read, Write};
+
+use anyhow::{Result, bail};
+
+use crate::{
+    chrome_trace::{ChromeTraceWriter, TraceEventWriter, TraceRowToChromeEvents},
+    perfetto::PerfettoWriter,
+    tracing::TraceRow,
+};
+
+/// The magic bytes at the start of a raw trace file written by [crate::trace_writer::TraceWriter].
+const RAW_TRACE_MAGIC: &[u8] = b"TRACEv0";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TraceExportFormat {
+    /// The JSON object format of the Chrome Trace Event Format, which can be opened in
+    /// `chrome://tracing`, Perfetto UI and speedscope.
+    ChromeJson,
+    /// The protobuf format of Perfetto.
+    Perfetto,
+}
+
+/// Converts a raw trace, as written by the raw trace layer, to the `format`.
+pub fn export_raw_trace(
+    input: impl Read,
+    output: impl Write,
+    format: TraceExportFormat,
+) -> Result<()> {
+    match format {
+        TraceExportFormat::ChromeJson => convert(input, ChromeTraceWriter::new(output)?),
+        TraceExportFormat::Perfetto => convert(input, PerfettoWriter::new(output)),
+    }
+}
+
+fn convert(mut input: impl Read, mut writer: impl TraceEventWriter) -> Result<()> {
+    let mut converter = TraceRowToChromeEvents::new();
+    let mut events = Vec::new();
+    let mut buffer = Vec::new();
+    let mut chunk = vec![0; 1024 * 1024];
+    let mut start = 0;
+    let mut first = true;
+    loop {
+        let bytes_read = input.read(&mut chunk)?;
+        if bytes_read == 0 {
+            break;
+        }
+        buffer.drain(..start);
+        buffer.extend_from_slice(&chunk[..bytes_read]);
+        start = 0;
+        if first {
+            if buffer.len() < RAW_TRACE_MAGIC.len() {
+                continue;
+            }
+            // Old traces have no magic bytes
+            if buffer.starts_with(RAW_TRACE_MAGIC) {
+                start = RAW_TRACE_MAGIC.len();
+            }
+            first = false;
+        }
+        let mut remaining = &buffer[start..];
+        loop {
+            match postcard::take_from_bytes::<TraceRow<'_>>(remaining) {
+                Ok((row, rest)) => {
+                    remaining = rest;
+                    converter.convert(row, &mut events);
+                    for event in events.drain(..) {
+                        writer.write_event(&event)?;
+                    }
+                }
+                Err(postcard::Error::DeserializeUnexpectedEnd) => break,
+                Err(err) => return Err(err.into()),
+            }
+        }
+        start = buffer.len() - remaining.len();
+    }
+    if start < buffer.len() {
+        bail!(
+            "Raw trace ends with an incomplete row ({} bytes)",
+            buffer.len() - start
+        );
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        chrome_trace::{ChromeEventsToTraceRow, ChromeTraceEvent},
+        perfetto::PerfettoDecoder,
+        tracing::TraceValue,
+    };
+
+    fn rows() -> Vec<TraceRow<'static>> {
+        vec![
+            TraceRow::Start {
+                ts: 10,
+                id: 1,
+                parent: None,
+                name: "build".into(),
+                target: "turbopack".into(),
+                values: vec![("name".into(), TraceValue::String("app".into()))],
+            },
+            TraceRow::Enter {
+                ts: 10,
+                id: 1,
+                thread_id: 3,
+            },
+            TraceRow::Start {
+                ts: 12,
+                id: 2,
+                parent: Some(1),
+                name: "parse".into(),
+                target: "turbopack".into(),
+                values: vec![("size".into(), TraceValue::UInt(42))],
+            },
+            TraceRow::Enter {
+                ts: 12,
+                id: 2,
+                thread_id: 3,
+            },
+            TraceRow::Record {
+                id: 2,
+                values: vec![("cached".into(), TraceValue::Bool(false))],
+            },
+            TraceRow::AllocationCounters {
+                ts: 15,
+                thread_id: 3,
+                allocations: 1024,
+                allocation_count: 4,
+                deallocations: 512,
+                deallocation_count: 2,
+            },
+            TraceRow::Event {
+                ts: 16,
+                parent: Some(2),
+                values: vec![("message".into(), TraceValue::String("done".into()))],
+            },
+            TraceRow::Exit {
+                ts: 20,
+                id: 2,
+                thread_id: 3,
+            },
+            TraceRow::End { ts: 20, id: 2 },
+            TraceRow::Exit {
+                ts: 30,
+                id: 1,
+                thread_id: 3,
+            },
+            TraceRow::End { ts: 30, id: 1 },
+        ]
+    }
+
+    fn raw_trace() -> Vec<u8> {
+        let mut raw = RAW_TRACE_MAGIC.to_vec();
+        for row in rows() {
+            raw.extend(postcard::to_allocvec(&row).unwrap());
+        }
+        raw
+    }
+
+    fn to_rows(events: Vec<ChromeTraceEvent>) -> Vec<TraceRow<'static>> {
+        let mut converter = ChromeEventsToTraceRow::new();
+        let mut rows = Vec::new();
+        for event in events {
+            converter.convert(event, &mut rows);
+        }
+        converter.finish(&mut rows);
+        rows
+    }
+
+    #[test]
+    fn chrome_json_roundtrip() {
+        let mut json = Vec::new();
+        export_raw_trace(&raw_trace()[..], &mut json, TraceExportFormat::ChromeJson).unwrap();
+
+        #[derive(serde::Deserialize)]
+        #[serde(rename_all = "camelCase")]
+        struct ChromeTrace {
+            trace_events: Vec<ChromeTraceEvent>,
+        }
+        let trace: ChromeTrace = serde_json::from_slice(&json).unwrap();
+        assert_eq!(to_rows(trace.trace_events), rows());
+    }
+
+    #[test]
+    fn perfetto_roundtrip() {
+        let mut proto = Vec::new();
+        export_raw_trace(&raw_trace()[..], &mut proto, TraceExportFormat::Perfetto).unwrap();
+
+        // Decode in two parts to cover packets split across reads
+        let mut decoder = PerfettoDecoder::new();
+        let mut events = Vec::new();
+        let split = proto.len() / 2;
+        let consumed = decoder.decode(&proto[..split], &mut events).unwrap();
+        assert!(consumed <= split);
+        assert_eq!(
+            decoder.decode(&proto[consumed..], &mut events).unwrap(),
+            proto.len() - consumed
+        );
+
+        // Perfetto has no timestamps for recorded fields, so they are at 0 in both formats
+        assert_eq!(to_rows(events), rows());
+    }
+}
@@ -4,9 +4,12 @@
 #![feature(arbitrary_self_types)]
 #![feature(arbitrary_self_types_pointers)]
 
+pub mod chrome_trace;
 pub mod exit;
+pub mod export;
 pub mod filter_layer;
 mod flavor;
+pub mod perfetto;
 pub mod raw_trace;
 pub mod trace_writer;
 pub mod tracing;
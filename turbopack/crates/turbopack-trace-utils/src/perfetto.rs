@@ -0,0 +1,618 @@
+use std::io::Write;
+
+use anyhow::{Result, bail};
+use rustc_hash::FxHashMap;
+use serde_json::{Map, Number, Value};
+
+use crate::chrome_trace::{ChromeTraceEvent, TURBOPACK_PID, TraceEventWriter};
+
+// Field numbers of the Perfetto trace protos, see
+// https://github.com/google/perfetto/tree/main/protos/perfetto/trace
+const TRACE_PACKET: u32 = 1;
+
+const PACKET_TIMESTAMP: u32 = 8;
+const PACKET_TRUSTED_PACKET_SEQUENCE_ID: u32 = 10;
+const PACKET_TRACK_EVENT: u32 = 11;
+const PACKET_TRACK_DESCRIPTOR: u32 = 60;
+
+const TRACK_UUID: u32 = 1;
+const TRACK_NAME: u32 = 2;
+const TRACK_PROCESS: u32 = 3;
+const TRACK_THREAD: u32 = 4;
+const TRACK_PARENT_UUID: u32 = 5;
+const TRACK_COUNTER: u32 = 8;
+
+const PROCESS_PID: u32 = 1;
+const THREAD_PID: u32 = 1;
+const THREAD_TID: u32 = 2;
+
+const EVENT_DEBUG_ANNOTATIONS: u32 = 4;
+const EVENT_TYPE: u32 = 9;
+const EVENT_TRACK_UUID: u32 = 11;
+const EVENT_CATEGORIES: u32 = 22;
+const EVENT_NAME: u32 = 23;
+const EVENT_COUNTER_VALUE: u32 = 30;
+const EVENT_DOUBLE_COUNTER_VALUE: u32 = 44;
+
+const EVENT_TYPE_SLICE_BEGIN: u64 = 1;
+const EVENT_TYPE_SLICE_END: u64 = 2;
+const EVENT_TYPE_INSTANT: u64 = 3;
+const EVENT_TYPE_COUNTER: u64 = 4;
+
+const ANNOTATION_BOOL: u32 = 2;
+const ANNOTATION_UINT: u32 = 3;
+const ANNOTATION_INT: u32 = 4;
+const ANNOTATION_DOUBLE: u32 = 5;
+const ANNOTATION_STRING: u32 = 6;
+const ANNOTATION_NAME: u32 = 10;
+
+const WIRE_VARINT: u8 = 0;
+const WIRE_FIXED64: u8 = 1;
+const WIRE_LEN: u8 = 2;
+const WIRE_FIXED32: u8 = 5;
+
+/// Debug annotations that carry the fields of [ChromeTraceEvent] that Perfetto has no equivalent
+/// for.
+const ANNOTATION_PH: &str = "turbopack.ph";
+const ANNOTATION_ID: &str = "turbopack.id";
+const ANNOTATION_PARENT: &str = "turbopack.parent";
+
+const SEQUENCE_ID: u64 = 1;
+
+#[derive(Default)]
+struct ProtoWriter {
+    buf: Vec<u8>,
+}
+
+impl ProtoWriter {
+    fn varint(&mut self, mut value: u64) {
+        while value >= 0x80 {
+            self.buf.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        self.buf.push(value as u8);
+    }
+
+    fn key(&mut self, field: u32, wire_type: u8) {
+        self.varint(((field as u64) << 3) | wire_type as u64);
+    }
+
+    fn uint(&mut self, field: u32, value: u64) {
+        self.key(field, WIRE_VARINT);
+        self.varint(value);
+    }
+
+    fn int(&mut self, field: u32, value: i64) {
+        self.uint(field, value as u64);
+    }
+
+    fn double(&mut self, field: u32, value: f64) {
+        self.key(field, WIRE_FIXED64);
+        self.buf.extend_from_slice(&value.to_bits().to_le_bytes());
+    }
+
+    fn bytes(&mut self, field: u32, value: &[u8]) {
+        self.key(field, WIRE_LEN);
+        self.varint(value.len() as u64);
+        self.buf.extend_from_slice(value);
+    }
+
+    fn string(&mut self, field: u32, value: &str) {
+        self.bytes(field, value.as_bytes());
+    }
+
+    fn message(&mut self, field: u32, f: impl FnOnce(&mut ProtoWriter)) {
+        let mut message = ProtoWriter::default();
+        f(&mut message);
+        self.bytes(field, &message.buf);
+    }
+
+    fn annotation(&mut self, name: &str, value: &Value) {
+        self.message(EVENT_DEBUG_ANNOTATIONS, |annotation| {
+            annotation.string(ANNOTATION_NAME, name);
+            match value {
+                Value::Bool(b) => annotation.uint(ANNOTATION_BOOL, *b as u64),
+                Value::Number(n) => {
+                    if let Some(u) = n.as_u64() {
+                        annotation.uint(ANNOTATION_UINT, u);
+                    } else if let Some(i) = n.as_i64() {
+                        annotation.int(ANNOTATION_INT, i);
+                    } else {
+                        annotation.double(ANNOTATION_DOUBLE, n.as_f64().unwrap_or_default());
+                    }
+                }
+                Value::String(s) => annotation.string(ANNOTATION_STRING, s),
+                value => annotation.string(ANNOTATION_STRING, &value.to_string()),
+            }
+        });
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+enum TrackKey {
+    Process { pid: u64 },
+    Thread { pid: u64, tid: u64 },
+    Counter { pid: u64, tid: u64, name: String },
+}
+
+/// Writes [ChromeTraceEvent]s as Perfetto protobuf trace.
+///
+/// Duration events are slices on thread tracks and counter events are counter tracks per thread
+/// and argument. All other events become instant events on the process track, with the event
+/// type and the ids stored in debug annotations, so they can be read back by [PerfettoDecoder].
+pub struct PerfettoWriter<W: Write> {
+    out: W,
+    tracks: FxHashMap<TrackKey, u64>,
+}
+
+impl<W: Write> PerfettoWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            tracks: FxHashMap::default(),
+        }
+    }
+
+    fn write_packet(&mut self, f: impl FnOnce(&mut ProtoWriter)) -> Result<()> {
+        let mut trace = ProtoWriter::default();
+        trace.message(TRACE_PACKET, |packet| {
+            packet.uint(PACKET_TRUSTED_PACKET_SEQUENCE_ID, SEQUENCE_ID);
+            f(packet)
+        });
+        self.out.write_all(&trace.buf)?;
+        Ok(())
+    }
+
+    /// Returns the uuid of the track, writing its descriptor on first use.
+    fn track(&mut self, key: TrackKey) -> Result<u64> {
+        if let Some(&uuid) = self.tracks.get(&key) {
+            return Ok(uuid);
+        }
+        let parent = match &key {
+            TrackKey::Process { .. } => None,
+            &TrackKey::Thread { pid, .. } => Some(self.track(TrackKey::Process { pid })?),
+            &TrackKey::Counter { pid, tid, .. } => Some(self.track(TrackKey::Thread { pid, tid })?),
+        };
+        let uuid = self.tracks.len() as u64 + 1;
+        self.write_packet(|packet| {
+            packet.message(PACKET_TRACK_DESCRIPTOR, |track| {
+                track.uint(TRACK_UUID, uuid);
+                if let Some(parent) = parent {
+                    track.uint(TRACK_PARENT_UUID, parent);
+                }
+                match &key {
+                    &TrackKey::Process { pid } => {
+                        track.message(TRACK_PROCESS, |process| process.uint(PROCESS_PID, pid));
+                        if pid == TURBOPACK_PID {
+                            track.string(TRACK_NAME, "turbopack");
+                        }
+                    }
+                    &TrackKey::Thread { pid, tid } => {
+                        track.message(TRACK_THREAD, |thread| {
+                            thread.uint(THREAD_PID, pid);
+                            thread.uint(THREAD_TID, tid);
+                        });
+                    }
+                    TrackKey::Counter { name, .. } => {
+                        track.string(TRACK_NAME, name);
+                        track.message(TRACK_COUNTER, |_| {});
+                    }
+                }
+            });
+        })?;
+        self.tracks.insert(key, uuid);
+        Ok(uuid)
+    }
+
+    fn write_track_event(
+        &mut self,
+        ts: f64,
+        track_uuid: u64,
+        f: impl FnOnce(&mut ProtoWriter),
+    ) -> Result<()> {
+        self.write_packet(|packet| {
+            packet.uint(PACKET_TIMESTAMP, (ts.max(0.0) * 1000.0) as u64);
+            packet.message(PACKET_TRACK_EVENT, |track_event| {
+                track_event.uint(EVENT_TRACK_UUID, track_uuid);
+                f(track_event)
+            });
+        })
+    }
+}
+
+fn write_name_and_args(track_event: &mut ProtoWriter, event: &ChromeTraceEvent) {
+    track_event.string(EVENT_NAME, &event.name);
+    if !event.cat.is_empty() {
+        track_event.string(EVENT_CATEGORIES, &event.cat);
+    }
+    for (name, value) in &event.args {
+        track_event.annotation(name, value);
+    }
+}
+
+impl<W: Write> TraceEventWriter for PerfettoWriter<W> {
+    fn write_event(&mut self, event: &ChromeTraceEvent) -> Result<()> {
+        let (pid, tid) = (event.pid, event.tid);
+        match event.ph.as_str() {
+            "B" | "X" => {
+                let track = self.track(TrackKey::Thread { pid, tid })?;
+                self.write_track_event(event.ts, track, |track_event| {
+                    track_event.uint(EVENT_TYPE, EVENT_TYPE_SLICE_BEGIN);
+                    write_name_and_args(track_event, event);
+                    if let Some(id) = event.id {
+                        track_event.annotation(ANNOTATION_ID, &id.into());
+                    }
+                })?;
+                if let Some(dur) = event.dur {
+                    self.write_track_event(event.ts + dur, track, |track_event| {
+                        track_event.uint(EVENT_TYPE, EVENT_TYPE_SLICE_END);
+                    })?;
+                }
+            }
+            "E" => {
+                let track = self.track(TrackKey::Thread { pid, tid })?;
+                self.write_track_event(event.ts, track, |track_event| {
+                    track_event.uint(EVENT_TYPE, EVENT_TYPE_SLICE_END);
+                })?;
+            }
+            "C" => {
+                for (key, value) in &event.args {
+                    let Value::Number(value) = value else {
+                        continue;
+                    };
+                    let track = self.track(TrackKey::Counter {
+                        pid,
+                        tid,
+                        name: format!("{}.{key}", event.name),
+                    })?;
+                    self.write_track_event(event.ts, track, |track_event| {
+                        track_event.uint(EVENT_TYPE, EVENT_TYPE_COUNTER);
+                        if let Some(i) = value.as_i64() {
+                            track_event.int(EVENT_COUNTER_VALUE, i);
+                        } else {
+                            track_event.double(
+                                EVENT_DOUBLE_COUNTER_VALUE,
+                                value.as_f64().unwrap_or_default(),
+                            );
+                        }
+                    })?;
+                }
+            }
+            "b" | "e" | "n" | "i" | "I" => {
+                let track = self.track(TrackKey::Process { pid })?;
+                self.write_track_event(event.ts, track, |track_event| {
+                    track_event.uint(EVENT_TYPE, EVENT_TYPE_INSTANT);
+                    write_name_and_args(track_event, event);
+                    track_event.annotation(ANNOTATION_PH, &Value::String(event.ph.clone()));
+                    if let Some(id) = event.id {
+                        track_event.annotation(ANNOTATION_ID, &id.into());
+                    }
+                    if let Some(parent) = event.parent {
+                        track_event.annotation(ANNOTATION_PARENT, &parent.into());
+                    }
+                })?;
+            }
+            // Metadata, flow events, etc.
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+enum FieldValue<'a> {
+    Varint(u64),
+    Fixed64(u64),
+    Bytes(&'a [u8]),
+    Fixed32,
+}
+
+struct ProtoReader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> ProtoReader<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn varint(&mut self) -> Option<u64> {
+        let mut value = 0;
+        for shift in (0..64).step_by(7) {
+            let byte = *self.data.get(self.pos)?;
+            self.pos += 1;
+            value |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+        None
+    }
+
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let bytes = self.data.get(self.pos..self.pos.checked_add(len)?)?;
+        self.pos += len;
+        Some(bytes)
+    }
+
+    /// Returns `None` when the data ends, also in the middle of a field.
+    fn try_next(&mut self) -> Result<Option<(u32, FieldValue<'a>)>> {
+        let Some(key) = self.varint() else {
+            return Ok(None);
+        };
+        let field = (key >> 3) as u32;
+        let value = match (key & 7) as u8 {
+            WIRE_VARINT => self.varint().map(FieldValue::Varint),
+            WIRE_FIXED64 => self
+                .take(8)
+                .map(|bytes| FieldValue::Fixed64(u64::from_le_bytes(bytes.try_into().unwrap()))),
+            WIRE_LEN => match self.varint() {
+                Some(len) => self.take(len as usize).map(FieldValue::Bytes),
+                None => None,
+            },
+            WIRE_FIXED32 => self.take(4).map(|_| FieldValue::Fixed32),
+            wire_type => bail!("Unsupported protobuf wire type {wire_type}"),
+        };
+        Ok(value.map(|value| (field, value)))
+    }
+
+    /// Returns the next field of a complete message.
+    fn next(&mut self) -> Result<Option<(u32, FieldValue<'a>)>> {
+        if self.pos == self.data.len() {
+            return Ok(None);
+        }
+        match self.try_next()? {
+            Some(field) => Ok(Some(field)),
+            None => bail!("Truncated protobuf message"),
+        }
+    }
+}
+
+fn string(bytes: &[u8]) -> String {
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+enum DecodedTrack {
+    Process { pid: u64 },
+    Thread { pid: u64, tid: u64 },
+    Counter { pid: u64, tid: u64, name: String },
+    Other,
+}
+
+/// Reads a Perfetto protobuf trace as [ChromeTraceEvent]s.
+///
+/// Supports traces written by [PerfettoWriter] and the track events of other producers, as long
+/// as they don't use interned strings.
+#[derive(Default)]
+pub struct PerfettoDecoder {
+    tracks: FxHashMap<u64, DecodedTrack>,
+    /// The ids of the open slices per track
+    slice_stacks: FxHashMap<u64, Vec<Option<u64>>>,
+    /// The last values of the counters per process, thread and counter name
+    counters: FxHashMap<(u64, u64, String), Map<String, Value>>,
+}
+
+impl PerfettoDecoder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Decodes all complete packets in `buffer` and returns the number of bytes consumed.
+    pub fn decode(&mut self, buffer: &[u8], events: &mut Vec<ChromeTraceEvent>) -> Result<usize> {
+        let mut reader = ProtoReader::new(buffer);
+        let mut consumed = 0;
+        while let Some((field, value)) = reader.try_next()? {
+            if let (TRACE_PACKET, FieldValue::Bytes(packet)) = (field, value) {
+                self.decode_packet(packet, events)?;
+            }
+            consumed = reader.pos;
+        }
+        Ok(consumed)
+    }
+
+    fn decode_packet(&mut self, packet: &[u8], events: &mut Vec<ChromeTraceEvent>) -> Result<()> {
+        let mut reader = ProtoReader::new(packet);
+        let mut timestamp = 0;
+        let mut track_event = None;
+        while let Some((field, value)) = reader.next()? {
+            match (field, value) {
+                (PACKET_TIMESTAMP, FieldValue::Varint(ts)) => timestamp = ts,
+                (PACKET_TRACK_EVENT, FieldValue::Bytes(bytes)) => track_event = Some(bytes),
+                (PACKET_TRACK_DESCRIPTOR, FieldValue::Bytes(bytes)) => {
+                    self.decode_track_descriptor(bytes)?
+                }
+                _ => {}
+            }
+        }
+        if let Some(track_event) = track_event {
+            self.decode_track_event(timestamp, track_event, events)?;
+        }
+        Ok(())
+    }
+
+    fn decode_track_descriptor(&mut self, descriptor: &[u8]) -> Result<()> {
+        let mut reader = ProtoReader::new(descriptor);
+        let mut uuid = 0;
+        let mut parent = None;
+        let mut name = String::new();
+        let mut process = None;
+        let mut thread = None;
+        let mut is_counter = false;
+        while let Some((field, value)) = reader.next()? {
+            match (field, value) {
+                (TRACK_UUID, FieldValue::Varint(value)) => uuid = value,
+                (TRACK_PARENT_UUID, FieldValue::Varint(value)) => parent = Some(value),
+                (TRACK_NAME, FieldValue::Bytes(bytes)) => name = string(bytes),
+                (TRACK_PROCESS, FieldValue::Bytes(bytes)) => {
+                    let mut reader = ProtoReader::new(bytes);
+                    let mut pid = 0;
+                    while let Some((field, value)) = reader.next()? {
+                        if let (PROCESS_PID, FieldValue::Varint(value)) = (field, value) {
+                            pid = value;
+                        }
+                    }
+                    process = Some(pid);
+                }
+                (TRACK_THREAD, FieldValue::Bytes(bytes)) => {
+                    let mut reader = ProtoReader::new(bytes);
+                    let (mut pid, mut tid) = (0, 0);
+                    while let Some((field, value)) = reader.next()? {
+                        match (field, value) {
+                            (THREAD_PID, FieldValue::Varint(value)) => pid = value,
+                            (THREAD_TID, FieldValue::Varint(value)) => tid = value,
+                            _ => {}
+                        }
+                    }
+                    thread = Some((pid, tid));
+                }
+                (TRACK_COUNTER, FieldValue::Bytes(_)) => is_counter = true,
+                _ => {}
+            }
+        }
+        let track = if let Some((pid, tid)) = thread {
+            DecodedTrack::Thread { pid, tid }
+        } else if let Some(pid) = process {
+            DecodedTrack::Process { pid }
+        } else if is_counter {
+            let (pid, tid) = self.process_and_thread(parent);
+            DecodedTrack::Counter { pid, tid, name }
+        } else {
+            DecodedTrack::Other
+        };
+        self.tracks.insert(uuid, track);
+        Ok(())
+    }
+
+    fn process_and_thread(&self, track: Option<u64>) -> (u64, u64) {
+        match track.and_then(|track| self.tracks.get(&track)) {
+            Some(&DecodedTrack::Process { pid }) => (pid, 0),
+            Some(&DecodedTrack::Thread { pid, tid }) => (pid, tid),
+            Some(&DecodedTrack::Counter { pid, tid, .. }) => (pid, tid),
+            Some(DecodedTrack::Other) | None => (0, 0),
+        }
+    }
+
+    fn decode_track_event(
+        &mut self,
+        timestamp: u64,
+        track_event: &[u8],
+        events: &mut Vec<ChromeTraceEvent>,
+    ) -> Result<()> {
+        let mut reader = ProtoReader::new(track_event);
+        let mut ty = 0;
+        let mut track_uuid = 0;
+        let mut name = String::new();
+        let mut cat = String::new();
+        let mut args = Map::new();
+        let mut counter_value = None;
+        while let Some((field, value)) = reader.next()? {
+            match (field, value) {
+                (EVENT_TYPE, FieldValue::Varint(value)) => ty = value,
+                (EVENT_TRACK_UUID, FieldValue::Varint(value)) => track_uuid = value,
+                (EVENT_NAME, FieldValue::Bytes(bytes)) => name = string(bytes),
+                (EVENT_CATEGORIES, FieldValue::Bytes(bytes)) if cat.is_empty() => {
+                    cat = string(bytes)
+                }
+                (EVENT_DEBUG_ANNOTATIONS, FieldValue::Bytes(bytes)) => {
+                    if let Some((name, value)) = decode_annotation(bytes)? {
+                        args.insert(name, value);
+                    }
+                }
+                (EVENT_COUNTER_VALUE, FieldValue::Varint(value)) => {
+                    counter_value = Some(Value::from(value as i64))
+                }
+                (EVENT_DOUBLE_COUNTER_VALUE, FieldValue::Fixed64(value)) => {
+                    counter_value = Number::from_f64(f64::from_bits(value)).map(Value::Number)
+                }
+                _ => {}
+            }
+        }
+
+        let mut event = ChromeTraceEvent::new("", 0);
+        event.ts = timestamp as f64 / 1000.0;
+        (event.pid, event.tid) = self.process_and_thread(Some(track_uuid));
+        match ty {
+            EVENT_TYPE_SLICE_BEGIN => {
+                event.ph = "B".to_string();
+                event.id = take_id(&mut args, ANNOTATION_ID);
+                self.slice_stacks
+                    .entry(track_uuid)
+                    .or_default()
+                    .push(event.id);
+            }
+            EVENT_TYPE_SLICE_END => {
+                event.ph = "E".to_string();
+                event.id = self
+                    .slice_stacks
+                    .get_mut(&track_uuid)
+                    .and_then(|stack| stack.pop())
+                    .flatten();
+            }
+            EVENT_TYPE_INSTANT => {
+                event.ph = match args.remove(ANNOTATION_PH) {
+                    Some(Value::String(ph)) => ph,
+                    _ => "i".to_string(),
+                };
+                event.id = take_id(&mut args, ANNOTATION_ID);
+                event.parent = take_id(&mut args, ANNOTATION_PARENT);
+            }
+            EVENT_TYPE_COUNTER => {
+                let Some(DecodedTrack::Counter { pid, tid, name }) = self.tracks.get(&track_uuid)
+                else {
+                    return Ok(());
+                };
+                let Some(value) = counter_value else {
+                    return Ok(());
+                };
+                // Counters with multiple values are split into one track per value
+                let (counter, key) = name.split_once('.').unwrap_or((name, name));
+                let values = self
+                    .counters
+                    .entry((*pid, *tid, counter.to_string()))
+                    .or_default();
+                values.insert(key.to_string(), value);
+                event.ph = "C".to_string();
+                event.name = counter.to_string();
+                event.id = Some(*tid);
+                event.args = values.clone();
+                events.push(event);
+                return Ok(());
+            }
+            _ => return Ok(()),
+        }
+        event.name = name;
+        event.cat = cat;
+        event.args = args;
+        events.push(event);
+        Ok(())
+    }
+}
+
+fn take_id(args: &mut Map<String, Value>, key: &str) -> Option<u64> {
+    args.remove(key).and_then(|id| id.as_u64())
+}
+
+fn decode_annotation(annotation: &[u8]) -> Result<Option<(String, Value)>> {
+    let mut reader = ProtoReader::new(annotation);
+    let mut name = None;
+    let mut value = Value::Null;
+    while let Some((field, field_value)) = reader.next()? {
+        match (field, field_value) {
+            (ANNOTATION_NAME, FieldValue::Bytes(bytes)) => name = Some(string(bytes)),
+            (ANNOTATION_BOOL, FieldValue::Varint(v)) => value = Value::Bool(v != 0),
+            (ANNOTATION_UINT, FieldValue::Varint(v)) => value = Value::from(v),
+            (ANNOTATION_INT, FieldValue::Varint(v)) => value = Value::from(v as i64),
+            (ANNOTATION_DOUBLE, FieldValue::Fixed64(v)) => {
+                value = Number::from_f64(f64::from_bits(v)).map_or(Value::Null, Value::Number)
+            }
+            (ANNOTATION_STRING, FieldValue::Bytes(bytes)) => value = Value::String(string(bytes)),
+            _ => {}
+        }
+    }
+    Ok(name.map(|name| (name, value)))
+}
@@ -6,7 +6,7 @@ use std::{
 use serde::{Deserialize, Serialize};
 
 /// A raw trace line.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum TraceRow<'a> {
     /// A new span has been started, but not entered yet.
     Start {
@@ -102,7 +102,7 @@ pub enum TraceRow<'a> {
     },
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub enum TraceValue<'a> {
     String(#[serde(borrow)] Cow<'a, str>),
     Bool(bool),
@@ -1,56 +1,72 @@
+use std::io::ErrorKind;
+
 use anyhow::{Context, Result};
 use rustc_hash::FxHashMap;
 use smallvec::SmallVec;
-use tracing::Instrument;
-use turbo_rcstr::RcStr;
-use turbo_tasks::{ReadRef, ResolvedVc, TryJoinIterExt, ValueToString, Vc};
+use tracing::{Instrument, Span, field::Empty};
+use turbo_rcstr::{RcStr, rcstr};
+use turbo_tasks::{ReadRef, ResolvedVc, TryJoinIterExt, ValueToString, Vc, mark_session_dependent};
+use turbo_tasks_fs::{FileSystemPath, to_sys_path};
 use turbo_tasks_hash::hash_xxh3_hash64;
 use turbopack_core::{
-    chunk::{ChunkableModule, ChunkingType, module_id_strategies::GlobalModuleIdStrategy},
+    chunk::{
+        ChunkableModule, ChunkingType,
+        module_id_strategies::{
+            ContentHashModuleIdStrategy, GlobalModuleIdStrategy, ModuleIdRecords,
+        },
+    },
     ident::AssetIdent,
+    issue::{Issue, IssueExt, IssueSeverity, IssueStage, OptionStyledString, StyledString},
     module::Module,
     module_graph::{ModuleGraph, RefData},
 };
 use turbopack_ecmascript::async_chunk::module::AsyncLoaderModule;
 
+/// Returns the idents of all modules in the graph, including the modules that are inserted by
+/// chunking (i.e. async loaders).
+async fn module_graph_idents(module_graph: ResolvedVc<ModuleGraph>) -> Result<Vec<Vc<AssetIdent>>> {
+    let module_graph = module_graph.await?;
+    let graphs = module_graph.graphs.iter().try_join().await?;
+
+    // All modules in the graph
+    let mut idents = graphs
+        .iter()
+        .flat_map(|graph| graph.iter_nodes())
+        .map(|m| m.module.ident())
+        .collect::<Vec<_>>();
+
+    // And additionally, all the modules that are inserted by chunking (i.e. async loaders)
+    module_graph
+        .traverse_all_edges_unordered(|parent, current| {
+            if let (
+                _,
+                &RefData {
+                    chunking_type: ChunkingType::Async,
+                    ..
+                },
+            ) = parent
+            {
+                let module = ResolvedVc::try_sidecast::<Box<dyn ChunkableModule>>(current.module)
+                    .context("expected chunkable module for async reference")?;
+                idents.push(AsyncLoaderModule::asset_ident_for(*module));
+            }
+            Ok(())
+        })
+        .await?;
+
+    Ok(idents)
+}
+
 #[turbo_tasks::function]
 pub async fn get_global_module_id_strategy(
     module_graph: ResolvedVc<ModuleGraph>,
 ) -> Result<Vc<GlobalModuleIdStrategy>> {
     let span = tracing::info_span!("compute module id map");
     async move {
-        let module_graph = module_graph.await?;
-        let graphs = module_graph.graphs.iter().try_join().await?;
-
-        // All modules in the graph
-        let module_idents = graphs
-            .iter()
-            .flat_map(|graph| graph.iter_nodes())
-            .map(|m| m.module.ident());
-
-        // And additionally, all the modules that are inserted by chunking (i.e. async loaders)
-        let mut async_idents = vec![];
-        module_graph
-            .traverse_all_edges_unordered(|parent, current| {
-                if let (
-                    _,
-                    &RefData {
-                        chunking_type: ChunkingType::Async,
-                        ..
-                    },
-                ) = parent
-                {
-                    let module =
-                        ResolvedVc::try_sidecast::<Box<dyn ChunkableModule>>(current.module)
-                            .context("expected chunkable module for async reference")?;
-                    async_idents.push(AsyncLoaderModule::asset_ident_for(*module));
-                }
-                Ok(())
-            })
-            .await?;
+        let module_idents = module_graph_idents(module_graph).await?;
 
         let mut module_id_map = module_idents
-            .chain(async_idents.into_iter())
+            .into_iter()
             .map(|ident| async move {
                 let ident = ident.to_resolved().await?;
                 let ident_str = ident.to_string().await?;
@@ -76,6 +92,155 @@ pub async fn get_global_module_id_strategy(
     .await
 }
 
+/// Computes short module ids from the hash of the module idents. The ids are stable across
+/// builds: the assignment is read from the records file at `records_path` and ids of existing
+/// modules are kept. Use [write_module_id_records] to persist the new assignment.
+#[turbo_tasks::function]
+pub async fn get_content_hash_module_id_strategy(
+    module_graph: ResolvedVc<ModuleGraph>,
+    records_path: Option<FileSystemPath>,
+) -> Result<Vc<ContentHashModuleIdStrategy>> {
+    let span = tracing::info_span!("compute content hash module id map", collisions = Empty);
+    async {
+        let previous = if let Some(records_path) = records_path {
+            read_module_id_records(records_path).owned().await?
+        } else {
+            ModuleIdRecords::default()
+        };
+
+        let idents = module_graph_idents(module_graph)
+            .await?
+            .into_iter()
+            .map(async |ident| {
+                let ident = ident.to_resolved().await?;
+                Ok((ident, ident.to_string().owned().await?))
+            })
+            .try_join()
+            .await?;
+
+        let (records, collisions) =
+            ModuleIdRecords::assign(&previous, idents.iter().map(|(_, ident)| ident.as_str()));
+        Span::current().record("collisions", collisions);
+
+        let module_id_map = idents
+            .into_iter()
+            .map(|(ident, ident_str)| {
+                let id = records
+                    .module_ids
+                    .get(&ident_str)
+                    .context("every module ident should have an assigned id")?
+                    .clone();
+                Ok((ident, id))
+            })
+            .collect::<Result<FxHashMap<_, _>>>()?;
+
+        Ok(ContentHashModuleIdStrategy {
+            module_id_map,
+            records,
+        }
+        .cell())
+    }
+    .instrument(span)
+    .await
+}
+
+#[turbo_tasks::value(transparent)]
+struct PreviousModuleIdRecords(ModuleIdRecords);
+
+/// Reads the module id records of the previous build.
+///
+/// The file is written by this build, so it's read untracked: a tracked read would invalidate the
+/// module ids every time the new records are written. The read is repeated in every session to
+/// pick up the records of the previous one. Missing records are treated as empty, which only means
+/// that ids are not carried over from the previous build. Malformed records are reported and
+/// ignored.
+#[turbo_tasks::function]
+async fn read_module_id_records(
+    records_path: FileSystemPath,
+) -> Result<Vc<PreviousModuleIdRecords>> {
+    mark_session_dependent();
+    let Some(sys_path) = to_sys_path(records_path.clone()).await? else {
+        return Ok(Vc::cell(ModuleIdRecords::default()));
+    };
+    let content = match tokio::fs::read(&sys_path).await {
+        Ok(content) => content,
+        Err(err) if err.kind() == ErrorKind::NotFound => {
+            return Ok(Vc::cell(ModuleIdRecords::default()));
+        }
+        Err(err) => {
+            return Err(err).with_context(|| format!("failed to read {}", sys_path.display()));
+        }
+    };
+    match serde_json::from_slice(&content) {
+        Ok(records) => Ok(Vc::cell(records)),
+        Err(err) => {
+            InvalidModuleIdRecordsIssue {
+                path: records_path,
+                error: err.to_string().into(),
+            }
+            .resolved_cell()
+            .emit();
+            Ok(Vc::cell(ModuleIdRecords::default()))
+        }
+    }
+}
+
+/// Persists the module id assignment of `strategy` at `records_path`.
+#[turbo_tasks::function]
+pub async fn write_module_id_records(
+    strategy: Vc<ContentHashModuleIdStrategy>,
+    records_path: FileSystemPath,
+) -> Result<()> {
+    records_path
+        .write(strategy.records_file())
+        .as_side_effect()
+        .await?;
+    Ok(())
+}
+
+#[turbo_tasks::value(shared)]
+struct InvalidModuleIdRecordsIssue {
+    path: FileSystemPath,
+    error: RcStr,
+}
+
+#[turbo_tasks::value_impl]
+impl Issue for InvalidModuleIdRecordsIssue {
+    fn severity(&self) -> IssueSeverity {
+        IssueSeverity::Warning
+    }
+
+    #[turbo_tasks::function]
+    fn title(&self) -> Vc<StyledString> {
+        StyledString::Text(rcstr!("Invalid module id records")).cell()
+    }
+
+    #[turbo_tasks::function]
+    fn stage(&self) -> Vc<IssueStage> {
+        IssueStage::Load.cell()
+    }
+
+    #[turbo_tasks::function]
+    fn file_path(&self) -> Vc<FileSystemPath> {
+        self.path.clone().cell()
+    }
+
+    #[turbo_tasks::function]
+    fn description(&self) -> Vc<OptionStyledString> {
+        Vc::cell(Some(
+            StyledString::Text(
+                format!(
+                    "The module ids of the previous build couldn't be read, so all module ids are \
+                     assigned anew: {}",
+                    self.error
+                )
+                .into(),
+            )
+            .resolved_cell(),
+        ))
+    }
+}
+
 const JS_MAX_SAFE_INTEGER: u64 = (1u64 << 53) - 1;
 
 /// Shorten hashes and handle any collisions.
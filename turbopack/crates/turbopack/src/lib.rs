@@ -22,7 +22,9 @@ use ecmascript::{
     side_effect_optimization::facade::module::EcmascriptModuleFacadeModule,
 };
 use graph::{AggregatedGraph, AggregatedGraphNodeContent, aggregate};
-use module_options::{ModuleOptions, ModuleOptionsContext, ModuleRuleEffect, ModuleType};
+use module_options::{
+    ModuleOptions, ModuleOptionsContext, ModuleRule, ModuleRuleEffect, ModuleType,
+};
 use tracing::{Instrument, field::Empty};
 use turbo_rcstr::{RcStr, rcstr};
 use turbo_tasks::{ResolvedVc, ValueToString, Vc};
@@ -56,6 +58,7 @@ use turbopack_ecmascript::{
         CachedExternalModule, CachedExternalTracingMode, CachedExternalType,
     },
     side_effect_optimization::locals::module::EcmascriptModuleLocalsModule,
+    text::{BytesContentFileSource, StyleSheetContentFileSource, TextContentFileSource},
     tree_shake::asset::EcmascriptModulePartAsset,
 };
 use turbopack_json::JsonModuleAsset;
@@ -233,19 +236,32 @@ async fn apply_module_type(
         }
         ModuleType::Json => ResolvedVc::upcast(JsonModuleAsset::new(*source).to_resolved().await?),
         ModuleType::Raw => ResolvedVc::upcast(RawModule::new(*source).to_resolved().await?),
-        ModuleType::CssModule => ResolvedVc::upcast(
-            ModuleCssAsset::new(*source, Vc::upcast(module_asset_context))
-                .to_resolved()
-                .await?,
+        ModuleType::CssModule {
+            type_declarations,
+            report_unused_classes,
+        } => ResolvedVc::upcast(
+            ModuleCssAsset::new(
+                *source,
+                Vc::upcast(module_asset_context),
+                type_declarations.clone(),
+                *report_unused_classes,
+            )
+            .to_resolved()
+            .await?,
         ),
 
-        ModuleType::Css { ty, environment } => ResolvedVc::upcast(
+        ModuleType::Css {
+            ty,
+            environment,
+            remove_unused_classes,
+        } => ResolvedVc::upcast(
             CssModuleAsset::new(
                 *source,
                 Vc::upcast(module_asset_context),
                 *ty,
                 css_import_context,
                 environment.as_deref().copied(),
+                *remove_unused_classes,
             )
             .to_resolved()
             .await?,
@@ -509,8 +525,64 @@ async fn process_default_internal(
         ReferenceType::EcmaScriptModules(EcmaScriptModulesReferenceSubType::ImportWithType(ty)) => {
             has_type_attribute = true;
 
+            let has_rule = !matches!(ty, ImportWithType::Json)
+                && has_import_attribute_rule(
+                    &options.await?.rules,
+                    source,
+                    &path_ref,
+                    &reference_type,
+                )
+                .await?;
             match ty {
                 ImportWithType::Json => Some(ModuleType::Json),
+                // Left to the module rules, e.g. `RuleCondition::ImportAttributeType`, which take
+                // precedence over the built-in handling
+                _ if has_rule => None,
+                ImportWithType::Css => {
+                    return process_content_import(
+                        module_asset_context,
+                        Vc::upcast(StyleSheetContentFileSource::new(*source)),
+                        processed_rules,
+                    )
+                    .await;
+                }
+                ImportWithType::Text => {
+                    return process_content_import(
+                        module_asset_context,
+                        Vc::upcast(TextContentFileSource::new(*source)),
+                        processed_rules,
+                    )
+                    .await;
+                }
+                ImportWithType::Bytes => {
+                    return process_content_import(
+                        module_asset_context,
+                        Vc::upcast(BytesContentFileSource::new(*source)),
+                        processed_rules,
+                    )
+                    .await;
+                }
+                ImportWithType::Other(ty) => {
+                    ModuleIssue {
+                        ident: ident.to_resolved().await?,
+                        title: StyledString::Text(rcstr!("Unknown import attribute type"))
+                            .resolved_cell(),
+                        description: StyledString::Text(
+                            format!(
+                                "The import attribute `type: \"{ty}\"` is not supported. \
+                                 Supported types are \"json\", \"css\", \"text\" and \"bytes\". \
+                                 Other types need a module rule matching the import attribute \
+                                 type."
+                            )
+                            .into(),
+                        )
+                        .resolved_cell(),
+                        source: Some(IssueSource::from_source_only(source)),
+                    }
+                    .resolved_cell()
+                    .emit();
+                    return Ok(ProcessResult::Ignore.cell());
+                }
             }
         }
         _ => None,
@@ -664,6 +736,41 @@ async fn process_default_internal(
     ))
 }
 
+/// Whether one of the `rules` handles the `type` import attribute of `reference_type`. Rules that
+/// apply to every import of the module, e.g. by its extension, don't count.
+async fn has_import_attribute_rule(
+    rules: &[ModuleRule],
+    source: ResolvedVc<Box<dyn Source>>,
+    path: &FileSystemPath,
+    reference_type: &ReferenceType,
+) -> Result<bool> {
+    let import = ReferenceType::EcmaScriptModules(EcmaScriptModulesReferenceSubType::Import);
+    for rule in rules {
+        if rule.matches(source, path, reference_type).await?
+            && !rule.matches(source, path, &import).await?
+        {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Processes a JS module that exports the content of an imported module, e.g. for
+/// `with { type: "text" }`, like a regular import.
+async fn process_content_import(
+    module_asset_context: Vc<ModuleAssetContext>,
+    source: Vc<Box<dyn Source>>,
+    processed_rules: Vec<usize>,
+) -> Result<Vc<ProcessResult>> {
+    Box::pin(process_default(
+        module_asset_context,
+        source.to_resolved().await?,
+        ReferenceType::EcmaScriptModules(EcmaScriptModulesReferenceSubType::Import),
+        processed_rules,
+    ))
+    .await
+}
+
 #[turbo_tasks::function]
 async fn externals_tracing_module_context(
     ty: ExternalType,
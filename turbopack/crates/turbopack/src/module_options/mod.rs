@@ -22,6 +22,7 @@ use turbopack_core::{
 use turbopack_css::CssModuleAssetType;
 use turbopack_ecmascript::{
     EcmascriptInputTransform, EcmascriptInputTransforms, EcmascriptOptions, SpecifiedModuleType,
+    text::TextContentSourceTransform, worker_chunk::wrapper_source::WorkerWrapperSourceTransform,
 };
 use turbopack_mdx::MdxTransform;
 use turbopack_node::transforms::{postcss::PostCssTransform, webpack::WebpackLoaders};
@@ -129,6 +130,7 @@ impl ModuleOptions {
                     esm_url_rewrite_behavior,
                     ref enable_typeof_window_inlining,
                     source_maps: ecmascript_source_maps,
+                    well_known_packages,
                     ..
                 },
             enable_mdx,
@@ -137,6 +139,9 @@ impl ModuleOptions {
                 CssOptionsContext {
                     enable_raw_css,
                     source_maps: css_source_maps,
+                    ref module_type_declarations,
+                    report_unused_module_classes,
+                    remove_unused_module_classes,
                     ..
                 },
             ref enable_postcss_transform,
@@ -146,6 +151,7 @@ impl ModuleOptions {
             execution_context,
             tree_shaking_mode,
             keep_last_successful_parse,
+            enable_import_queries,
             ..
         } = *module_options_context.await?;
 
@@ -175,6 +181,7 @@ impl ModuleOptions {
             ignore_dynamic_requests,
             extract_source_map: matches!(ecmascript_source_maps, SourceMapsType::Full),
             keep_last_successful_parse,
+            well_known_packages,
             ..Default::default()
         };
         let ecmascript_options_vc = ecmascript_options.resolved_cell();
@@ -455,6 +462,7 @@ impl ModuleOptions {
                     vec![ModuleRuleEffect::ModuleType(ModuleType::Css {
                         ty: CssModuleAssetType::Default,
                         environment,
+                        remove_unused_classes: false,
                     })],
                 ),
                 ModuleRule::new(
@@ -465,6 +473,7 @@ impl ModuleOptions {
                     vec![ModuleRuleEffect::ModuleType(ModuleType::Css {
                         ty: CssModuleAssetType::Module,
                         environment,
+                        remove_unused_classes: remove_unused_module_classes,
                     })],
                 ),
             ]);
@@ -519,6 +528,7 @@ impl ModuleOptions {
                     vec![ModuleRuleEffect::ModuleType(ModuleType::Css {
                         ty: CssModuleAssetType::Default,
                         environment,
+                        remove_unused_classes: false,
                     })],
                 ),
                 ModuleRule::new(
@@ -534,7 +544,10 @@ impl ModuleOptions {
                             CssReferenceSubType::AtImport(None),
                         ))),
                     ]),
-                    vec![ModuleRuleEffect::ModuleType(ModuleType::CssModule)],
+                    vec![ModuleRuleEffect::ModuleType(ModuleType::CssModule {
+                        type_declarations: module_type_declarations.clone(),
+                        report_unused_classes: report_unused_module_classes,
+                    })],
                 ),
                 ModuleRule::new(
                     RuleCondition::all(vec![
@@ -550,6 +563,7 @@ impl ModuleOptions {
                     vec![ModuleRuleEffect::ModuleType(ModuleType::Css {
                         ty: CssModuleAssetType::Module,
                         environment,
+                        remove_unused_classes: remove_unused_module_classes,
                     })],
                 ),
                 // Ecmascript CSS Modules referencing the actual CSS module to include it
@@ -561,6 +575,7 @@ impl ModuleOptions {
                     vec![ModuleRuleEffect::ModuleType(ModuleType::Css {
                         ty: CssModuleAssetType::Module,
                         environment,
+                        remove_unused_classes: remove_unused_module_classes,
                     })],
                 ),
                 // Ecmascript CSS Modules referencing the actual CSS module to list the classes
@@ -577,6 +592,7 @@ impl ModuleOptions {
                     vec![ModuleRuleEffect::ModuleType(ModuleType::Css {
                         ty: CssModuleAssetType::Module,
                         environment,
+                        remove_unused_classes: remove_unused_module_classes,
                     })],
                 ),
             ]);
@@ -624,6 +640,41 @@ impl ModuleOptions {
             ));
         }
 
+        if enable_import_queries {
+            // Source transforms go first, so the original file content is used instead of the
+            // output of other transforms. The transformed source is processed again, without
+            // this rule, and ends up as an Ecmascript module.
+            rules.splice(
+                0..0,
+                [
+                    // Not `ModuleType::Raw`: a `RawModule` isn't chunkable and has no exports, it
+                    // only references a file for tracing. `?raw` needs the content as the default
+                    // export, like a `with { type: "text" }` import.
+                    ModuleRule::new(
+                        RuleCondition::ResourceQueryHasParam("raw".to_string()),
+                        vec![ModuleRuleEffect::SourceTransforms(ResolvedVc::cell(vec![
+                            ResolvedVc::upcast(
+                                TextContentSourceTransform::new().to_resolved().await?,
+                            ),
+                        ]))],
+                    ),
+                    ModuleRule::new(
+                        RuleCondition::ResourceQueryHasParam("worker".to_string()),
+                        vec![ModuleRuleEffect::SourceTransforms(ResolvedVc::cell(vec![
+                            ResolvedVc::upcast(
+                                WorkerWrapperSourceTransform::new().to_resolved().await?,
+                            ),
+                        ]))],
+                    ),
+                ],
+            );
+            // The module type has to be set last, so it overrides the defaults
+            rules.push(ModuleRule::new(
+                RuleCondition::ResourceQueryHasParam("url".to_string()),
+                vec![ModuleRuleEffect::ModuleType(ModuleType::StaticUrlJs)],
+            ));
+        }
+
         if let Some(webpack_loaders_options) = enable_webpack_loaders {
             let webpack_loaders_options = webpack_loaders_options.await?;
             let execution_context =
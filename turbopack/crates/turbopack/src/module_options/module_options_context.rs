@@ -9,7 +9,10 @@ use turbopack_core::{
     chunk::SourceMapsType, compile_time_info::CompileTimeInfo, condition::ContextCondition,
     environment::Environment, resolve::options::ImportMapping,
 };
-use turbopack_ecmascript::{TreeShakingMode, references::esm::UrlRewriteBehavior};
+use turbopack_css::CssModuleTypeDeclarations;
+use turbopack_ecmascript::{
+    TreeShakingMode, references::esm::UrlRewriteBehavior, well_known_packages::WellKnownPackages,
+};
 pub use turbopack_mdx::MdxTransformOptions;
 use turbopack_node::{
     execution_context::ExecutionContext,
@@ -166,6 +169,11 @@ pub struct ModuleOptionsContext {
     /// are temporarily introduced.
     pub keep_last_successful_parse: bool,
 
+    /// Handle Vite-style import queries: `?raw` imports the file content as a
+    /// string, `?url` imports the URL of the emitted file and `?worker` imports
+    /// a function that constructs a `Worker` for the module.
+    pub enable_import_queries: bool,
+
     /// Custom rules to be applied after all default rules.
     pub module_rules: Vec<ModuleRule>,
     /// A list of rules to use a different module option context for certain
@@ -196,6 +204,9 @@ pub struct EcmascriptOptionsContext {
     pub ignore_dynamic_requests: bool,
     /// Specifies how Source Maps are handled.
     pub source_maps: SourceMapsType,
+    /// Describes packages that load files at runtime, so the analyzer can follow them like the
+    /// built-in ones (e.g. `bindings` or `node-gyp-build`).
+    pub well_known_packages: Option<ResolvedVc<WellKnownPackages>>,
 
     pub placeholder_for_future_extensions: (),
 }
@@ -214,6 +225,19 @@ pub struct CssOptionsContext {
     /// Specifies how Source Maps are handled.
     pub source_maps: SourceMapsType,
 
+    /// Emit a `.d.ts` file declaring the exported class names for every CSS Module, so
+    /// TypeScript can check their usage.
+    pub module_type_declarations: Option<CssModuleTypeDeclarations>,
+
+    /// Report the classes of CSS Modules that no importing module reads. Requires the export
+    /// usage of the module graph to be passed to the chunking context.
+    pub report_unused_module_classes: bool,
+
+    /// Leave out the rules that only match classes of CSS Modules that no importing module reads.
+    /// Meant for production builds, requires the export usage like
+    /// `report_unused_module_classes`.
+    pub remove_unused_module_classes: bool,
+
     pub placeholder_for_future_extensions: (),
 }
 
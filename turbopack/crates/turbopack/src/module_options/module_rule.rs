@@ -6,7 +6,7 @@ use turbopack_core::{
     environment::Environment, reference_type::ReferenceType, source::Source,
     source_transform::SourceTransforms,
 };
-use turbopack_css::CssModuleAssetType;
+use turbopack_css::{CssModuleAssetType, CssModuleTypeDeclarations};
 use turbopack_ecmascript::{EcmascriptInputTransforms, EcmascriptOptions};
 use turbopack_wasm::source::WebAssemblySourceType;
 
@@ -119,10 +119,14 @@ pub enum ModuleType {
     },
     Json,
     Raw,
-    CssModule,
+    CssModule {
+        type_declarations: Option<CssModuleTypeDeclarations>,
+        report_unused_classes: bool,
+    },
     Css {
         ty: CssModuleAssetType,
         environment: Option<ResolvedVc<Environment>>,
+        remove_unused_classes: bool,
     },
     StaticUrlJs,
     StaticUrlCss,
@@ -1,13 +1,23 @@
+use std::io::Read;
+
 use anyhow::{Result, bail};
 use serde::{Deserialize, Serialize};
 use smallvec::SmallVec;
 use turbo_esregex::EsRegex;
-use turbo_tasks::{NonLocalValue, ReadRef, ResolvedVc, primitives::Regex, trace::TraceRawVcs};
-use turbo_tasks_fs::{FileSystemPath, glob::Glob};
+use turbo_rcstr::RcStr;
+use turbo_tasks::{NonLocalValue, ReadRef, ResolvedVc, Vc, primitives::Regex, trace::TraceRawVcs};
+use turbo_tasks_fs::{FileContent, FileSystemPath, glob::Glob};
 use turbopack_core::{
-    reference_type::ReferenceType, source::Source, virtual_source::VirtualSource,
+    asset::Asset,
+    reference_type::{EcmaScriptModulesReferenceSubType, ReferenceType},
+    source::Source,
+    virtual_source::VirtualSource,
 };
 
+/// The number of bytes at the start of a file that are scanned for directives
+/// and magic comments.
+const PREAMBLE_MAX_BYTES: u32 = 4096;
+
 #[derive(Debug, Clone, Serialize, Deserialize, TraceRawVcs, PartialEq, Eq, NonLocalValue)]
 pub enum RuleCondition {
     All(Vec<RuleCondition>),
@@ -36,6 +46,33 @@ pub enum RuleCondition {
         glob: ReadRef<Glob>,
     },
     ResourceBasePathGlob(#[turbo_tasks(trace_ignore)] ReadRef<Glob>),
+    /// Matches if the query string of the resource is exactly the given
+    /// string, including the leading `?` (e.g. `?raw`).
+    ResourceQueryEquals(String),
+    /// Matches if the query string of the resource contains a parameter with
+    /// the given key, e.g. `worker` matches `?worker` and `?worker&inline`.
+    ResourceQueryHasParam(String),
+    /// Matches if the resource is imported with a `type` import attribute of
+    /// the given value, e.g. `json` for `import data from "./data.json" with {
+    /// type: "json" }`. A rule with this condition replaces the built-in
+    /// handling of the `css`, `text` and `bytes` types.
+    ImportAttributeType(String),
+    /// Matches if the directive prologue of the file contains the given
+    /// directive, e.g. `use client` for a file starting with `"use client";`.
+    /// Leading comments and a hashbang are skipped.
+    ContentDirective(String),
+    /// Matches if one of the comments before the first statement of the file
+    /// contains the given text, e.g. `@generated`.
+    ContentMagicComment(String),
+    /// Matches if the content of the file starts with the given bytes.
+    ContentStartsWith(Vec<u8>),
+    /// Matches if the regex matches the first `max_bytes` bytes of the file.
+    /// Invalid UTF-8 sequences are replaced before matching.
+    ContentRegex {
+        #[turbo_tasks(trace_ignore)]
+        regex: ReadRef<EsRegex>,
+        max_bytes: u32,
+    },
 }
 
 impl RuleCondition {
@@ -166,6 +203,44 @@ impl RuleCondition {
                     RuleCondition::ResourcePathEsRegex(regex) => {
                         return Ok(regex.is_match(&path.path));
                     }
+                    RuleCondition::ResourceQueryEquals(query) => {
+                        return Ok(source.ident().await?.query == query.as_str());
+                    }
+                    RuleCondition::ResourceQueryHasParam(key) => {
+                        let ident = source.ident().await?;
+                        return Ok(ident.query.strip_prefix('?').is_some_and(|query| {
+                            query
+                                .split('&')
+                                .any(|param| param.split('=').next() == Some(key.as_str()))
+                        }));
+                    }
+                    RuleCondition::ImportAttributeType(ty) => {
+                        return Ok(matches!(
+                            reference_type,
+                            ReferenceType::EcmaScriptModules(
+                                EcmaScriptModulesReferenceSubType::ImportWithType(with_type)
+                            ) if with_type.as_str() == ty
+                        ));
+                    }
+                    RuleCondition::ContentDirective(directive) => {
+                        let preamble = source_preamble(*source).await?;
+                        return Ok(preamble.directives.iter().any(|d| d.as_str() == directive));
+                    }
+                    RuleCondition::ContentMagicComment(text) => {
+                        let preamble = source_preamble(*source).await?;
+                        return Ok(preamble.comments.iter().any(|c| c.contains(text.as_str())));
+                    }
+                    RuleCondition::ContentStartsWith(prefix) => {
+                        let max_bytes = u32::try_from(prefix.len()).unwrap_or(u32::MAX);
+                        let head = source_content_head(*source, max_bytes).await?;
+                        return Ok(head.as_ref().is_some_and(|head| head.starts_with(prefix)));
+                    }
+                    RuleCondition::ContentRegex { regex, max_bytes } => {
+                        let head = source_content_head(*source, *max_bytes).await?;
+                        return Ok(head
+                            .as_ref()
+                            .is_some_and(|head| regex.is_match(&String::from_utf8_lossy(head))));
+                    }
                 }
             }
         }
@@ -221,12 +296,113 @@ impl RuleCondition {
     }
 }
 
+#[turbo_tasks::value(transparent)]
+struct SourceContentHead(Option<Vec<u8>>);
+
+/// Reads up to `max_bytes` bytes from the start of the source content. Returns
+/// `None` when the source has no file content.
+#[turbo_tasks::function]
+async fn source_content_head(
+    source: ResolvedVc<Box<dyn Source>>,
+    max_bytes: u32,
+) -> Result<Vc<SourceContentHead>> {
+    let content = source.content().file_content().await?;
+    let FileContent::Content(file) = &*content else {
+        return Ok(Vc::cell(None));
+    };
+    let mut head = Vec::new();
+    file.content()
+        .read()
+        .take(max_bytes as u64)
+        .read_to_end(&mut head)?;
+    Ok(Vc::cell(Some(head)))
+}
+
+/// Directives and comments that appear before the first statement of a file.
+#[turbo_tasks::value]
+struct SourcePreamble {
+    directives: Vec<RcStr>,
+    comments: Vec<RcStr>,
+}
+
+#[turbo_tasks::function]
+async fn source_preamble(source: ResolvedVc<Box<dyn Source>>) -> Result<Vc<SourcePreamble>> {
+    let head = source_content_head(*source, PREAMBLE_MAX_BYTES).await?;
+    let Some(head) = head.as_ref() else {
+        return Ok(SourcePreamble {
+            directives: Vec::new(),
+            comments: Vec::new(),
+        }
+        .cell());
+    };
+    let (directives, comments) = scan_preamble(&String::from_utf8_lossy(head));
+    Ok(SourcePreamble {
+        directives,
+        comments,
+    }
+    .cell())
+}
+
+/// Scans the directive prologue of a JavaScript-like source. This is a
+/// lightweight scanner that stops at the first token that is neither a
+/// comment nor a string literal statement, so it never needs a full parse.
+fn scan_preamble(code: &str) -> (Vec<RcStr>, Vec<RcStr>) {
+    let mut directives = Vec::new();
+    let mut comments = Vec::new();
+    let mut rest = code.strip_prefix('\u{feff}').unwrap_or(code);
+    if rest.starts_with("#!") {
+        rest = rest.find('\n').map_or("", |i| &rest[i + 1..]);
+    }
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("//") {
+            let end = after.find('\n').unwrap_or(after.len());
+            comments.push(after[..end].trim().into());
+            rest = &after[end..];
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            let Some(end) = after.find("*/") else {
+                // Unterminated comment, possibly truncated by the byte limit
+                break;
+            };
+            comments.push(after[..end].trim().into());
+            rest = &after[end + 2..];
+        } else if let Some(quote) = rest.chars().next().filter(|c| *c == '"' || *c == '\'') {
+            let after = &rest[1..];
+            let Some(end) = after.find([quote, '\\', '\n']) else {
+                break;
+            };
+            if !after[end..].starts_with(quote) {
+                // Escapes and line breaks don't appear in directives we care about
+                break;
+            }
+            let value = &after[..end];
+            let tail = after[end + 1..].trim_start_matches([' ', '\t']);
+            if !(tail.is_empty()
+                || tail.starts_with([';', '\n', '\r'])
+                || tail.starts_with("//")
+                || tail.starts_with("/*"))
+            {
+                // The string is part of an expression, e.g. `"a" + b`
+                break;
+            }
+            directives.push(value.into());
+            rest = tail.strip_prefix(';').unwrap_or(tail);
+        } else {
+            break;
+        }
+    }
+    (directives, comments)
+}
+
 #[cfg(test)]
 pub mod tests {
+    use turbo_rcstr::rcstr;
     use turbo_tasks::Vc;
     use turbo_tasks_backend::{BackendOptions, TurboTasksBackend, noop_backing_storage};
-    use turbo_tasks_fs::{FileContent, FileSystem, VirtualFileSystem};
-    use turbopack_core::{asset::AssetContent, file_source::FileSource};
+    use turbo_tasks_fs::{File, FileContent, FileSystem, VirtualFileSystem};
+    use turbopack_core::{
+        asset::AssetContent, file_source::FileSource, reference_type::ImportWithType,
+    };
 
     use super::*;
 
@@ -361,6 +537,67 @@ pub mod tests {
                     .unwrap()
             );
         }
+        {
+            let query_source = Vc::upcast::<Box<dyn Source>>(FileSource::new_with_query(
+                non_virtual_path.clone(),
+                rcstr!("?worker&inline"),
+            ))
+            .to_resolved()
+            .await?;
+            let condition = RuleCondition::ResourceQueryHasParam("worker".to_string());
+            assert!(
+                condition
+                    .matches(query_source, &non_virtual_path, &ReferenceType::Undefined)
+                    .await
+                    .unwrap()
+            );
+            assert!(
+                !condition
+                    .matches(
+                        non_virtual_source,
+                        &non_virtual_path,
+                        &ReferenceType::Undefined
+                    )
+                    .await
+                    .unwrap()
+            );
+            let condition = RuleCondition::ResourceQueryEquals("?worker".to_string());
+            assert!(
+                !condition
+                    .matches(query_source, &non_virtual_path, &ReferenceType::Undefined)
+                    .await
+                    .unwrap()
+            );
+        }
+        {
+            let condition = RuleCondition::ImportAttributeType("css".to_string());
+            assert!(
+                condition
+                    .matches(
+                        non_virtual_source,
+                        &non_virtual_path,
+                        &ReferenceType::EcmaScriptModules(
+                            EcmaScriptModulesReferenceSubType::ImportWithType(ImportWithType::new(
+                                "css"
+                            ))
+                        )
+                    )
+                    .await
+                    .unwrap()
+            );
+            assert!(
+                !condition
+                    .matches(
+                        non_virtual_source,
+                        &non_virtual_path,
+                        &ReferenceType::EcmaScriptModules(
+                            EcmaScriptModulesReferenceSubType::ImportWithType(ImportWithType::Json)
+                        )
+                    )
+                    .await
+                    .unwrap()
+            );
+        }
         anyhow::Ok(())
     }
 
@@ -495,4 +732,107 @@ pub mod tests {
         }
         anyhow::Ok(())
     }
+
+    #[test]
+    fn test_scan_preamble() {
+        let (directives, comments) = scan_preamble(
+            "#!/usr/bin/env node\n// @generated\n/* eslint-disable */\n'use strict';\n\"use \
+             client\"\nimport foo from 'foo';\n\"not a directive\";",
+        );
+        assert_eq!(
+            directives,
+            vec![RcStr::from("use strict"), "use client".into()]
+        );
+        assert_eq!(
+            comments,
+            vec![RcStr::from("@generated"), "eslint-disable".into()]
+        );
+
+        let (directives, comments) = scan_preamble("\"use client\" + foo;");
+        assert!(directives.is_empty());
+        assert!(comments.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rule_condition_content() {
+        crate::register();
+        let tt = turbo_tasks::TurboTasks::new(TurboTasksBackend::new(
+            BackendOptions::default(),
+            noop_backing_storage(),
+        ));
+        tt.run_once(async { run_content_test().await })
+            .await
+            .unwrap();
+    }
+
+    #[turbo_tasks::function]
+    pub async fn run_content_test() -> Result<()> {
+        let fs = VirtualFileSystem::new();
+        let client_path = fs.root().await?.join("client.js")?;
+        let client_source = Vc::upcast::<Box<dyn Source>>(VirtualSource::new(
+            client_path.clone(),
+            AssetContent::File(
+                FileContent::Content(File::from(
+                    "// @generated by codegen\n\"use client\";\nexport default 1;\n",
+                ))
+                .resolved_cell(),
+            )
+            .cell(),
+        ))
+        .to_resolved()
+        .await?;
+
+        let missing_path = fs.root().await?.join("missing.js")?;
+        let missing_source = Vc::upcast::<Box<dyn Source>>(FileSource::new(missing_path.clone()))
+            .to_resolved()
+            .await?;
+
+        let conditions = [
+            RuleCondition::ContentDirective("use client".to_string()),
+            RuleCondition::ContentMagicComment("@generated".to_string()),
+            RuleCondition::ContentStartsWith(b"// @gen".to_vec()),
+            RuleCondition::ContentRegex {
+                regex: EsRegex::new("^export default", "m")?.cell().await?,
+                max_bytes: 64,
+            },
+        ];
+        for condition in conditions {
+            assert!(
+                condition
+                    .matches(client_source, &client_path, &ReferenceType::Undefined)
+                    .await
+                    .unwrap()
+            );
+            assert!(
+                !condition
+                    .matches(missing_source, &missing_path, &ReferenceType::Undefined)
+                    .await
+                    .unwrap()
+            );
+        }
+
+        {
+            let condition = RuleCondition::ContentDirective("use server".to_string());
+            assert!(
+                !condition
+                    .matches(client_source, &client_path, &ReferenceType::Undefined)
+                    .await
+                    .unwrap()
+            );
+        }
+        {
+            // The regex only sees the first `max_bytes` bytes
+            let condition = RuleCondition::ContentRegex {
+                regex: EsRegex::new("export default", "")?.cell().await?,
+                max_bytes: 16,
+            };
+            assert!(
+                !condition
+                    .matches(client_source, &client_path, &ReferenceType::Undefined)
+                    .await
+                    .unwrap()
+            );
+        }
+        anyhow::Ok(())
+    }
 }
@@ -0,0 +1,166 @@
+#![feature(arbitrary_self_types)]
+#![feature(arbitrary_self_types_pointers)]
+
+use std::{fs, path::Path};
+
+use anyhow::Result;
+use turbo_rcstr::{RcStr, rcstr};
+use turbo_tasks::{TurboTasks, Vc, apply_effects};
+use turbo_tasks_backend::{BackendOptions, TurboTasksBackend, noop_backing_storage};
+use turbo_tasks_fs::{DiskFileSystem, FileSystem};
+use turbopack::{
+    ModuleAssetContext,
+    css::{CssModuleTypeDeclarations, emit_css_module_type_declarations},
+    module_options::{CssOptionsContext, ModuleOptionsContext},
+    register,
+};
+use turbopack_core::{
+    chunk::ChunkGroupEntry,
+    compile_time_info::CompileTimeInfo,
+    context::AssetContext,
+    environment::{Environment, ExecutionEnvironment, NodeJsEnvironment},
+    file_source::FileSource,
+    ident::Layer,
+    issue::{IssueDescriptionExt, StyledString},
+    module::Module,
+    module_graph::ModuleGraph,
+    reference_type::ReferenceType,
+};
+use turbopack_resolve::resolve_options_context::ResolveOptionsContext;
+
+/// Processes `index.ts` in `root`, with type declarations for CSS Modules when
+/// `type_declarations` is set.
+async fn index_module(root: RcStr, type_declarations: bool) -> Result<Vc<Box<dyn Module>>> {
+    let root = DiskFileSystem::new(rcstr!("project"), root)
+        .root()
+        .owned()
+        .await?;
+    let asset_context = ModuleAssetContext::new(
+        Default::default(),
+        CompileTimeInfo::new(Environment::new(ExecutionEnvironment::NodeJsLambda(
+            NodeJsEnvironment::default().resolved_cell(),
+        ))),
+        ModuleOptionsContext {
+            css: CssOptionsContext {
+                module_type_declarations: type_declarations
+                    .then_some(CssModuleTypeDeclarations::NextToSource),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+        .cell(),
+        ResolveOptionsContext::default().cell(),
+        Layer::new(rcstr!("test")),
+    );
+    Ok(asset_context
+        .process(
+            Vc::upcast(FileSource::new(root.join("index.ts")?)),
+            ReferenceType::Undefined,
+        )
+        .module())
+}
+
+#[turbo_tasks::function(operation)]
+async fn analyze_operation(root: RcStr) -> Result<()> {
+    index_module(root, false).await?.references().await?;
+    Ok(())
+}
+
+#[turbo_tasks::function(operation)]
+async fn emit_type_declarations_operation(root: RcStr) -> Result<()> {
+    let module = index_module(root, true).await?.to_resolved().await?;
+    let module_graph =
+        ModuleGraph::from_modules(Vc::cell(vec![ChunkGroupEntry::Entry(vec![module])]), false);
+    emit_css_module_type_declarations(module_graph)
+        .as_side_effect()
+        .await?;
+    Ok(())
+}
+
+fn write_project(dir: &Path) -> Result<()> {
+    fs::write(
+        dir.join("index.ts"),
+        "import styles from './button.module.css';\n\nconsole.log(styles.button, styles.buton);\n",
+    )?;
+    fs::write(
+        dir.join("button.module.css"),
+        ".button { color: red; }\n.primary { color: blue; }\n",
+    )?;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn unknown_css_module_class_is_reported() -> Result<()> {
+    register();
+    include!(concat!(env!("OUT_DIR"), "/register_test_css_modules.rs"));
+
+    let dir = tempfile::tempdir()?;
+    write_project(dir.path())?;
+    let root = RcStr::from(dir.path().to_str().unwrap());
+
+    let tt = TurboTasks::new(TurboTasksBackend::new(
+        BackendOptions {
+            storage_mode: None,
+            ..Default::default()
+        },
+        noop_backing_storage(),
+    ));
+    let titles = tt
+        .run_once(async move {
+            let operation = analyze_operation(root);
+            operation.read_strongly_consistent().await?;
+            let issues = operation.peek_issues_with_path().await?;
+            let mut titles = Vec::new();
+            for issue in issues.iter() {
+                titles.push(issue.title().owned().await?);
+            }
+            Ok(titles)
+        })
+        .await?;
+
+    // `styles.button` exists, only `styles.buton` is reported
+    assert_eq!(
+        titles,
+        vec![StyledString::Line(vec![
+            StyledString::Code(rcstr!("buton")),
+            StyledString::Text(rcstr!(" is not exported by ")),
+            StyledString::Code(rcstr!("./button.module.css")),
+        ])]
+    );
+
+    tt.stop_and_wait().await;
+    Ok(())
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn css_module_type_declarations_are_emitted() -> Result<()> {
+    register();
+    include!(concat!(env!("OUT_DIR"), "/register_test_css_modules.rs"));
+
+    let dir = tempfile::tempdir()?;
+    write_project(dir.path())?;
+    let root = RcStr::from(dir.path().to_str().unwrap());
+
+    let tt = TurboTasks::new(TurboTasksBackend::new(
+        BackendOptions {
+            storage_mode: None,
+            ..Default::default()
+        },
+        noop_backing_storage(),
+    ));
+    tt.run_once(async move {
+        let operation = emit_type_declarations_operation(root);
+        operation.read_strongly_consistent().await?;
+        apply_effects(operation).await?;
+        Ok(())
+    })
+    .await?;
+
+    let declarations = fs::read_to_string(dir.path().join("button.module.css.d.ts"))?;
+    assert!(declarations.contains("readonly \"button\": string;"));
+    assert!(declarations.contains("readonly \"primary\": string;"));
+    assert!(declarations.contains("export default styles;"));
+
+    tt.stop_and_wait().await;
+    Ok(())
+}
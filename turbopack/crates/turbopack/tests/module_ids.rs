@@ -0,0 +1,156 @@
+#![feature(arbitrary_self_types)]
+#![feature(arbitrary_self_types_pointers)]
+
+use std::{collections::BTreeMap, fs};
+
+use anyhow::Result;
+use turbo_rcstr::{RcStr, rcstr};
+use turbo_tasks::{ResolvedVc, TryJoinIterExt, TurboTasks, ValueToString, Vc, apply_effects};
+use turbo_tasks_backend::{BackendOptions, TurboTasksBackend, noop_backing_storage};
+use turbo_tasks_fs::{DiskFileSystem, FileSystem};
+use turbopack::{
+    ModuleAssetContext,
+    global_module_ids::{get_content_hash_module_id_strategy, write_module_id_records},
+    register,
+};
+use turbopack_core::{
+    chunk::{ChunkGroupEntry, module_id_strategies::ModuleIdStrategy},
+    compile_time_info::CompileTimeInfo,
+    context::AssetContext,
+    environment::{Environment, ExecutionEnvironment, NodeJsEnvironment},
+    file_source::FileSource,
+    ident::Layer,
+    module::Module,
+    module_graph::ModuleGraph,
+    reference_type::ReferenceType,
+};
+use turbopack_resolve::resolve_options_context::ResolveOptionsContext;
+
+const RECORDS_PATH: &str = "cache/module-ids.json";
+
+#[turbo_tasks::value(transparent, operation)]
+struct ModuleIds(BTreeMap<RcStr, RcStr>);
+
+/// Assigns content hashed module ids to the given files and persists the records, like a build
+/// does.
+#[turbo_tasks::function(operation)]
+async fn module_ids_operation(root: RcStr, files: Vec<RcStr>) -> Result<Vc<ModuleIds>> {
+    let root = DiskFileSystem::new(rcstr!("project"), root)
+        .root()
+        .owned()
+        .await?;
+    let asset_context = ModuleAssetContext::new(
+        Default::default(),
+        CompileTimeInfo::new(Environment::new(ExecutionEnvironment::NodeJsLambda(
+            NodeJsEnvironment::default().resolved_cell(),
+        ))),
+        Default::default(),
+        ResolveOptionsContext::default().cell(),
+        Layer::new(rcstr!("test")),
+    );
+    let root = &root;
+    let modules = files
+        .iter()
+        .map(async |file| {
+            asset_context
+                .process(
+                    Vc::upcast(FileSource::new(root.join(file)?)),
+                    ReferenceType::Undefined,
+                )
+                .module()
+                .to_resolved()
+                .await
+        })
+        .try_join()
+        .await?;
+
+    let module_graph = ModuleGraph::from_modules(
+        Vc::cell(vec![ChunkGroupEntry::Entry(modules.clone())]),
+        false,
+    )
+    .to_resolved()
+    .await?;
+    let records_path = root.join(RECORDS_PATH)?;
+    let strategy = get_content_hash_module_id_strategy(*module_graph, Some(records_path.clone()));
+    write_module_id_records(strategy, records_path)
+        .as_side_effect()
+        .await?;
+
+    let ids = files
+        .into_iter()
+        .zip(modules)
+        .map(
+            async |(file, module): (RcStr, ResolvedVc<Box<dyn Module>>)| {
+                let id = strategy
+                    .get_module_id(module.ident())
+                    .to_string()
+                    .owned()
+                    .await?;
+                Ok((file, id))
+            },
+        )
+        .try_join()
+        .await?;
+    Ok(Vc::cell(ids.into_iter().collect()))
+}
+
+/// Runs a build in a new turbo-tasks instance, so that nothing is shared with previous builds
+/// except for the records file.
+async fn build(root: &str, files: &[&str]) -> Result<BTreeMap<RcStr, RcStr>> {
+    let tt = TurboTasks::new(TurboTasksBackend::new(
+        BackendOptions {
+            storage_mode: None,
+            ..Default::default()
+        },
+        noop_backing_storage(),
+    ));
+    let root = RcStr::from(root);
+    let files = files.iter().map(|file| RcStr::from(*file)).collect();
+    let ids = tt
+        .run_once(async move {
+            let operation = module_ids_operation(root, files);
+            let ids = operation.read_strongly_consistent().owned().await?;
+            apply_effects(operation).await?;
+            Ok(ids)
+        })
+        .await?;
+    tt.stop_and_wait().await;
+    Ok(ids)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn module_ids_are_stable_across_builds() -> Result<()> {
+    register();
+    include!(concat!(env!("OUT_DIR"), "/register_test_module_ids.rs"));
+
+    let dir = tempfile::tempdir()?;
+    for file in ["a.js", "b.js", "c.js"] {
+        fs::write(dir.path().join(file), format!("export default {file:?};\n"))?;
+    }
+    let root = dir.path().to_str().unwrap();
+    let records_path = dir.path().join(RECORDS_PATH);
+
+    let first = build(root, &["a.js", "b.js"]).await?;
+    assert!(records_path.exists());
+
+    // The records are read back, so a module keeps its id even if it would be derived
+    // differently now
+    let records = fs::read_to_string(&records_path)?;
+    let a_id = &first["a.js"];
+    fs::write(
+        &records_path,
+        records.replace(&format!("\"{a_id}\""), "\"kept\""),
+    )?;
+    let second = build(root, &["a.js", "b.js", "c.js"]).await?;
+    assert_eq!(second["a.js"], "kept");
+    assert_eq!(second["b.js"], first["b.js"]);
+    assert_ne!(second["c.js"], second["b.js"]);
+
+    // Malformed records are reported and ignored
+    fs::write(&records_path, "{")?;
+    let third = build(root, &["a.js", "b.js"]).await?;
+    assert_eq!(third["b.js"], first["b.js"]);
+    assert!(fs::read_to_string(&records_path)?.contains("\"version\""));
+
+    Ok(())
+}